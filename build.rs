@@ -11,6 +11,9 @@ fn main() {
     // Debug output for build detection
     println!("cargo:warning=Building for target: {target_arch}-{target_os}-{target_env}");
 
+    // Git and build metadata reported by /v4/info
+    embed_build_metadata();
+
     // Set up cross-compilation environment variables early
     setup_cross_compilation_env(&target_arch, &target_os, &target_env);
 
@@ -294,3 +297,59 @@ fn setup_cross_compilation_env(target_arch: &str, target_os: &str, target_env: &
         }
     }
 }
+
+/// Embed git and build metadata as compile-time environment variables
+///
+/// Builds outside a git checkout report "unknown" and a commit time of 0.
+/// `SOURCE_DATE_EPOCH` overrides the build time for reproducible builds.
+fn embed_build_metadata() {
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        println!("cargo:rerun-if-changed=.git/{head_ref}");
+    }
+
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_else(|| "unknown".into());
+    let commit = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".into());
+    let commit_time = git(&["log", "-1", "--format=%ct"])
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .map(|seconds| seconds * 1000)
+        .unwrap_or(0);
+    let build_time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .map(|seconds| seconds * 1000)
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or(0)
+        });
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let rustc_version = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .and_then(|version| version.split_whitespace().nth(1).map(str::to_string))
+        .unwrap_or_else(|| "unknown".into());
+
+    println!("cargo:rustc-env=LAVALINK_GIT_BRANCH={branch}");
+    println!("cargo:rustc-env=LAVALINK_GIT_COMMIT={commit}");
+    println!("cargo:rustc-env=LAVALINK_GIT_COMMIT_TIME={commit_time}");
+    println!("cargo:rustc-env=LAVALINK_BUILD_TIME={build_time}");
+    println!("cargo:rustc-env=LAVALINK_RUSTC_VERSION={rustc_version}");
+}
+
+/// Run a git command in the crate directory, returning its trimmed output
+fn git(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let stdout = stdout.trim();
+    (!stdout.is_empty()).then(|| stdout.to_string())
+}
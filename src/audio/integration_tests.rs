@@ -64,6 +64,19 @@ mod audio_integration_tests {
         assert!(!bandcamp_source.is_valid_bandcamp_url("https://youtube.com/watch?v=test"));
         assert!(!bandcamp_source.is_valid_bandcamp_url("https://spotify.com/track/test"));
         assert!(!bandcamp_source.can_handle("https://soundcloud.com/test"));
+
+        // Every page the scraper loads is accepted
+        #[cfg(feature = "audio-sources")]
+        for url in [
+            "https://artist.bandcamp.com",
+            "https://artist.bandcamp.com/",
+            "https://artist.bandcamp.com/music",
+            "https://artist.bandcamp.com/releases",
+            "https://artist.bandcamp.com/releases/",
+        ] {
+            assert!(crate::audio::sources::bandcamp::classify_url(url).is_some());
+            assert!(bandcamp_source.is_valid_bandcamp_url(url), "{url}");
+        }
     }
 
     #[tokio::test]
@@ -235,7 +235,8 @@ use tokio::process::Command as AsyncCommand;
 
 use crate::config::SourcesConfig;
 use crate::protocol::{
-    Exception, LoadResult, LoadResultData, LoadType, Severity, Track, TrackInfo,
+    normalize_chapters, Chapter, Exception, LoadResult, LoadResultData, LoadType, Severity, Track,
+    TrackInfo,
 };
 
 /// Helper function to create a Track with conditional compilation for optional fields
@@ -250,6 +251,31 @@ fn create_track(encoded: String, info: TrackInfo) -> Track {
     }
 }
 
+/// Create a track from yt-dlp JSON, publishing the video's chapters if it has any
+fn create_ytdlp_track(encoded: String, info: TrackInfo, json: &serde_json::Value) -> Track {
+    let mut chapters: Vec<Chapter> = json
+        .get("chapters")
+        .and_then(|chapters| chapters.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|chapter| {
+            let start_time = chapter.get("start_time")?.as_f64()?;
+            let title = chapter.get("title").and_then(|t| t.as_str()).unwrap_or("");
+            let mut chapter_info = Chapter::new(title, (start_time * 1000.0) as u64);
+            chapter_info.end_time = chapter
+                .get("end_time")
+                .and_then(|end| end.as_f64())
+                .map(|end| (end * 1000.0) as u64);
+            Some(chapter_info)
+        })
+        .collect();
+    normalize_chapters(&mut chapters, Some(info.length));
+
+    let mut track = create_track(encoded, info);
+    track.set_chapters(&chapters);
+    track
+}
+
 /// Audio source manager for loading tracks from various sources
 #[derive(Clone)]
 pub struct AudioSourceManager {
@@ -267,6 +293,8 @@ pub enum AudioSourceType {
     Vimeo(VimeoAudioSource),
     Nico(NicoAudioSource),
     Local(LocalAudioSource),
+    Podcast(PodcastAudioSource),
+    Synth(SynthAudioSource),
     Fallback(FallbackAudioSource),
 }
 
@@ -287,8 +315,23 @@ pub trait AudioSource {
 }
 
 /// HTTP audio source for direct URLs
-#[derive(Clone)]
-pub struct HttpAudioSource;
+#[derive(Clone, Default)]
+pub struct HttpAudioSource {
+    /// Feeds only recognised by their content type are handed to the podcast source
+    #[cfg(feature = "audio-sources")]
+    podcasts: Option<std::sync::Arc<sources::PodcastClient>>,
+}
+
+impl HttpAudioSource {
+    /// Create an HTTP source that hands feeds to the given podcast source
+    #[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
+    pub fn with_podcasts(podcast: Option<&PodcastAudioSource>) -> Self {
+        Self {
+            #[cfg(feature = "audio-sources")]
+            podcasts: podcast.map(|podcast| podcast.client.clone()),
+        }
+    }
+}
 
 /// YouTube audio source (placeholder)
 #[derive(Clone)]
@@ -319,20 +362,49 @@ impl SoundCloudAudioSource {
     }
 }
 
+impl SoundCloudAudioSource {
+    /// Tracks SoundCloud recommends after the given one; needs the API client
+    async fn related(
+        &self,
+        track: &crate::protocol::Track,
+        limit: usize,
+    ) -> Result<Vec<crate::protocol::Track>> {
+        #[cfg(feature = "audio-sources")]
+        if let (Some(api_client), Some(uri)) = (&self.api_client, &track.info.uri) {
+            let sc_track = api_client.resolve_url(uri).await?;
+            let mut tracks = Vec::new();
+            for related in api_client.related_tracks(sc_track.id, limit as u32).await? {
+                tracks.push(api_client.to_lavalink_track(&related).await?);
+            }
+            return Ok(tracks);
+        }
+
+        let _ = (track, limit);
+        Err(anyhow::anyhow!(
+            "Related SoundCloud tracks need a configured API client"
+        ))
+    }
+}
+
 impl Default for SoundCloudAudioSource {
     fn default() -> Self {
         Self::new()
     }
 }
 
-/// Bandcamp audio source (placeholder)
+/// Bandcamp audio source backed by page scraping, with yt-dlp as a fallback
 #[derive(Clone)]
-pub struct BandcampAudioSource;
+pub struct BandcampAudioSource {
+    #[cfg(feature = "audio-sources")]
+    scraper: std::sync::Arc<sources::BandcampScraper>,
+}
 
 impl BandcampAudioSource {
-    #[allow(dead_code)] // Used in tests
     pub fn new() -> Self {
-        Self
+        Self {
+            #[cfg(feature = "audio-sources")]
+            scraper: std::sync::Arc::new(sources::BandcampScraper::new()),
+        }
     }
 }
 
@@ -363,9 +435,27 @@ impl Default for TwitchAudioSource {
 #[derive(Clone)]
 pub struct VimeoAudioSource;
 
-/// Niconico audio source (placeholder)
+/// Niconico audio source
 #[derive(Clone)]
-pub struct NicoAudioSource;
+pub struct NicoAudioSource {
+    #[cfg(feature = "audio-sources")]
+    client: std::sync::Arc<sources::NicoClient>,
+}
+
+impl NicoAudioSource {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "audio-sources")]
+            client: std::sync::Arc::new(sources::NicoClient::new()),
+        }
+    }
+}
+
+impl Default for NicoAudioSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// Local file audio source
 #[derive(Clone)]
@@ -377,9 +467,15 @@ pub struct LocalAudioSource {
 impl LocalAudioSource {
     #[allow(dead_code)] // Used in tests
     pub fn new() -> Self {
+        Self::with_library_config(None)
+    }
+
+    /// Create a local source indexing the configured library directories
+    #[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
+    pub fn with_library_config(config: Option<&crate::config::LocalLibraryConfig>) -> Self {
         Self {
             #[cfg(feature = "audio-sources")]
-            inner: sources::LocalAudioSource::new(),
+            inner: sources::LocalAudioSource::from_library_config(config),
         }
     }
 }
@@ -390,10 +486,68 @@ impl Default for LocalAudioSource {
     }
 }
 
+/// Podcast RSS/Atom feed source
+#[derive(Clone)]
+pub struct PodcastAudioSource {
+    #[cfg(feature = "audio-sources")]
+    client: std::sync::Arc<sources::PodcastClient>,
+}
+
+impl PodcastAudioSource {
+    #[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
+    pub fn with_config(config: Option<&crate::config::PodcastFeedsConfig>) -> Self {
+        Self {
+            #[cfg(feature = "audio-sources")]
+            client: std::sync::Arc::new(sources::PodcastClient::new(config)),
+        }
+    }
+}
+
+/// Synthetic tone and noise source (`synth:sine?freq=440`)
+#[derive(Clone)]
+pub struct SynthAudioSource;
+
 /// Fallback audio source for unsupported platforms (Spotify, Apple Music, Deezer)
-/// Converts unsupported URLs to YouTube searches
+/// Loads platform metadata and mirrors tracks to a playable source at playback time
 #[derive(Clone)]
-pub struct FallbackAudioSource;
+pub struct FallbackAudioSource {
+    #[cfg_attr(not(feature = "audio-sources"), allow(dead_code))]
+    providers: Vec<String>,
+    #[cfg(feature = "audio-sources")]
+    client: std::sync::Arc<sources::MirrorMetadataClient>,
+}
+
+impl FallbackAudioSource {
+    pub fn new() -> Self {
+        Self::with_providers(None)
+    }
+
+    /// Create a fallback source using custom mirror query templates
+    pub fn with_providers(providers: Option<Vec<String>>) -> Self {
+        let providers = providers.unwrap_or_else(default_mirror_providers);
+        Self {
+            #[cfg(feature = "audio-sources")]
+            client: std::sync::Arc::new(sources::MirrorMetadataClient::new(providers.clone())),
+            providers,
+        }
+    }
+}
+
+impl Default for FallbackAudioSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_mirror_providers() -> Vec<String> {
+    #[cfg(feature = "audio-sources")]
+    return sources::mirror::DEFAULT_MIRROR_PROVIDERS
+        .iter()
+        .map(|provider| provider.to_string())
+        .collect();
+    #[cfg(not(feature = "audio-sources"))]
+    Vec::new()
+}
 
 impl AudioSourceManager {
     /// Create a new audio source manager
@@ -406,6 +560,9 @@ impl AudioSourceManager {
         let mut sources = Vec::new();
 
         // Order matters: more specific sources should be checked first
+        let podcast = config.is_none_or(|c| c.podcast.unwrap_or(true)).then(|| {
+            PodcastAudioSource::with_config(config.and_then(|c| c.podcast_feeds.as_ref()))
+        });
         if config.is_none_or(|c| c.youtube.unwrap_or(true)) {
             sources.push(AudioSourceType::YouTube(YouTubeAudioSource));
         }
@@ -413,7 +570,7 @@ impl AudioSourceManager {
             sources.push(AudioSourceType::SoundCloud(SoundCloudAudioSource::new()));
         }
         if config.is_none_or(|c| c.bandcamp.unwrap_or(true)) {
-            sources.push(AudioSourceType::Bandcamp(BandcampAudioSource));
+            sources.push(AudioSourceType::Bandcamp(BandcampAudioSource::new()));
         }
         if config.is_none_or(|c| c.twitch.unwrap_or(true)) {
             sources.push(AudioSourceType::Twitch(TwitchAudioSource));
@@ -422,23 +579,92 @@ impl AudioSourceManager {
             sources.push(AudioSourceType::Vimeo(VimeoAudioSource));
         }
         if config.is_some_and(|c| c.nico.unwrap_or(false)) {
-            sources.push(AudioSourceType::Nico(NicoAudioSource));
+            sources.push(AudioSourceType::Nico(NicoAudioSource::new()));
+        }
+        if config.is_none_or(|c| c.synth.unwrap_or(true)) {
+            sources.push(AudioSourceType::Synth(SynthAudioSource));
         }
         if config.is_some_and(|c| c.local.unwrap_or(false)) {
-            sources.push(AudioSourceType::Local(LocalAudioSource::new()));
+            let library = config.and_then(|c| c.local_library.as_ref());
+            sources.push(AudioSourceType::Local(
+                LocalAudioSource::with_library_config(library),
+            ));
         }
 
         // Always add fallback for unsupported sources
-        sources.push(AudioSourceType::Fallback(FallbackAudioSource));
+        let mirror_providers = config
+            .and_then(|c| c.mirroring.as_ref())
+            .and_then(|m| m.providers.clone());
+        sources.push(AudioSourceType::Fallback(
+            FallbackAudioSource::with_providers(mirror_providers),
+        ));
 
         // HTTP should be last as fallback
         if config.is_none_or(|c| c.http.unwrap_or(true)) {
-            sources.push(AudioSourceType::Http(HttpAudioSource));
+            sources.push(AudioSourceType::Http(HttpAudioSource::with_podcasts(
+                podcast.as_ref(),
+            )));
+        }
+
+        // Feed URLs reach the podcast source through HTTP, which checks what
+        // they serve; it only handles them itself when HTTP is disabled
+        if let Some(podcast) = podcast {
+            sources.push(AudioSourceType::Podcast(podcast));
         }
 
         Self { sources }
     }
 
+    /// Names of the enabled sources, in the order identifiers are matched
+    pub fn source_names(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .map(|source| source.name().to_string())
+            .collect()
+    }
+
+    /// The configured local file source, if local playback is enabled
+    #[cfg(feature = "audio-sources")]
+    pub fn local_source(&self) -> Option<&sources::LocalAudioSource> {
+        self.sources.iter().find_map(|source| match source {
+            AudioSourceType::Local(local) => Some(&local.inner),
+            _ => None,
+        })
+    }
+
+    /// The configured Bandcamp scraper, if the Bandcamp source is enabled
+    #[cfg(feature = "audio-sources")]
+    pub fn bandcamp_scraper(&self) -> Option<&sources::BandcampScraper> {
+        self.sources.iter().find_map(|source| match source {
+            AudioSourceType::Bandcamp(bandcamp) => Some(bandcamp.scraper.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// The configured Niconico client, if the Niconico source is enabled
+    #[cfg(feature = "audio-sources")]
+    pub fn nico_client(&self) -> Option<&sources::NicoClient> {
+        self.sources.iter().find_map(|source| match source {
+            AudioSourceType::Nico(nico) => Some(nico.client.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Find a playable mirror for a Spotify, Apple Music or Deezer track
+    /// using the configured mirror providers and sources
+    #[cfg(feature = "audio-sources")]
+    pub async fn resolve_mirror(&self, track: &Track) -> Result<Track> {
+        let fallback = self
+            .sources
+            .iter()
+            .find_map(|source| match source {
+                AudioSourceType::Fallback(fallback) => Some(fallback),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow::anyhow!("No mirroring source is configured"))?;
+        fallback.resolve_mirror(track, self).await
+    }
+
     /// Load a track from any available source
     pub async fn load_item(&self, identifier: &str) -> Result<LoadResult> {
         // Try each source in order until one can handle the identifier
@@ -456,6 +682,78 @@ impl AudioSourceManager {
     }
 }
 
+impl AudioSourceManager {
+    /// Tracks related to the given one, used to continue autoplaying players
+    ///
+    /// YouTube tracks use the video's mix and SoundCloud tracks the related
+    /// tracks endpoint. Other sources, and those two when they come up empty,
+    /// search for more tracks by the same artist: first on the track's own
+    /// source, then on YouTube.
+    pub async fn related_tracks(
+        &self,
+        track: &crate::protocol::Track,
+        limit: usize,
+    ) -> Result<Vec<crate::protocol::Track>> {
+        let source = self
+            .sources
+            .iter()
+            .find(|source| source.name() == track.info.source_name);
+
+        let related = match source {
+            Some(AudioSourceType::YouTube(youtube)) => {
+                youtube.mix(&track.info.identifier, limit).await
+            }
+            Some(AudioSourceType::SoundCloud(soundcloud)) => soundcloud.related(track, limit).await,
+            _ => Ok(Vec::new()),
+        };
+        match related {
+            Ok(tracks) if !tracks.is_empty() => return Ok(tracks),
+            Ok(_) => {}
+            Err(e) => tracing::debug!(
+                "Falling back to an artist search for related tracks of {}: {}",
+                track.info.identifier,
+                e
+            ),
+        }
+
+        let author = track.info.author.trim();
+        if author.is_empty() || author.eq_ignore_ascii_case("unknown") {
+            return Ok(Vec::new());
+        }
+
+        let mut searches = Vec::new();
+        if let Some(source) = source {
+            searches.push(source);
+        }
+        searches.extend(
+            self.sources
+                .iter()
+                .filter(|source| matches!(source, AudioSourceType::YouTube(_))),
+        );
+        for source in searches {
+            let tracks = match source.search(author).await {
+                Ok(result) => match result.data {
+                    Some(LoadResultData::Search(tracks)) => tracks,
+                    Some(LoadResultData::Playlist(playlist)) => playlist.tracks,
+                    Some(LoadResultData::Track(track)) => vec![*track],
+                    _ => Vec::new(),
+                },
+                Err(_) => Vec::new(),
+            };
+            let tracks: Vec<_> = tracks
+                .into_iter()
+                .filter(|related| related.encoded != track.encoded)
+                .take(limit)
+                .collect();
+            if !tracks.is_empty() {
+                return Ok(tracks);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+}
+
 // Implementation for AudioSourceType enum
 #[async_trait]
 impl AudioSource for AudioSourceType {
@@ -469,6 +767,8 @@ impl AudioSource for AudioSourceType {
             AudioSourceType::Vimeo(source) => source.name(),
             AudioSourceType::Nico(source) => source.name(),
             AudioSourceType::Local(source) => source.name(),
+            AudioSourceType::Podcast(source) => source.name(),
+            AudioSourceType::Synth(source) => source.name(),
             AudioSourceType::Fallback(source) => source.name(),
         }
     }
@@ -483,6 +783,8 @@ impl AudioSource for AudioSourceType {
             AudioSourceType::Vimeo(source) => source.can_handle(identifier),
             AudioSourceType::Nico(source) => source.can_handle(identifier),
             AudioSourceType::Local(source) => source.can_handle(identifier),
+            AudioSourceType::Podcast(source) => source.can_handle(identifier),
+            AudioSourceType::Synth(source) => source.can_handle(identifier),
             AudioSourceType::Fallback(source) => source.can_handle(identifier),
         }
     }
@@ -497,6 +799,8 @@ impl AudioSource for AudioSourceType {
             AudioSourceType::Vimeo(source) => source.load_track(identifier).await,
             AudioSourceType::Nico(source) => source.load_track(identifier).await,
             AudioSourceType::Local(source) => source.load_track(identifier).await,
+            AudioSourceType::Podcast(source) => source.load_track(identifier).await,
+            AudioSourceType::Synth(source) => source.load_track(identifier).await,
             AudioSourceType::Fallback(source) => source.load_track(identifier).await,
         }
     }
@@ -511,6 +815,8 @@ impl AudioSource for AudioSourceType {
             AudioSourceType::Vimeo(source) => source.search(query).await,
             AudioSourceType::Nico(source) => source.search(query).await,
             AudioSourceType::Local(source) => source.search(query).await,
+            AudioSourceType::Podcast(source) => source.search(query).await,
+            AudioSourceType::Synth(source) => source.search(query).await,
             AudioSourceType::Fallback(source) => source.search(query).await,
         }
     }
@@ -537,6 +843,7 @@ impl AudioSource for HttpAudioSource {
             || identifier.contains("twitch.tv")
             || identifier.contains("vimeo.com")
             || identifier.contains("nicovideo.jp")
+            || identifier.contains("nico.ms")
         {
             return false;
         }
@@ -557,6 +864,14 @@ impl AudioSource for HttpAudioSource {
             });
         }
 
+        // Radio playlists are resolved to the stream they list
+        #[cfg(feature = "audio-sources")]
+        if sources::http_content_detection::playlist_format_from_url(identifier).is_some() {
+            return sources::HttpContentDetectionSource::new()
+                .load_track(identifier)
+                .await;
+        }
+
         // Create HTTP client
         let client = reqwest::Client::new();
 
@@ -572,12 +887,37 @@ impl AudioSource for HttpAudioSource {
                         .and_then(|s| s.parse::<u64>().ok())
                         .unwrap_or(0);
 
-                    let _content_type = response
+                    #[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
+                    let content_type = response
                         .headers()
                         .get("content-type")
                         .and_then(|v| v.to_str().ok())
                         .unwrap_or("audio/unknown");
 
+                    #[cfg(feature = "audio-sources")]
+                    if sources::http_content_detection::playlist_format_from_content_type(
+                        &content_type.to_lowercase(),
+                    )
+                    .is_some()
+                    {
+                        return sources::HttpContentDetectionSource::new()
+                            .load_track(identifier)
+                            .await;
+                    }
+
+                    // Feeds are recognised by their content type, or by their URL
+                    // when the server doesn't say it serves media
+                    #[cfg(feature = "audio-sources")]
+                    if let Some(podcasts) = &self.podcasts {
+                        let serves_media = content_type.starts_with("audio/")
+                            || content_type.starts_with("video/");
+                        if sources::podcast::is_feed_content_type(content_type)
+                            || (!serves_media && sources::podcast::is_feed_url(identifier))
+                        {
+                            return podcasts.load_feed(identifier).await;
+                        }
+                    }
+
                     // Create track info
                     let track_info = crate::protocol::TrackInfo {
                         identifier: identifier.to_string(),
@@ -804,6 +1144,43 @@ impl YouTubeAudioSource {
         Ok(tracks)
     }
 
+    /// Tracks of the YouTube mix generated for a video, without the video itself
+    async fn mix(&self, video_id: &str, limit: usize) -> Result<Vec<crate::protocol::Track>> {
+        if !self.is_ytdlp_available().await {
+            return Err(anyhow::anyhow!(
+                "yt-dlp is not installed or not available in PATH"
+            ));
+        }
+
+        // Mixes are playlists with the id "RD" followed by the seed video
+        let url = format!("https://www.youtube.com/watch?v={video_id}&list=RD{video_id}");
+        let output = AsyncCommand::new("yt-dlp")
+            .args([
+                "--dump-json",
+                "--yes-playlist",
+                "--flat-playlist", // Entries only, without resolving every video
+                "--playlist-items",
+                &format!("2:{}", limit + 1),
+                "--no-warnings",
+                "--ignore-errors",
+                &url,
+            ])
+            .output()
+            .await?;
+
+        if !output.status.success() && output.stdout.is_empty() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("yt-dlp failed: {}", error_msg));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .filter_map(|json| self.json_to_track(flat_entry(json)))
+            .filter(|track| track.info.identifier != video_id)
+            .collect())
+    }
+
     /// Check if yt-dlp is available in the system
     async fn is_ytdlp_available(&self) -> bool {
         AsyncCommand::new("yt-dlp")
@@ -861,10 +1238,31 @@ impl YouTubeAudioSource {
 
         let encoded = base64::engine::general_purpose::STANDARD.encode(track_data.to_string());
 
-        Some(create_track(encoded, track_info))
+        Some(create_ytdlp_track(encoded, track_info, &json))
     }
 }
 
+/// Fill in the fields `--flat-playlist` entries leave out, so they convert like full videos
+fn flat_entry(mut json: serde_json::Value) -> serde_json::Value {
+    if let Some(entry) = json.as_object_mut() {
+        if entry
+            .get("uploader")
+            .is_none_or(|uploader| uploader.is_null())
+        {
+            let channel = entry.get("channel").cloned();
+            entry.insert(
+                "uploader".to_string(),
+                channel.unwrap_or(serde_json::Value::Null),
+            );
+        }
+        entry
+            .entry("webpage_url")
+            .or_insert(serde_json::Value::Null);
+        entry.entry("duration").or_insert(serde_json::Value::Null);
+    }
+    json
+}
+
 // Placeholder implementations for other audio sources
 
 #[async_trait]
@@ -1206,7 +1604,7 @@ impl SoundCloudAudioSource {
             track_data.to_string(),
         );
 
-        Some(create_track(encoded, track_info))
+        Some(create_ytdlp_track(encoded, track_info, &json))
     }
 }
 
@@ -1240,6 +1638,13 @@ impl AudioSource for BandcampAudioSource {
             });
         }
 
+        // Scrape the page first so albums and artist pages resolve to playlists
+        #[cfg(feature = "audio-sources")]
+        match self.scraper.load_item(identifier).await {
+            Ok(result) => return Ok(result),
+            Err(e) => debug!("Bandcamp scraping failed, falling back to yt-dlp: {}", e),
+        }
+
         // Use yt-dlp to extract track information
         match self.extract_track_info(identifier).await {
             Ok(track_info) => {
@@ -1401,6 +1806,7 @@ impl BandcampAudioSource {
         let patterns = [
             r"^https?://[\w-]+\.bandcamp\.com/track/[\w-]+", // Track URL
             r"^https?://[\w-]+\.bandcamp\.com/album/[\w-]+", // Album URL
+            r"^https?://[\w-]+\.bandcamp\.com/?((music|releases)/?)?$", // Artist root page
             r"^https?://bandcamp\.com/[\w-]+",               // Artist page
         ];
 
@@ -1522,7 +1928,7 @@ impl BandcampAudioSource {
             track_data.to_string(),
         );
 
-        Some(create_track(encoded, track_info))
+        Some(create_ytdlp_track(encoded, track_info, &json))
     }
 }
 
@@ -1762,7 +2168,7 @@ impl TwitchAudioSource {
             track_data.to_string(),
         );
 
-        Some(create_track(encoded, track_info))
+        Some(create_ytdlp_track(encoded, track_info, &json))
     }
 }
 
@@ -1975,7 +2381,7 @@ impl VimeoAudioSource {
             track_data.to_string(),
         );
 
-        Some(create_track(encoded, track_info))
+        Some(create_ytdlp_track(encoded, track_info, &json))
     }
 }
 
@@ -1987,20 +2393,83 @@ impl AudioSource for NicoAudioSource {
 
     fn can_handle(&self, identifier: &str) -> bool {
         identifier.contains("nicovideo.jp")
+            || identifier.contains("nico.ms")
+            || identifier.starts_with("nicosearch:")
     }
 
-    async fn load_track(&self, _identifier: &str) -> Result<LoadResult> {
+    async fn load_track(&self, identifier: &str) -> Result<LoadResult> {
+        // Handle Niconico search queries
+        if let Some(query) = identifier.strip_prefix("nicosearch:") {
+            return self.search(query).await;
+        }
+
+        #[cfg(feature = "audio-sources")]
+        {
+            let Some(video_id) = sources::niconico::extract_video_id(identifier) else {
+                return Ok(LoadResult {
+                    load_type: LoadType::Error,
+                    data: Some(LoadResultData::Exception(Exception {
+                        message: Some("Invalid Niconico URL".to_string()),
+                        severity: Severity::Common,
+                        cause: "URL does not contain a Niconico video id".to_string(),
+                    })),
+                });
+            };
+
+            match self.client.load_video(&video_id).await {
+                Ok(track) => Ok(LoadResult {
+                    load_type: LoadType::Track,
+                    data: Some(LoadResultData::Track(Box::new(track))),
+                }),
+                Err(e) => Ok(LoadResult {
+                    load_type: LoadType::Error,
+                    data: Some(LoadResultData::Exception(Exception {
+                        message: Some(format!("Niconico extraction failed: {e}")),
+                        severity: Severity::Common,
+                        cause: e.to_string(),
+                    })),
+                }),
+            }
+        }
+
+        #[cfg(not(feature = "audio-sources"))]
         Ok(LoadResult {
             load_type: LoadType::Error,
             data: Some(LoadResultData::Exception(Exception {
-                message: Some("Nico audio source not yet implemented".to_string()),
+                message: Some("Niconico support requires the audio-sources feature".to_string()),
                 severity: Severity::Common,
-                cause: "Not implemented".to_string(),
+                cause: "Feature disabled".to_string(),
             })),
         })
     }
 
-    async fn search(&self, _query: &str) -> Result<LoadResult> {
+    async fn search(&self, query: &str) -> Result<LoadResult> {
+        info!("Searching Niconico for: {}", query);
+
+        #[cfg(feature = "audio-sources")]
+        match self.client.search(query, 10).await {
+            Ok(tracks) if tracks.is_empty() => Ok(LoadResult {
+                load_type: LoadType::Empty,
+                data: None,
+            }),
+            Ok(tracks) => Ok(LoadResult {
+                load_type: LoadType::Search,
+                data: Some(LoadResultData::Search(tracks)),
+            }),
+            Err(e) => {
+                warn!("Niconico search failed: {}", e);
+                Ok(LoadResult {
+                    load_type: LoadType::Error,
+                    data: Some(LoadResultData::Exception(Exception {
+                        message: Some(format!("Niconico search failed: {e}")),
+                        severity: Severity::Common,
+                        cause: format!("Search error: {e}"),
+                    })),
+                })
+            }
+        }
+
+        #[cfg(not(feature = "audio-sources"))]
         Ok(LoadResult {
             load_type: LoadType::Empty,
             data: None,
@@ -2150,6 +2619,135 @@ impl AudioSource for LocalAudioSource {
     }
 }
 
+#[async_trait]
+impl AudioSource for PodcastAudioSource {
+    fn name(&self) -> &str {
+        "podcast"
+    }
+
+    fn can_handle(&self, identifier: &str) -> bool {
+        #[cfg(feature = "audio-sources")]
+        {
+            identifier.starts_with(sources::podcast::PODCAST_SEARCH_PREFIX)
+                || sources::podcast::is_feed_url(identifier)
+        }
+        #[cfg(not(feature = "audio-sources"))]
+        {
+            identifier.starts_with("podcast:")
+        }
+    }
+
+    async fn load_track(&self, identifier: &str) -> Result<LoadResult> {
+        #[cfg(feature = "audio-sources")]
+        {
+            match identifier.strip_prefix(sources::podcast::PODCAST_SEARCH_PREFIX) {
+                // "podcast:<feed url>" loads the feed, anything else searches
+                Some(rest) if rest.starts_with("http://") || rest.starts_with("https://") => {
+                    self.client.load_feed(rest).await
+                }
+                Some(query) => self.search(query).await,
+                None => self.client.load_feed(identifier).await,
+            }
+        }
+
+        #[cfg(not(feature = "audio-sources"))]
+        {
+            let _ = identifier;
+            Ok(LoadResult {
+                load_type: LoadType::Error,
+                data: Some(LoadResultData::Exception(Exception {
+                    message: Some("Podcast support requires the audio-sources feature".to_string()),
+                    severity: Severity::Common,
+                    cause: "Feature disabled".to_string(),
+                })),
+            })
+        }
+    }
+
+    async fn search(&self, query: &str) -> Result<LoadResult> {
+        info!("Searching podcast feeds for: {}", query);
+
+        #[cfg(feature = "audio-sources")]
+        match self.client.search(query).await {
+            Ok(tracks) if tracks.is_empty() => Ok(LoadResult {
+                load_type: LoadType::Empty,
+                data: None,
+            }),
+            Ok(tracks) => Ok(LoadResult {
+                load_type: LoadType::Search,
+                data: Some(LoadResultData::Search(tracks)),
+            }),
+            Err(e) => {
+                warn!("Podcast search failed: {}", e);
+                Ok(LoadResult {
+                    load_type: LoadType::Error,
+                    data: Some(LoadResultData::Exception(Exception {
+                        message: Some(format!("Podcast search failed: {e}")),
+                        severity: Severity::Common,
+                        cause: format!("Search error: {e}"),
+                    })),
+                })
+            }
+        }
+
+        #[cfg(not(feature = "audio-sources"))]
+        Ok(LoadResult {
+            load_type: LoadType::Empty,
+            data: None,
+        })
+    }
+}
+
+#[async_trait]
+impl AudioSource for SynthAudioSource {
+    fn name(&self) -> &str {
+        "synth"
+    }
+
+    fn can_handle(&self, identifier: &str) -> bool {
+        identifier.starts_with("synth:")
+    }
+
+    async fn load_track(&self, identifier: &str) -> Result<LoadResult> {
+        #[cfg(feature = "audio-sources")]
+        match sources::SynthSpec::parse(identifier) {
+            Ok(spec) => Ok(LoadResult {
+                load_type: LoadType::Track,
+                data: Some(LoadResultData::Track(Box::new(spec.to_track(identifier)))),
+            }),
+            Err(e) => Ok(LoadResult {
+                load_type: LoadType::Error,
+                data: Some(LoadResultData::Exception(Exception {
+                    message: Some(format!("Invalid synth identifier: {e}")),
+                    severity: Severity::Common,
+                    cause: e.to_string(),
+                })),
+            }),
+        }
+
+        #[cfg(not(feature = "audio-sources"))]
+        {
+            let _ = identifier;
+            Ok(LoadResult {
+                load_type: LoadType::Error,
+                data: Some(LoadResultData::Exception(Exception {
+                    message: Some("Synth support requires the audio-sources feature".to_string()),
+                    severity: Severity::Common,
+                    cause: "Feature disabled".to_string(),
+                })),
+            })
+        }
+    }
+
+    async fn search(&self, _query: &str) -> Result<LoadResult> {
+        // Synthetic tracks are addressed by identifier only
+        Ok(LoadResult {
+            load_type: LoadType::Empty,
+            data: None,
+        })
+    }
+}
+
 impl Default for AudioSourceManager {
     fn default() -> Self {
         Self::new()
@@ -2271,45 +2869,31 @@ impl AudioSource for FallbackAudioSource {
             || self.is_deezer_url(identifier)
     }
 
+    #[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
     async fn load_track(&self, identifier: &str) -> Result<LoadResult> {
-        // Extract track information and convert to YouTube search
-        if let Some(search_query) = self.extract_track_info(identifier).await {
-            // Use YouTube source to search for the track
-            let youtube_source = YouTubeAudioSource;
-            let search_result = youtube_source.search(&search_query).await?;
-
-            // If we found results, return the first one as a single track
-            if let LoadResult {
-                load_type: LoadType::Search,
-                data: Some(LoadResultData::Search(tracks)),
-            } = search_result
-            {
-                if let Some(first_track) = tracks.into_iter().next() {
-                    return Ok(LoadResult {
-                        load_type: LoadType::Track,
-                        data: Some(LoadResultData::Track(Box::new(first_track))),
-                    });
-                }
-            }
-
-            // If no results found, return empty
-            Ok(LoadResult {
-                load_type: LoadType::Empty,
-                data: None,
-            })
-        } else {
-            // Could not extract track info
-            Ok(LoadResult {
+        // Tracks keep the platform metadata and are only mirrored when played
+        #[cfg(feature = "audio-sources")]
+        match self.client.load_item(identifier).await {
+            Ok(result) => Ok(result),
+            Err(e) => Ok(LoadResult {
                 load_type: LoadType::Error,
                 data: Some(LoadResultData::Exception(Exception {
-                    message: Some(
-                        "Could not extract track information from unsupported URL".to_string(),
-                    ),
+                    message: Some(format!("Failed to load track metadata: {e}")),
                     severity: Severity::Common,
                     cause: "Unsupported platform URL format".to_string(),
                 })),
-            })
+            }),
         }
+
+        #[cfg(not(feature = "audio-sources"))]
+        Ok(LoadResult {
+            load_type: LoadType::Error,
+            data: Some(LoadResultData::Exception(Exception {
+                message: Some("Mirroring requires the audio-sources feature".to_string()),
+                severity: Severity::Common,
+                cause: "Feature disabled".to_string(),
+            })),
+        })
     }
 
     async fn search(&self, _query: &str) -> Result<LoadResult> {
@@ -2337,75 +2921,70 @@ impl FallbackAudioSource {
         url.contains("deezer.com")
     }
 
-    /// Extract track information and create a search query
-    async fn extract_track_info(&self, identifier: &str) -> Option<String> {
-        if self.is_spotify_url(identifier) {
-            self.extract_spotify_info(identifier).await
-        } else if self.is_apple_music_url(identifier) {
-            self.extract_apple_music_info(identifier).await
-        } else if self.is_deezer_url(identifier) {
-            self.extract_deezer_info(identifier).await
-        } else {
-            None
-        }
-    }
-
-    /// Extract Spotify track information
-    async fn extract_spotify_info(&self, url: &str) -> Option<String> {
-        // For now, we'll use a simple approach - extract track ID and use Spotify Web API
-        // In a production environment, you'd want to use the Spotify Web API
-
-        // Extract track ID from URL
-        if let Some(track_id) = self.extract_spotify_track_id(url) {
-            // For demo purposes, we'll create a generic search query
-            // In production, you'd call Spotify API to get track details
-            tracing::info!("Converting Spotify track {} to YouTube search", track_id);
-            Some(format!("spotify track {track_id}"))
-        } else {
-            None
-        }
-    }
+    /// Find a playable mirror for a Spotify, Apple Music or Deezer track
+    ///
+    /// Each mirror query is loaded through `manager` and the best-scoring
+    /// candidate of the first query with an acceptable match is returned.
+    #[cfg(feature = "audio-sources")]
+    pub async fn resolve_mirror(
+        &self,
+        track: &crate::protocol::Track,
+        manager: &AudioSourceManager,
+    ) -> Result<crate::protocol::Track> {
+        // Queries stored at load time carry the templates configured then
+        #[cfg(feature = "plugins")]
+        let stored_queries: Option<Vec<String>> = track
+            .plugin_info
+            .get("mirrorQueries")
+            .and_then(|queries| serde_json::from_value(queries.clone()).ok());
+        #[cfg(not(feature = "plugins"))]
+        let stored_queries: Option<Vec<String>> = None;
+
+        let queries = stored_queries
+            .unwrap_or_else(|| sources::mirror::expand_queries(&self.providers, &track.info));
+
+        for query in queries {
+            let candidates = match manager.load_item(&query).await {
+                Ok(LoadResult {
+                    data: Some(LoadResultData::Search(tracks)),
+                    ..
+                }) => tracks,
+                Ok(LoadResult {
+                    data: Some(LoadResultData::Track(track)),
+                    ..
+                }) => vec![*track],
+                Ok(LoadResult {
+                    data: Some(LoadResultData::Playlist(playlist)),
+                    ..
+                }) => playlist.tracks,
+                Ok(_) => continue,
+                Err(e) => {
+                    debug!("Mirror query '{}' failed: {}", query, e);
+                    continue;
+                }
+            };
 
-    /// Extract Apple Music track information
-    async fn extract_apple_music_info(&self, url: &str) -> Option<String> {
-        // Extract track information from Apple Music URL
-        // This is a simplified implementation
-        if url.contains("music.apple.com") {
-            tracing::info!("Converting Apple Music URL to YouTube search");
-            Some("apple music track".to_string())
-        } else {
-            None
+            if let Some(mirror) = sources::mirror::pick_best(&track.info, candidates) {
+                info!(
+                    "Mirrored {} track '{}' to {} ({})",
+                    track.info.source_name,
+                    track.info.title,
+                    mirror.info.source_name,
+                    mirror
+                        .info
+                        .uri
+                        .as_deref()
+                        .unwrap_or(&mirror.info.identifier)
+                );
+                return Ok(mirror);
+            }
         }
-    }
 
-    /// Extract Deezer track information
-    async fn extract_deezer_info(&self, url: &str) -> Option<String> {
-        // Extract track information from Deezer URL
-        // This is a simplified implementation
-        if url.contains("deezer.com") {
-            tracing::info!("Converting Deezer URL to YouTube search");
-            Some("deezer track".to_string())
-        } else {
-            None
-        }
-    }
-
-    /// Extract Spotify track ID from URL
-    fn extract_spotify_track_id(&self, url: &str) -> Option<String> {
-        // Handle different Spotify URL formats
-        if url.starts_with("spotify:track:") {
-            // spotify:track:4iV5W9uYEdYUVa79Axb7Rh
-            url.strip_prefix("spotify:track:").map(|s| s.to_string())
-        } else if url.contains("spotify.com/track/") {
-            // https://open.spotify.com/track/4iV5W9uYEdYUVa79Axb7Rh
-            url.split("/track/")
-                .nth(1)?
-                .split('?')
-                .next()
-                .map(|s| s.to_string())
-        } else {
-            None
-        }
+        Err(anyhow::anyhow!(
+            "No mirror found for {} - {}",
+            track.info.author,
+            track.info.title
+        ))
     }
 }
 
@@ -9,10 +9,23 @@ use reqwest::Client;
 use scraper::{Html, Selector};
 use serde_json::Value;
 use std::time::Duration;
-use tracing::debug;
+use tracing::{debug, warn};
 use url::Url;
 
-use crate::protocol::{Track, TrackInfo};
+use crate::protocol::{
+    LoadResult, LoadResultData, LoadType, Playlist, PlaylistInfo, Track, TrackInfo,
+};
+
+/// Upper bound on releases fetched when loading an artist's discography
+const MAX_DISCOGRAPHY_RELEASES: usize = 50;
+
+/// Kind of page a Bandcamp URL points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandcampPage {
+    Track,
+    Album,
+    Artist,
+}
 
 /// Bandcamp web scraper for track and album information
 pub struct BandcampScraper {
@@ -33,11 +46,124 @@ impl BandcampScraper {
         Self { client }
     }
 
+    /// Load a Bandcamp URL, returning a playlist for album and artist pages
+    pub async fn load_item(&self, url: &str) -> Result<LoadResult> {
+        match classify_url(url) {
+            Some(BandcampPage::Track) => {
+                let track = self.load_track(url).await?;
+                Ok(LoadResult {
+                    load_type: LoadType::Track,
+                    data: Some(LoadResultData::Track(Box::new(track))),
+                })
+            }
+            Some(BandcampPage::Album) => self.load_album(url).await,
+            Some(BandcampPage::Artist) => self.load_discography(url).await,
+            None => Err(anyhow!("Unsupported Bandcamp URL: {}", url)),
+        }
+    }
+
     /// Load a track from a Bandcamp URL
     pub async fn load_track(&self, url: &str) -> Result<Track> {
         debug!("Loading Bandcamp track: {}", url);
 
-        // Fetch the page
+        let html = self.fetch_page(url).await?;
+
+        // Prefer the embedded tralbum data since it carries the stream URL
+        if let Some(tralbum) = parse_tralbum(&html) {
+            if let Some(track) = tracks_from_tralbum(&tralbum, url).into_iter().next() {
+                return Ok(track);
+            }
+        }
+
+        let document = Html::parse_document(&html);
+        self.extract_track_from_html(&document, url)
+    }
+
+    /// Load an album page as a playlist
+    pub async fn load_album(&self, url: &str) -> Result<LoadResult> {
+        debug!("Loading Bandcamp album: {}", url);
+
+        let html = self.fetch_page(url).await?;
+        let tralbum =
+            parse_tralbum(&html).ok_or_else(|| anyhow!("No album data found on Bandcamp page"))?;
+
+        Ok(playlist_from_tralbum(&tralbum, url))
+    }
+
+    /// Load every release listed on an artist page as a single playlist
+    pub async fn load_discography(&self, url: &str) -> Result<LoadResult> {
+        debug!("Loading Bandcamp discography: {}", url);
+
+        let html = self.fetch_page(url).await?;
+
+        // Artists with a single release have it rendered directly on the root page
+        if let Some(tralbum) = parse_tralbum(&html) {
+            return Ok(playlist_from_tralbum(&tralbum, url));
+        }
+
+        let (artist, release_urls) = {
+            let document = Html::parse_document(&html);
+            let artist = self
+                .extract_artist(&document)
+                .unwrap_or_else(|| "Unknown Artist".to_string());
+            (artist, extract_discography_urls(&document, url))
+        };
+
+        let mut tracks = Vec::new();
+        for release_url in release_urls.iter().take(MAX_DISCOGRAPHY_RELEASES) {
+            let release_html = match self.fetch_page(release_url).await {
+                Ok(html) => html,
+                Err(e) => {
+                    warn!("Skipping Bandcamp release {}: {}", release_url, e);
+                    continue;
+                }
+            };
+
+            if let Some(tralbum) = parse_tralbum(&release_html) {
+                tracks.extend(tracks_from_tralbum(&tralbum, release_url));
+            }
+        }
+
+        if tracks.is_empty() {
+            return Ok(LoadResult {
+                load_type: LoadType::Empty,
+                data: None,
+            });
+        }
+
+        Ok(LoadResult {
+            load_type: LoadType::Playlist,
+            data: Some(LoadResultData::Playlist(Playlist {
+                info: PlaylistInfo {
+                    name: artist,
+                    selected_track: None,
+                },
+                #[cfg(feature = "plugins")]
+                plugin_info: std::collections::HashMap::from([
+                    ("type".to_string(), Value::from("artist")),
+                    ("url".to_string(), Value::from(url)),
+                    ("totalReleases".to_string(), Value::from(release_urls.len())),
+                ]),
+                tracks,
+            })),
+        })
+    }
+
+    /// Look up the current stream URL of a track page
+    ///
+    /// Stream URLs carry a token that expires, so they are resolved when a
+    /// track is played instead of being stored with it.
+    pub async fn stream_url(&self, track_url: &str) -> Result<String> {
+        let html = self.fetch_page(track_url).await?;
+        let tralbum = parse_tralbum(&html)
+            .ok_or_else(|| anyhow!("No track data on Bandcamp page: {}", track_url))?;
+
+        stream_url_from_tralbum(&tralbum, track_url)
+            .ok_or_else(|| anyhow!("Bandcamp track has no stream: {}", track_url))
+    }
+
+    /// Fetch the HTML of a Bandcamp page
+    async fn fetch_page(&self, url: &str) -> Result<String> {
         let response = self.client.get(url).send().await?;
 
         if !response.status().is_success() {
@@ -47,11 +173,7 @@ impl BandcampScraper {
             ));
         }
 
-        let html = response.text().await?;
-        let document = Html::parse_document(&html);
-
-        // Extract track data from the page
-        self.extract_track_from_html(&document, url).await
+        Ok(response.text().await?)
     }
 
     /// Search for tracks on Bandcamp
@@ -72,11 +194,10 @@ impl BandcampScraper {
         let document = Html::parse_document(&html);
 
         self.extract_search_results(&document, limit.unwrap_or(20))
-            .await
     }
 
     /// Extract track information from Bandcamp page HTML
-    async fn extract_track_from_html(&self, document: &Html, page_url: &str) -> Result<Track> {
+    fn extract_track_from_html(&self, document: &Html, page_url: &str) -> Result<Track> {
         // Try to find the track data in the page's JavaScript
         let script_selector = Selector::parse("script[type='application/ld+json']").unwrap();
 
@@ -93,7 +214,6 @@ impl BandcampScraper {
 
         // Fallback to parsing HTML elements
         self.extract_track_from_html_elements(document, page_url)
-            .await
     }
 
     /// Parse track from JSON-LD structured data
@@ -145,11 +265,7 @@ impl BandcampScraper {
     }
 
     /// Extract track from HTML elements (fallback method)
-    async fn extract_track_from_html_elements(
-        &self,
-        document: &Html,
-        page_url: &str,
-    ) -> Result<Track> {
+    fn extract_track_from_html_elements(&self, document: &Html, page_url: &str) -> Result<Track> {
         // Try to extract title from meta tags or page title
         let title = self
             .extract_title(document)
@@ -184,7 +300,7 @@ impl BandcampScraper {
     }
 
     /// Extract search results from Bandcamp search page
-    async fn extract_search_results(&self, document: &Html, limit: u32) -> Result<Vec<Track>> {
+    fn extract_search_results(&self, document: &Html, limit: u32) -> Result<Vec<Track>> {
         let mut tracks = Vec::new();
 
         // Bandcamp search results are in .searchresult elements
@@ -345,6 +461,240 @@ impl Default for BandcampScraper {
     }
 }
 
+/// Classify a Bandcamp URL by the kind of page it points at
+pub fn classify_url(url: &str) -> Option<BandcampPage> {
+    let parsed_url = Url::parse(url).ok()?;
+    let host = parsed_url.host_str()?;
+
+    // Only artist subdomains host tracks, albums and discographies
+    if !host.ends_with(".bandcamp.com") || host == "www.bandcamp.com" {
+        return None;
+    }
+
+    let mut segments = parsed_url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter();
+
+    match (segments.next(), segments.next()) {
+        (Some("track"), Some(_)) => Some(BandcampPage::Track),
+        (Some("album"), Some(_)) => Some(BandcampPage::Album),
+        (None, _) | (Some("music"), None) | (Some("releases"), None) => Some(BandcampPage::Artist),
+        _ => None,
+    }
+}
+
+/// Parse the `data-tralbum` JSON embedded in track and album pages
+pub fn parse_tralbum(html: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("[data-tralbum]").unwrap();
+
+    // Attribute values are entity-decoded by the HTML parser
+    document
+        .select(&selector)
+        .filter_map(|element| element.value().attr("data-tralbum"))
+        .find_map(|raw| serde_json::from_str::<Value>(raw).ok())
+}
+
+/// Build tracks from tralbum data, skipping entries without a stream
+pub fn tracks_from_tralbum(tralbum: &Value, page_url: &str) -> Vec<Track> {
+    let base_url = tralbum["url"]
+        .as_str()
+        .and_then(|url| Url::parse(url).ok())
+        .or_else(|| Url::parse(page_url).ok());
+    let album_artist = tralbum["artist"].as_str().unwrap_or("Unknown Artist");
+    #[cfg(feature = "plugins")]
+    let album_name = match tralbum["item_type"].as_str() {
+        Some("album") => tralbum["current"]["title"].as_str(),
+        _ => None,
+    };
+    let artwork_url = tralbum["art_id"]
+        .as_u64()
+        .or_else(|| tralbum["current"]["art_id"].as_u64())
+        .map(artwork_url_for);
+
+    let Some(entries) = tralbum["trackinfo"].as_array() else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            // Stream URLs expire, so only whether there is one is kept
+            entry["file"]["mp3-128"].as_str()?;
+            let title = entry["title"].as_str().unwrap_or("Unknown Title");
+            let author = entry["artist"].as_str().unwrap_or(album_artist);
+            let length = entry["duration"]
+                .as_f64()
+                .map(|seconds| (seconds * 1000.0) as u64)
+                .unwrap_or(0);
+
+            let track_url = entry["title_link"]
+                .as_str()
+                .and_then(|link| base_url.as_ref()?.join(link).ok())
+                .map(|url| url.to_string())
+                .unwrap_or_else(|| page_url.to_string());
+
+            #[cfg(feature = "plugins")]
+            let plugin_info = album_name
+                .map(|album_name| ("albumName".to_string(), Value::from(album_name)))
+                .into_iter()
+                .collect();
+
+            let mut track = Track {
+                encoded: String::new(),
+                info: TrackInfo {
+                    identifier: track_url.clone(),
+                    is_seekable: true,
+                    author: author.to_string(),
+                    length,
+                    is_stream: false,
+                    position: 0,
+                    title: title.to_string(),
+                    uri: Some(track_url),
+                    artwork_url: artwork_url.clone(),
+                    isrc: None,
+                    source_name: "bandcamp".to_string(),
+                },
+                #[cfg(feature = "plugins")]
+                plugin_info,
+                #[cfg(feature = "rest-api")]
+                user_data: std::collections::HashMap::new(),
+            };
+
+            // Encode the whole track so its plugin info survives a decode round-trip
+            track.encoded = track.encode();
+            Some(track)
+        })
+        .collect()
+}
+
+/// Find the stream URL of `track_url` in tralbum data
+///
+/// Album pages list every track; a track page's only entry is taken as is.
+pub fn stream_url_from_tralbum(tralbum: &Value, track_url: &str) -> Option<String> {
+    let base_url = tralbum["url"]
+        .as_str()
+        .and_then(|url| Url::parse(url).ok())
+        .or_else(|| Url::parse(track_url).ok());
+    let entries = tralbum["trackinfo"].as_array()?;
+
+    let entry = entries
+        .iter()
+        .find(|entry| {
+            entry["title_link"]
+                .as_str()
+                .and_then(|link| base_url.as_ref()?.join(link).ok())
+                .is_some_and(|url| url.as_str() == track_url)
+        })
+        .or(match entries.as_slice() {
+            [entry] => Some(entry),
+            _ => None,
+        })?;
+
+    entry["file"]["mp3-128"].as_str().map(str::to_string)
+}
+
+/// Build a playlist load result from album tralbum data
+pub fn playlist_from_tralbum(tralbum: &Value, page_url: &str) -> LoadResult {
+    let tracks = tracks_from_tralbum(tralbum, page_url);
+    if tracks.is_empty() {
+        return LoadResult {
+            load_type: LoadType::Empty,
+            data: None,
+        };
+    }
+
+    let name = tralbum["current"]["title"]
+        .as_str()
+        .unwrap_or("Unknown Album")
+        .to_string();
+
+    LoadResult {
+        load_type: LoadType::Playlist,
+        data: Some(LoadResultData::Playlist(Playlist {
+            info: PlaylistInfo {
+                name,
+                selected_track: None,
+            },
+            #[cfg(feature = "plugins")]
+            plugin_info: std::collections::HashMap::from([
+                ("type".to_string(), Value::from("album")),
+                (
+                    "url".to_string(),
+                    Value::from(tralbum["url"].as_str().unwrap_or(page_url)),
+                ),
+                ("author".to_string(), tralbum["artist"].clone()),
+                (
+                    "artworkUrl".to_string(),
+                    tracks[0]
+                        .info
+                        .artwork_url
+                        .clone()
+                        .map(Value::from)
+                        .unwrap_or(Value::Null),
+                ),
+            ]),
+            tracks,
+        })),
+    }
+}
+
+/// Collect album and track URLs from an artist's music grid
+pub fn extract_discography_urls(document: &Html, page_url: &str) -> Vec<String> {
+    let Ok(base_url) = Url::parse(page_url) else {
+        return Vec::new();
+    };
+
+    let link_selector = Selector::parse("#music-grid li a[href]").unwrap();
+    let grid_selector = Selector::parse("#music-grid[data-client-items]").unwrap();
+
+    let mut links: Vec<String> = document
+        .select(&link_selector)
+        .filter_map(|element| element.value().attr("href"))
+        .map(str::to_string)
+        .collect();
+
+    // Releases beyond the first page of the grid are only listed in client items
+    if let Some(grid) = document.select(&grid_selector).next() {
+        if let Some(items) = grid
+            .value()
+            .attr("data-client-items")
+            .and_then(|raw| serde_json::from_str::<Vec<Value>>(raw).ok())
+        {
+            links.extend(
+                items
+                    .iter()
+                    .filter_map(|item| item["page_url"].as_str())
+                    .map(str::to_string),
+            );
+        }
+    }
+
+    let mut urls = Vec::new();
+    for link in links {
+        let Ok(url) = base_url.join(&link) else {
+            continue;
+        };
+        let url = url.to_string();
+        if matches!(
+            classify_url(&url),
+            Some(BandcampPage::Album | BandcampPage::Track)
+        ) && !urls.contains(&url)
+        {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+/// Build the full-size artwork URL for a Bandcamp art id
+fn artwork_url_for(art_id: u64) -> String {
+    format!("https://f4.bcbits.com/img/a{art_id:010}_10.jpg")
+}
+
 /// Validate if a URL is a valid Bandcamp URL
 #[allow(dead_code)]
 pub fn is_valid_bandcamp_url(url: &str) -> bool {
@@ -375,6 +725,138 @@ mod tests {
         assert!(!is_valid_bandcamp_url("not-a-url"));
     }
 
+    const ALBUM_PAGE: &str = include_str!("../../../tests/fixtures/bandcamp/album.html");
+    const TRACK_PAGE: &str = include_str!("../../../tests/fixtures/bandcamp/track.html");
+    const ARTIST_PAGE: &str = include_str!("../../../tests/fixtures/bandcamp/artist.html");
+
+    #[test]
+    fn test_bandcamp_page_classification() {
+        assert_eq!(
+            classify_url("https://artist.bandcamp.com/track/song"),
+            Some(BandcampPage::Track)
+        );
+        assert_eq!(
+            classify_url("https://artist.bandcamp.com/album/album-name"),
+            Some(BandcampPage::Album)
+        );
+        assert_eq!(
+            classify_url("https://artist.bandcamp.com/"),
+            Some(BandcampPage::Artist)
+        );
+        assert_eq!(
+            classify_url("https://artist.bandcamp.com/music"),
+            Some(BandcampPage::Artist)
+        );
+        assert_eq!(
+            classify_url("https://artist.bandcamp.com/releases"),
+            Some(BandcampPage::Artist)
+        );
+        assert_eq!(classify_url("https://bandcamp.com/search?q=test"), None);
+        assert_eq!(classify_url("https://artist.bandcamp.com/merch"), None);
+    }
+
+    #[test]
+    fn test_album_fixture_builds_playlist() {
+        let tralbum = parse_tralbum(ALBUM_PAGE).expect("album page has tralbum data");
+        let url = "https://example-ensemble.bandcamp.com/album/signals-noise";
+        let result = playlist_from_tralbum(&tralbum, url);
+
+        assert!(matches!(result.load_type, LoadType::Playlist));
+        let Some(LoadResultData::Playlist(playlist)) = result.data else {
+            panic!("expected playlist data");
+        };
+
+        assert_eq!(playlist.info.name, "Signals & Noise");
+        // The purchase-only track has no stream and is skipped
+        assert_eq!(playlist.tracks.len(), 2);
+
+        let first = &playlist.tracks[0];
+        assert_eq!(first.info.title, "Opening Static");
+        assert_eq!(first.info.author, "The Example Ensemble");
+        assert_eq!(first.info.length, 201_372);
+        assert_eq!(
+            first.info.uri.as_deref(),
+            Some("https://example-ensemble.bandcamp.com/track/opening-static")
+        );
+        assert_eq!(
+            first.info.artwork_url.as_deref(),
+            Some("https://f4.bcbits.com/img/a1234567890_10.jpg")
+        );
+
+        let second = &playlist.tracks[1];
+        assert_eq!(second.info.author, "Guest Vocalist");
+        assert_eq!(second.info.length, 187_000);
+
+        #[cfg(feature = "plugins")]
+        {
+            // The expiring stream URL is resolved at play time instead
+            assert!(!first.plugin_info.contains_key("streamUrl"));
+            assert_eq!(first.plugin_info["albumName"], "Signals & Noise");
+            assert_eq!(playlist.plugin_info["type"], "album");
+        }
+    }
+
+    #[test]
+    fn test_track_fixture_builds_single_track() {
+        let tralbum = parse_tralbum(TRACK_PAGE).expect("track page has tralbum data");
+        let url = "https://example-ensemble.bandcamp.com/track/carrier-wave";
+        let tracks = tracks_from_tralbum(&tralbum, url);
+
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].info.title, "Carrier Wave");
+        assert_eq!(tracks[0].info.identifier, url);
+        assert_eq!(tracks[0].info.length, 187_000);
+
+        // The encoded form decodes back to the same track
+        let decoded = Track::decode(&tracks[0].encoded).expect("encoded track decodes");
+        assert_eq!(decoded.encoded, tracks[0].encoded);
+        assert_eq!(decoded.info, tracks[0].info);
+        #[cfg(feature = "plugins")]
+        assert_eq!(decoded.plugin_info, tracks[0].plugin_info);
+    }
+
+    #[test]
+    fn test_stream_url_is_found_by_track_page() {
+        let album = parse_tralbum(ALBUM_PAGE).expect("album page has tralbum data");
+        assert_eq!(
+            stream_url_from_tralbum(
+                &album,
+                "https://example-ensemble.bandcamp.com/track/opening-static"
+            )
+            .as_deref(),
+            Some("https://t4.bcbits.com/stream/aaaa/mp3-128/1111111111?p=0&ts=1700000000&t=abc")
+        );
+        assert_eq!(
+            stream_url_from_tralbum(
+                &album,
+                "https://example-ensemble.bandcamp.com/track/not-on-this-album"
+            ),
+            None
+        );
+
+        let track = parse_tralbum(TRACK_PAGE).expect("track page has tralbum data");
+        let url = "https://example-ensemble.bandcamp.com/track/carrier-wave";
+        assert!(stream_url_from_tralbum(&track, url).is_some());
+    }
+
+    #[test]
+    fn test_artist_fixture_lists_releases() {
+        assert!(parse_tralbum(ARTIST_PAGE).is_none());
+
+        let document = Html::parse_document(ARTIST_PAGE);
+        let urls =
+            extract_discography_urls(&document, "https://example-ensemble.bandcamp.com/music");
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://example-ensemble.bandcamp.com/album/signals-noise",
+                "https://example-ensemble.bandcamp.com/track/lone-single",
+                "https://example-ensemble.bandcamp.com/album/early-recordings",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_bandcamp_scraper_creation() {
         let _scraper = BandcampScraper::new();
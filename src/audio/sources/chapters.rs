@@ -0,0 +1,578 @@
+//! Chapter metadata embedded in audio containers
+//!
+//! Symphonia does not expose chapters, so this module walks the container
+//! headers itself: Nero `chpl` boxes in MP4/M4A/M4B files, the `Chapters`
+//! element of Matroska/WebM files and `CHAPTERxxx` Vorbis comments in Ogg
+//! (Vorbis and Opus) and FLAC files. Only metadata is read, never audio.
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::protocol::Chapter;
+
+/// Largest metadata box, element or packet that is read into memory
+const MAX_METADATA_SIZE: u64 = 16 * 1024 * 1024;
+/// Ogg pages read while looking for the comment header
+const MAX_OGG_PAGES: usize = 64;
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const MKV_SEGMENT: u32 = 0x1853_8067;
+const MKV_CHAPTERS: u32 = 0x1043_A770;
+const MKV_EDITION_ENTRY: u32 = 0x45B9;
+const MKV_CHAPTER_ATOM: u32 = 0xB6;
+const MKV_CHAPTER_TIME_START: u32 = 0x91;
+const MKV_CHAPTER_TIME_END: u32 = 0x92;
+const MKV_CHAPTER_FLAG_HIDDEN: u32 = 0x98;
+const MKV_CHAPTER_DISPLAY: u32 = 0x80;
+const MKV_CHAP_STRING: u32 = 0x85;
+
+/// Read the chapters embedded in an audio file, unsorted and without end
+/// times filled in
+///
+/// Files in other formats, or without chapters, have none.
+pub fn read_chapters(path: &Path) -> Result<Vec<Chapter>> {
+    read_chapters_from(&mut BufReader::new(File::open(path)?))
+}
+
+/// Read the chapters embedded in a seekable audio stream
+pub fn read_chapters_from<R: Read + Seek>(reader: &mut R) -> Result<Vec<Chapter>> {
+    let mut magic = [0u8; 8];
+    let read = read_up_to(reader, &mut magic)?;
+    reader.seek(SeekFrom::Start(0))?;
+    let magic = &magic[..read];
+
+    if magic.starts_with(b"OggS") {
+        ogg_chapters(reader)
+    } else if magic.starts_with(b"fLaC") {
+        flac_chapters(reader)
+    } else if magic.starts_with(&EBML_HEADER.to_be_bytes()) {
+        matroska_chapters(reader)
+    } else if magic.get(4..8) == Some(b"ftyp".as_slice()) {
+        mp4_chapters(reader)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+fn read_body<R: Read + Seek>(reader: &mut R, start: u64, end: u64) -> Result<Vec<u8>> {
+    let length = end.saturating_sub(start);
+    if length > MAX_METADATA_SIZE {
+        return Err(anyhow!("Metadata block of {} bytes is too large", length));
+    }
+    reader.seek(SeekFrom::Start(start))?;
+    let mut body = vec![0u8; length as usize];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+// MP4
+
+fn mp4_chapters<R: Read + Seek>(reader: &mut R) -> Result<Vec<Chapter>> {
+    let mut range = (0, reader.seek(SeekFrom::End(0))?);
+    for kind in [b"moov", b"udta", b"chpl"] {
+        match find_mp4_box(reader, range, kind)? {
+            Some(found) => range = found,
+            None => return Ok(Vec::new()),
+        }
+    }
+    parse_chpl(&read_body(reader, range.0, range.1)?)
+}
+
+/// Body range of the first box of the given type among the boxes in `range`
+fn find_mp4_box<R: Read + Seek>(
+    reader: &mut R,
+    (mut position, end): (u64, u64),
+    kind: &[u8; 4],
+) -> Result<Option<(u64, u64)>> {
+    while position + 8 <= end {
+        reader.seek(SeekFrom::Start(position))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+
+        let (header_length, size) = match u32::from_be_bytes(header[..4].try_into()?) {
+            // The box extends to the end of its parent
+            0 => (8, end - position),
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large)?;
+                (16, u64::from_be_bytes(large))
+            }
+            size => (8, size as u64),
+        };
+        if size < header_length || position + size > end {
+            return Err(anyhow!("Malformed MP4 box at offset {}", position));
+        }
+        if &header[4..8] == kind {
+            return Ok(Some((position + header_length, position + size)));
+        }
+        position += size;
+    }
+    Ok(None)
+}
+
+/// Parse a Nero chapter list, whose start times are in 100 ns units
+fn parse_chpl(body: &[u8]) -> Result<Vec<Chapter>> {
+    let malformed = || anyhow!("Malformed chpl box");
+    let version = *body.first().ok_or_else(malformed)?;
+    let mut position = if version > 0 { 8 } else { 4 };
+    let count = *body.get(position).ok_or_else(malformed)?;
+    position += 1;
+
+    let mut chapters = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let start = body.get(position..position + 8).ok_or_else(malformed)?;
+        let start = u64::from_be_bytes(start.try_into()?);
+        let title_length = *body.get(position + 8).ok_or_else(malformed)? as usize;
+        position += 9;
+        let title = body
+            .get(position..position + title_length)
+            .ok_or_else(malformed)?;
+        position += title_length;
+
+        chapters.push(Chapter::new(
+            String::from_utf8_lossy(title).trim(),
+            start / 10_000,
+        ));
+    }
+    Ok(chapters)
+}
+
+// Matroska
+
+fn matroska_chapters<R: Read + Seek>(reader: &mut R) -> Result<Vec<Chapter>> {
+    let file_end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    // Skip the EBML header, then look through the segment's top-level elements
+    let (id, size) = read_ebml_header(reader)?;
+    if id != EBML_HEADER {
+        return Err(anyhow!("Not a Matroska file"));
+    }
+    let position = reader.stream_position()? + size.ok_or_else(|| anyhow!("Bad EBML header"))?;
+    reader.seek(SeekFrom::Start(position))?;
+
+    let (id, size) = read_ebml_header(reader)?;
+    if id != MKV_SEGMENT {
+        return Err(anyhow!("Matroska segment not found"));
+    }
+    let mut position = reader.stream_position()?;
+    let segment_end = size.map_or(file_end, |size| (position + size).min(file_end));
+
+    while position < segment_end {
+        reader.seek(SeekFrom::Start(position))?;
+        let (id, size) = read_ebml_header(reader)?;
+        let body_start = reader.stream_position()?;
+        // Elements of unknown size (live clusters) cannot be skipped
+        let Some(size) = size else {
+            break;
+        };
+        if id == MKV_CHAPTERS {
+            let body = read_body(reader, body_start, body_start + size)?;
+            return Ok(parse_mkv_chapters(&body));
+        }
+        position = body_start + size;
+    }
+    Ok(Vec::new())
+}
+
+/// Read an element ID and size; `None` for elements of unknown size
+fn read_ebml_header<R: Read>(reader: &mut R) -> Result<(u32, Option<u64>)> {
+    let (id, _) = read_vint(reader, true)?;
+    let (size, length) = read_vint(reader, false)?;
+    let unknown = size == (1u64 << (7 * length)) - 1;
+    Ok((id as u32, (!unknown).then_some(size)))
+}
+
+fn read_vint<R: Read>(reader: &mut R, keep_marker: bool) -> Result<(u64, usize)> {
+    let mut first = [0u8; 1];
+    reader.read_exact(&mut first)?;
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > 8 {
+        return Err(anyhow!("Invalid EBML variable-length integer"));
+    }
+
+    let mut value = first[0] as u64;
+    if !keep_marker {
+        value &= 0xFF >> length;
+    }
+    let mut rest = [0u8; 7];
+    reader.read_exact(&mut rest[..length - 1])?;
+    for byte in &rest[..length - 1] {
+        value = (value << 8) | *byte as u64;
+    }
+    Ok((value, length))
+}
+
+/// Children of an in-memory master element; stops at the first malformed one
+fn ebml_children(mut data: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut children = Vec::new();
+    while !data.is_empty() {
+        let mut cursor = data;
+        let Ok((id, Some(size))) = read_ebml_header(&mut cursor) else {
+            break;
+        };
+        let header_length = data.len() - cursor.len();
+        let Some(body) = usize::try_from(size)
+            .ok()
+            .and_then(|size| cursor.get(..size))
+        else {
+            break;
+        };
+        children.push((id, body));
+        data = &data[header_length + body.len()..];
+    }
+    children
+}
+
+fn ebml_uint(data: &[u8]) -> u64 {
+    data.iter()
+        .take(8)
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+/// Top-level chapters of the first edition, with times converted from ns
+fn parse_mkv_chapters(body: &[u8]) -> Vec<Chapter> {
+    let Some((_, edition)) = ebml_children(body)
+        .into_iter()
+        .find(|(id, _)| *id == MKV_EDITION_ENTRY)
+    else {
+        return Vec::new();
+    };
+
+    ebml_children(edition)
+        .into_iter()
+        .filter(|(id, _)| *id == MKV_CHAPTER_ATOM)
+        .filter_map(|(_, atom)| {
+            let mut chapter = Chapter::new(String::new(), 0);
+            let mut has_start = false;
+            for (id, data) in ebml_children(atom) {
+                match id {
+                    MKV_CHAPTER_TIME_START => {
+                        chapter.start_time = ebml_uint(data) / 1_000_000;
+                        has_start = true;
+                    }
+                    MKV_CHAPTER_TIME_END => chapter.end_time = Some(ebml_uint(data) / 1_000_000),
+                    MKV_CHAPTER_FLAG_HIDDEN if ebml_uint(data) != 0 => return None,
+                    MKV_CHAPTER_DISPLAY if chapter.title.is_empty() => {
+                        if let Some((_, title)) = ebml_children(data)
+                            .into_iter()
+                            .find(|(id, _)| *id == MKV_CHAP_STRING)
+                        {
+                            chapter.title = String::from_utf8_lossy(title)
+                                .trim_end_matches('\0')
+                                .trim()
+                                .to_string();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            has_start.then_some(chapter)
+        })
+        .collect()
+}
+
+// Ogg and FLAC
+
+fn ogg_chapters<R: Read + Seek>(reader: &mut R) -> Result<Vec<Chapter>> {
+    let mut serial = None;
+    let mut packets: Vec<Vec<u8>> = Vec::new();
+    let mut packet = Vec::new();
+
+    for _ in 0..MAX_OGG_PAGES {
+        let mut header = [0u8; 27];
+        if read_up_to(reader, &mut header)? < header.len() || &header[..4] != b"OggS" {
+            break;
+        }
+        let mut lacing = vec![0u8; header[26] as usize];
+        reader.read_exact(&mut lacing)?;
+        let mut data = vec![0u8; lacing.iter().map(|&l| l as usize).sum()];
+        reader.read_exact(&mut data)?;
+
+        // Only the first logical stream is of interest
+        let page_serial = u32::from_le_bytes(header[14..18].try_into()?);
+        if *serial.get_or_insert(page_serial) != page_serial {
+            continue;
+        }
+
+        let mut offset = 0;
+        for &length in &lacing {
+            packet.extend_from_slice(&data[offset..offset + length as usize]);
+            offset += length as usize;
+            if packet.len() as u64 > MAX_METADATA_SIZE {
+                return Err(anyhow!("Ogg header packet is too large"));
+            }
+            if length < 255 {
+                packets.push(std::mem::take(&mut packet));
+            }
+        }
+
+        // The comment header is the second packet of Vorbis and Opus streams
+        if let Some(comments) = packets.get(1) {
+            let comments = comments
+                .strip_prefix(b"\x03vorbis")
+                .or_else(|| comments.strip_prefix(b"OpusTags"));
+            return Ok(comments
+                .map(|comments| comment_chapters(&parse_vorbis_comments(comments)))
+                .unwrap_or_default());
+        }
+    }
+    Ok(Vec::new())
+}
+
+fn flac_chapters<R: Read + Seek>(reader: &mut R) -> Result<Vec<Chapter>> {
+    let mut position = 4;
+    loop {
+        reader.seek(SeekFrom::Start(position))?;
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as u64;
+        let body_start = position + 4;
+
+        // Block type 4 is VORBIS_COMMENT
+        if header[0] & 0x7F == 4 {
+            let body = read_body(reader, body_start, body_start + length)?;
+            return Ok(comment_chapters(&parse_vorbis_comments(&body)));
+        }
+        if header[0] & 0x80 != 0 {
+            return Ok(Vec::new());
+        }
+        position = body_start + length;
+    }
+}
+
+/// Parse a Vorbis comment block into `(KEY, value)` pairs with upper-case keys
+fn parse_vorbis_comments(data: &[u8]) -> Vec<(String, String)> {
+    fn read_u32(data: &[u8], position: &mut usize) -> Option<usize> {
+        let bytes = data.get(*position..*position + 4)?;
+        *position += 4;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    }
+
+    let mut position = 0;
+    let mut comments = Vec::new();
+    let Some(vendor_length) = read_u32(data, &mut position) else {
+        return comments;
+    };
+    position += vendor_length;
+    let Some(count) = read_u32(data, &mut position) else {
+        return comments;
+    };
+
+    for _ in 0..count {
+        let Some(length) = read_u32(data, &mut position) else {
+            break;
+        };
+        let Some(comment) = data.get(position..position + length) else {
+            break;
+        };
+        position += length;
+        if let Some((key, value)) = String::from_utf8_lossy(comment).split_once('=') {
+            comments.push((key.to_ascii_uppercase(), value.to_string()));
+        }
+    }
+    comments
+}
+
+/// Chapters from `CHAPTER001=00:00:00.000` and `CHAPTER001NAME=Intro` comments
+fn comment_chapters(comments: &[(String, String)]) -> Vec<Chapter> {
+    let mut chapters: BTreeMap<u32, Chapter> = BTreeMap::new();
+    let mut starts: BTreeMap<u32, u64> = BTreeMap::new();
+
+    for (key, value) in comments {
+        let Some(rest) = key.strip_prefix("CHAPTER") else {
+            continue;
+        };
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let Ok(number) = rest[..digits].parse::<u32>() else {
+            continue;
+        };
+        let chapter = chapters
+            .entry(number)
+            .or_insert_with(|| Chapter::new(String::new(), 0));
+        match &rest[digits..] {
+            "" => {
+                if let Some(start) = parse_timestamp(value) {
+                    starts.insert(number, start);
+                }
+            }
+            "NAME" => chapter.title = value.trim().to_string(),
+            "URL" => chapter.url = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    chapters
+        .into_iter()
+        .filter_map(|(number, mut chapter)| {
+            chapter.start_time = *starts.get(&number)?;
+            Some(chapter)
+        })
+        .collect()
+}
+
+/// Parse `HH:MM:SS.mmm` (hours and minutes optional) into milliseconds
+fn parse_timestamp(text: &str) -> Option<u64> {
+    let mut seconds = 0.0;
+    for part in text.trim().split(':') {
+        let value: f64 = part.trim().parse().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        seconds = seconds * 60.0 + value;
+    }
+    Some((seconds * 1000.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn ebml(id: u32, body: &[u8]) -> Vec<u8> {
+        let mut data: Vec<u8> = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        // Eight byte sizes keep the test independent of body lengths
+        data.push(0x01);
+        data.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn vorbis_comments(comments: &[&str]) -> Vec<u8> {
+        let mut data = 4u32.to_le_bytes().to_vec();
+        data.extend_from_slice(b"test");
+        data.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+        for comment in comments {
+            data.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            data.extend_from_slice(comment.as_bytes());
+        }
+        data
+    }
+
+    fn titles(chapters: &[Chapter]) -> Vec<(&str, u64)> {
+        chapters
+            .iter()
+            .map(|chapter| (chapter.title.as_str(), chapter.start_time))
+            .collect()
+    }
+
+    #[test]
+    fn test_mp4_nero_chapters() {
+        let mut chpl = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+        for (start, title) in [(0u64, "Intro"), (905_000_000, "Part One")] {
+            chpl.extend_from_slice(&start.to_be_bytes());
+            chpl.push(title.len() as u8);
+            chpl.extend_from_slice(title.as_bytes());
+        }
+
+        let mut file = mp4_box(b"ftyp", b"M4B \0\0\0\0");
+        let mut moov = mp4_box(b"mvhd", &[0; 16]);
+        moov.extend(mp4_box(b"udta", &mp4_box(b"chpl", &chpl)));
+        file.extend(mp4_box(b"moov", &moov));
+        file.extend(mp4_box(b"mdat", &[0; 32]));
+
+        let chapters = read_chapters_from(&mut Cursor::new(file)).unwrap();
+        assert_eq!(titles(&chapters), vec![("Intro", 0), ("Part One", 90_500)]);
+
+        let plain = mp4_box(b"ftyp", b"M4A \0\0\0\0");
+        assert!(read_chapters_from(&mut Cursor::new(plain))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_matroska_chapters() {
+        let atom = |start_ms: u64, title: &str, hidden: bool| {
+            let mut body = ebml(
+                MKV_CHAPTER_TIME_START,
+                &(start_ms * 1_000_000).to_be_bytes(),
+            );
+            body.extend(ebml(MKV_CHAPTER_FLAG_HIDDEN, &[hidden as u8]));
+            body.extend(ebml(
+                MKV_CHAPTER_DISPLAY,
+                &ebml(MKV_CHAP_STRING, title.as_bytes()),
+            ));
+            ebml(MKV_CHAPTER_ATOM, &body)
+        };
+        let mut edition = atom(0, "Opening", false);
+        edition.extend(atom(1_000, "Hidden", true));
+        edition.extend(atom(60_000, "Verse", false));
+
+        let mut segment = ebml(0x1549_A966, &[0; 4]);
+        segment.extend(ebml(MKV_CHAPTERS, &ebml(MKV_EDITION_ENTRY, &edition)));
+        let mut file = ebml(EBML_HEADER, &ebml(0x4282, b"webm"));
+        file.extend(ebml(MKV_SEGMENT, &segment));
+
+        let chapters = read_chapters_from(&mut Cursor::new(file)).unwrap();
+        assert_eq!(titles(&chapters), vec![("Opening", 0), ("Verse", 60_000)]);
+    }
+
+    #[test]
+    fn test_ogg_and_flac_comment_chapters() {
+        let comments = vorbis_comments(&[
+            "TITLE=Audiobook",
+            "CHAPTER002=00:01:30.250",
+            "chapter002name=Second",
+            "CHAPTER001=00:00:00.000",
+            "CHAPTER001NAME=First",
+        ]);
+
+        // Identification and comment packets, the latter spanning two pages
+        let mut comment_packet = b"\x03vorbis".to_vec();
+        comment_packet.extend_from_slice(&comments);
+        comment_packet.resize(300, 0);
+        let ogg_page = |sequence: u32, lacing: &[u8], data: &[u8]| {
+            let mut page = b"OggS\0\0".to_vec();
+            page.extend_from_slice(&[0; 8]);
+            page.extend_from_slice(&7u32.to_le_bytes());
+            page.extend_from_slice(&sequence.to_le_bytes());
+            page.extend_from_slice(&[0; 4]);
+            page.push(lacing.len() as u8);
+            page.extend_from_slice(lacing);
+            page.extend_from_slice(data);
+            page
+        };
+        let mut first = b"\x01vorbis".to_vec();
+        first.extend_from_slice(&comment_packet[..255]);
+        let mut file = ogg_page(0, &[7, 255], &first);
+        file.extend(ogg_page(1, &[45], &comment_packet[255..]));
+
+        let chapters = read_chapters_from(&mut Cursor::new(file)).unwrap();
+        assert_eq!(titles(&chapters), vec![("First", 0), ("Second", 90_250)]);
+
+        let mut flac = b"fLaC".to_vec();
+        flac.extend_from_slice(&[0, 0, 0, 34]);
+        flac.extend_from_slice(&[0; 34]);
+        flac.push(0x84);
+        flac.extend_from_slice(&(comments.len() as u32).to_be_bytes()[1..]);
+        flac.extend_from_slice(&comments);
+
+        let chapters = read_chapters_from(&mut Cursor::new(flac)).unwrap();
+        assert_eq!(titles(&chapters), vec![("First", 0), ("Second", 90_250)]);
+    }
+}
@@ -0,0 +1,455 @@
+//! HLS playlist parsing and segment fetching
+//!
+//! Master playlists are resolved to a single rendition (an audio-only one
+//! when available) and media playlists are polled for new segments, so live
+//! radio streams keep playing as the server publishes them. Segments are
+//! played through a [`LiveStream`].
+
+use anyhow::{anyhow, Result};
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use url::Url;
+
+use super::live::LiveStream;
+
+/// Segments buffered ahead of the decoder
+const SEGMENT_BUFFER: usize = 4;
+
+/// Segments from the end of a live playlist to start playback at
+const LIVE_START_SEGMENTS: usize = 3;
+
+/// Consecutive failures tolerated before the stream is ended
+const MAX_FETCH_FAILURES: u32 = 3;
+
+/// A parsed HLS playlist
+#[derive(Debug, Clone, PartialEq)]
+pub enum HlsPlaylist {
+    Master(HlsMasterPlaylist),
+    Media(HlsMediaPlaylist),
+}
+
+/// Variant streams and renditions listed by a master playlist
+#[cfg_attr(not(feature = "discord"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HlsMasterPlaylist {
+    pub variants: Vec<HlsVariant>,
+    /// `EXT-X-MEDIA` audio renditions as `(uri, is_default)`
+    pub audio_renditions: Vec<(String, bool)>,
+}
+
+/// A variant stream from a master playlist
+#[cfg_attr(not(feature = "discord"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsVariant {
+    pub uri: String,
+    pub bandwidth: u64,
+    pub codecs: Option<String>,
+}
+
+/// Segments listed by a media playlist
+#[cfg_attr(not(feature = "discord"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HlsMediaPlaylist {
+    pub target_duration: f64,
+    pub media_sequence: u64,
+    pub segments: Vec<String>,
+    /// Whether `EXT-X-ENDLIST` was present, i.e. the playlist is not live
+    pub ended: bool,
+    /// Whether segments are encrypted with `EXT-X-KEY`
+    pub encrypted: bool,
+}
+
+/// Check whether a playlist body is an HLS playlist rather than a plain M3U
+pub fn is_hls_playlist(body: &str) -> bool {
+    body.trim_start().starts_with("#EXTM3U") && body.contains("#EXT-X-")
+}
+
+/// Parse an HLS playlist, resolving URIs against the playlist URL
+pub fn parse_playlist(body: &str, base: &Url) -> Result<HlsPlaylist> {
+    if !body.trim_start().starts_with("#EXTM3U") {
+        return Err(anyhow!("Not an HLS playlist"));
+    }
+
+    let mut master = HlsMasterPlaylist::default();
+    let mut media = HlsMediaPlaylist {
+        target_duration: 10.0,
+        ..Default::default()
+    };
+    let mut is_master = false;
+    let mut pending_variant: Option<(u64, Option<String>)> = None;
+
+    for line in body.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            is_master = true;
+            let attributes = parse_attributes(attributes);
+            let bandwidth = attributes
+                .get("BANDWIDTH")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0);
+            pending_variant = Some((bandwidth, attributes.get("CODECS").cloned()));
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+            is_master = true;
+            let attributes = parse_attributes(attributes);
+            if attributes.get("TYPE").map(String::as_str) == Some("AUDIO") {
+                if let Some(uri) = attributes.get("URI").and_then(|uri| base.join(uri).ok()) {
+                    let is_default = attributes.get("DEFAULT").map(String::as_str) == Some("YES");
+                    master.audio_renditions.push((uri.to_string(), is_default));
+                }
+            }
+        } else if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            media.target_duration = value.parse().unwrap_or(media.target_duration);
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            media.media_sequence = value.parse().unwrap_or(0);
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-KEY:") {
+            let method = parse_attributes(attributes).remove("METHOD");
+            media.encrypted |= method.as_deref().is_some_and(|method| method != "NONE");
+        } else if line == "#EXT-X-ENDLIST" {
+            media.ended = true;
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Ok(uri) = base.join(line) {
+            match pending_variant.take() {
+                Some((bandwidth, codecs)) => master.variants.push(HlsVariant {
+                    uri: uri.to_string(),
+                    bandwidth,
+                    codecs,
+                }),
+                None => media.segments.push(uri.to_string()),
+            }
+        }
+    }
+
+    Ok(if is_master {
+        HlsPlaylist::Master(master)
+    } else {
+        HlsPlaylist::Media(media)
+    })
+}
+
+/// Pick the media playlist to play from a master playlist
+///
+/// Audio renditions are preferred, then the best audio-only variant, then the
+/// lowest-bandwidth variant since any video is discarded anyway.
+pub fn select_media_playlist(master: &HlsMasterPlaylist) -> Option<String> {
+    if let Some((uri, _)) = master
+        .audio_renditions
+        .iter()
+        .find(|(_, is_default)| *is_default)
+        .or_else(|| master.audio_renditions.first())
+    {
+        return Some(uri.clone());
+    }
+
+    let is_audio_only = |variant: &&HlsVariant| {
+        variant.codecs.as_deref().is_some_and(|codecs| {
+            codecs
+                .split(',')
+                .map(str::trim)
+                .all(|codec| codec.starts_with("mp4a") || codec == "opus" || codec == "flac")
+        })
+    };
+
+    master
+        .variants
+        .iter()
+        .filter(is_audio_only)
+        .max_by_key(|variant| variant.bandwidth)
+        .or_else(|| {
+            master
+                .variants
+                .iter()
+                .min_by_key(|variant| variant.bandwidth)
+        })
+        .map(|variant| variant.uri.clone())
+}
+
+fn parse_attributes(attributes: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut rest = attributes;
+
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().to_string();
+        rest = &rest[eq + 1..];
+
+        // Quoted values may contain commas
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let value = &quoted[..end];
+            rest = quoted.get(end + 1..).unwrap_or("");
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+
+        result.insert(key, value.to_string());
+        rest = rest.trim_start_matches(',');
+    }
+
+    result
+}
+
+/// First segment of a freshly loaded playlist to play
+///
+/// Live playback starts near the edge instead of replaying the whole window.
+fn live_start(playlist: &HlsMediaPlaylist) -> usize {
+    if playlist.ended {
+        0
+    } else {
+        playlist.segments.len().saturating_sub(LIVE_START_SEGMENTS)
+    }
+}
+
+/// Sequence number of the next segment to fetch from a refreshed playlist
+///
+/// Segments that expired before they were fetched are skipped. A media
+/// sequence that went backwards, or a position past the end of the window,
+/// means the server restarted its numbering, so playback picks up at the
+/// live edge again.
+fn resume_sequence(next_sequence: u64, previous_sequence: u64, playlist: &HlsMediaPlaylist) -> u64 {
+    let window_end = playlist.media_sequence + playlist.segments.len() as u64;
+    if playlist.media_sequence < previous_sequence || next_sequence > window_end {
+        playlist.media_sequence + live_start(playlist) as u64
+    } else {
+        next_sequence.max(playlist.media_sequence)
+    }
+}
+
+/// Resolve an HLS URL to its media playlist and start fetching segments
+///
+/// Encrypted playlists are rejected so callers can fall back to a player
+/// that handles decryption.
+pub async fn open_stream(client: Client, url: &str, headers: HeaderMap) -> Result<LiveStream> {
+    let mut media_url = url.to_string();
+    let mut playlist = fetch_playlist(&client, &media_url, &headers).await?;
+
+    if let HlsPlaylist::Master(master) = &playlist {
+        media_url = select_media_playlist(master)
+            .ok_or_else(|| anyhow!("HLS master playlist has no playable variants"))?;
+        debug!("Selected HLS media playlist {}", media_url);
+        playlist = fetch_playlist(&client, &media_url, &headers).await?;
+    }
+
+    let HlsPlaylist::Media(media) = playlist else {
+        return Err(anyhow!("HLS variant is not a media playlist"));
+    };
+    if media.encrypted {
+        return Err(anyhow!("Encrypted HLS playlists are not supported"));
+    }
+
+    let (sender, receiver) = mpsc::channel(SEGMENT_BUFFER);
+    let fetcher = tokio::spawn(run_segment_fetcher(
+        client, media_url, headers, media, sender,
+    ));
+    Ok(LiveStream::new(receiver, fetcher))
+}
+
+async fn fetch_playlist(client: &Client, url: &str, headers: &HeaderMap) -> Result<HlsPlaylist> {
+    let response = client.get(url).headers(headers.clone()).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP {} fetching {}", response.status(), url));
+    }
+    let body = response.text().await?;
+    parse_playlist(&body, &Url::parse(url)?)
+}
+
+async fn fetch_segment(client: &Client, url: &str, headers: &HeaderMap) -> Result<Vec<u8>> {
+    let response = client.get(url).headers(headers.clone()).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP {} fetching {}", response.status(), url));
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+async fn run_segment_fetcher(
+    client: Client,
+    media_url: String,
+    headers: HeaderMap,
+    mut playlist: HlsMediaPlaylist,
+    sender: mpsc::Sender<Vec<u8>>,
+) {
+    let mut next_sequence = playlist.media_sequence + live_start(&playlist) as u64;
+    let mut failures = 0;
+
+    loop {
+        let start = (next_sequence - playlist.media_sequence) as usize;
+        let new_segments = playlist.segments.get(start..).unwrap_or_default().to_vec();
+        for segment_url in &new_segments {
+            match fetch_segment(&client, segment_url, &headers).await {
+                Ok(bytes) => {
+                    failures = 0;
+                    if sender.send(bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to fetch HLS segment {}: {}", segment_url, e);
+                    failures += 1;
+                    if failures >= MAX_FETCH_FAILURES {
+                        return;
+                    }
+                }
+            }
+            next_sequence += 1;
+        }
+
+        if playlist.ended {
+            return;
+        }
+
+        // Poll at half the target duration when the playlist hasn't moved on
+        let wait = if new_segments.is_empty() {
+            playlist.target_duration / 2.0
+        } else {
+            playlist.target_duration
+        };
+        tokio::time::sleep(Duration::from_secs_f64(wait.max(0.5))).await;
+
+        match fetch_playlist(&client, &media_url, &headers).await {
+            Ok(HlsPlaylist::Media(refreshed)) => {
+                failures = 0;
+                let resumed = resume_sequence(next_sequence, playlist.media_sequence, &refreshed);
+                if resumed != next_sequence {
+                    warn!(
+                        "HLS stream {} jumped from segment {} to {}",
+                        media_url, next_sequence, resumed
+                    );
+                }
+                next_sequence = resumed;
+                playlist = refreshed;
+            }
+            Ok(HlsPlaylist::Master(_)) => {
+                warn!(
+                    "HLS media playlist {} turned into a master playlist",
+                    media_url
+                );
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to refresh HLS playlist {}: {}", media_url, e);
+                failures += 1;
+                if failures >= MAX_FETCH_FAILURES {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_master_playlist() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Main, English\",DEFAULT=YES,URI=\"audio/main.m3u8\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f,mp4a.40.2\",AUDIO=\"aac\"\n\
+            video/720.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS=\"mp4a.40.2\"\n\
+            audio/64k.m3u8\n";
+        let base = Url::parse("https://radio.example/live/master.m3u8").unwrap();
+
+        let HlsPlaylist::Master(master) = parse_playlist(body, &base).unwrap() else {
+            panic!("expected a master playlist");
+        };
+        assert_eq!(master.variants.len(), 2);
+        assert_eq!(master.variants[0].bandwidth, 1_280_000);
+        assert_eq!(
+            master.audio_renditions,
+            vec![(
+                "https://radio.example/live/audio/main.m3u8".to_string(),
+                true
+            )]
+        );
+        assert_eq!(
+            select_media_playlist(&master).as_deref(),
+            Some("https://radio.example/live/audio/main.m3u8")
+        );
+
+        let without_renditions = HlsMasterPlaylist {
+            audio_renditions: Vec::new(),
+            ..master
+        };
+        assert_eq!(
+            select_media_playlist(&without_renditions).as_deref(),
+            Some("https://radio.example/live/audio/64k.m3u8")
+        );
+    }
+
+    #[test]
+    fn test_parse_live_media_playlist() {
+        let body = "#EXTM3U\n\
+            #EXT-X-VERSION:3\n\
+            #EXT-X-TARGETDURATION:6\n\
+            #EXT-X-MEDIA-SEQUENCE:1042\n\
+            #EXTINF:6.0,\n\
+            seg1042.aac\n\
+            #EXTINF:6.0,\n\
+            https://cdn.radio.example/seg1043.aac\n";
+        let base = Url::parse("https://radio.example/live/audio.m3u8").unwrap();
+
+        assert!(is_hls_playlist(body));
+        assert_eq!(
+            parse_playlist(body, &base).unwrap(),
+            HlsPlaylist::Media(HlsMediaPlaylist {
+                target_duration: 6.0,
+                media_sequence: 1042,
+                segments: vec![
+                    "https://radio.example/live/seg1042.aac".to_string(),
+                    "https://cdn.radio.example/seg1043.aac".to_string(),
+                ],
+                ended: false,
+                encrypted: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_detects_encrypted_and_ended_playlists() {
+        let body = "#EXTM3U\n\
+            #EXT-X-TARGETDURATION:10\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\n\
+            #EXTINF:10.0,\n\
+            seg0.ts\n\
+            #EXT-X-ENDLIST\n";
+        let base = Url::parse("https://vod.example/a.m3u8").unwrap();
+
+        let HlsPlaylist::Media(media) = parse_playlist(body, &base).unwrap() else {
+            panic!("expected a media playlist");
+        };
+        assert!(media.encrypted);
+        assert!(media.ended);
+        assert!(!is_hls_playlist(
+            "#EXTM3U\n#EXTINF:-1,Radio\nhttp://radio.example/stream\n"
+        ));
+    }
+
+    #[test]
+    fn test_resume_sequence_follows_the_live_window() {
+        let playlist = |media_sequence: u64, segments: usize| HlsMediaPlaylist {
+            target_duration: 6.0,
+            media_sequence,
+            segments: vec!["seg.aac".to_string(); segments],
+            ended: false,
+            encrypted: false,
+        };
+
+        // Live playback starts three segments from the edge
+        assert_eq!(live_start(&playlist(100, 10)), 7);
+
+        // The window moved on, continue where we left off
+        assert_eq!(resume_sequence(110, 100, &playlist(102, 10)), 110);
+        // Segments expired before they were fetched
+        assert_eq!(resume_sequence(101, 100, &playlist(105, 10)), 105);
+        // The server restarted its numbering
+        assert_eq!(resume_sequence(110, 100, &playlist(0, 5)), 2);
+        assert_eq!(resume_sequence(110, 100, &playlist(100, 4)), 101);
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! This module provides an HTTP audio source that can detect
 //! audio content types, extract metadata, and validate streams.
+//! Internet radio playlists (M3U, PLS and HLS) are followed to the stream
+//! they list.
 
 use anyhow::{anyhow, Result};
 use base64::Engine;
@@ -10,10 +12,30 @@ use std::time::Duration;
 use tracing::debug;
 use url::Url;
 
+use super::hls;
 use crate::protocol::{
     Exception, LoadResult, LoadResultData, LoadType, Severity, Track, TrackInfo,
 };
 
+/// Maximum number of nested radio playlists to follow
+const MAX_PLAYLIST_DEPTH: usize = 3;
+
+/// Playlist formats used to distribute internet radio streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadioPlaylistFormat {
+    /// M3U or extended M3U; `.m3u8` files may turn out to be HLS
+    M3u,
+    /// Shoutcast/Winamp PLS
+    Pls,
+}
+
+/// A stream entry listed in a radio playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RadioPlaylistEntry {
+    pub url: String,
+    pub title: Option<String>,
+}
+
 /// HTTP audio source with content detection and metadata extraction
 pub struct HttpContentDetectionSource {
     #[allow(dead_code)]
@@ -61,15 +83,12 @@ impl HttpContentDetectionSource {
             });
         }
 
-        // Perform HEAD request to check content type and availability
-        match self.validate_audio_url(url).await {
-            Ok(metadata) => {
-                let track = self.create_track_from_metadata(url, metadata).await?;
-                Ok(LoadResult {
-                    load_type: LoadType::Track,
-                    data: Some(LoadResultData::Track(Box::new(track))),
-                })
-            }
+        // Follow radio playlists and check content type and availability
+        match self.resolve_track(url).await {
+            Ok(track) => Ok(LoadResult {
+                load_type: LoadType::Track,
+                data: Some(LoadResultData::Track(Box::new(track))),
+            }),
             Err(e) => Ok(LoadResult {
                 load_type: LoadType::Error,
                 data: Some(LoadResultData::Exception(Exception {
@@ -81,6 +100,63 @@ impl HttpContentDetectionSource {
         }
     }
 
+    /// Resolve a URL to a playable track, following radio playlists to the
+    /// first stream they list
+    async fn resolve_track(&self, url: &str) -> Result<Track> {
+        let mut current = url.to_string();
+        let mut entry_title: Option<String> = None;
+
+        for _ in 0..=MAX_PLAYLIST_DEPTH {
+            let format = match playlist_format_from_url(&current) {
+                Some(format) => format,
+                None => {
+                    let mut metadata = self.validate_audio_url(&current).await?;
+                    match playlist_format_from_content_type(&metadata.content_type) {
+                        Some(format) => format,
+                        None => {
+                            if metadata.station_name.is_none() {
+                                if let Some(title) = entry_title {
+                                    metadata.title = title;
+                                }
+                            }
+                            return self.create_track_from_metadata(&current, metadata).await;
+                        }
+                    }
+                }
+            };
+
+            let body = self.fetch_playlist(&current).await?;
+            if format == RadioPlaylistFormat::M3u && hls::is_hls_playlist(&body) {
+                return Ok(self.create_hls_track(&current, &body, entry_title));
+            }
+
+            let base = Url::parse(&current)?;
+            let entries = match format {
+                RadioPlaylistFormat::M3u => parse_m3u(&body, &base),
+                RadioPlaylistFormat::Pls => parse_pls(&body, &base),
+            };
+            let entry = entries
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("Playlist {} lists no streams", current))?;
+
+            debug!("Following radio playlist {} to {}", current, entry.url);
+            entry_title = entry.title.or(entry_title);
+            current = entry.url;
+        }
+
+        Err(anyhow!("Radio playlists are nested too deeply"))
+    }
+
+    /// Download a playlist file
+    async fn fetch_playlist(&self, url: &str) -> Result<String> {
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP request failed: {}", response.status()));
+        }
+        Ok(response.text().await?)
+    }
+
     /// Validate if URL is a valid HTTP/HTTPS URL
     fn is_valid_http_url(&self, url: &str) -> bool {
         url.starts_with("http://") || url.starts_with("https://")
@@ -141,15 +217,31 @@ impl HttpContentDetectionSource {
             }
         }
 
-        // Extract filename from URL for title
-        let filename = self.extract_filename_from_url(url);
-        let title = self.clean_filename_for_title(&filename);
+        // Radio servers announce the station name in ICY headers
+        let station_name = head_response
+            .headers()
+            .get("icy-name")
+            .and_then(|name| name.to_str().ok())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+        let is_icy = station_name.is_some()
+            || head_response
+                .headers()
+                .contains_key(super::icy::ICY_METAINT_HEADER);
+
+        // Fall back to the filename from the URL for the title
+        let title = station_name.clone().unwrap_or_else(|| {
+            let filename = self.extract_filename_from_url(url);
+            self.clean_filename_for_title(&filename)
+        });
 
         Ok(AudioMetadata {
             title,
             content_type: content_type.clone(),
             content_length,
-            is_stream: self.is_likely_stream(&content_type, content_length),
+            is_stream: is_icy || self.is_likely_stream(&content_type, content_length),
+            station_name,
         })
     }
 
@@ -303,6 +395,128 @@ impl HttpContentDetectionSource {
 
         Ok(track)
     }
+
+    /// Create a track for an HLS playlist
+    fn create_hls_track(&self, url: &str, body: &str, title: Option<String>) -> Track {
+        // Master playlists don't say whether they're live, so assume they are
+        let is_stream = match Url::parse(url).map(|base| hls::parse_playlist(body, &base)) {
+            Ok(Ok(hls::HlsPlaylist::Media(media))) => !media.ended,
+            _ => true,
+        };
+        let title = title.unwrap_or_else(|| {
+            let filename = self.extract_filename_from_url(url);
+            self.clean_filename_for_title(&filename)
+        });
+
+        let mut track = Track {
+            encoded: String::new(),
+            info: TrackInfo {
+                identifier: url.to_string(),
+                is_seekable: false,
+                author: "Unknown Artist".to_string(),
+                length: 0,
+                is_stream,
+                position: 0,
+                title,
+                uri: Some(url.to_string()),
+                artwork_url: None,
+                isrc: None,
+                source_name: "http".to_string(),
+            },
+            #[cfg(feature = "plugins")]
+            plugin_info: std::collections::HashMap::from([(
+                "hls".to_string(),
+                serde_json::Value::from(true),
+            )]),
+            #[cfg(feature = "rest-api")]
+            user_data: std::collections::HashMap::new(),
+        };
+
+        // Encode the whole track so the HLS flag survives a decode round-trip
+        track.encoded = track.encode();
+        track
+    }
+}
+
+/// Detect a radio playlist from the URL's file extension
+pub fn playlist_format_from_url(url: &str) -> Option<RadioPlaylistFormat> {
+    let path = Url::parse(url).ok()?.path().to_lowercase();
+    if path.ends_with(".m3u") || path.ends_with(".m3u8") {
+        Some(RadioPlaylistFormat::M3u)
+    } else if path.ends_with(".pls") {
+        Some(RadioPlaylistFormat::Pls)
+    } else {
+        None
+    }
+}
+
+/// Detect a radio playlist from a response content type
+pub fn playlist_format_from_content_type(content_type: &str) -> Option<RadioPlaylistFormat> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    match mime {
+        "audio/x-scpls" | "audio/scpls" | "application/pls+xml" => Some(RadioPlaylistFormat::Pls),
+        "audio/mpegurl"
+        | "audio/x-mpegurl"
+        | "application/x-mpegurl"
+        | "application/vnd.apple.mpegurl" => Some(RadioPlaylistFormat::M3u),
+        _ => None,
+    }
+}
+
+/// Parse the stream entries of an M3U playlist
+pub fn parse_m3u(body: &str, base: &Url) -> Vec<RadioPlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut title = None;
+
+    for line in body.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // "#EXTINF:-1,Station Name"
+            title = info
+                .split_once(',')
+                .map(|(_, name)| name.trim().to_string())
+                .filter(|name| !name.is_empty());
+        } else if !line.starts_with('#') {
+            if let Ok(url) = base.join(line) {
+                entries.push(RadioPlaylistEntry {
+                    url: url.to_string(),
+                    title: title.take(),
+                });
+            }
+        }
+    }
+
+    entries
+}
+
+/// Parse the stream entries of a PLS playlist, in entry order
+pub fn parse_pls(body: &str, base: &Url) -> Vec<RadioPlaylistEntry> {
+    let mut files = std::collections::BTreeMap::new();
+    let mut titles = std::collections::HashMap::new();
+
+    for line in body.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        if let Some(index) = key.strip_prefix("file").and_then(|i| i.parse::<u32>().ok()) {
+            files.insert(index, value.trim().to_string());
+        } else if let Some(index) = key
+            .strip_prefix("title")
+            .and_then(|i| i.parse::<u32>().ok())
+        {
+            titles.insert(index, value.trim().to_string());
+        }
+    }
+
+    files
+        .into_iter()
+        .filter_map(|(index, file)| {
+            Some(RadioPlaylistEntry {
+                url: base.join(&file).ok()?.to_string(),
+                title: titles.remove(&index).filter(|title| !title.is_empty()),
+            })
+        })
+        .collect()
 }
 
 impl Default for HttpContentDetectionSource {
@@ -321,6 +535,8 @@ struct AudioMetadata {
     #[allow(dead_code)]
     content_length: Option<u64>,
     is_stream: bool,
+    /// Station name from the `icy-name` header
+    station_name: Option<String>,
 }
 
 #[cfg(test)]
@@ -395,4 +611,62 @@ mod tests {
         // Not audio
         assert!(!source.has_audio_signature(&[0x89, 0x50, 0x4E, 0x47])); // PNG
     }
+
+    #[test]
+    fn test_radio_playlist_detection() {
+        assert_eq!(
+            playlist_format_from_url("https://radio.example/listen.PLS"),
+            Some(RadioPlaylistFormat::Pls)
+        );
+        assert_eq!(
+            playlist_format_from_url("https://radio.example/live.m3u8?token=abc"),
+            Some(RadioPlaylistFormat::M3u)
+        );
+        assert_eq!(
+            playlist_format_from_url("https://radio.example/stream.mp3"),
+            None
+        );
+        assert_eq!(
+            playlist_format_from_content_type("audio/x-scpls; charset=utf-8"),
+            Some(RadioPlaylistFormat::Pls)
+        );
+        assert_eq!(
+            playlist_format_from_content_type("application/vnd.apple.mpegurl"),
+            Some(RadioPlaylistFormat::M3u)
+        );
+        assert_eq!(playlist_format_from_content_type("audio/mpeg"), None);
+    }
+
+    #[test]
+    fn test_parse_m3u() {
+        let body =
+            "#EXTM3U\n#EXTINF:-1,Example FM\nhttp://stream.radio.example:8000/live\n\nbackup.mp3\n";
+        let base = Url::parse("https://radio.example/playlists/listen.m3u").unwrap();
+
+        assert_eq!(
+            parse_m3u(body, &base),
+            vec![
+                RadioPlaylistEntry {
+                    url: "http://stream.radio.example:8000/live".to_string(),
+                    title: Some("Example FM".to_string()),
+                },
+                RadioPlaylistEntry {
+                    url: "https://radio.example/playlists/backup.mp3".to_string(),
+                    title: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pls() {
+        let body = "[playlist]\nNumberOfEntries=2\nFile2=http://backup.radio.example/live\nTitle2=Backup\nFile1=http://stream.radio.example/live\nTitle1=Example FM\nLength1=-1\nVersion=2\n";
+        let base = Url::parse("https://radio.example/listen.pls").unwrap();
+
+        let entries = parse_pls(body, &base);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].url, "http://stream.radio.example/live");
+        assert_eq!(entries[0].title.as_deref(), Some("Example FM"));
+        assert_eq!(entries[1].title.as_deref(), Some("Backup"));
+    }
 }
@@ -0,0 +1,299 @@
+//! Shoutcast/Icecast (ICY) in-band metadata support
+//!
+//! Radio servers interleave metadata blocks with the audio when a client asks
+//! for them with `Icy-MetaData: 1`. The `icy-metaint` response header gives
+//! the number of audio bytes between blocks; each block starts with a length
+//! byte (in units of 16 bytes) followed by text such as
+//! `StreamTitle='Artist - Song';StreamUrl='';`.
+
+use anyhow::{anyhow, Result};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Response};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Header a client sends to request in-band metadata
+pub const ICY_METADATA_HEADER: &str = "Icy-MetaData";
+
+/// Response header carrying the metadata interval
+pub const ICY_METAINT_HEADER: &str = "icy-metaint";
+
+/// Chunks of audio buffered ahead of the decoder
+#[cfg(feature = "discord")]
+const STREAM_BUFFER: usize = 16;
+
+/// A `StreamTitle` change announced by a radio stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcyStreamTitle {
+    pub title: String,
+    pub url: Option<String>,
+}
+
+/// Incremental parser that separates metadata blocks from stream audio
+#[derive(Debug)]
+pub struct IcyMetadataParser {
+    metaint: usize,
+    audio_remaining: usize,
+    metadata_remaining: Option<usize>,
+    metadata: Vec<u8>,
+}
+
+impl IcyMetadataParser {
+    /// Create a parser for a stream with the given `icy-metaint`
+    pub fn new(metaint: usize) -> Self {
+        Self {
+            metaint,
+            audio_remaining: metaint,
+            metadata_remaining: None,
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Feed a chunk of the response body, returning any complete metadata blocks
+    ///
+    /// The audio in between is appended to `audio`. Empty blocks, which
+    /// servers send when nothing changed, are skipped.
+    pub fn feed(&mut self, mut chunk: &[u8], audio: &mut Vec<u8>) -> Vec<String> {
+        let mut blocks = Vec::new();
+
+        while !chunk.is_empty() {
+            match self.metadata_remaining {
+                None if self.audio_remaining > 0 => {
+                    let take = self.audio_remaining.min(chunk.len());
+                    audio.extend_from_slice(&chunk[..take]);
+                    self.audio_remaining -= take;
+                    chunk = &chunk[take..];
+                }
+                None => {
+                    self.metadata_remaining = Some(chunk[0] as usize * 16);
+                    chunk = &chunk[1..];
+                }
+                Some(remaining) => {
+                    let take = remaining.min(chunk.len());
+                    self.metadata.extend_from_slice(&chunk[..take]);
+                    chunk = &chunk[take..];
+                    self.metadata_remaining = Some(remaining - take);
+                }
+            }
+
+            if self.metadata_remaining == Some(0) {
+                let text = String::from_utf8_lossy(&self.metadata)
+                    .trim_end_matches('\0')
+                    .to_string();
+                if !text.is_empty() {
+                    blocks.push(text);
+                }
+                self.metadata.clear();
+                self.metadata_remaining = None;
+                self.audio_remaining = self.metaint;
+            }
+        }
+
+        blocks
+    }
+}
+
+/// Parse the `StreamTitle` (and `StreamUrl`, if any) out of a metadata block
+pub fn parse_stream_title(metadata: &str) -> Option<IcyStreamTitle> {
+    let title = metadata_field(metadata, "StreamTitle")?;
+    let url = metadata_field(metadata, "StreamUrl").filter(|url| !url.is_empty());
+    Some(IcyStreamTitle { title, url })
+}
+
+fn metadata_field(metadata: &str, key: &str) -> Option<String> {
+    let start = metadata.find(&format!("{key}='"))? + key.len() + 2;
+    // Titles may contain quotes, so the value ends at the last "';" before the next field
+    let rest = &metadata[start..];
+    let end = rest
+        .match_indices("';")
+        .map(|(index, _)| index)
+        .find(|index| {
+            let next = &rest[index + 2..];
+            next.is_empty() || next.starts_with(|c: char| c.is_ascii_alphabetic() || c == '\0')
+        })
+        .unwrap_or_else(|| {
+            rest.trim_end_matches(['\0', ';'])
+                .trim_end_matches('\'')
+                .len()
+        });
+    Some(rest[..end].to_string())
+}
+
+/// Follow a radio stream's metadata, sending each new `StreamTitle`
+///
+/// Returns once the stream ends, the receiver is dropped, or the server turns
+/// out not to support ICY metadata. Voice playback reads the audio over the
+/// same connection through [`open_stream`] instead.
+#[cfg(not(feature = "discord"))]
+pub async fn watch_stream_titles(
+    client: &Client,
+    url: &str,
+    sender: mpsc::UnboundedSender<IcyStreamTitle>,
+) -> Result<()> {
+    let (response, metaint) = request_stream(client, url, HeaderMap::new()).await?;
+    if metaint.is_none() {
+        debug!("Stream {} does not provide ICY metadata", url);
+        return Ok(());
+    }
+    follow_stream(response, metaint, sender, None).await
+}
+
+/// Open a radio stream for playback, sending each new `StreamTitle`
+///
+/// The audio and its metadata share one connection. Streams without ICY
+/// metadata are passed through unchanged. Also returns the stream's content
+/// type, if it announced one.
+#[cfg(feature = "discord")]
+pub async fn open_stream(
+    client: &Client,
+    url: &str,
+    headers: HeaderMap,
+    titles: mpsc::UnboundedSender<IcyStreamTitle>,
+) -> Result<(super::live::LiveStream, Option<String>)> {
+    let (response, metaint) = request_stream(client, url, headers).await?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    let url = url.to_string();
+    let reader = tokio::spawn(async move {
+        if let Err(e) = follow_stream(response, metaint, titles, Some(sender)).await {
+            debug!("Radio stream {} ended: {}", url, e);
+        }
+    });
+    Ok((super::live::LiveStream::new(receiver, reader), content_type))
+}
+
+/// Request a stream with metadata enabled, returning its `icy-metaint` if any
+async fn request_stream(
+    client: &Client,
+    url: &str,
+    headers: HeaderMap,
+) -> Result<(Response, Option<usize>)> {
+    let response = client
+        .get(url)
+        .headers(headers)
+        .header(ICY_METADATA_HEADER, "1")
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP {} fetching {}", response.status(), url));
+    }
+
+    let metaint = response
+        .headers()
+        .get(ICY_METAINT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|metaint| *metaint > 0);
+    Ok((response, metaint))
+}
+
+/// Split a stream into titles and audio until it ends
+///
+/// Without an audio sender only the titles are followed, and the stream is
+/// left as soon as nobody listens for them anymore.
+async fn follow_stream(
+    mut response: Response,
+    metaint: Option<usize>,
+    titles: mpsc::UnboundedSender<IcyStreamTitle>,
+    audio: Option<mpsc::Sender<Vec<u8>>>,
+) -> Result<()> {
+    let mut parser = metaint.map(IcyMetadataParser::new);
+    let mut last_title: Option<IcyStreamTitle> = None;
+
+    while let Some(chunk) = response.chunk().await? {
+        let mut audio_chunk = Vec::new();
+        let blocks = match parser.as_mut() {
+            Some(parser) => parser.feed(&chunk, &mut audio_chunk),
+            None => {
+                audio_chunk.extend_from_slice(&chunk);
+                Vec::new()
+            }
+        };
+
+        for block in blocks {
+            let Some(title) = parse_stream_title(&block) else {
+                continue;
+            };
+            if last_title.as_ref() == Some(&title) {
+                continue;
+            }
+            last_title = Some(title.clone());
+            if titles.send(title).is_err() && audio.is_none() {
+                return Ok(());
+            }
+        }
+
+        if let Some(audio) = &audio {
+            if audio.send(audio_chunk).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_block(text: &str) -> Vec<u8> {
+        let padded_len = text.len().div_ceil(16) * 16;
+        let mut block = vec![(padded_len / 16) as u8];
+        block.extend_from_slice(text.as_bytes());
+        block.resize(padded_len + 1, 0);
+        block
+    }
+
+    #[test]
+    fn test_parser_extracts_blocks_across_chunks() {
+        let mut stream = vec![0xAA; 8];
+        stream.extend(metadata_block("StreamTitle='First Song';"));
+        stream.extend(vec![0xAA; 8]);
+        stream.push(0); // Empty block, nothing changed
+        stream.extend(vec![0xAA; 8]);
+        stream.extend(metadata_block("StreamTitle='Second Song';StreamUrl='';"));
+
+        let mut parser = IcyMetadataParser::new(8);
+        let mut audio = Vec::new();
+        let blocks: Vec<String> = stream
+            .chunks(5)
+            .flat_map(|chunk| parser.feed(chunk, &mut audio))
+            .collect();
+
+        assert_eq!(
+            blocks,
+            vec![
+                "StreamTitle='First Song';".to_string(),
+                "StreamTitle='Second Song';StreamUrl='';".to_string(),
+            ]
+        );
+        // Only the audio in between the blocks is passed on
+        assert_eq!(audio, vec![0xAA; 24]);
+    }
+
+    #[test]
+    fn test_parse_stream_title() {
+        assert_eq!(
+            parse_stream_title("StreamTitle='Artist - Song';StreamUrl='https://radio.example';"),
+            Some(IcyStreamTitle {
+                title: "Artist - Song".to_string(),
+                url: Some("https://radio.example".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_stream_title("StreamTitle='Don't Stop';"),
+            Some(IcyStreamTitle {
+                title: "Don't Stop".to_string(),
+                url: None,
+            })
+        );
+        assert_eq!(parse_stream_title("StreamUrl='x';"), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Blocking media source over bytes produced by a background task
+//!
+//! Live streams such as HLS playlists and ICY radio streams, and HTTP bodies
+//! played as they download, are fetched by an async task that hands the audio
+//! over through a bounded channel, giving the fetcher natural backpressure.
+//! The task is stopped when the stream is dropped.
+
+use anyhow::{anyhow, Result};
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+use std::io::{Read, Seek, SeekFrom};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Chunks of an HTTP body buffered ahead of the decoder
+const HTTP_BUFFER: usize = 16;
+
+/// A live stream exposed as a readable media source
+///
+/// Reads block until the next chunk arrives, so the stream must be read
+/// from a blocking context such as the voice mixer thread or a blocking task.
+pub struct LiveStream {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    current: Vec<u8>,
+    offset: usize,
+    producer: JoinHandle<()>,
+}
+
+impl LiveStream {
+    /// Read the chunks `producer` sends through the channel of `receiver`
+    pub fn new(receiver: mpsc::Receiver<Vec<u8>>, producer: JoinHandle<()>) -> Self {
+        Self {
+            receiver,
+            current: Vec::new(),
+            offset: 0,
+            producer,
+        }
+    }
+}
+
+/// Play an HTTP body as it downloads
+///
+/// Streams that never end, like radio, are read for as long as they play.
+pub async fn open_http(client: &Client, url: &str, headers: HeaderMap) -> Result<LiveStream> {
+    let mut response = client.get(url).headers(headers).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP {} fetching {}", response.status(), url));
+    }
+
+    let (sender, receiver) = mpsc::channel(HTTP_BUFFER);
+    let url = url.to_string();
+    let producer = tokio::spawn(async move {
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    if sender.send(chunk.to_vec()).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    debug!("Stream {} ended: {}", url, e);
+                    break;
+                }
+            }
+        }
+    });
+    Ok(LiveStream::new(receiver, producer))
+}
+
+impl Read for LiveStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset >= self.current.len() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => {
+                    self.current = chunk;
+                    self.offset = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.current.len() - self.offset);
+        buf[..len].copy_from_slice(&self.current[self.offset..self.offset + len]);
+        self.offset += len;
+        Ok(len)
+    }
+}
+
+impl Seek for LiveStream {
+    fn seek(&mut self, _pos: SeekFrom) -> std::io::Result<u64> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Live streams are not seekable",
+        ))
+    }
+}
+
+impl symphonia::core::io::MediaSource for LiveStream {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl Drop for LiveStream {
+    fn drop(&mut self) {
+        self.producer.abort();
+    }
+}
@@ -4,58 +4,65 @@
 //! using Symphonia for comprehensive audio format support.
 
 use anyhow::{anyhow, Result};
-use base64::Engine;
-use std::collections::HashMap;
-use std::fs::File;
+use async_trait::async_trait;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use tracing::{debug, warn};
 
-#[cfg(feature = "audio-processing")]
-use symphonia::core::formats::FormatOptions;
-#[cfg(feature = "audio-processing")]
-use symphonia::core::io::MediaSourceStream;
-#[cfg(feature = "audio-processing")]
-use symphonia::core::meta::MetadataOptions;
-#[cfg(feature = "audio-processing")]
-use symphonia::core::probe::Hint;
-
+use super::local_library::{
+    self, EmbeddedArtwork, LocalLibrary, LocalLibraryOptions, LocalTrackTags, DEFAULT_DIRECTORIES,
+    DEFAULT_MAX_DEPTH, DEFAULT_SCAN_INTERVAL,
+};
+use crate::config::LocalLibraryConfig;
+use crate::lyrics::{self, Lyrics, LyricsProvider};
 use crate::protocol::{
-    Exception, LoadResult, LoadResultData, LoadType, Severity, Track, TrackInfo,
+    Exception, LoadResult, LoadResultData, LoadType, Playlist, PlaylistInfo, Severity, Track,
 };
 
+/// Prefix for fuzzy searches over the local library
+pub const LOCAL_SEARCH_PREFIX: &str = "local:";
+/// Prefix for loading a local album as a playlist
+pub const LOCAL_ALBUM_PREFIX: &str = "localalbum:";
+/// Prefix for loading all local tracks of an artist as a playlist
+pub const LOCAL_ARTIST_PREFIX: &str = "localartist:";
+
 /// Local file audio source with metadata extraction
 #[derive(Clone)]
 pub struct LocalAudioSource {
-    /// Allowed base directories for security
-    allowed_directories: Vec<PathBuf>,
-    /// Maximum search depth for directory traversal
-    max_search_depth: usize,
-    /// Supported audio file extensions
-    supported_extensions: Vec<String>,
+    /// Tag index over the allowed directories, also holding the source's
+    /// directory, depth and extension settings
+    library: Arc<LocalLibrary>,
 }
 
 impl LocalAudioSource {
     /// Create a new local audio source with default configuration
     pub fn new() -> Self {
-        Self {
-            allowed_directories: vec![
-                PathBuf::from("./music"),
-                PathBuf::from("./audio"),
-                PathBuf::from("./tracks"),
-            ],
-            max_search_depth: 3,
-            supported_extensions: vec![
-                "mp3".to_string(),
-                "flac".to_string(),
-                "wav".to_string(),
-                "ogg".to_string(),
-                "m4a".to_string(),
-                "aac".to_string(),
-                "opus".to_string(),
-                "wma".to_string(),
-            ],
-        }
+        Self::from_library_config(None)
+    }
+
+    /// Create a local audio source from the `localLibrary` sources configuration
+    pub fn from_library_config(config: Option<&LocalLibraryConfig>) -> Self {
+        let directories = config
+            .and_then(|c| c.directories.clone())
+            .map(|dirs| dirs.into_iter().map(PathBuf::from).collect())
+            .unwrap_or_else(|| DEFAULT_DIRECTORIES.iter().map(PathBuf::from).collect());
+
+        Self::from_options(LocalLibraryOptions {
+            directories,
+            max_depth: config
+                .and_then(|c| c.max_depth)
+                .unwrap_or(DEFAULT_MAX_DEPTH),
+            extensions: default_extensions(),
+            index_file: config
+                .and_then(|c| c.index_file.as_ref())
+                .map(PathBuf::from),
+            scan_interval: config
+                .and_then(|c| c.scan_interval_ms)
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_SCAN_INTERVAL),
+        })
     }
 
     /// Create a new local audio source with custom configuration
@@ -65,15 +72,46 @@ impl LocalAudioSource {
         max_search_depth: usize,
         supported_extensions: Vec<String>,
     ) -> Self {
+        Self::from_options(LocalLibraryOptions {
+            directories: allowed_directories,
+            max_depth: max_search_depth,
+            extensions: supported_extensions,
+            index_file: None,
+            scan_interval: DEFAULT_SCAN_INTERVAL,
+        })
+    }
+
+    fn from_options(options: LocalLibraryOptions) -> Self {
         Self {
-            allowed_directories,
-            max_search_depth,
-            supported_extensions,
+            library: Arc::new(LocalLibrary::new(options)),
         }
     }
 
-    /// Load a track from a local file path
+    /// Load a track from a local file path, or a `local:` search or playlist query
     pub async fn load_track(&self, path: &str) -> Result<LoadResult> {
+        if let Some(query) = path.strip_prefix(LOCAL_SEARCH_PREFIX) {
+            let tracks = self.search_tracks(query, None).await?;
+            return Ok(if tracks.is_empty() {
+                LoadResult {
+                    load_type: LoadType::Empty,
+                    data: None,
+                }
+            } else {
+                LoadResult {
+                    load_type: LoadType::Search,
+                    data: Some(LoadResultData::Search(tracks)),
+                }
+            });
+        }
+        if let Some(query) = path.strip_prefix(LOCAL_ALBUM_PREFIX) {
+            self.prepare_library().await;
+            return Ok(playlist_result(self.library.album(query), "album"));
+        }
+        if let Some(query) = path.strip_prefix(LOCAL_ARTIST_PREFIX) {
+            self.prepare_library().await;
+            return Ok(playlist_result(self.library.artist(query), "artist"));
+        }
+
         debug!("Loading local track: {}", path);
 
         // Normalize the path
@@ -139,32 +177,54 @@ impl LocalAudioSource {
         }
     }
 
-    /// Search for audio files in allowed directories
+    /// Fuzzy search over the tags of indexed files in allowed directories
     pub async fn search_tracks(&self, query: &str, limit: Option<u32>) -> Result<Vec<Track>> {
         debug!("Searching local files for: {}", query);
 
-        let mut tracks = Vec::new();
         let limit = limit.unwrap_or(50).min(200) as usize;
-        let query_lower = query.to_lowercase();
+        self.prepare_library().await;
 
-        for base_dir in &self.allowed_directories {
-            if !base_dir.exists() {
-                continue;
-            }
+        Ok(self
+            .library
+            .search(query, limit)
+            .iter()
+            .map(LocalTrackTags::to_track)
+            .collect())
+    }
 
-            if let Ok(found_tracks) = self
-                .search_directory(base_dir, &query_lower, limit - tracks.len())
-                .await
-            {
-                tracks.extend(found_tracks);
-                if tracks.len() >= limit {
-                    break;
-                }
-            }
+    /// Start the watcher and wait for the first scan if none has run yet
+    async fn prepare_library(&self) {
+        self.library.watch();
+        self.library.ensure_scanned().await;
+    }
+
+    /// Read the embedded cover of an allowed local audio file
+    pub async fn read_artwork(&self, path: &str) -> Result<Option<EmbeddedArtwork>> {
+        let file_path = self.normalize_path(path)?;
+        if !file_path.is_file()
+            || !self.is_path_allowed(&file_path)
+            || !self.is_supported_audio_file(&file_path)
+        {
+            return Ok(None);
+        }
+
+        tokio::task::spawn_blocking(move || local_library::read_artwork(&file_path)).await?
+    }
+
+    /// Read lyrics for an allowed local audio file
+    ///
+    /// A `.lrc` sidecar next to the file wins over embedded SYLT (synced) and
+    /// USLT (plain, or LRC formatted) tags.
+    pub async fn read_lyrics(&self, path: &str) -> Result<Option<Lyrics>> {
+        let file_path = self.normalize_path(path)?;
+        if !file_path.is_file()
+            || !self.is_path_allowed(&file_path)
+            || !self.is_supported_audio_file(&file_path)
+        {
+            return Ok(None);
         }
 
-        tracks.truncate(limit);
-        Ok(tracks)
+        tokio::task::spawn_blocking(move || read_file_lyrics(&file_path)).await?
     }
 
     /// Normalize file path (handle file:// URLs and relative paths)
@@ -189,13 +249,15 @@ impl LocalAudioSource {
 
     /// Check if a path is within allowed directories
     fn is_path_allowed(&self, path: &Path) -> bool {
+        let allowed_directories = &self.library.options().directories;
+
         // If no allowed directories are configured, allow all paths
-        if self.allowed_directories.is_empty() {
+        if allowed_directories.is_empty() {
             return true;
         }
 
         // Check if the path is within any allowed directory
-        for allowed_dir in &self.allowed_directories {
+        for allowed_dir in allowed_directories {
             // Try to canonicalize both paths, but fall back to string comparison if canonicalization fails
             match (path.canonicalize(), allowed_dir.canonicalize()) {
                 (Ok(canonical_path), Ok(canonical_allowed)) => {
@@ -223,214 +285,36 @@ impl LocalAudioSource {
     fn is_supported_audio_file(&self, path: &Path) -> bool {
         if let Some(extension) = path.extension() {
             if let Some(ext_str) = extension.to_str() {
-                return self.supported_extensions.contains(&ext_str.to_lowercase());
+                return self
+                    .library
+                    .options()
+                    .extensions
+                    .contains(&ext_str.to_lowercase());
             }
         }
         false
     }
 
-    /// Extract metadata from an audio file using Symphonia
-    #[cfg(feature = "audio-processing")]
+    /// Extract metadata from an audio file, reusing the index when it is current
     async fn extract_metadata(&self, path: &Path) -> Result<Track> {
-        // Open the file
-        let file = File::open(path)?;
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-        // Create a probe hint based on file extension
-        let mut hint = Hint::new();
-        if let Some(extension) = path.extension() {
-            if let Some(ext_str) = extension.to_str() {
-                hint.with_extension(ext_str);
-            }
+        if let Some(tags) = self.library.get(path) {
+            return Ok(tags.to_track());
         }
 
-        // Probe the file to determine format
-        let format_opts = FormatOptions::default();
-        let metadata_opts = MetadataOptions::default();
-
-        let probed =
-            symphonia::default::get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
-
-        let mut format = probed.format;
-        let track = format
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
-            .ok_or_else(|| anyhow!("No audio track found"))?;
-
-        // Extract basic information
-        let duration_ms = if let Some(n_frames) = track.codec_params.n_frames {
-            if let Some(sample_rate) = track.codec_params.sample_rate {
-                n_frames * 1000 / sample_rate as u64
-            } else {
-                0
-            }
-        } else {
-            0
-        };
-
-        // Extract metadata
-        let metadata = format.metadata();
-        let mut title = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Unknown")
-            .to_string();
-        let mut artist = "Unknown Artist".to_string();
-
-        // Try to get metadata from the file
-        if let Some(metadata_rev) = metadata.current() {
-            for tag in metadata_rev.tags() {
-                match tag.key.as_str() {
-                    "TITLE" | "TIT2" => title = tag.value.to_string(),
-                    "ARTIST" | "TPE1" => artist = tag.value.to_string(),
-                    _ => {}
-                }
-            }
-        }
-
-        // Create file URL
-        let file_url = format!("file://{}", path.display());
-
-        // Create track info
-        let track_info = TrackInfo {
-            identifier: path.to_string_lossy().to_string(),
-            is_seekable: true,
-            author: artist,
-            length: duration_ms,
-            is_stream: false,
-            position: 0,
-            title,
-            uri: Some(file_url.clone()),
-            artwork_url: None,
-            isrc: None,
-            source_name: "local".to_string(),
-        };
-
-        // Create encoded track data
-        let track_data = serde_json::json!({
-            "identifier": path.to_string_lossy(),
-            "source": "local",
-            "uri": file_url,
-            "title": track_info.title,
-            "author": track_info.author,
-            "duration": duration_ms
-        });
-
-        let encoded = base64::engine::general_purpose::STANDARD.encode(track_data.to_string());
-
-        Ok(Track {
-            encoded,
-            info: track_info,
-            #[cfg(feature = "plugins")]
-            plugin_info: HashMap::new(),
-            #[cfg(feature = "rest-api")]
-            user_data: HashMap::new(),
-        })
-    }
-
-    /// Fallback metadata extraction when audio-processing feature is disabled
-    #[cfg(not(feature = "audio-processing"))]
-    async fn extract_metadata(&self, path: &Path) -> Result<Track> {
-        let title = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Unknown")
-            .to_string();
-
-        let file_url = format!("file://{}", path.display());
-
-        let track_info = TrackInfo {
-            identifier: path.to_string_lossy().to_string(),
-            is_seekable: true,
-            author: "Unknown Artist".to_string(),
-            length: 0, // Cannot determine without audio processing
-            is_stream: false,
-            position: 0,
-            title,
-            uri: Some(file_url.clone()),
-            artwork_url: None,
-            isrc: None,
-            source_name: "local".to_string(),
-        };
-
-        let track_data = serde_json::json!({
-            "identifier": path.to_string_lossy(),
-            "source": "local",
-            "uri": file_url,
-            "title": track_info.title,
-            "author": track_info.author,
-            "duration": 0
-        });
-
-        let encoded = base64::engine::general_purpose::STANDARD.encode(track_data.to_string());
-
-        Ok(Track {
-            encoded,
-            info: track_info,
-            plugin_info: HashMap::new(),
-            user_data: HashMap::new(),
-        })
-    }
-
-    /// Search for audio files in a directory recursively
-    async fn search_directory(&self, dir: &Path, query: &str, limit: usize) -> Result<Vec<Track>> {
-        let mut tracks = Vec::new();
-
-        if tracks.len() >= limit {
-            return Ok(tracks);
-        }
-
-        // Use an iterative approach to avoid async recursion issues
-        let mut dirs_to_search = vec![(dir.to_path_buf(), 0)];
-
-        while let Some((current_dir, depth)) = dirs_to_search.pop() {
-            if depth > self.max_search_depth || tracks.len() >= limit {
-                continue;
-            }
-
-            let read_dir = match std::fs::read_dir(&current_dir) {
-                Ok(rd) => rd,
-                Err(e) => {
-                    debug!("Failed to read directory {}: {}", current_dir.display(), e);
-                    continue;
-                }
-            };
-
-            for entry in read_dir {
-                if tracks.len() >= limit {
-                    break;
-                }
-
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => continue,
-                };
-
-                let path = entry.path();
-
-                if path.is_dir() {
-                    // Add subdirectory to search queue
-                    dirs_to_search.push((path, depth + 1));
-                } else if self.is_supported_audio_file(&path) {
-                    // Check if filename matches query
-                    if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                        if filename.to_lowercase().contains(query) {
-                            // Try to extract metadata and add to results
-                            if let Ok(track) = self.extract_metadata(&path).await {
-                                tracks.push(track);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(tracks)
+        let path = path.to_path_buf();
+        let tags = tokio::task::spawn_blocking(move || local_library::probe_file(&path)).await??;
+        Ok(tags.to_track())
     }
 
     /// Check if the local source can handle a given identifier
     pub fn can_handle(&self, identifier: &str) -> bool {
+        if identifier.starts_with(LOCAL_SEARCH_PREFIX)
+            || identifier.starts_with(LOCAL_ALBUM_PREFIX)
+            || identifier.starts_with(LOCAL_ARTIST_PREFIX)
+        {
+            return true;
+        }
+
         // Handle file:// URLs
         if identifier.starts_with("file://") {
             let path = identifier.strip_prefix("file://").unwrap_or(identifier);
@@ -457,6 +341,80 @@ impl Default for LocalAudioSource {
     }
 }
 
+#[async_trait]
+impl LyricsProvider for LocalAudioSource {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn fetch(&self, track: &Track) -> Result<Option<Lyrics>> {
+        if track.info.source_name != "local" {
+            return Ok(None);
+        }
+        self.read_lyrics(&track.info.identifier).await
+    }
+}
+
+fn read_file_lyrics(path: &Path) -> Result<Option<Lyrics>> {
+    let sidecar = path.with_extension("lrc");
+    if sidecar.is_file() {
+        let text = std::fs::read_to_string(&sidecar)?;
+        return Ok(Some(text_lyrics(&text, "lrc")));
+    }
+
+    match lyrics::sylt::read_file(path) {
+        Ok(Some(lines)) if !lines.is_empty() => {
+            return Ok(Some(Lyrics::synced("local", "sylt", lines)));
+        }
+        Ok(_) => {}
+        Err(e) => debug!("Failed to read SYLT lyrics from {}: {}", path.display(), e),
+    }
+
+    Ok(local_library::read_lyrics(path)?.map(|text| text_lyrics(&text, "embedded")))
+}
+
+/// Lyrics from text that may or may not be LRC formatted
+fn text_lyrics(text: &str, provider: &str) -> Lyrics {
+    if lyrics::looks_like_lrc(text) {
+        Lyrics::synced("local", provider, lyrics::parse_lrc(text))
+    } else {
+        Lyrics::plain("local", provider, text)
+    }
+}
+
+fn default_extensions() -> Vec<String> {
+    ["mp3", "flac", "wav", "ogg", "m4a", "aac", "opus", "wma"]
+        .iter()
+        .map(|ext| ext.to_string())
+        .collect()
+}
+
+#[cfg_attr(not(feature = "plugins"), allow(unused_variables))]
+fn playlist_result(group: Option<(String, Vec<LocalTrackTags>)>, kind: &str) -> LoadResult {
+    let Some((name, tracks)) = group else {
+        return LoadResult {
+            load_type: LoadType::Empty,
+            data: None,
+        };
+    };
+
+    LoadResult {
+        load_type: LoadType::Playlist,
+        data: Some(LoadResultData::Playlist(Playlist {
+            info: PlaylistInfo {
+                name,
+                selected_track: None,
+            },
+            #[cfg(feature = "plugins")]
+            plugin_info: std::collections::HashMap::from([(
+                "type".to_string(),
+                serde_json::Value::from(kind),
+            )]),
+            tracks: tracks.iter().map(LocalTrackTags::to_track).collect(),
+        })),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -467,8 +425,12 @@ mod tests {
     async fn test_local_source_creation() {
         let source = LocalAudioSource::new();
         assert_eq!(source.name(), "local");
-        assert_eq!(source.max_search_depth, 3);
-        assert!(source.supported_extensions.contains(&"mp3".to_string()));
+        assert_eq!(source.library.options().max_depth, 3);
+        assert!(source
+            .library
+            .options()
+            .extensions
+            .contains(&"mp3".to_string()));
     }
 
     #[tokio::test]
@@ -546,6 +508,65 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_local_prefix_queries() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("Morning Walk.mp3"),
+            b"fake mp3 content",
+        )
+        .unwrap();
+
+        let source = LocalAudioSource::with_config(
+            vec![temp_dir.path().to_path_buf()],
+            3,
+            vec!["mp3".to_string()],
+        );
+        assert!(source.can_handle("local:morning"));
+        assert!(source.can_handle("localalbum:anything"));
+        assert!(source.can_handle("localartist:anyone"));
+
+        let result = source.load_track("local:mornin walk").await.unwrap();
+        assert!(matches!(result.load_type, LoadType::Search));
+        if let Some(LoadResultData::Search(tracks)) = result.data {
+            assert_eq!(tracks[0].info.title, "Morning Walk");
+        }
+
+        // Untagged files have no album to build a playlist from
+        let result = source.load_track("localalbum:morning").await.unwrap();
+        assert!(matches!(result.load_type, LoadType::Empty));
+    }
+
+    #[tokio::test]
+    async fn test_read_lyrics_sidecar() {
+        let temp_dir = TempDir::new().unwrap();
+        let audio = temp_dir.path().join("Sung.mp3");
+        fs::write(&audio, b"fake mp3 content").unwrap();
+
+        let source = LocalAudioSource::with_config(
+            vec![temp_dir.path().to_path_buf()],
+            3,
+            vec!["mp3".to_string()],
+        );
+        let path = audio.to_str().unwrap();
+        assert!(source.read_lyrics(path).await.is_err());
+
+        fs::write(
+            temp_dir.path().join("Sung.lrc"),
+            "[00:01.00]First\n[00:02.50]Second",
+        )
+        .unwrap();
+        let lyrics = source.read_lyrics(path).await.unwrap().unwrap();
+        assert_eq!(lyrics.provider, "lrc");
+        assert_eq!(lyrics.lines.len(), 2);
+        assert_eq!(lyrics.lines[1].timestamp, 2_500);
+
+        fs::write(temp_dir.path().join("Sung.lrc"), "Just plain words").unwrap();
+        let lyrics = source.read_lyrics(path).await.unwrap().unwrap();
+        assert!(lyrics.lines.is_empty());
+        assert_eq!(lyrics.text.as_deref(), Some("Just plain words"));
+    }
+
     #[tokio::test]
     async fn test_search_empty_query() {
         let source = LocalAudioSource::new();
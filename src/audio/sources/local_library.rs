@@ -0,0 +1,868 @@
+//! Persistent tag index for the local file source
+//!
+//! The library keeps the tags (title, artist, album, ISRC, duration, chapters)
+//! of every supported file below the configured directories, so searches never
+//! have to walk the filesystem or probe files. A polling watcher compares file
+//! modification times and sizes against the index and only probes files that
+//! were added or changed, removing entries for files that disappeared. The
+//! index is written to disk as JSON so restarts only re-probe what changed.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, UNIX_EPOCH};
+
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{
+    MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey, Visual,
+};
+use symphonia::core::probe::{Hint, ProbeResult};
+use tracing::{debug, info, warn};
+
+use super::chapters;
+use crate::protocol::{normalize_chapters, Chapter, Track, TrackInfo};
+
+/// Directories indexed when none are configured
+pub const DEFAULT_DIRECTORIES: [&str; 3] = ["./music", "./audio", "./tracks"];
+
+/// Default maximum directory depth below each configured directory
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Default interval between watcher scans
+pub const DEFAULT_SCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Minimum fuzzy score for a search result
+pub const MIN_SEARCH_SCORE: f64 = 0.6;
+
+/// Version of the on-disk index format
+const INDEX_VERSION: u32 = 3;
+
+/// Tags stored in the index for a single file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalTrackTags {
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub album: Option<String>,
+    pub isrc: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration_ms: u64,
+    /// Modification time (seconds since the epoch) when the file was probed
+    pub modified: u64,
+    /// File size in bytes when the file was probed
+    pub size: u64,
+    /// Whether the file embeds cover art
+    #[serde(default)]
+    pub has_artwork: bool,
+    /// Chapters embedded in the file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+}
+
+impl LocalTrackTags {
+    /// Build a Lavalink track for this file
+    pub fn to_track(&self) -> Track {
+        let file_url = format!("file://{}", self.path.display());
+
+        let track_info = TrackInfo {
+            identifier: self.path.to_string_lossy().to_string(),
+            is_seekable: true,
+            author: self.artist.clone(),
+            length: self.duration_ms,
+            is_stream: false,
+            position: 0,
+            title: self.title.clone(),
+            uri: Some(file_url.clone()),
+            // Embedded covers are referenced by the audio file itself and served
+            // through the artwork endpoint
+            artwork_url: self.has_artwork.then(|| file_url.clone()),
+            isrc: self.isrc.clone(),
+            source_name: "local".to_string(),
+        };
+
+        let track_data = serde_json::json!({
+            "identifier": self.path.to_string_lossy(),
+            "source": "local",
+            "uri": file_url,
+            "title": track_info.title,
+            "author": track_info.author,
+            "duration": self.duration_ms
+        });
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(track_data.to_string());
+
+        #[cfg(feature = "plugins")]
+        let plugin_info = self
+            .album
+            .iter()
+            .map(|album| {
+                (
+                    "albumName".to_string(),
+                    serde_json::Value::from(album.as_str()),
+                )
+            })
+            .collect();
+
+        let mut track = Track {
+            encoded,
+            info: track_info,
+            #[cfg(feature = "plugins")]
+            plugin_info,
+            #[cfg(feature = "rest-api")]
+            user_data: HashMap::new(),
+        };
+        track.set_chapters(&self.chapters);
+        track
+    }
+
+    fn is_current(&self, stamp: (u64, u64)) -> bool {
+        (self.modified, self.size) == stamp
+    }
+}
+
+/// Options controlling which files the library indexes
+#[derive(Debug, Clone)]
+pub struct LocalLibraryOptions {
+    pub directories: Vec<PathBuf>,
+    pub max_depth: usize,
+    pub extensions: Vec<String>,
+    /// Where the index is persisted; `None` keeps it in memory only
+    pub index_file: Option<PathBuf>,
+    pub scan_interval: Duration,
+}
+
+/// Counts of index entries changed by a scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexChanges {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl IndexChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.updated == 0 && self.removed == 0
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    entries: Vec<LocalTrackTags>,
+}
+
+/// Tag index over the configured local directories
+pub struct LocalLibrary {
+    options: LocalLibraryOptions,
+    entries: RwLock<HashMap<PathBuf, LocalTrackTags>>,
+    scanned: AtomicBool,
+    watching: AtomicBool,
+}
+
+impl LocalLibrary {
+    /// Create a library, loading the persisted index if there is one
+    pub fn new(options: LocalLibraryOptions) -> Self {
+        let entries = options
+            .index_file
+            .as_deref()
+            .filter(|path| path.exists())
+            .and_then(|path| match load_index(path) {
+                Ok(entries) => {
+                    info!(
+                        "Loaded {} local library entries from {}",
+                        entries.len(),
+                        path.display()
+                    );
+                    Some(entries)
+                }
+                Err(e) => {
+                    warn!("Ignoring local library index {}: {}", path.display(), e);
+                    None
+                }
+            });
+
+        Self {
+            scanned: AtomicBool::new(entries.is_some()),
+            entries: RwLock::new(entries.unwrap_or_default()),
+            watching: AtomicBool::new(false),
+            options,
+        }
+    }
+
+    pub fn options(&self) -> &LocalLibraryOptions {
+        &self.options
+    }
+
+    /// Look up the indexed tags for a file if they are still current
+    pub fn get(&self, path: &Path) -> Option<LocalTrackTags> {
+        let path = path.canonicalize().ok()?;
+        let stamp = file_stamp(&path)?;
+        let entries = self.entries.read().ok()?;
+        entries
+            .get(&path)
+            .filter(|tags| tags.is_current(stamp))
+            .cloned()
+    }
+
+    /// Walk the directories, probing only new or changed files
+    ///
+    /// This blocks on filesystem access and should run on a blocking thread.
+    pub fn rescan(&self) -> IndexChanges {
+        let files = self.collect_files();
+        let mut changes = IndexChanges::default();
+
+        // Probe outside the lock so searches are not held up by a large scan
+        let stale: Vec<(PathBuf, (u64, u64))> = {
+            let Ok(entries) = self.entries.read() else {
+                return changes;
+            };
+            files
+                .iter()
+                .filter(|(path, stamp)| !entries.get(*path).is_some_and(|t| t.is_current(**stamp)))
+                .map(|(path, stamp)| (path.clone(), *stamp))
+                .collect()
+        };
+
+        let probed: Vec<LocalTrackTags> = stale
+            .into_iter()
+            .map(|(path, stamp)| read_tags(&path, stamp))
+            .collect();
+
+        if let Ok(mut entries) = self.entries.write() {
+            let before = entries.len();
+            entries.retain(|path, _| files.contains_key(path));
+            changes.removed = before - entries.len();
+
+            for tags in probed {
+                match entries.insert(tags.path.clone(), tags) {
+                    Some(_) => changes.updated += 1,
+                    None => changes.added += 1,
+                }
+            }
+        }
+
+        self.scanned.store(true, Ordering::Release);
+
+        if !changes.is_empty() {
+            debug!(
+                "Local library scan: {} added, {} updated, {} removed",
+                changes.added, changes.updated, changes.removed
+            );
+            self.persist();
+        }
+
+        changes
+    }
+
+    /// Make sure at least one scan has completed
+    pub async fn ensure_scanned(self: &Arc<Self>) {
+        if self.scanned.load(Ordering::Acquire) {
+            return;
+        }
+        let library = Arc::clone(self);
+        if let Err(e) = tokio::task::spawn_blocking(move || library.rescan()).await {
+            warn!("Local library scan failed: {}", e);
+        }
+    }
+
+    /// Start the background watcher if it is not running yet
+    ///
+    /// The watcher only holds a weak reference and stops once the library is
+    /// dropped. Outside a Tokio runtime this does nothing and searches fall back
+    /// to scanning on demand.
+    pub fn watch(self: &Arc<Self>) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if self.watching.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let library = Arc::downgrade(self);
+        let interval = self.options.scan_interval;
+        handle.spawn(watch_library(library, interval));
+    }
+
+    /// Fuzzy search over title, artist, album and ISRC
+    pub fn search(&self, query: &str, limit: usize) -> Vec<LocalTrackTags> {
+        let query_tokens = tokenize(query);
+        if query_tokens.is_empty() {
+            return Vec::new();
+        }
+        let Ok(entries) = self.entries.read() else {
+            return Vec::new();
+        };
+
+        let mut scored: Vec<(f64, &LocalTrackTags)> = entries
+            .values()
+            .filter_map(|tags| {
+                if tags
+                    .isrc
+                    .as_deref()
+                    .is_some_and(|isrc| isrc.eq_ignore_ascii_case(query.trim()))
+                {
+                    return Some((2.0, tags));
+                }
+                let haystack = format!(
+                    "{} {} {}",
+                    tags.title,
+                    tags.artist,
+                    tags.album.as_deref().unwrap_or_default()
+                );
+                let score = fuzzy_score(&query_tokens, &tokenize(&haystack));
+                // Title matches rank above matches that only hit the artist or album
+                let title_bonus = fuzzy_score(&query_tokens, &tokenize(&tags.title)) * 0.1;
+                (score >= MIN_SEARCH_SCORE).then_some((score + title_bonus, tags))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.title.cmp(&b.1.title)));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, tags)| tags.clone())
+            .collect()
+    }
+
+    /// Tracks of the album best matching `query`, in track order
+    pub fn album(&self, query: &str) -> Option<(String, Vec<LocalTrackTags>)> {
+        let mut tracks = self.best_group(query, |tags| tags.album.clone())?;
+        let name = tracks.first()?.album.clone()?;
+        tracks.sort_by(|a, b| {
+            a.track_number
+                .unwrap_or(u32::MAX)
+                .cmp(&b.track_number.unwrap_or(u32::MAX))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        Some((name, tracks))
+    }
+
+    /// Tracks of the artist best matching `query`, grouped by album
+    pub fn artist(&self, query: &str) -> Option<(String, Vec<LocalTrackTags>)> {
+        let mut tracks = self.best_group(query, |tags| Some(tags.artist.clone()))?;
+        let name = tracks.first()?.artist.clone();
+        tracks.sort_by(|a, b| {
+            a.album
+                .cmp(&b.album)
+                .then_with(|| {
+                    a.track_number
+                        .unwrap_or(u32::MAX)
+                        .cmp(&b.track_number.unwrap_or(u32::MAX))
+                })
+                .then_with(|| a.title.cmp(&b.title))
+        });
+        Some((name, tracks))
+    }
+
+    /// All entries sharing the group key that best matches `query`
+    fn best_group<F>(&self, query: &str, key: F) -> Option<Vec<LocalTrackTags>>
+    where
+        F: Fn(&LocalTrackTags) -> Option<String>,
+    {
+        let query_tokens = tokenize(query);
+        if query_tokens.is_empty() {
+            return None;
+        }
+        let entries = self.entries.read().ok()?;
+
+        let mut groups: HashMap<String, Vec<&LocalTrackTags>> = HashMap::new();
+        for tags in entries.values() {
+            if let Some(name) = key(tags) {
+                groups.entry(name).or_default().push(tags);
+            }
+        }
+
+        let (_, best) = groups
+            .into_iter()
+            .map(|(name, tracks)| (fuzzy_score(&query_tokens, &tokenize(&name)), name, tracks))
+            .filter(|(score, _, _)| *score >= MIN_SEARCH_SCORE)
+            .map(|(score, name, tracks)| ((score, name), tracks))
+            .max_by(|a, b| {
+                a.0 .0
+                    .total_cmp(&b.0 .0)
+                    // Prefer the alphabetically first name on ties for stable results
+                    .then_with(|| b.0 .1.cmp(&a.0 .1))
+            })?;
+
+        Some(best.into_iter().cloned().collect())
+    }
+
+    /// Supported files below the configured directories with their stamps
+    fn collect_files(&self) -> HashMap<PathBuf, (u64, u64)> {
+        let mut files = HashMap::new();
+        let mut dirs_to_search: Vec<(PathBuf, usize)> = self
+            .options
+            .directories
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .map(|dir| (dir, 0))
+            .collect();
+
+        while let Some((current_dir, depth)) = dirs_to_search.pop() {
+            let read_dir = match std::fs::read_dir(&current_dir) {
+                Ok(rd) => rd,
+                Err(e) => {
+                    debug!("Failed to read directory {}: {}", current_dir.display(), e);
+                    continue;
+                }
+            };
+
+            for entry in read_dir.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    if depth < self.options.max_depth {
+                        dirs_to_search.push((path, depth + 1));
+                    }
+                } else if self.is_supported(&path) {
+                    let Ok(path) = path.canonicalize() else {
+                        continue;
+                    };
+                    if let Some(stamp) = file_stamp(&path) {
+                        files.insert(path, stamp);
+                    }
+                }
+            }
+        }
+
+        files
+    }
+
+    fn is_supported(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.options.extensions.contains(&ext.to_lowercase()))
+    }
+
+    fn persist(&self) {
+        let Some(path) = self.options.index_file.as_deref() else {
+            return;
+        };
+        let Ok(entries) = self.entries.read() else {
+            return;
+        };
+        let mut index = IndexFile {
+            version: INDEX_VERSION,
+            entries: entries.values().cloned().collect(),
+        };
+        drop(entries);
+        index.entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        if let Err(e) = write_index(path, &index) {
+            warn!(
+                "Failed to write local library index {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+async fn watch_library(library: Weak<LocalLibrary>, interval: Duration) {
+    loop {
+        let Some(strong) = library.upgrade() else {
+            return;
+        };
+        if let Err(e) = tokio::task::spawn_blocking(move || strong.rescan()).await {
+            warn!("Local library scan failed: {}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+fn load_index(path: &Path) -> Result<HashMap<PathBuf, LocalTrackTags>> {
+    let index: IndexFile = serde_json::from_slice(&std::fs::read(path)?)?;
+    if index.version != INDEX_VERSION {
+        return Err(anyhow!("unsupported index version {}", index.version));
+    }
+    Ok(index
+        .entries
+        .into_iter()
+        .map(|tags| (tags.path.clone(), tags))
+        .collect())
+}
+
+fn write_index(path: &Path, index: &IndexFile) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first so a crash never leaves a truncated index
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, serde_json::to_vec(index)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// Modification time and size used to detect changed files
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((modified, metadata.len()))
+}
+
+/// Read the tags of a file, falling back to the file name when probing fails
+pub fn read_tags(path: &Path, stamp: (u64, u64)) -> LocalTrackTags {
+    let mut tags = untagged(path, stamp);
+    if let Err(e) = probe_tags(path, &mut tags) {
+        debug!("Failed to probe {}: {}", path.display(), e);
+    }
+    tags
+}
+
+/// Read the tags of a file, failing if it cannot be probed as audio
+pub fn probe_file(path: &Path) -> Result<LocalTrackTags> {
+    let stamp = file_stamp(path).ok_or_else(|| anyhow!("Cannot read file metadata"))?;
+    let mut tags = untagged(path, stamp);
+    probe_tags(path, &mut tags)?;
+    Ok(tags)
+}
+
+fn untagged(path: &Path, stamp: (u64, u64)) -> LocalTrackTags {
+    LocalTrackTags {
+        path: path.to_path_buf(),
+        title: path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Unknown")
+            .to_string(),
+        artist: "Unknown Artist".to_string(),
+        album: None,
+        isrc: None,
+        track_number: None,
+        duration_ms: 0,
+        modified: stamp.0,
+        size: stamp.1,
+        has_artwork: false,
+        chapters: Vec::new(),
+    }
+}
+
+fn probe(path: &Path) -> Result<ProbeResult> {
+    let file = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext_str) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext_str);
+    }
+
+    Ok(symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?)
+}
+
+/// Metadata revisions of a probed file
+///
+/// ID3v2 tags (including APIC frames) are read by the probe, container tags
+/// such as FLAC PICTURE blocks or MP4 `covr` atoms by the format reader.
+fn revisions(probed: &mut ProbeResult) -> Vec<MetadataRevision> {
+    let mut revisions = Vec::new();
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        revisions.push(revision.clone());
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        revisions.push(revision.clone());
+    }
+    revisions
+}
+
+fn probe_tags(path: &Path, tags: &mut LocalTrackTags) -> Result<()> {
+    let mut probed = probe(path)?;
+
+    let track = probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("No audio track found"))?;
+
+    if let (Some(n_frames), Some(sample_rate)) =
+        (track.codec_params.n_frames, track.codec_params.sample_rate)
+    {
+        tags.duration_ms = n_frames * 1000 / sample_rate as u64;
+    }
+
+    for revision in revisions(&mut probed) {
+        apply_revision(&revision, tags);
+        tags.has_artwork |= !revision.visuals().is_empty();
+    }
+
+    match chapters::read_chapters(path) {
+        Ok(mut chapters) => {
+            normalize_chapters(&mut chapters, Some(tags.duration_ms));
+            tags.chapters = chapters;
+        }
+        Err(e) => debug!("Failed to read chapters of {}: {}", path.display(), e),
+    }
+
+    Ok(())
+}
+
+/// Embedded cover art read from an audio file
+#[derive(Debug, Clone)]
+pub struct EmbeddedArtwork {
+    pub media_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Read the embedded cover of a file, preferring the front cover
+pub fn read_artwork(path: &Path) -> Result<Option<EmbeddedArtwork>> {
+    let mut probed = probe(path)?;
+    let visuals: Vec<Visual> = revisions(&mut probed)
+        .iter()
+        .flat_map(|revision| revision.visuals().to_vec())
+        .collect();
+
+    Ok(select_cover(visuals).map(|visual| EmbeddedArtwork {
+        media_type: visual.media_type,
+        data: visual.data.into_vec(),
+    }))
+}
+
+/// Read embedded unsynchronised lyrics (ID3 USLT, Vorbis LYRICS and similar)
+pub fn read_lyrics(path: &Path) -> Result<Option<String>> {
+    let mut probed = probe(path)?;
+    Ok(revisions(&mut probed).iter().find_map(|revision| {
+        revision.tags().iter().find_map(|tag| {
+            let is_lyrics = tag.std_key == Some(StandardTagKey::Lyrics)
+                || matches!(tag.key.as_str(), "LYRICS" | "UNSYNCEDLYRICS" | "USLT");
+            let value = tag.value.to_string();
+            (is_lyrics && !value.trim().is_empty()).then_some(value)
+        })
+    }))
+}
+
+fn select_cover(visuals: Vec<Visual>) -> Option<Visual> {
+    let front = visuals
+        .iter()
+        .position(|visual| visual.usage == Some(StandardVisualKey::FrontCover));
+    visuals.into_iter().nth(front.unwrap_or(0))
+}
+
+fn apply_revision(revision: &MetadataRevision, tags: &mut LocalTrackTags) {
+    for tag in revision.tags() {
+        let value = tag.value.to_string().trim().to_string();
+        if value.is_empty() {
+            continue;
+        }
+        match (tag.std_key, tag.key.as_str()) {
+            (Some(StandardTagKey::TrackTitle), _) | (_, "TITLE" | "TIT2") => tags.title = value,
+            (Some(StandardTagKey::Artist), _) | (_, "ARTIST" | "TPE1") => tags.artist = value,
+            (Some(StandardTagKey::Album), _) | (_, "ALBUM" | "TALB") => tags.album = Some(value),
+            (Some(StandardTagKey::IdentIsrc), _) | (_, "ISRC" | "TSRC") => tags.isrc = Some(value),
+            (Some(StandardTagKey::TrackNumber), _) | (_, "TRACKNUMBER" | "TRCK") => {
+                // Track numbers are often written as "3/12"
+                tags.track_number = value.split('/').next().and_then(|n| n.trim().parse().ok());
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Average of the best match for each query token against the haystack tokens
+///
+/// Exact tokens score 1.0, prefixes 0.9 and typos within the edit distance
+/// allowed for the token length 0.7.
+pub fn fuzzy_score(query: &[String], haystack: &[String]) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+
+    let total: f64 = query
+        .iter()
+        .map(|token| {
+            haystack
+                .iter()
+                .map(|candidate| token_score(token, candidate))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+
+    total / query.len() as f64
+}
+
+fn token_score(token: &str, candidate: &str) -> f64 {
+    if token == candidate {
+        return 1.0;
+    }
+    if candidate.starts_with(token) {
+        return 0.9;
+    }
+
+    let allowed = match token.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    if allowed > 0 && edit_distance(token, candidate) <= allowed {
+        0.7
+    } else {
+        0.0
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tags(path: &str, title: &str, artist: &str, album: &str, number: u32) -> LocalTrackTags {
+        LocalTrackTags {
+            path: PathBuf::from(path),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: Some(album.to_string()),
+            isrc: None,
+            track_number: Some(number),
+            duration_ms: 180_000,
+            modified: 0,
+            size: 0,
+            has_artwork: false,
+            chapters: Vec::new(),
+        }
+    }
+
+    fn library_with(entries: Vec<LocalTrackTags>) -> LocalLibrary {
+        let library = LocalLibrary::new(LocalLibraryOptions {
+            directories: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            extensions: vec!["mp3".to_string()],
+            index_file: None,
+            scan_interval: DEFAULT_SCAN_INTERVAL,
+        });
+        library
+            .entries
+            .write()
+            .unwrap()
+            .extend(entries.into_iter().map(|t| (t.path.clone(), t)));
+        library
+    }
+
+    #[test]
+    fn test_fuzzy_search_over_tags() {
+        let mut with_isrc = tags("/m/c.mp3", "Teardrop", "Massive Attack", "Mezzanine", 3);
+        with_isrc.isrc = Some("GBAAA9800030".to_string());
+        let library = library_with(vec![
+            tags("/m/a.mp3", "Angel", "Massive Attack", "Mezzanine", 1),
+            tags("/m/b.mp3", "Windowlicker", "Aphex Twin", "Windowlicker", 1),
+            with_isrc,
+        ]);
+
+        // Typos and prefixes still match
+        let results = library.search("masive atta", 10);
+        assert_eq!(results.len(), 2);
+
+        let results = library.search("teardrop massive", 10);
+        assert_eq!(results[0].title, "Teardrop");
+
+        let results = library.search("gbaaa9800030", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Teardrop");
+
+        assert!(library.search("nothing like this", 10).is_empty());
+        assert!(library.search("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_album_and_artist_playlists() {
+        let library = library_with(vec![
+            tags("/m/3.mp3", "Teardrop", "Massive Attack", "Mezzanine", 3),
+            tags("/m/1.mp3", "Angel", "Massive Attack", "Mezzanine", 1),
+            tags(
+                "/m/x.mp3",
+                "Unfinished Sympathy",
+                "Massive Attack",
+                "Blue Lines",
+                7,
+            ),
+            tags("/m/w.mp3", "Windowlicker", "Aphex Twin", "Windowlicker", 1),
+        ]);
+
+        let (name, tracks) = library.album("mezanine").unwrap();
+        assert_eq!(name, "Mezzanine");
+        let titles: Vec<_> = tracks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Angel", "Teardrop"]);
+
+        let (name, tracks) = library.artist("massive attack").unwrap();
+        assert_eq!(name, "Massive Attack");
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[0].album.as_deref(), Some("Blue Lines"));
+
+        assert!(library.album("unknown album").is_none());
+    }
+
+    #[test]
+    fn test_rescan_is_incremental_and_persisted() {
+        let temp_dir = TempDir::new().unwrap();
+        let music = temp_dir.path().join("music");
+        std::fs::create_dir_all(music.join("nested")).unwrap();
+        std::fs::write(music.join("first.mp3"), b"not really audio").unwrap();
+        std::fs::write(music.join("nested/second.mp3"), b"not really audio").unwrap();
+        std::fs::write(music.join("cover.jpg"), b"not audio").unwrap();
+
+        let index_file = temp_dir.path().join("index/library.json");
+        let options = LocalLibraryOptions {
+            directories: vec![music.clone()],
+            max_depth: DEFAULT_MAX_DEPTH,
+            extensions: vec!["mp3".to_string()],
+            index_file: Some(index_file.clone()),
+            scan_interval: DEFAULT_SCAN_INTERVAL,
+        };
+
+        let library = LocalLibrary::new(options.clone());
+        let changes = library.rescan();
+        assert_eq!(changes.added, 2);
+        assert_eq!(library.search("second", 10)[0].title, "second");
+
+        // Nothing changed, so nothing is probed again
+        assert!(library.rescan().is_empty());
+
+        std::fs::remove_file(music.join("first.mp3")).unwrap();
+        std::fs::write(music.join("third.mp3"), b"not really audio").unwrap();
+        let changes = library.rescan();
+        assert_eq!((changes.added, changes.removed), (1, 1));
+
+        // A new library picks the index up from disk
+        let reloaded = LocalLibrary::new(options);
+        assert_eq!(reloaded.entries.read().unwrap().len(), 2);
+        assert_eq!(reloaded.search("third", 10).len(), 1);
+    }
+}
@@ -0,0 +1,952 @@
+//! Metadata mirroring for Spotify, Apple Music and Deezer
+//!
+//! These platforms don't serve playable audio, so their tracks are loaded
+//! with the original platform metadata and mirrored to a playable source at
+//! playback time. Mirroring runs the configured search queries in order
+//! (ISRC first by default) and scores each candidate on duration, title and
+//! artist so covers, live versions and hour-long loops are passed over.
+
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Duration;
+use url::Url;
+
+use crate::protocol::{
+    LoadResult, LoadResultData, LoadType, Playlist, PlaylistInfo, Track, TrackInfo,
+};
+
+/// Mirror queries used when none are configured
+pub const DEFAULT_MIRROR_PROVIDERS: &[&str] = &["ytsearch:\"%ISRC%\"", "ytsearch:%QUERY%"];
+
+/// Candidates scoring below this are never picked as a mirror
+const MIN_MATCH_SCORE: f64 = 0.5;
+
+/// Durations closer than this are treated as identical
+const DURATION_EXACT_MS: u64 = 3_000;
+
+/// Durations further apart than this get no duration credit
+const DURATION_MAX_DIFF_MS: u64 = 30_000;
+
+/// Words that mark an alternate rendition unless the original title has them too
+const PENALTY_WORDS: &[&str] = &[
+    "cover",
+    "live",
+    "remix",
+    "karaoke",
+    "instrumental",
+    "loop",
+    "hour",
+    "hours",
+    "nightcore",
+    "sped",
+    "slowed",
+    "reverb",
+    "8d",
+];
+
+/// Words that video titles and channel names add without changing the recording
+const NOISE_WORDS: &[&str] = &[
+    "official",
+    "video",
+    "audio",
+    "lyrics",
+    "lyric",
+    "music",
+    "hd",
+    "hq",
+    "4k",
+    "mv",
+    "feat",
+    "ft",
+    "featuring",
+    "the",
+    "topic",
+    "vevo",
+];
+
+/// Platform a mirrored track originates from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorPlatform {
+    Spotify,
+    AppleMusic,
+    Deezer,
+}
+
+impl MirrorPlatform {
+    /// Source name reported in `TrackInfo.source_name`
+    pub fn source_name(&self) -> &'static str {
+        match self {
+            MirrorPlatform::Spotify => "spotify",
+            MirrorPlatform::AppleMusic => "applemusic",
+            MirrorPlatform::Deezer => "deezer",
+        }
+    }
+
+    /// Look up the platform for a track's source name
+    pub fn from_source_name(name: &str) -> Option<Self> {
+        match name {
+            "spotify" => Some(MirrorPlatform::Spotify),
+            "applemusic" => Some(MirrorPlatform::AppleMusic),
+            "deezer" => Some(MirrorPlatform::Deezer),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of item a platform URL points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorItemKind {
+    Track,
+    Album,
+    Playlist,
+}
+
+impl MirrorItemKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MirrorItemKind::Track => "track",
+            MirrorItemKind::Album => "album",
+            MirrorItemKind::Playlist => "playlist",
+        }
+    }
+}
+
+/// Check whether tracks from this source need mirroring before playback
+pub fn is_mirrored_source(source_name: &str) -> bool {
+    MirrorPlatform::from_source_name(source_name).is_some()
+}
+
+/// Loads track, album and playlist metadata from mirrored platforms
+pub struct MirrorMetadataClient {
+    client: Client,
+    providers: Vec<String>,
+}
+
+impl MirrorMetadataClient {
+    /// Create a client that tags tracks with the given mirror query templates
+    pub fn new(providers: Vec<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { client, providers }
+    }
+
+    /// Load a platform URL, returning a playlist for albums and playlists
+    pub async fn load_item(&self, url: &str) -> Result<LoadResult> {
+        if let Some((kind, id)) = parse_spotify_url(url) {
+            self.load_spotify(kind, &id).await
+        } else if let Some((kind, id)) = parse_deezer_url(url) {
+            self.load_deezer(kind, &id).await
+        } else if let Some((kind, id, country)) = parse_apple_music_url(url) {
+            self.load_apple_music(kind, &id, &country, url).await
+        } else {
+            Err(anyhow!("Unsupported or malformed URL: {}", url))
+        }
+    }
+
+    async fn load_spotify(&self, kind: MirrorItemKind, id: &str) -> Result<LoadResult> {
+        let embed_url = format!("https://open.spotify.com/embed/{}/{}", kind.as_str(), id);
+        let html = self.fetch_text(&embed_url).await?;
+        let entity = parse_spotify_embed(&html)
+            .ok_or_else(|| anyhow!("Spotify embed page has no entity data"))?;
+
+        Ok(spotify_load_result(&entity, kind, id, &self.providers))
+    }
+
+    async fn load_deezer(&self, kind: MirrorItemKind, id: &str) -> Result<LoadResult> {
+        let api_url = format!("https://api.deezer.com/{}/{}", kind.as_str(), id);
+        let json: Value = self.client.get(&api_url).send().await?.json().await?;
+        if let Some(message) = json["error"]["message"].as_str() {
+            return Err(anyhow!("Deezer API error: {}", message));
+        }
+
+        Ok(deezer_load_result(&json, kind, &self.providers))
+    }
+
+    async fn load_apple_music(
+        &self,
+        kind: MirrorItemKind,
+        id: &str,
+        country: &str,
+        url: &str,
+    ) -> Result<LoadResult> {
+        // Playlists aren't in the iTunes lookup API, so read their song IDs from the page
+        let (ids, playlist_name) = match kind {
+            MirrorItemKind::Playlist => {
+                let html = self.fetch_text(url).await?;
+                let ids = extract_apple_playlist_song_ids(&html);
+                if ids.is_empty() {
+                    return Err(anyhow!("Apple Music playlist page lists no songs"));
+                }
+                let name = extract_apple_playlist_name(&html)
+                    .unwrap_or_else(|| "Apple Music Playlist".to_string());
+                (ids.join(","), Some(name))
+            }
+            _ => (id.to_string(), None),
+        };
+
+        let lookup_url = format!(
+            "https://itunes.apple.com/lookup?id={}&entity=song&country={}",
+            ids, country
+        );
+        let json: Value = self.client.get(&lookup_url).send().await?.json().await?;
+        let results = json["results"].as_array().cloned().unwrap_or_default();
+
+        Ok(match playlist_name {
+            Some(name) => playlist_result(
+                name,
+                kind.as_str(),
+                url,
+                None,
+                apple_tracks(&results, &self.providers),
+                MirrorPlatform::AppleMusic,
+            ),
+            None => apple_load_result(&results, kind, url, &self.providers),
+        })
+    }
+
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {} fetching {}", response.status(), url));
+        }
+        Ok(response.text().await?)
+    }
+}
+
+/// Parse a Spotify URL or URI into its item kind and ID
+pub fn parse_spotify_url(url: &str) -> Option<(MirrorItemKind, String)> {
+    let (kind, id) = if let Some(uri) = url.strip_prefix("spotify:") {
+        let mut parts = uri.split(':');
+        (parts.next()?.to_string(), parts.next()?.to_string())
+    } else {
+        let parsed = Url::parse(url).ok()?;
+        if parsed.host_str()? != "open.spotify.com" {
+            return None;
+        }
+        // Localised links are prefixed with a segment such as "intl-de"
+        let segments: Vec<&str> = parsed
+            .path_segments()?
+            .filter(|segment| !segment.is_empty() && !segment.starts_with("intl-"))
+            .collect();
+        (segments.first()?.to_string(), segments.get(1)?.to_string())
+    };
+
+    let kind = match kind.as_str() {
+        "track" => MirrorItemKind::Track,
+        "album" => MirrorItemKind::Album,
+        "playlist" => MirrorItemKind::Playlist,
+        _ => return None,
+    };
+    Some((kind, id))
+}
+
+/// Parse a Deezer URL into its item kind and ID
+pub fn parse_deezer_url(url: &str) -> Option<(MirrorItemKind, String)> {
+    let parsed = Url::parse(url).ok()?;
+    if !parsed.host_str()?.ends_with("deezer.com") {
+        return None;
+    }
+
+    // Links may carry a language prefix such as "/en/track/123"
+    let segments: Vec<&str> = parsed.path_segments()?.collect();
+    segments.windows(2).find_map(|pair| {
+        let kind = match pair[0] {
+            "track" => MirrorItemKind::Track,
+            "album" => MirrorItemKind::Album,
+            "playlist" => MirrorItemKind::Playlist,
+            _ => return None,
+        };
+        pair[1]
+            .chars()
+            .all(|c| c.is_ascii_digit())
+            .then(|| (kind, pair[1].to_string()))
+    })
+}
+
+/// Parse an Apple Music URL into its item kind, ID and storefront country
+pub fn parse_apple_music_url(url: &str) -> Option<(MirrorItemKind, String, String)> {
+    let parsed = Url::parse(url).ok()?;
+    if parsed.host_str()? != "music.apple.com" {
+        return None;
+    }
+
+    let segments: Vec<&str> = parsed.path_segments()?.collect();
+    let country = segments.first()?.to_string();
+    let kind = *segments.get(1)?;
+    let id = segments.last()?.to_string();
+
+    // Album links to a single song carry the song ID in the "i" parameter
+    let song_id = parsed
+        .query_pairs()
+        .find(|(key, _)| key == "i")
+        .map(|(_, value)| value.into_owned());
+
+    match (kind, song_id) {
+        ("album", Some(song_id)) => Some((MirrorItemKind::Track, song_id, country)),
+        ("album", None) => Some((MirrorItemKind::Album, id, country)),
+        ("song", _) => Some((MirrorItemKind::Track, id, country)),
+        ("playlist", _) => Some((MirrorItemKind::Playlist, id, country)),
+        _ => None,
+    }
+}
+
+/// Expand mirror query templates for a track
+///
+/// Templates may use `%ISRC%`, `%QUERY%` (artist and title), `%TITLE%` and
+/// `%ARTIST%`. Templates needing an ISRC are skipped when the track has none.
+pub fn expand_queries(templates: &[String], info: &TrackInfo) -> Vec<String> {
+    templates
+        .iter()
+        .filter_map(|template| {
+            let isrc = info.isrc.as_deref().unwrap_or_default();
+            if template.contains("%ISRC%") && isrc.is_empty() {
+                return None;
+            }
+            Some(
+                template
+                    .replace("%ISRC%", isrc)
+                    .replace("%QUERY%", &format!("{} - {}", info.author, info.title))
+                    .replace("%TITLE%", &info.title)
+                    .replace("%ARTIST%", &info.author),
+            )
+        })
+        .collect()
+}
+
+/// Score how well a candidate matches the track being mirrored, from 0 to 1
+pub fn score_candidate(target: &TrackInfo, candidate: &TrackInfo) -> f64 {
+    let target_title = tokenize(&target.title);
+    let candidate_title = tokenize(&candidate.title);
+    let mut candidate_all = candidate_title.clone();
+    candidate_all.extend(tokenize(&candidate.author));
+
+    let title_score = overlap(&target_title, &candidate_title);
+    let artist_score = overlap(&tokenize(primary_artist(&target.author)), &candidate_all);
+    let duration_score = duration_similarity(target.length, candidate.length);
+
+    let penalty = PENALTY_WORDS
+        .iter()
+        .filter(|word| candidate_title.contains(**word) && !target_title.contains(**word))
+        .count() as f64
+        * 0.25;
+
+    (0.45 * title_score + 0.25 * artist_score + 0.3 * duration_score - penalty).max(0.0)
+}
+
+/// Pick the best-scoring candidate, if any scores high enough to be a mirror
+pub fn pick_best(target: &TrackInfo, candidates: Vec<Track>) -> Option<Track> {
+    candidates
+        .into_iter()
+        .map(|candidate| (score_candidate(target, &candidate.info), candidate))
+        .filter(|(score, _)| *score >= MIN_MATCH_SCORE)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate)
+}
+
+fn tokenize(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !NOISE_WORDS.contains(token))
+        .map(str::to_string)
+        .collect()
+}
+
+fn overlap(expected: &HashSet<String>, actual: &HashSet<String>) -> f64 {
+    if expected.is_empty() {
+        return 0.0;
+    }
+    expected.intersection(actual).count() as f64 / expected.len() as f64
+}
+
+fn primary_artist(author: &str) -> &str {
+    author
+        .split([',', '&'])
+        .next()
+        .map(str::trim)
+        .unwrap_or(author)
+}
+
+fn duration_similarity(expected: u64, actual: u64) -> f64 {
+    // Unknown durations neither help nor hurt
+    if expected == 0 || actual == 0 {
+        return 0.5;
+    }
+
+    let diff = expected.abs_diff(actual);
+    if diff <= DURATION_EXACT_MS {
+        1.0
+    } else if diff >= DURATION_MAX_DIFF_MS {
+        0.0
+    } else {
+        1.0 - (diff - DURATION_EXACT_MS) as f64 / (DURATION_MAX_DIFF_MS - DURATION_EXACT_MS) as f64
+    }
+}
+
+/// Extract the entity object from a Spotify embed page
+pub fn parse_spotify_embed(html: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("script#__NEXT_DATA__").unwrap();
+    let raw = document
+        .select(&selector)
+        .next()?
+        .text()
+        .collect::<String>();
+    let data: Value = serde_json::from_str(&raw).ok()?;
+
+    let entity = &data["props"]["pageProps"]["state"]["data"]["entity"];
+    entity.is_object().then(|| entity.clone())
+}
+
+/// Build a load result from a Spotify embed entity
+pub fn spotify_load_result(
+    entity: &Value,
+    kind: MirrorItemKind,
+    id: &str,
+    providers: &[String],
+) -> LoadResult {
+    let artwork_url = largest_spotify_image(entity);
+    let name = entity["name"]
+        .as_str()
+        .or_else(|| entity["title"].as_str())
+        .unwrap_or("Unknown Title");
+
+    if kind == MirrorItemKind::Track {
+        let author = entity["artists"]
+            .as_array()
+            .map(|artists| join_names(artists, "name"))
+            .or_else(|| entity["subtitle"].as_str().map(str::to_string))
+            .unwrap_or_else(|| "Unknown Artist".to_string());
+
+        let track = build_track(
+            MirrorPlatform::Spotify,
+            id,
+            name,
+            &author,
+            entity["duration"].as_u64().unwrap_or(0),
+            &format!("https://open.spotify.com/track/{id}"),
+            artwork_url,
+            None,
+            providers,
+        );
+        return LoadResult {
+            load_type: LoadType::Track,
+            data: Some(LoadResultData::Track(Box::new(track))),
+        };
+    }
+
+    let tracks = entity["trackList"]
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|entry| entry["isPlayable"].as_bool().unwrap_or(true))
+                .filter_map(|entry| {
+                    let track_id = entry["uri"].as_str()?.strip_prefix("spotify:track:")?;
+                    Some(build_track(
+                        MirrorPlatform::Spotify,
+                        track_id,
+                        entry["title"].as_str().unwrap_or("Unknown Title"),
+                        entry["subtitle"].as_str().unwrap_or("Unknown Artist"),
+                        entry["duration"].as_u64().unwrap_or(0),
+                        &format!("https://open.spotify.com/track/{track_id}"),
+                        artwork_url.clone(),
+                        None,
+                        providers,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    playlist_result(
+        name.to_string(),
+        kind.as_str(),
+        &format!("https://open.spotify.com/{}/{}", kind.as_str(), id),
+        entity["subtitle"].as_str(),
+        tracks,
+        MirrorPlatform::Spotify,
+    )
+}
+
+fn largest_spotify_image(entity: &Value) -> Option<String> {
+    let images = entity["visualIdentity"]["image"]
+        .as_array()
+        .or_else(|| entity["coverArt"]["sources"].as_array())?;
+
+    images
+        .iter()
+        .max_by_key(|image| {
+            image["maxWidth"]
+                .as_u64()
+                .or_else(|| image["width"].as_u64())
+                .unwrap_or(0)
+        })
+        .and_then(|image| image["url"].as_str())
+        .map(str::to_string)
+}
+
+/// Build a load result from a Deezer API response
+pub fn deezer_load_result(json: &Value, kind: MirrorItemKind, providers: &[String]) -> LoadResult {
+    if kind == MirrorItemKind::Track {
+        let track = deezer_track(json, json["album"]["cover_xl"].as_str(), providers);
+        return match track {
+            Some(track) => LoadResult {
+                load_type: LoadType::Track,
+                data: Some(LoadResultData::Track(Box::new(track))),
+            },
+            None => LoadResult {
+                load_type: LoadType::Empty,
+                data: None,
+            },
+        };
+    }
+
+    // Album track listings omit the cover, so fall back to the album's own
+    let collection_cover = json["cover_xl"]
+        .as_str()
+        .or_else(|| json["picture_xl"].as_str());
+    let tracks = json["tracks"]["data"]
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let cover = entry["album"]["cover_xl"].as_str().or(collection_cover);
+                    deezer_track(entry, cover, providers)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    playlist_result(
+        json["title"]
+            .as_str()
+            .unwrap_or("Unknown Title")
+            .to_string(),
+        kind.as_str(),
+        json["link"].as_str().unwrap_or_default(),
+        json["artist"]["name"]
+            .as_str()
+            .or_else(|| json["creator"]["name"].as_str()),
+        tracks,
+        MirrorPlatform::Deezer,
+    )
+}
+
+fn deezer_track(json: &Value, cover: Option<&str>, providers: &[String]) -> Option<Track> {
+    let id = json["id"].as_u64()?.to_string();
+    Some(build_track(
+        MirrorPlatform::Deezer,
+        &id,
+        json["title"].as_str().unwrap_or("Unknown Title"),
+        json["artist"]["name"].as_str().unwrap_or("Unknown Artist"),
+        json["duration"].as_u64().unwrap_or(0) * 1000,
+        json["link"]
+            .as_str()
+            .unwrap_or(&format!("https://www.deezer.com/track/{id}")),
+        cover.map(str::to_string),
+        json["isrc"].as_str().map(str::to_string),
+        providers,
+    ))
+}
+
+/// Build a load result from iTunes lookup results
+pub fn apple_load_result(
+    results: &[Value],
+    kind: MirrorItemKind,
+    url: &str,
+    providers: &[String],
+) -> LoadResult {
+    let tracks = apple_tracks(results, providers);
+
+    if kind == MirrorItemKind::Track {
+        return match tracks.into_iter().next() {
+            Some(track) => LoadResult {
+                load_type: LoadType::Track,
+                data: Some(LoadResultData::Track(Box::new(track))),
+            },
+            None => LoadResult {
+                load_type: LoadType::Empty,
+                data: None,
+            },
+        };
+    }
+
+    let collection = results
+        .iter()
+        .find(|result| result["wrapperType"] == "collection");
+    playlist_result(
+        collection
+            .and_then(|c| c["collectionName"].as_str())
+            .unwrap_or("Unknown Album")
+            .to_string(),
+        kind.as_str(),
+        url,
+        collection.and_then(|c| c["artistName"].as_str()),
+        tracks,
+        MirrorPlatform::AppleMusic,
+    )
+}
+
+fn apple_tracks(results: &[Value], providers: &[String]) -> Vec<Track> {
+    results
+        .iter()
+        .filter(|result| result["wrapperType"] == "track")
+        .filter_map(|result| {
+            let id = result["trackId"].as_u64()?.to_string();
+            Some(build_track(
+                MirrorPlatform::AppleMusic,
+                &id,
+                result["trackName"].as_str().unwrap_or("Unknown Title"),
+                result["artistName"].as_str().unwrap_or("Unknown Artist"),
+                result["trackTimeMillis"].as_u64().unwrap_or(0),
+                result["trackViewUrl"].as_str().unwrap_or_default(),
+                // The lookup API only returns thumbnails, but larger sizes share the URL scheme
+                result["artworkUrl100"]
+                    .as_str()
+                    .map(|url| url.replace("100x100", "600x600")),
+                None,
+                providers,
+            ))
+        })
+        .collect()
+}
+
+/// Collect song IDs from an Apple Music playlist page's structured data
+pub fn extract_apple_playlist_song_ids(html: &str) -> Vec<String> {
+    apple_playlist_schema(html)
+        .and_then(|schema| schema["track"].as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|track| track["url"].as_str())
+        .filter_map(|url| url.rsplit('/').next())
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn extract_apple_playlist_name(html: &str) -> Option<String> {
+    apple_playlist_schema(html)?["name"]
+        .as_str()
+        .map(str::to_string)
+}
+
+fn apple_playlist_schema(html: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+    document
+        .select(&selector)
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text().collect::<String>()).ok())
+        .find(|schema| schema["@type"] == "MusicPlaylist")
+}
+
+fn join_names(entries: &[Value], key: &str) -> String {
+    entries
+        .iter()
+        .filter_map(|entry| entry[key].as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg_attr(not(feature = "plugins"), allow(unused_variables))]
+fn playlist_result(
+    name: String,
+    kind: &str,
+    url: &str,
+    author: Option<&str>,
+    tracks: Vec<Track>,
+    platform: MirrorPlatform,
+) -> LoadResult {
+    if tracks.is_empty() {
+        return LoadResult {
+            load_type: LoadType::Empty,
+            data: None,
+        };
+    }
+
+    LoadResult {
+        load_type: LoadType::Playlist,
+        data: Some(LoadResultData::Playlist(Playlist {
+            info: PlaylistInfo {
+                name,
+                selected_track: None,
+            },
+            #[cfg(feature = "plugins")]
+            plugin_info: std::collections::HashMap::from([
+                ("type".to_string(), Value::from(kind)),
+                ("url".to_string(), Value::from(url)),
+                (
+                    "author".to_string(),
+                    author.map(Value::from).unwrap_or(Value::Null),
+                ),
+                (
+                    "artworkUrl".to_string(),
+                    tracks[0]
+                        .info
+                        .artwork_url
+                        .clone()
+                        .map(Value::from)
+                        .unwrap_or(Value::Null),
+                ),
+                (
+                    "sourceName".to_string(),
+                    Value::from(platform.source_name()),
+                ),
+            ]),
+            tracks,
+        })),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(feature = "plugins"), allow(unused_variables))]
+fn build_track(
+    platform: MirrorPlatform,
+    identifier: &str,
+    title: &str,
+    author: &str,
+    length: u64,
+    uri: &str,
+    artwork_url: Option<String>,
+    isrc: Option<String>,
+    providers: &[String],
+) -> Track {
+    let info = TrackInfo {
+        identifier: identifier.to_string(),
+        is_seekable: true,
+        author: author.to_string(),
+        length,
+        is_stream: false,
+        position: 0,
+        title: title.to_string(),
+        uri: Some(uri.to_string()),
+        artwork_url,
+        isrc,
+        source_name: platform.source_name().to_string(),
+    };
+
+    // Queries are expanded up front so the configured templates apply at playback
+    #[cfg(feature = "plugins")]
+    let plugin_info = std::collections::HashMap::from([(
+        "mirrorQueries".to_string(),
+        Value::from(expand_queries(providers, &info)),
+    )]);
+
+    let mut track = Track {
+        encoded: String::new(),
+        info,
+        #[cfg(feature = "plugins")]
+        plugin_info,
+        #[cfg(feature = "rest-api")]
+        user_data: std::collections::HashMap::new(),
+    };
+
+    // Encode the whole track so the mirror queries survive a decode round-trip
+    track.encoded = track.encode();
+    track
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(title: &str, author: &str, length: u64) -> TrackInfo {
+        TrackInfo {
+            identifier: title.to_string(),
+            is_seekable: true,
+            author: author.to_string(),
+            length,
+            is_stream: false,
+            position: 0,
+            title: title.to_string(),
+            uri: None,
+            artwork_url: None,
+            isrc: None,
+            source_name: "youtube".to_string(),
+        }
+    }
+
+    fn candidate(title: &str, author: &str, length: u64) -> Track {
+        Track {
+            encoded: String::new(),
+            info: info(title, author, length),
+            #[cfg(feature = "plugins")]
+            plugin_info: std::collections::HashMap::new(),
+            #[cfg(feature = "rest-api")]
+            user_data: std::collections::HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_platform_url_parsing() {
+        assert_eq!(
+            parse_spotify_url("https://open.spotify.com/intl-de/track/4iV5W9uYEdYUVa79Axb7Rh?si=x"),
+            Some((MirrorItemKind::Track, "4iV5W9uYEdYUVa79Axb7Rh".to_string()))
+        );
+        assert_eq!(
+            parse_spotify_url("spotify:playlist:37i9dQZF1DXcBWIGoYBM5M"),
+            Some((
+                MirrorItemKind::Playlist,
+                "37i9dQZF1DXcBWIGoYBM5M".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_spotify_url("https://open.spotify.com/artist/abc"),
+            None
+        );
+
+        assert_eq!(
+            parse_deezer_url("https://www.deezer.com/en/album/302127"),
+            Some((MirrorItemKind::Album, "302127".to_string()))
+        );
+        assert_eq!(
+            parse_deezer_url("https://www.deezer.com/en/profile/1"),
+            None
+        );
+
+        assert_eq!(
+            parse_apple_music_url(
+                "https://music.apple.com/us/album/signals/1440857781?i=1440857795"
+            ),
+            Some((
+                MirrorItemKind::Track,
+                "1440857795".to_string(),
+                "us".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_apple_music_url("https://music.apple.com/gb/playlist/mix/pl.u-abc123"),
+            Some((
+                MirrorItemKind::Playlist,
+                "pl.u-abc123".to_string(),
+                "gb".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_expand_queries() {
+        let templates: Vec<String> = DEFAULT_MIRROR_PROVIDERS
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let mut target = info("Signals", "The Example Ensemble", 200_000);
+
+        assert_eq!(
+            expand_queries(&templates, &target),
+            vec!["ytsearch:The Example Ensemble - Signals".to_string()]
+        );
+
+        target.isrc = Some("USRC17607839".to_string());
+        assert_eq!(
+            expand_queries(&templates, &target),
+            vec![
+                "ytsearch:\"USRC17607839\"".to_string(),
+                "ytsearch:The Example Ensemble - Signals".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pick_best_skips_alternate_versions() {
+        let target = info("Signals", "The Example Ensemble", 200_000);
+        let candidates = vec![
+            candidate("Signals (10 Hour Loop)", "Loop Channel", 36_000_000),
+            candidate("Signals - Acoustic Cover", "Someone Else", 195_000),
+            candidate(
+                "The Example Ensemble - Signals (Live)",
+                "Fan Uploads",
+                230_000,
+            ),
+            candidate("Signals", "The Example Ensemble - Topic", 201_000),
+            candidate(
+                "The Example Ensemble - Signals (Official Video)",
+                "ExampleEnsembleVEVO",
+                212_000,
+            ),
+        ];
+
+        let best = pick_best(&target, candidates).unwrap();
+        assert_eq!(best.info.author, "The Example Ensemble - Topic");
+    }
+
+    #[test]
+    fn test_pick_best_rejects_unrelated_results() {
+        let target = info("Signals", "The Example Ensemble", 200_000);
+        let candidates = vec![candidate(
+            "Something Else Entirely",
+            "Another Artist",
+            90_000,
+        )];
+
+        assert!(pick_best(&target, candidates).is_none());
+    }
+
+    #[test]
+    fn test_spotify_embed_album() {
+        let html = include_str!("../../../tests/fixtures/mirror/spotify_album.html");
+        let entity = parse_spotify_embed(html).unwrap();
+        let providers = vec!["ytsearch:%QUERY%".to_string()];
+
+        let result = spotify_load_result(&entity, MirrorItemKind::Album, "1abc", &providers);
+        let Some(LoadResultData::Playlist(playlist)) = result.data else {
+            panic!("expected a playlist");
+        };
+
+        assert_eq!(playlist.info.name, "Signals & Noise");
+        assert_eq!(playlist.tracks.len(), 2);
+
+        let first = &playlist.tracks[0];
+        assert_eq!(first.info.source_name, "spotify");
+        assert_eq!(first.info.identifier, "0aaa");
+        assert_eq!(first.info.title, "Signals");
+        assert_eq!(first.info.author, "The Example Ensemble");
+        assert_eq!(first.info.length, 200_000);
+        assert_eq!(
+            first.info.artwork_url.as_deref(),
+            Some("https://i.scdn.co/image/large")
+        );
+        #[cfg(feature = "plugins")]
+        assert_eq!(
+            first.plugin_info["mirrorQueries"],
+            serde_json::json!(["ytsearch:The Example Ensemble - Signals"])
+        );
+    }
+
+    #[test]
+    fn test_deezer_track_keeps_isrc() {
+        let json = serde_json::json!({
+            "id": 3135556,
+            "title": "Signals",
+            "isrc": "USRC17607839",
+            "link": "https://www.deezer.com/track/3135556",
+            "duration": 200,
+            "artist": { "name": "The Example Ensemble" },
+            "album": { "cover_xl": "https://e-cdns-images.dzcdn.net/images/cover/xl.jpg" }
+        });
+
+        let result = deezer_load_result(&json, MirrorItemKind::Track, &[]);
+        let Some(LoadResultData::Track(track)) = result.data else {
+            panic!("expected a track");
+        };
+
+        assert_eq!(track.info.source_name, "deezer");
+        assert_eq!(track.info.isrc.as_deref(), Some("USRC17607839"));
+        assert_eq!(track.info.length, 200_000);
+        assert_eq!(
+            track.info.artwork_url.as_deref(),
+            Some("https://e-cdns-images.dzcdn.net/images/cover/xl.jpg")
+        );
+
+        // The encoded form decodes back to the same track
+        let decoded = Track::decode(&track.encoded).expect("encoded track decodes");
+        assert_eq!(decoded, *track);
+    }
+}
@@ -6,15 +6,42 @@
 #[cfg(feature = "audio-sources")]
 pub mod soundcloud;
 
+#[cfg(feature = "audio-sources")]
+pub mod chapters;
+
 #[cfg(feature = "audio-sources")]
 pub mod bandcamp;
 
 #[cfg(feature = "audio-sources")]
 pub mod http_content_detection;
 
+#[cfg(feature = "audio-sources")]
+pub mod hls;
+
+#[cfg(feature = "audio-sources")]
+pub mod icy;
+
+#[cfg(feature = "audio-sources")]
+pub mod live;
+
 #[cfg(feature = "audio-sources")]
 pub mod local;
 
+#[cfg(feature = "audio-sources")]
+pub mod local_library;
+
+#[cfg(feature = "audio-sources")]
+pub mod niconico;
+
+#[cfg(feature = "audio-sources")]
+pub mod mirror;
+
+#[cfg(feature = "audio-sources")]
+pub mod podcast;
+
+#[cfg(feature = "audio-sources")]
+pub mod synth;
+
 // Re-export main types
 #[cfg(feature = "audio-sources")]
 #[allow(unused_imports)]
@@ -30,3 +57,17 @@ pub use http_content_detection::HttpContentDetectionSource;
 
 #[cfg(feature = "audio-sources")]
 pub use local::LocalAudioSource;
+
+#[cfg(feature = "audio-sources")]
+#[allow(unused_imports)]
+pub use niconico::{NicoClient, NicoStream};
+
+#[cfg(feature = "audio-sources")]
+#[allow(unused_imports)]
+pub use mirror::MirrorMetadataClient;
+
+#[cfg(feature = "audio-sources")]
+pub use podcast::PodcastClient;
+
+#[cfg(feature = "audio-sources")]
+pub use synth::SynthSpec;
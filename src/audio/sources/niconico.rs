@@ -0,0 +1,628 @@
+//! Niconico integration for Lavalink-rust
+//!
+//! This module extracts video metadata from Niconico watch pages, searches
+//! through the snapshot search API and negotiates playback sessions with
+//! either the domand (HLS) or the legacy DMC delivery backend.
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderMap, HeaderValue, COOKIE, SET_COOKIE};
+use reqwest::Client;
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::protocol::{Track, TrackInfo};
+
+const WATCH_URL: &str = "https://www.nicovideo.jp/watch/";
+const SEARCH_URL: &str =
+    "https://snapshot.search.nicovideo.jp/api/v2/snapshot/video/contents/search";
+const DOMAND_ACCESS_URL: &str = "https://nvapi.nicovideo.jp/v1/watch";
+
+/// Fallback heartbeat lifetime when the session does not specify one
+const DEFAULT_HEARTBEAT_LIFETIME_MS: u64 = 120_000;
+
+/// Niconico client for metadata, search and stream negotiation
+pub struct NicoClient {
+    client: Client,
+}
+
+/// A negotiated Niconico stream
+///
+/// DMC sessions expire unless they receive heartbeats, so the stream keeps a
+/// background task alive for as long as it is held. Dropping the stream stops
+/// the heartbeat.
+pub struct NicoStream {
+    /// URL to fetch the media from
+    pub url: String,
+    /// Whether the URL points at an HLS playlist
+    pub is_hls: bool,
+    /// Cookies required to fetch the media
+    pub cookies: Option<String>,
+    heartbeat: Option<JoinHandle<()>>,
+}
+
+impl NicoStream {
+    /// Headers to send when fetching the media
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = self
+            .cookies
+            .as_deref()
+            .and_then(|cookies| HeaderValue::from_str(cookies).ok())
+        {
+            headers.insert(COOKIE, value);
+        }
+        headers
+    }
+}
+
+impl Drop for NicoStream {
+    fn drop(&mut self) {
+        if let Some(heartbeat) = self.heartbeat.take() {
+            heartbeat.abort();
+        }
+    }
+}
+
+impl NicoClient {
+    /// Create a new Niconico client
+    pub fn new() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self { client }
+    }
+
+    /// Load a video's metadata as a track
+    pub async fn load_video(&self, video_id: &str) -> Result<Track> {
+        let watch_data = self.fetch_watch_data(video_id).await?;
+        track_from_watch_data(&watch_data)
+            .ok_or_else(|| anyhow!("Niconico watch page is missing video metadata"))
+    }
+
+    /// Search videos through the snapshot search API
+    pub async fn search(&self, query: &str, limit: u32) -> Result<Vec<Track>> {
+        debug!("Searching Niconico for: {}", query);
+
+        let limit = limit.min(100).to_string();
+        let response = self
+            .client
+            .get(SEARCH_URL)
+            .query(&[
+                ("q", query),
+                ("targets", "title,tags"),
+                ("fields", "contentId,title,lengthSeconds,thumbnailUrl"),
+                ("_sort", "-viewCounter"),
+                ("_limit", limit.as_str()),
+                ("_context", "lavalink-rust"),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Niconico search failed: {}", response.status()));
+        }
+
+        let body: Value = response.json().await?;
+        Ok(tracks_from_search_response(&body))
+    }
+
+    /// Negotiate a playable stream for a video
+    pub async fn open_stream(&self, video_id: &str) -> Result<NicoStream> {
+        let watch_data = self.fetch_watch_data(video_id).await?;
+        let media = &watch_data["media"];
+
+        if media["domand"].is_object() {
+            return self.open_domand_stream(video_id, &watch_data).await;
+        }
+
+        if media["delivery"]["movie"]["session"].is_object() {
+            return self
+                .open_dmc_stream(&media["delivery"]["movie"]["session"])
+                .await;
+        }
+
+        Err(anyhow!("Niconico video {} has no playable media", video_id))
+    }
+
+    /// Fetch and parse the embedded data of a watch page
+    async fn fetch_watch_data(&self, video_id: &str) -> Result<Value> {
+        let url = format!("{WATCH_URL}{video_id}");
+        let response = self.client.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to fetch Niconico watch page: {}",
+                response.status()
+            ));
+        }
+
+        let html = response.text().await?;
+        parse_watch_data(&html).ok_or_else(|| anyhow!("No watch data found on Niconico page"))
+    }
+
+    /// Request HLS access rights from the domand backend
+    async fn open_domand_stream(&self, video_id: &str, watch_data: &Value) -> Result<NicoStream> {
+        let domand = &watch_data["media"]["domand"];
+        let access_right_key = domand["accessRightKey"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing domand access right key"))?;
+        let track_id = watch_data["client"]["watchTrackId"]
+            .as_str()
+            .unwrap_or_default();
+
+        let outputs = select_domand_outputs(domand)
+            .ok_or_else(|| anyhow!("No available domand audio output"))?;
+
+        let response = self
+            .client
+            .post(format!(
+                "{DOMAND_ACCESS_URL}/{video_id}/access-rights/hls?actionTrackId={track_id}"
+            ))
+            .header("X-Access-Right-Key", access_right_key)
+            .header("X-Frontend-Id", "6")
+            .header("X-Frontend-Version", "0")
+            .header("X-Request-With", "https://www.nicovideo.jp")
+            .json(&json!({ "outputs": [outputs] }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Niconico domand negotiation failed: {}",
+                response.status()
+            ));
+        }
+
+        // Segment requests are authorised by the cookies set here
+        let cookies = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split(';').next())
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let body: Value = response.json().await?;
+        let url = body["data"]["contentUrl"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Domand response is missing the content URL"))?
+            .to_string();
+
+        Ok(NicoStream {
+            url,
+            is_hls: true,
+            cookies: (!cookies.is_empty()).then_some(cookies),
+            heartbeat: None,
+        })
+    }
+
+    /// Create a DMC session and keep it alive with heartbeats
+    async fn open_dmc_stream(&self, session: &Value) -> Result<NicoStream> {
+        let api_url = session["urls"][0]["url"]
+            .as_str()
+            .ok_or_else(|| anyhow!("DMC session is missing an API URL"))?
+            .to_string();
+
+        let response = self
+            .client
+            .post(format!("{api_url}?_format=json"))
+            .json(&build_dmc_session_request(session))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Niconico DMC negotiation failed: {}",
+                response.status()
+            ));
+        }
+
+        let body: Value = response.json().await?;
+        let created = body["data"].clone();
+        let url = created["session"]["content_uri"]
+            .as_str()
+            .ok_or_else(|| anyhow!("DMC response is missing the content URI"))?
+            .to_string();
+        let session_id = created["session"]["id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("DMC response is missing the session id"))?
+            .to_string();
+
+        let lifetime = session["heartbeatLifetime"]
+            .as_u64()
+            .unwrap_or(DEFAULT_HEARTBEAT_LIFETIME_MS);
+        let heartbeat = tokio::spawn(run_dmc_heartbeat(
+            self.client.clone(),
+            format!("{api_url}/{session_id}?_format=json&_method=PUT"),
+            created,
+            heartbeat_interval(lifetime),
+        ));
+
+        Ok(NicoStream {
+            is_hls: url.contains(".m3u8"),
+            url,
+            cookies: None,
+            heartbeat: Some(heartbeat),
+        })
+    }
+}
+
+impl Default for NicoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send heartbeats for a DMC session until the task is aborted
+async fn run_dmc_heartbeat(client: Client, url: String, mut session: Value, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        match client.post(&url).json(&session).send().await {
+            Ok(response) if response.status().is_success() => {
+                // The server may hand back an updated session document
+                if let Ok(body) = response.json::<Value>().await {
+                    if body["data"]["session"].is_object() {
+                        session = body["data"].clone();
+                    }
+                }
+                debug!("Sent Niconico DMC heartbeat");
+            }
+            Ok(response) => {
+                warn!("Niconico DMC heartbeat rejected: {}", response.status());
+                break;
+            }
+            Err(e) => warn!("Niconico DMC heartbeat failed: {}", e),
+        }
+    }
+}
+
+/// Heartbeats are sent at a third of the session lifetime
+fn heartbeat_interval(lifetime_ms: u64) -> Duration {
+    Duration::from_millis((lifetime_ms / 3).max(1_000))
+}
+
+/// Extract a video id from a Niconico URL or bare id
+pub fn extract_video_id(identifier: &str) -> Option<String> {
+    let is_video_id = |id: &str| {
+        let digits = id.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        id.len() > digits.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+
+    let candidate = match Url::parse(identifier) {
+        Ok(url) => {
+            let host = url.host_str()?;
+            let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+            match (host, segments.as_slice()) {
+                ("nico.ms", [id]) => id.to_string(),
+                (host, ["watch", id]) if host.ends_with("nicovideo.jp") => id.to_string(),
+                _ => return None,
+            }
+        }
+        Err(_) => identifier.to_string(),
+    };
+
+    is_video_id(&candidate).then_some(candidate)
+}
+
+/// Parse the watch data embedded in a watch page
+///
+/// Current pages ship it in a `server-response` meta tag, older ones in the
+/// `data-api-data` attribute of `#js-initial-watch-data`.
+pub fn parse_watch_data(html: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+
+    let meta_selector = Selector::parse("meta[name='server-response']").unwrap();
+    if let Some(content) = document
+        .select(&meta_selector)
+        .find_map(|element| element.value().attr("content"))
+    {
+        if let Ok(value) = serde_json::from_str::<Value>(content) {
+            let response = &value["data"]["response"];
+            if response.is_object() {
+                return Some(response.clone());
+            }
+        }
+    }
+
+    let legacy_selector = Selector::parse("#js-initial-watch-data[data-api-data]").unwrap();
+    document
+        .select(&legacy_selector)
+        .filter_map(|element| element.value().attr("data-api-data"))
+        .find_map(|raw| serde_json::from_str::<Value>(raw).ok())
+}
+
+/// Build a track from parsed watch data
+pub fn track_from_watch_data(watch_data: &Value) -> Option<Track> {
+    let video = &watch_data["video"];
+    let id = video["id"].as_str()?;
+    let title = video["title"].as_str().unwrap_or("Unknown Title");
+    let author = watch_data["owner"]["nickname"]
+        .as_str()
+        .or_else(|| watch_data["channel"]["name"].as_str())
+        .unwrap_or("Unknown Artist");
+    let length = video["duration"].as_u64().unwrap_or(0) * 1000;
+
+    let thumbnail = &video["thumbnail"];
+    let artwork_url = ["ogp", "largeUrl", "middleUrl", "url"]
+        .iter()
+        .find_map(|key| thumbnail[*key].as_str())
+        .map(str::to_string);
+
+    Some(build_track(id, title, author, length, artwork_url))
+}
+
+/// Build tracks from a snapshot search API response
+pub fn tracks_from_search_response(body: &Value) -> Vec<Track> {
+    body["data"]
+        .as_array()
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|result| {
+                    let id = result["contentId"].as_str()?;
+                    let title = result["title"].as_str().unwrap_or("Unknown Title");
+                    let length = result["lengthSeconds"].as_u64().unwrap_or(0) * 1000;
+                    let artwork_url = result["thumbnailUrl"].as_str().map(str::to_string);
+
+                    // The snapshot API does not expose uploader names
+                    Some(build_track(
+                        id,
+                        title,
+                        "Unknown Artist",
+                        length,
+                        artwork_url,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Pick the cheapest available video paired with the best available audio
+///
+/// Domand only serves muxed outputs, so a video rendition is always required.
+fn select_domand_outputs(domand: &Value) -> Option<[String; 2]> {
+    let available = |key: &str| -> Vec<&Value> {
+        domand[key]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter(|item| item["isAvailable"].as_bool().unwrap_or(false))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let video = available("videos")
+        .into_iter()
+        .min_by_key(|item| item["qualityLevel"].as_u64().unwrap_or(u64::MAX))?;
+    let audio = available("audios")
+        .into_iter()
+        .max_by_key(|item| item["qualityLevel"].as_u64().unwrap_or(0))?;
+
+    Some([
+        video["id"].as_str()?.to_string(),
+        audio["id"].as_str()?.to_string(),
+    ])
+}
+
+/// Build a DMC session creation request from the watch page session data
+pub fn build_dmc_session_request(session: &Value) -> Value {
+    let first_id = |key: &str| session[key][0].clone();
+    let yes_no = |value: &Value| {
+        if value.as_bool().unwrap_or(true) {
+            "yes"
+        } else {
+            "no"
+        }
+    };
+    let endpoint = &session["urls"][0];
+
+    json!({
+        "session": {
+            "recipe_id": session["recipeId"],
+            "content_id": session["contentId"],
+            "content_type": "movie",
+            "content_src_id_sets": [{
+                "content_src_ids": [{
+                    "src_id_to_mux": {
+                        // Videos are listed best-first; audio-only playback wants the smallest
+                        "video_src_ids": [session["videos"].as_array().and_then(|v| v.last()).cloned().unwrap_or(Value::Null)],
+                        "audio_src_ids": [first_id("audios")],
+                    }
+                }]
+            }],
+            "timing_constraint": "unlimited",
+            "keep_method": {
+                "heartbeat": {
+                    "lifetime": session["heartbeatLifetime"].as_u64().unwrap_or(DEFAULT_HEARTBEAT_LIFETIME_MS)
+                }
+            },
+            "protocol": {
+                "name": "http",
+                "parameters": {
+                    "http_parameters": {
+                        "parameters": {
+                            "http_output_download_parameters": {
+                                "use_well_known_port": yes_no(&endpoint["isWellKnownPort"]),
+                                "use_ssl": yes_no(&endpoint["isSsl"]),
+                                "transfer_preset": session["transferPresets"][0].as_str().unwrap_or(""),
+                            }
+                        }
+                    }
+                }
+            },
+            "content_uri": "",
+            "session_operation_auth": {
+                "session_operation_auth_by_signature": {
+                    "token": session["token"],
+                    "signature": session["signature"],
+                }
+            },
+            "content_auth": {
+                "auth_type": session["authTypes"]["http"],
+                "content_key_timeout": session["contentKeyTimeout"],
+                "service_id": "nicovideo",
+                "service_user_id": session["serviceUserId"],
+            },
+            "client_info": {
+                "player_id": session["playerId"],
+            },
+            "priority": session["priority"],
+        }
+    })
+}
+
+fn build_track(
+    id: &str,
+    title: &str,
+    author: &str,
+    length: u64,
+    artwork_url: Option<String>,
+) -> Track {
+    let info = TrackInfo {
+        identifier: id.to_string(),
+        is_seekable: true,
+        author: author.to_string(),
+        length,
+        is_stream: false,
+        position: 0,
+        title: title.to_string(),
+        uri: Some(format!("{WATCH_URL}{id}")),
+        artwork_url,
+        isrc: None,
+        source_name: "nico".to_string(),
+    };
+
+    let mut track = Track {
+        encoded: String::new(),
+        info,
+        #[cfg(feature = "plugins")]
+        plugin_info: std::collections::HashMap::new(),
+        #[cfg(feature = "rest-api")]
+        user_data: std::collections::HashMap::new(),
+    };
+    track.encoded = track.encode();
+    track
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATCH_PAGE: &str = include_str!("../../../tests/fixtures/niconico/watch.html");
+
+    #[test]
+    fn test_video_id_extraction() {
+        assert_eq!(
+            extract_video_id("https://www.nicovideo.jp/watch/sm9"),
+            Some("sm9".to_string())
+        );
+        assert_eq!(
+            extract_video_id("https://sp.nicovideo.jp/watch/so12345?ref=share"),
+            Some("so12345".to_string())
+        );
+        assert_eq!(
+            extract_video_id("https://nico.ms/nm2829323"),
+            Some("nm2829323".to_string())
+        );
+        assert_eq!(extract_video_id("sm9"), Some("sm9".to_string()));
+        assert_eq!(extract_video_id("https://www.nicovideo.jp/ranking"), None);
+        assert_eq!(extract_video_id("12345"), None);
+    }
+
+    #[test]
+    fn test_watch_page_fixture_metadata() {
+        let watch_data = parse_watch_data(WATCH_PAGE).expect("watch page has embedded data");
+        let track = track_from_watch_data(&watch_data).expect("watch data has a video");
+
+        assert_eq!(track.info.identifier, "sm9");
+        assert_eq!(track.info.title, "Example Video Title");
+        assert_eq!(track.info.author, "example_uploader");
+        assert_eq!(track.info.length, 320_000);
+        assert_eq!(
+            track.info.artwork_url.as_deref(),
+            Some("https://img.cdn.nimg.jp/s/nicovideo/thumbnails/9/9.original/r1280x720l?key=ogp")
+        );
+        assert_eq!(
+            track.info.uri.as_deref(),
+            Some("https://www.nicovideo.jp/watch/sm9")
+        );
+
+        // The encoded form decodes back to the same track
+        let decoded = Track::decode(&track.encoded).expect("encoded track decodes");
+        assert_eq!(decoded.info, track.info);
+        assert_eq!(decoded.encoded, track.encoded);
+
+        assert_eq!(
+            select_domand_outputs(&watch_data["media"]["domand"]),
+            Some([
+                "video-h264-360p".to_string(),
+                "audio-aac-192kbps".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_search_response_parsing() {
+        let body = json!({
+            "meta": { "status": 200, "totalCount": 2 },
+            "data": [
+                { "contentId": "sm9", "title": "First", "lengthSeconds": 320, "thumbnailUrl": "https://nicovideo.cdn.nimg.jp/thumbnails/9/9" },
+                { "contentId": "sm10", "title": "Second", "lengthSeconds": 61 }
+            ]
+        });
+
+        let tracks = tracks_from_search_response(&body);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].info.identifier, "sm9");
+        assert_eq!(tracks[0].info.length, 320_000);
+        assert_eq!(tracks[1].info.artwork_url, None);
+    }
+
+    #[test]
+    fn test_dmc_session_request() {
+        let session = json!({
+            "recipeId": "nicovideo-sm9",
+            "playerId": "nicovideo-6-abc_123",
+            "videos": ["archive_h264_720p", "archive_h264_360p"],
+            "audios": ["archive_aac_128kbps", "archive_aac_64kbps"],
+            "contentId": "out1",
+            "heartbeatLifetime": 120000,
+            "contentKeyTimeout": 600000,
+            "priority": 0.2,
+            "serviceUserId": "1",
+            "token": "{\"service_id\":\"nicovideo\"}",
+            "signature": "deadbeef",
+            "authTypes": { "http": "ht2", "hls": "ht2" },
+            "transferPresets": [],
+            "urls": [{ "url": "https://api.dmc.nicovideo.jp/api/sessions", "isWellKnownPort": true, "isSsl": true }]
+        });
+
+        let request = build_dmc_session_request(&session);
+        let body = &request["session"];
+
+        assert_eq!(body["recipe_id"], "nicovideo-sm9");
+        let mux = &body["content_src_id_sets"][0]["content_src_ids"][0]["src_id_to_mux"];
+        assert_eq!(mux["video_src_ids"][0], "archive_h264_360p");
+        assert_eq!(mux["audio_src_ids"][0], "archive_aac_128kbps");
+        assert_eq!(body["keep_method"]["heartbeat"]["lifetime"], 120000);
+        assert_eq!(body["content_auth"]["auth_type"], "ht2");
+        let http = &body["protocol"]["parameters"]["http_parameters"]["parameters"]
+            ["http_output_download_parameters"];
+        assert_eq!(http["use_ssl"], "yes");
+        assert_eq!(http["transfer_preset"], "");
+
+        assert_eq!(heartbeat_interval(120_000), Duration::from_secs(40));
+    }
+}
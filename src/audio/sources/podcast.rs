@@ -0,0 +1,1135 @@
+//! Podcast feed source for Lavalink-rust
+//!
+//! This module loads RSS and Atom podcast feeds as playlists of their
+//! episodes, newest first, and searches the episodes of feeds that were
+//! loaded before. Episode chapters published as `podcast:chapters` JSON are
+//! exposed in the track's `pluginInfo`.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::DateTime;
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use url::Url;
+
+use super::local_library::{fuzzy_score, tokenize, MIN_SEARCH_SCORE};
+use crate::config::PodcastFeedsConfig;
+use crate::protocol::{
+    normalize_chapters, Chapter, Exception, LoadResult, LoadResultData, LoadType, Playlist,
+    PlaylistInfo, Severity, Track, TrackInfo,
+};
+
+/// Prefix for searching the episodes of cached feeds
+pub const PODCAST_SEARCH_PREFIX: &str = "podcast:";
+
+/// Largest feed document that is downloaded
+const MAX_FEED_SIZE: usize = 20 * 1024 * 1024;
+/// Chapters are fetched for this many of the newest episodes that have them
+const MAX_CHAPTER_FETCHES: usize = 10;
+const CHAPTER_FETCH_CONCURRENCY: usize = 4;
+/// How long a fetched feed is served from the cache before it is fetched again
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const MAX_SEARCH_RESULTS: usize = 20;
+
+/// A parsed podcast feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastFeed {
+    pub url: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub artwork_url: Option<String>,
+    /// Episodes, newest first
+    pub episodes: Vec<PodcastEpisode>,
+}
+
+/// A feed item with a playable enclosure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastEpisode {
+    pub title: String,
+    pub author: Option<String>,
+    pub enclosure_url: String,
+    pub duration_ms: Option<u64>,
+    /// Publication time as a unix timestamp in seconds
+    pub published: Option<i64>,
+    pub guid: Option<String>,
+    pub artwork_url: Option<String>,
+    pub link: Option<String>,
+    pub chapters_url: Option<String>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+}
+
+struct CachedFeed {
+    feed: Arc<PodcastFeed>,
+    /// When the feed was fetched; `None` for feeds restored from the cache file
+    fetched: Option<Instant>,
+}
+
+/// Fetches, parses and caches podcast feeds
+pub struct PodcastClient {
+    client: Client,
+    cache: RwLock<HashMap<String, CachedFeed>>,
+    /// Feeds fetched for search even if no client loaded them yet
+    feeds: Vec<String>,
+    cache_file: Option<PathBuf>,
+    refresh_interval: Duration,
+}
+
+impl PodcastClient {
+    /// Create a client, restoring feeds from the cache file if one is configured
+    pub fn new(config: Option<&PodcastFeedsConfig>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("Lavalink-rust/4.0.0")
+            .build()
+            .expect("Failed to create HTTP client");
+
+        let cache_file = config
+            .and_then(|c| c.cache_file.as_ref())
+            .map(PathBuf::from);
+        let cache = cache_file
+            .as_deref()
+            .map(load_cache_file)
+            .unwrap_or_default();
+
+        Self {
+            client,
+            cache: RwLock::new(cache),
+            feeds: config.and_then(|c| c.feeds.clone()).unwrap_or_default(),
+            cache_file,
+            refresh_interval: config
+                .and_then(|c| c.refresh_interval_ms)
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_REFRESH_INTERVAL),
+        }
+    }
+
+    /// Load a feed as a playlist of its episodes
+    pub async fn load_feed(&self, url: &str) -> Result<LoadResult> {
+        let feed = match self.feed(url).await {
+            Ok(feed) => feed,
+            Err(e) => {
+                return Ok(LoadResult {
+                    load_type: LoadType::Error,
+                    data: Some(LoadResultData::Exception(Exception {
+                        message: Some(format!("Failed to load podcast feed: {e}")),
+                        severity: Severity::Common,
+                        cause: e.to_string(),
+                    })),
+                })
+            }
+        };
+        if feed.episodes.is_empty() {
+            return Ok(LoadResult {
+                load_type: LoadType::Empty,
+                data: None,
+            });
+        }
+
+        Ok(LoadResult {
+            load_type: LoadType::Playlist,
+            data: Some(LoadResultData::Playlist(Playlist {
+                info: PlaylistInfo {
+                    name: feed.title.clone(),
+                    selected_track: None,
+                },
+                #[cfg(feature = "plugins")]
+                plugin_info: HashMap::from([
+                    ("type".to_string(), serde_json::Value::from("podcast")),
+                    ("url".to_string(), serde_json::Value::from(feed.url.clone())),
+                    (
+                        "artworkUrl".to_string(),
+                        serde_json::Value::from(feed.artwork_url.clone()),
+                    ),
+                    (
+                        "author".to_string(),
+                        serde_json::Value::from(feed.author.clone()),
+                    ),
+                ]),
+                tracks: feed
+                    .episodes
+                    .iter()
+                    .map(|episode| episode.to_track(&feed))
+                    .collect(),
+            })),
+        })
+    }
+
+    /// Search the episodes of cached and configured feeds
+    pub async fn search(&self, query: &str) -> Result<Vec<Track>> {
+        for url in &self.feeds {
+            if !self.is_cached(url) {
+                if let Err(e) = self.feed(url).await {
+                    warn!("Failed to fetch podcast feed {}: {}", url, e);
+                }
+            }
+        }
+
+        let query = tokenize(query);
+        let feeds: Vec<Arc<PodcastFeed>> = self
+            .cache
+            .read()
+            .map(|cache| cache.values().map(|cached| cached.feed.clone()).collect())
+            .unwrap_or_default();
+
+        let mut matches: Vec<(f64, Option<i64>, Track)> = Vec::new();
+        for feed in &feeds {
+            let feed_tokens = tokenize(&format!(
+                "{} {}",
+                feed.title,
+                feed.author.as_deref().unwrap_or_default()
+            ));
+            for episode in &feed.episodes {
+                let mut haystack = tokenize(&episode.title);
+                haystack.extend(feed_tokens.iter().cloned());
+                let score = fuzzy_score(&query, &haystack);
+                if score >= MIN_SEARCH_SCORE {
+                    matches.push((score, episode.published, episode.to_track(feed)));
+                }
+            }
+        }
+
+        // Best matches first, newer episodes first among equally good matches
+        matches.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+        Ok(matches
+            .into_iter()
+            .take(MAX_SEARCH_RESULTS)
+            .map(|(_, _, track)| track)
+            .collect())
+    }
+
+    fn is_cached(&self, url: &str) -> bool {
+        self.cache.read().is_ok_and(|cache| cache.contains_key(url))
+    }
+
+    /// A fresh feed from the cache, or fetched from the network
+    async fn feed(&self, url: &str) -> Result<Arc<PodcastFeed>> {
+        let cached = self.cache.read().ok().and_then(|cache| {
+            cache
+                .get(url)
+                .map(|cached| (cached.feed.clone(), cached.fetched))
+        });
+        if let Some((feed, Some(fetched))) = &cached {
+            if fetched.elapsed() < self.refresh_interval {
+                return Ok(feed.clone());
+            }
+        }
+
+        let feed = match self.fetch_feed(url).await {
+            Ok(feed) => Arc::new(feed),
+            Err(e) => {
+                // A stale copy is better than nothing when the host is down
+                if let Some((feed, _)) = cached {
+                    warn!("Serving cached podcast feed {} after error: {}", url, e);
+                    return Ok(feed);
+                }
+                return Err(e);
+            }
+        };
+
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(
+                url.to_string(),
+                CachedFeed {
+                    feed: feed.clone(),
+                    fetched: Some(Instant::now()),
+                },
+            );
+        }
+        self.persist().await;
+        Ok(feed)
+    }
+
+    async fn fetch_feed(&self, url: &str) -> Result<PodcastFeed> {
+        debug!("Fetching podcast feed: {}", url);
+        let response = self
+            .client
+            .get(url)
+            .header(
+                reqwest::header::ACCEPT,
+                "application/rss+xml, application/atom+xml, application/xml;q=0.9, */*;q=0.8",
+            )
+            .send()
+            .await?
+            .error_for_status()?;
+
+        if response
+            .content_length()
+            .is_some_and(|length| length as usize > MAX_FEED_SIZE)
+        {
+            return Err(anyhow!("Feed is larger than {} bytes", MAX_FEED_SIZE));
+        }
+        let body = response.bytes().await?;
+        if body.len() > MAX_FEED_SIZE {
+            return Err(anyhow!("Feed is larger than {} bytes", MAX_FEED_SIZE));
+        }
+
+        let mut feed = parse_feed(&String::from_utf8_lossy(&body), url)?;
+        self.fetch_chapters(&mut feed).await;
+        info!(
+            "Loaded podcast feed {} with {} episodes",
+            feed.title,
+            feed.episodes.len()
+        );
+        Ok(feed)
+    }
+
+    /// Fetch chapter documents for the newest episodes that link one
+    async fn fetch_chapters(&self, feed: &mut PodcastFeed) {
+        let targets: Vec<(usize, String)> = feed
+            .episodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, episode)| episode.chapters_url.clone().map(|url| (index, url)))
+            .take(MAX_CHAPTER_FETCHES)
+            .collect();
+
+        let results: Vec<(usize, Result<Vec<Chapter>>)> = stream::iter(targets)
+            .map(|(index, url)| async move { (index, self.fetch_chapter_list(&url).await) })
+            .buffer_unordered(CHAPTER_FETCH_CONCURRENCY)
+            .collect()
+            .await;
+
+        for (index, result) in results {
+            match result {
+                Ok(mut chapters) => {
+                    let episode = &mut feed.episodes[index];
+                    normalize_chapters(&mut chapters, episode.duration_ms);
+                    episode.chapters = chapters;
+                }
+                Err(e) => debug!(
+                    "Failed to fetch chapters for {}: {}",
+                    feed.episodes[index].title, e
+                ),
+            }
+        }
+    }
+
+    async fn fetch_chapter_list(&self, url: &str) -> Result<Vec<Chapter>> {
+        let body = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        parse_chapters(&body)
+    }
+
+    /// Write the cached feeds to the cache file, if one is configured
+    async fn persist(&self) {
+        let Some(path) = self.cache_file.clone() else {
+            return;
+        };
+        let feeds: Vec<Arc<PodcastFeed>> = match self.cache.read() {
+            Ok(cache) => cache.values().map(|cached| cached.feed.clone()).collect(),
+            Err(_) => return,
+        };
+
+        let result = tokio::task::spawn_blocking(move || write_cache_file(&path, &feeds)).await;
+        if let Ok(Err(e)) | Err(e) = result.map_err(anyhow::Error::from) {
+            warn!("Failed to write podcast feed cache: {}", e);
+        }
+    }
+}
+
+impl PodcastEpisode {
+    /// Convert to a track, falling back to feed metadata where the episode has none
+    #[cfg_attr(not(feature = "plugins"), allow(unused_variables))]
+    pub fn to_track(&self, feed: &PodcastFeed) -> Track {
+        let info = TrackInfo {
+            identifier: self.enclosure_url.clone(),
+            is_seekable: true,
+            author: self
+                .author
+                .clone()
+                .or_else(|| feed.author.clone())
+                .unwrap_or_else(|| feed.title.clone()),
+            length: self.duration_ms.unwrap_or(0),
+            is_stream: false,
+            position: 0,
+            title: self.title.clone(),
+            uri: Some(self.enclosure_url.clone()),
+            artwork_url: self
+                .artwork_url
+                .clone()
+                .or_else(|| feed.artwork_url.clone()),
+            isrc: None,
+            source_name: "podcast".to_string(),
+        };
+
+        let encoded = serde_json::to_vec(&info).unwrap_or_default();
+
+        #[cfg(feature = "plugins")]
+        let plugin_info = {
+            let mut plugin_info = HashMap::from([
+                (
+                    "feedUrl".to_string(),
+                    serde_json::Value::from(feed.url.clone()),
+                ),
+                (
+                    "feedTitle".to_string(),
+                    serde_json::Value::from(feed.title.clone()),
+                ),
+            ]);
+            let optional = [
+                ("guid", self.guid.clone()),
+                ("link", self.link.clone()),
+                ("chaptersUrl", self.chapters_url.clone()),
+                (
+                    "published",
+                    self.published
+                        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                        .map(|time| time.to_rfc3339()),
+                ),
+            ];
+            for (key, value) in optional {
+                if let Some(value) = value {
+                    plugin_info.insert(key.to_string(), serde_json::Value::from(value));
+                }
+            }
+            plugin_info
+        };
+
+        let mut track = Track {
+            encoded: base64::engine::general_purpose::STANDARD.encode(encoded),
+            info,
+            #[cfg(feature = "plugins")]
+            plugin_info,
+            #[cfg(feature = "rest-api")]
+            user_data: HashMap::new(),
+        };
+        track.set_chapters(&self.chapters);
+        track
+    }
+}
+
+/// Whether a URL looks like a podcast feed rather than a media file
+///
+/// Only the path is considered and it has to name a feed outright, like
+/// `show.rss`, `/feed` or `podcast.xml`; plain XML documents and pages on
+/// feed hosts are left to the HTTP source, which checks their content type.
+pub fn is_feed_url(url: &str) -> bool {
+    let Ok(parsed) = Url::parse(url) else {
+        return false;
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return false;
+    }
+
+    let host = parsed.host_str().unwrap_or_default();
+    // YouTube channel feeds are Atom but link videos, not enclosures
+    if host.ends_with("youtube.com") {
+        return false;
+    }
+
+    let path = parsed.path().to_lowercase();
+    let Some(last_segment) = path.rsplit('/').find(|segment| !segment.is_empty()) else {
+        return false;
+    };
+    let is_feed_name = |name: &str| matches!(name, "feed" | "rss" | "atom" | "podcast");
+    match last_segment.rsplit_once('.') {
+        Some((_, "rss" | "atom")) => true,
+        Some((stem, "xml")) => is_feed_name(stem),
+        Some(_) => false,
+        None => matches!(last_segment, "feed" | "rss" | "atom"),
+    }
+}
+
+/// Whether a content type is used for RSS or Atom documents
+pub fn is_feed_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    matches!(
+        mime.as_str(),
+        "application/rss+xml" | "application/atom+xml" | "application/xml" | "text/xml"
+    )
+}
+
+/// Parse an RSS 2.0 or Atom document into a feed with episodes sorted newest first
+pub fn parse_feed(body: &str, feed_url: &str) -> Result<PodcastFeed> {
+    let root = xml::parse(body).ok_or_else(|| anyhow!("Feed is not an XML document"))?;
+
+    let mut feed = if root.is("rss") {
+        let channel = root
+            .child("channel")
+            .ok_or_else(|| anyhow!("RSS feed has no channel"))?;
+        parse_rss_channel(channel, feed_url)
+    } else if root.is("feed") {
+        parse_atom_feed(&root, feed_url)
+    } else {
+        return Err(anyhow!("Document is not an RSS or Atom feed"));
+    };
+
+    // Newest first; undated episodes keep their feed order at the end
+    feed.episodes
+        .sort_by_key(|episode| std::cmp::Reverse(episode.published));
+    Ok(feed)
+}
+
+fn parse_rss_channel(channel: &xml::Element, feed_url: &str) -> PodcastFeed {
+    let episodes = channel
+        .children("item")
+        .filter_map(|item| {
+            let enclosure_url = item
+                .child("enclosure")
+                .and_then(|enclosure| enclosure.attr("url"))
+                .or_else(|| {
+                    item.child("media:content")
+                        .and_then(|media| media.attr("url"))
+                })?;
+
+            Some(PodcastEpisode {
+                title: item
+                    .child_text("title")
+                    .or_else(|| item.child_text("itunes:title"))
+                    .unwrap_or_else(|| "Unknown episode".to_string()),
+                author: item
+                    .child_text("itunes:author")
+                    .or_else(|| item.child_text("dc:creator"))
+                    .or_else(|| item.child_text("author")),
+                enclosure_url: resolve_url(feed_url, enclosure_url),
+                duration_ms: item
+                    .child_text("itunes:duration")
+                    .and_then(|duration| parse_duration(&duration)),
+                published: item
+                    .child_text("pubDate")
+                    .and_then(|date| parse_date(&date))
+                    .or_else(|| {
+                        item.child_text("dc:date")
+                            .and_then(|date| parse_date(&date))
+                    }),
+                guid: item.child_text("guid"),
+                artwork_url: item
+                    .child("itunes:image")
+                    .and_then(|image| image.attr("href"))
+                    .map(|href| resolve_url(feed_url, href)),
+                link: item.child_text("link"),
+                chapters_url: chapters_url(item, feed_url),
+                chapters: Vec::new(),
+            })
+        })
+        .collect();
+
+    PodcastFeed {
+        url: feed_url.to_string(),
+        title: channel
+            .child_text("title")
+            .unwrap_or_else(|| "Unknown podcast".to_string()),
+        author: channel
+            .child_text("itunes:author")
+            .or_else(|| channel.child_text("managingEditor")),
+        artwork_url: channel
+            .child("itunes:image")
+            .and_then(|image| image.attr("href").map(str::to_string))
+            .or_else(|| {
+                channel
+                    .child("image")
+                    .and_then(|image| image.child_text("url"))
+            })
+            .map(|url| resolve_url(feed_url, &url)),
+        episodes,
+    }
+}
+
+fn parse_atom_feed(root: &xml::Element, feed_url: &str) -> PodcastFeed {
+    let episodes = root
+        .children("entry")
+        .filter_map(|entry| {
+            let enclosure_url = entry
+                .children("link")
+                .find(|link| link.attr("rel") == Some("enclosure"))
+                .and_then(|link| link.attr("href"))?;
+
+            Some(PodcastEpisode {
+                title: entry
+                    .child_text("title")
+                    .unwrap_or_else(|| "Unknown episode".to_string()),
+                author: entry
+                    .child("author")
+                    .and_then(|author| author.child_text("name")),
+                enclosure_url: resolve_url(feed_url, enclosure_url),
+                duration_ms: entry
+                    .child_text("itunes:duration")
+                    .and_then(|duration| parse_duration(&duration)),
+                published: entry
+                    .child_text("published")
+                    .or_else(|| entry.child_text("updated"))
+                    .and_then(|date| parse_date(&date)),
+                guid: entry.child_text("id"),
+                artwork_url: entry
+                    .child("itunes:image")
+                    .and_then(|image| image.attr("href"))
+                    .map(|href| resolve_url(feed_url, href)),
+                link: entry
+                    .children("link")
+                    .find(|link| matches!(link.attr("rel"), None | Some("alternate")))
+                    .and_then(|link| link.attr("href"))
+                    .map(str::to_string),
+                chapters_url: chapters_url(entry, feed_url),
+                chapters: Vec::new(),
+            })
+        })
+        .collect();
+
+    PodcastFeed {
+        url: feed_url.to_string(),
+        title: root
+            .child_text("title")
+            .unwrap_or_else(|| "Unknown podcast".to_string()),
+        author: root
+            .child("author")
+            .and_then(|author| author.child_text("name")),
+        artwork_url: root
+            .child_text("logo")
+            .or_else(|| root.child_text("icon"))
+            .map(|url| resolve_url(feed_url, &url)),
+        episodes,
+    }
+}
+
+/// The JSON chapters document linked from an item, if any
+fn chapters_url(item: &xml::Element, feed_url: &str) -> Option<String> {
+    item.children("podcast:chapters")
+        .find(|chapters| {
+            chapters
+                .attr("type")
+                .is_none_or(|kind| kind.contains("json"))
+        })
+        .and_then(|chapters| chapters.attr("url"))
+        .map(|url| resolve_url(feed_url, url))
+}
+
+/// Parse a Podcasting 2.0 JSON chapters document
+///
+/// Chapters marked `"toc": false` are left out; missing end times are taken
+/// from the start of the next chapter.
+pub fn parse_chapters(json: &str) -> Result<Vec<Chapter>> {
+    #[derive(Deserialize)]
+    struct ChaptersDocument {
+        chapters: Vec<ChapterEntry>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ChapterEntry {
+        start_time: f64,
+        end_time: Option<f64>,
+        title: Option<String>,
+        url: Option<String>,
+        img: Option<String>,
+        toc: Option<bool>,
+    }
+
+    let document: ChaptersDocument = serde_json::from_str(json)?;
+    let mut chapters: Vec<Chapter> = document
+        .chapters
+        .into_iter()
+        .filter(|entry| entry.toc != Some(false) && entry.start_time >= 0.0)
+        .map(|entry| Chapter {
+            title: entry.title.unwrap_or_default(),
+            start_time: (entry.start_time * 1000.0).round() as u64,
+            end_time: entry
+                .end_time
+                .filter(|end| *end >= 0.0)
+                .map(|end| (end * 1000.0).round() as u64),
+            url: entry.url,
+            img: entry.img,
+        })
+        .collect();
+
+    normalize_chapters(&mut chapters, None);
+    Ok(chapters)
+}
+
+/// Parse `itunes:duration`, given as seconds, `MM:SS` or `HH:MM:SS`
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+
+    let mut seconds = 0.0;
+    for part in parts {
+        let value: f64 = part.trim().parse().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        seconds = seconds * 60.0 + value;
+    }
+    Some((seconds * 1000.0).round() as u64)
+}
+
+/// Parse an RFC 2822 (RSS) or RFC 3339 (Atom) date into unix seconds
+fn parse_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    DateTime::parse_from_rfc2822(text)
+        .or_else(|_| DateTime::parse_from_rfc3339(text))
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+fn resolve_url(base: &str, url: &str) -> String {
+    let url = url.trim();
+    Url::parse(base)
+        .and_then(|base| base.join(url))
+        .map(|resolved| resolved.to_string())
+        .unwrap_or_else(|_| url.to_string())
+}
+
+fn load_cache_file(path: &Path) -> HashMap<String, CachedFeed> {
+    let feeds: Vec<PodcastFeed> = match std::fs::read(path) {
+        Ok(data) => match serde_json::from_slice(&data) {
+            Ok(feeds) => feeds,
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable podcast feed cache {}: {}",
+                    path.display(),
+                    e
+                );
+                return HashMap::new();
+            }
+        },
+        Err(_) => return HashMap::new(),
+    };
+
+    feeds
+        .into_iter()
+        .map(|feed| {
+            (
+                feed.url.clone(),
+                CachedFeed {
+                    feed: Arc::new(feed),
+                    fetched: None,
+                },
+            )
+        })
+        .collect()
+}
+
+fn write_cache_file(path: &Path, feeds: &[Arc<PodcastFeed>]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first so a crash never leaves a truncated cache
+    let temp = path.with_extension("tmp");
+    let feeds: Vec<&PodcastFeed> = feeds.iter().map(|feed| feed.as_ref()).collect();
+    std::fs::write(&temp, serde_json::to_vec(&feeds)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// A small, forgiving XML reader that is just enough for feeds
+mod xml {
+    /// Deepest element nesting that is accepted
+    ///
+    /// Feeds nest a handful of levels; the limit keeps hostile documents from
+    /// overflowing the stack when the tree is walked or dropped.
+    pub const MAX_DEPTH: usize = 64;
+
+    #[derive(Debug, Default)]
+    pub struct Element {
+        pub name: String,
+        pub attributes: Vec<(String, String)>,
+        pub children: Vec<Node>,
+    }
+
+    #[derive(Debug)]
+    pub enum Node {
+        Element(Element),
+        Text(String),
+    }
+
+    impl Element {
+        pub fn is(&self, name: &str) -> bool {
+            self.name.eq_ignore_ascii_case(name)
+        }
+
+        pub fn attr(&self, name: &str) -> Option<&str> {
+            self.attributes
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        }
+
+        pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+            self.children.iter().filter_map(move |node| match node {
+                Node::Element(element) if element.is(name) => Some(element),
+                _ => None,
+            })
+        }
+
+        pub fn child(&self, name: &str) -> Option<&Element> {
+            self.children.iter().find_map(|node| match node {
+                Node::Element(element) if element.is(name) => Some(element),
+                _ => None,
+            })
+        }
+
+        /// Trimmed text of a child element, if present and not empty
+        pub fn child_text(&self, name: &str) -> Option<String> {
+            let text = self.child(name)?.text();
+            (!text.is_empty()).then_some(text)
+        }
+
+        pub fn text(&self) -> String {
+            let mut text = String::new();
+            self.collect_text(&mut text);
+            text.trim().to_string()
+        }
+
+        fn collect_text(&self, output: &mut String) {
+            for node in &self.children {
+                match node {
+                    Node::Text(text) => output.push_str(text),
+                    Node::Element(element) => element.collect_text(output),
+                }
+            }
+        }
+    }
+
+    /// Parse a document and return its root element
+    ///
+    /// Unclosed elements are closed at the end of the document and stray
+    /// closing tags are ignored. Documents nested deeper than [`MAX_DEPTH`]
+    /// are rejected.
+    pub fn parse(input: &str) -> Option<Element> {
+        let mut stack = vec![Element::default()];
+        let mut rest = input.trim_start_matches('\u{feff}');
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("<!--") {
+                rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+                let end = after.find("]]>").unwrap_or(after.len());
+                push_text(&mut stack, after[..end].to_string());
+                rest = after.get(end + 3..).unwrap_or("");
+            } else if rest.starts_with("<?") || rest.starts_with("<!") {
+                rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            } else if let Some(after) = rest.strip_prefix("</") {
+                let end = after.find('>').unwrap_or(after.len());
+                close(&mut stack, after[..end].trim());
+                rest = after.get(end + 1..).unwrap_or("");
+            } else if rest.starts_with('<') {
+                let end = tag_end(rest)?;
+                let tag = &rest[1..end];
+                let (tag, self_closing) = match tag.strip_suffix('/') {
+                    Some(tag) => (tag, true),
+                    None => (tag, false),
+                };
+                let element = parse_tag(tag);
+                if self_closing {
+                    stack.last_mut()?.children.push(Node::Element(element));
+                } else if stack.len() > MAX_DEPTH {
+                    return None;
+                } else {
+                    stack.push(element);
+                }
+                rest = &rest[end + 1..];
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                push_text(&mut stack, decode_entities(&rest[..end]));
+                rest = &rest[end..];
+            }
+        }
+
+        while stack.len() > 1 {
+            let element = stack.pop()?;
+            stack.last_mut()?.children.push(Node::Element(element));
+        }
+        stack
+            .pop()?
+            .children
+            .into_iter()
+            .find_map(|node| match node {
+                Node::Element(element) => Some(element),
+                Node::Text(_) => None,
+            })
+    }
+
+    fn push_text(stack: &mut [Element], text: String) {
+        if let Some(current) = stack.last_mut() {
+            if !text.is_empty() {
+                current.children.push(Node::Text(text));
+            }
+        }
+    }
+
+    fn close(stack: &mut Vec<Element>, name: &str) {
+        let Some(open) = stack.iter().skip(1).rposition(|element| element.is(name)) else {
+            return;
+        };
+        // Close everything opened inside the matching element as well
+        while stack.len() > open + 1 {
+            if let Some(element) = stack.pop() {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Node::Element(element));
+                }
+            }
+        }
+    }
+
+    /// Index of the `>` ending the tag at the start of `input`, skipping quoted values
+    fn tag_end(input: &str) -> Option<usize> {
+        let mut quote = None;
+        for (index, c) in input.char_indices() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c == '>' => return Some(index),
+                None => {}
+            }
+        }
+        None
+    }
+
+    fn parse_tag(tag: &str) -> Element {
+        let tag = tag.trim();
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let mut element = Element {
+            name: tag[..name_end].to_string(),
+            ..Element::default()
+        };
+
+        let mut rest = tag[name_end..].trim_start();
+        while !rest.is_empty() {
+            let key_end = rest
+                .find(|c: char| c == '=' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            let key = rest[..key_end].to_string();
+            rest = rest[key_end..].trim_start();
+
+            let mut value = String::new();
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let body = &after[1..];
+                        let end = body.find(quote).unwrap_or(body.len());
+                        value = decode_entities(&body[..end]);
+                        rest = body.get(end + 1..).unwrap_or("");
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        value = decode_entities(&after[..end]);
+                        rest = &after[end..];
+                    }
+                }
+            }
+            if !key.is_empty() {
+                element.attributes.push((key, value));
+            }
+            rest = rest.trim_start();
+        }
+        element
+    }
+
+    pub fn decode_entities(text: &str) -> String {
+        if !text.contains('&') {
+            return text.to_string();
+        }
+
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('&') {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+                let entity = &rest[1..end];
+                let c = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                        .and_then(char::from_u32),
+                };
+                c.map(|c| (c, end))
+            });
+            match decoded {
+                Some((c, end)) => {
+                    output.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    output.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+     xmlns:podcast="https://podcastindex.org/namespace/1.0">
+  <channel>
+    <title>Rust &amp; Audio</title>
+    <itunes:author>The Hosts</itunes:author>
+    <itunes:image href="https://example.com/cover.jpg"/>
+    <item>
+      <title><![CDATA[Episode 1: <Hello>]]></title>
+      <enclosure url="https://example.com/ep1.mp3" length="123" type="audio/mpeg"/>
+      <itunes:duration>59:30</itunes:duration>
+      <pubDate>Mon, 01 Jan 2024 10:00:00 +0000</pubDate>
+      <guid isPermaLink="false">ep-1</guid>
+    </item>
+    <item>
+      <title>Episode 2: Decoders</title>
+      <itunes:author>Guest Host</itunes:author>
+      <enclosure url="/media/ep2.mp3" type="audio/mpeg"/>
+      <itunes:duration>3725</itunes:duration>
+      <itunes:image href="https://example.com/ep2.jpg"/>
+      <pubDate>Mon, 08 Jan 2024 10:00:00 +0000</pubDate>
+      <podcast:chapters url="https://example.com/ep2.json" type="application/json+chapters"/>
+    </item>
+    <item>
+      <title>Trailer without audio</title>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn test_parse_rss_feed() {
+        let feed = parse_feed(RSS_FEED, "https://example.com/feed.xml").unwrap();
+        assert_eq!(feed.title, "Rust & Audio");
+        assert_eq!(feed.author.as_deref(), Some("The Hosts"));
+        assert_eq!(feed.episodes.len(), 2);
+
+        // Newest first
+        let newest = &feed.episodes[0];
+        assert_eq!(newest.title, "Episode 2: Decoders");
+        assert_eq!(newest.enclosure_url, "https://example.com/media/ep2.mp3");
+        assert_eq!(newest.duration_ms, Some(3_725_000));
+        assert_eq!(
+            newest.chapters_url.as_deref(),
+            Some("https://example.com/ep2.json")
+        );
+
+        let oldest = &feed.episodes[1];
+        assert_eq!(oldest.title, "Episode 1: <Hello>");
+        assert_eq!(oldest.duration_ms, Some(3_570_000));
+        assert_eq!(oldest.guid.as_deref(), Some("ep-1"));
+
+        let track = newest.to_track(&feed);
+        assert_eq!(track.info.source_name, "podcast");
+        assert_eq!(track.info.author, "Guest Host");
+        assert_eq!(track.info.length, 3_725_000);
+        assert_eq!(
+            track.info.artwork_url.as_deref(),
+            Some("https://example.com/ep2.jpg")
+        );
+        let track = oldest.to_track(&feed);
+        assert_eq!(track.info.author, "The Hosts");
+        assert_eq!(
+            track.info.artwork_url.as_deref(),
+            Some("https://example.com/cover.jpg")
+        );
+    }
+
+    #[test]
+    fn test_parse_atom_feed() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Cast</title>
+  <author><name>Writer</name></author>
+  <logo>https://example.com/logo.png</logo>
+  <entry>
+    <title>Older</title>
+    <link rel="enclosure" type="audio/ogg" href="https://example.com/older.ogg"/>
+    <updated>2023-05-01T12:00:00Z</updated>
+  </entry>
+  <entry>
+    <title>Newer</title>
+    <link rel="alternate" href="https://example.com/newer"/>
+    <link rel="enclosure" type="audio/ogg" href="https://example.com/newer.ogg"/>
+    <published>2023-06-01T12:00:00+02:00</published>
+  </entry>
+</feed>"#;
+        let feed = parse_feed(atom, "https://example.com/atom").unwrap();
+        assert_eq!(feed.title, "Atom Cast");
+        assert_eq!(feed.author.as_deref(), Some("Writer"));
+        let titles: Vec<&str> = feed.episodes.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Newer", "Older"]);
+        assert_eq!(
+            feed.episodes[0].link.as_deref(),
+            Some("https://example.com/newer")
+        );
+
+        assert!(parse_feed("<html><body>nope</body></html>", "https://x").is_err());
+    }
+
+    #[test]
+    fn test_deeply_nested_documents_are_rejected() {
+        let nested = |depth: usize| {
+            format!(
+                "<rss><channel><title>Deep</title>{}{}</channel></rss>",
+                "<a>".repeat(depth),
+                "</a>".repeat(depth)
+            )
+        };
+        assert!(parse_feed(&nested(10), "https://x").is_ok());
+        assert!(parse_feed(&nested(1_000_000), "https://x").is_err());
+        // Unclosed elements count as well
+        assert!(parse_feed(&"<a>".repeat(1_000_000), "https://x").is_err());
+    }
+
+    #[test]
+    fn test_parse_chapters_and_helpers() {
+        let chapters = parse_chapters(
+            r#"{"version":"1.2.0","chapters":[
+                {"startTime":90.5,"title":"News"},
+                {"startTime":0,"title":"Intro","img":"https://example.com/i.png"},
+                {"startTime":60,"title":"Hidden","toc":false},
+                {"startTime":300,"endTime":400,"title":"Outro"}]}"#,
+        )
+        .unwrap();
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Intro", "News", "Outro"]);
+        assert_eq!(chapters[0].end_time, Some(90_500));
+        assert_eq!(chapters[1].start_time, 90_500);
+        assert_eq!(chapters[2].end_time, Some(400_000));
+
+        assert_eq!(parse_duration("1:02:03"), Some(3_723_000));
+        assert_eq!(parse_duration("90.5"), Some(90_500));
+        assert_eq!(parse_duration("soon"), None);
+
+        assert!(is_feed_url("https://example.com/podcast/feed"));
+        assert!(is_feed_url("https://example.com/show.rss"));
+        assert!(is_feed_url("https://example.com/shows/podcast.xml"));
+        assert!(!is_feed_url("https://feeds.example.com/show"));
+        assert!(!is_feed_url("https://example.com/podcast"));
+        assert!(!is_feed_url("https://example.com/sitemap.xml"));
+        assert!(!is_feed_url("https://example.com/feed/episode.mp3"));
+        assert!(!is_feed_url("https://example.com/episode.mp3"));
+        assert!(!is_feed_url(
+            "https://www.youtube.com/feeds/videos.xml?channel_id=x"
+        ));
+        assert!(is_feed_content_type("application/rss+xml; charset=utf-8"));
+        assert!(!is_feed_content_type("audio/mpeg"));
+    }
+}
@@ -257,6 +257,39 @@ impl SoundCloudApiClient {
         Ok(search_response.collection)
     }
 
+    /// Get the tracks SoundCloud recommends after a track
+    pub async fn related_tracks(&self, track_id: u64, limit: u32) -> Result<Vec<SoundCloudTrack>> {
+        let access_token = self.get_access_token().await?;
+
+        let related_url = format!("{}/tracks/{}/related", self.config.api_base_url, track_id);
+        let limit = limit.clamp(1, 200);
+
+        let response = self
+            .client
+            .get(&related_url)
+            .header("Authorization", format!("OAuth {access_token}"))
+            .query(&[
+                ("limit", limit.to_string().as_str()),
+                ("access", "playable"),
+                ("linked_partitioning", "true"),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "SoundCloud related tracks failed: {} - {}",
+                status,
+                body
+            ));
+        }
+
+        let related_response: SoundCloudSearchResponse = response.json().await?;
+        Ok(related_response.collection)
+    }
+
     /// Get stream URL for a track
     pub async fn get_stream_url(&self, track_id: u64) -> Result<String> {
         let access_token = self.get_access_token().await?;
@@ -0,0 +1,408 @@
+//! Synthetic audio source for Lavalink-rust
+//!
+//! This module generates tones and noise with fundsp, so soundboards and
+//! end-to-end tests can play audio without network access or fixtures.
+//! Identifiers look like `synth:sine?freq=440&duration=5000`; the same
+//! identifier always renders the same samples.
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use fundsp::hacker32::{
+    brown, constant, lfo, pink, saw_hz, square_hz, triangle_hz, white, zero, An, AttoHash,
+    AudioUnit, Sine,
+};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::protocol::{Track, TrackInfo};
+
+/// Prefix of synthetic track identifiers
+pub const SYNTH_PREFIX: &str = "synth:";
+/// Sample rate of rendered audio
+pub const SAMPLE_RATE: u32 = 48_000;
+/// Rendered audio is interleaved stereo
+pub const CHANNELS: u32 = 2;
+
+const DEFAULT_DURATION_MS: u64 = 30_000;
+const DEFAULT_FREQUENCY: f32 = 440.0;
+const DEFAULT_SWEEP_START: f32 = 20.0;
+const DEFAULT_SWEEP_END: f32 = 20_000.0;
+/// Length of one sweep when the track itself is endless
+const ENDLESS_SWEEP_MS: u64 = 10_000;
+const DEFAULT_AMPLITUDE: f32 = 0.5;
+const BYTES_PER_FRAME: usize = CHANNELS as usize * std::mem::size_of::<f32>();
+const BLOCK_FRAMES: usize = 1024;
+
+/// Signal generated by a synthetic track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+    White,
+    Pink,
+    Brown,
+    Silence,
+    /// Logarithmic sine sweep from `freq` to `to`
+    Sweep,
+}
+
+impl Waveform {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "sine" => Self::Sine,
+            "square" => Self::Square,
+            "saw" | "sawtooth" => Self::Saw,
+            "triangle" => Self::Triangle,
+            "white" | "noise" => Self::White,
+            "pink" => Self::Pink,
+            "brown" => Self::Brown,
+            "silence" => Self::Silence,
+            "sweep" => Self::Sweep,
+            _ => return None,
+        })
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Sine => "Sine",
+            Self::Square => "Square",
+            Self::Saw => "Saw",
+            Self::Triangle => "Triangle",
+            Self::White => "White noise",
+            Self::Pink => "Pink noise",
+            Self::Brown => "Brown noise",
+            Self::Silence => "Silence",
+            Self::Sweep => "Sweep",
+        }
+    }
+
+    fn is_tonal(&self) -> bool {
+        matches!(self, Self::Sine | Self::Square | Self::Saw | Self::Triangle)
+    }
+}
+
+/// A parsed `synth:` identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthSpec {
+    pub waveform: Waveform,
+    /// Tone frequency in Hz, or the start frequency of a sweep
+    pub frequency: f32,
+    /// End frequency of a sweep in Hz
+    pub end_frequency: f32,
+    /// Length in milliseconds; `None` plays until stopped
+    pub duration_ms: Option<u64>,
+    /// Peak amplitude between 0 and 1
+    pub amplitude: f32,
+    /// Seed for noise and oscillator phase
+    pub seed: u64,
+}
+
+impl SynthSpec {
+    /// Parse an identifier such as `synth:sine?freq=440&duration=5000`
+    ///
+    /// `duration=0` makes an endless stream.
+    pub fn parse(identifier: &str) -> Result<Self> {
+        let rest = identifier
+            .strip_prefix(SYNTH_PREFIX)
+            .ok_or_else(|| anyhow!("Not a synth identifier: {}", identifier))?;
+        let (name, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let waveform = Waveform::from_name(name.trim())
+            .ok_or_else(|| anyhow!("Unknown synth waveform: {}", name))?;
+
+        let sweep = waveform == Waveform::Sweep;
+        let mut spec = Self {
+            waveform,
+            frequency: if sweep {
+                DEFAULT_SWEEP_START
+            } else {
+                DEFAULT_FREQUENCY
+            },
+            end_frequency: DEFAULT_SWEEP_END,
+            duration_ms: Some(DEFAULT_DURATION_MS),
+            amplitude: DEFAULT_AMPLITUDE,
+            seed: 0,
+        };
+
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            let invalid = || anyhow!("Invalid value for synth parameter {}: {}", key, value);
+            match key.as_ref() {
+                "freq" | "from" => spec.frequency = value.parse().map_err(|_| invalid())?,
+                "to" if sweep => spec.end_frequency = value.parse().map_err(|_| invalid())?,
+                "duration" => {
+                    let duration: u64 = value.parse().map_err(|_| invalid())?;
+                    spec.duration_ms = (duration > 0).then_some(duration);
+                }
+                "amplitude" | "volume" => spec.amplitude = value.parse().map_err(|_| invalid())?,
+                "seed" => spec.seed = value.parse().map_err(|_| invalid())?,
+                _ => return Err(anyhow!("Unknown synth parameter: {}", key)),
+            }
+        }
+
+        let nyquist = SAMPLE_RATE as f32 / 2.0;
+        for frequency in [spec.frequency, spec.end_frequency] {
+            if !(frequency > 0.0 && frequency <= nyquist) {
+                return Err(anyhow!(
+                    "Synth frequency must be between 0 and {} Hz",
+                    nyquist
+                ));
+            }
+        }
+        if !(0.0..=1.0).contains(&spec.amplitude) {
+            return Err(anyhow!("Synth amplitude must be between 0 and 1"));
+        }
+
+        Ok(spec)
+    }
+
+    /// Number of stereo frames, or `None` for an endless stream
+    pub fn total_frames(&self) -> Option<u64> {
+        self.duration_ms
+            .map(|duration| duration * SAMPLE_RATE as u64 / 1000)
+    }
+
+    /// Build a track for this spec under the given identifier
+    pub fn to_track(&self, identifier: &str) -> Track {
+        let title = if self.waveform.is_tonal() {
+            format!("{} {} Hz", self.waveform.title(), self.frequency)
+        } else if self.waveform == Waveform::Sweep {
+            format!(
+                "{} {}-{} Hz",
+                self.waveform.title(),
+                self.frequency,
+                self.end_frequency
+            )
+        } else {
+            self.waveform.title().to_string()
+        };
+
+        let info = TrackInfo {
+            identifier: identifier.to_string(),
+            is_seekable: true,
+            author: "Synthesizer".to_string(),
+            length: self.duration_ms.unwrap_or(0),
+            is_stream: self.duration_ms.is_none(),
+            position: 0,
+            title,
+            uri: Some(identifier.to_string()),
+            artwork_url: None,
+            isrc: None,
+            source_name: "synth".to_string(),
+        };
+        let encoded = serde_json::to_vec(&info).unwrap_or_default();
+
+        Track {
+            encoded: base64::engine::general_purpose::STANDARD.encode(encoded),
+            info,
+            #[cfg(feature = "plugins")]
+            plugin_info: HashMap::new(),
+            #[cfg(feature = "rest-api")]
+            user_data: HashMap::new(),
+        }
+    }
+
+    /// Build the mono signal graph for this spec
+    fn unit(&self) -> Box<dyn AudioUnit> {
+        let amplitude = self.amplitude;
+        let mut unit: Box<dyn AudioUnit> = match self.waveform {
+            Waveform::Sine => {
+                Box::new((constant(self.frequency) >> An(Sine::<f32>::with_phase(0.0))) * amplitude)
+            }
+            Waveform::Square => Box::new(square_hz(self.frequency) * amplitude),
+            Waveform::Saw => Box::new(saw_hz(self.frequency) * amplitude),
+            Waveform::Triangle => Box::new(triangle_hz(self.frequency) * amplitude),
+            Waveform::White => Box::new(white() * amplitude),
+            Waveform::Pink => Box::new(pink() * amplitude),
+            Waveform::Brown => Box::new(brown() * amplitude),
+            Waveform::Silence => Box::new(zero()),
+            Waveform::Sweep => {
+                let (from, to) = (self.frequency, self.end_frequency);
+                let length = self.duration_ms.unwrap_or(ENDLESS_SWEEP_MS) as f32 / 1000.0;
+                let frequency =
+                    lfo(move |time: f32| from * (to / from).powf((time / length) % 1.0));
+                Box::new((frequency >> An(Sine::<f32>::with_phase(0.0))) * amplitude)
+            }
+        };
+        unit.set_sample_rate(SAMPLE_RATE as f64);
+        // Seeds every noise generator in the graph
+        unit.ping(false, AttoHash::new(self.seed));
+        unit.reset();
+        unit
+    }
+}
+
+/// Renders a [`SynthSpec`] as interleaved stereo `f32` PCM
+///
+/// As a [`Read`] source it yields little-endian `f32` bytes, the raw layout
+/// songbird's `RawAdapter` expects. Without voice playback the player
+/// renders samples directly to follow the track's position.
+pub struct SynthStream {
+    spec: SynthSpec,
+    unit: Box<dyn AudioUnit>,
+    /// Frames rendered so far
+    frame: u64,
+    /// Rendered bytes not yet read
+    pending: Vec<u8>,
+    pending_offset: usize,
+}
+
+impl SynthStream {
+    pub fn new(spec: SynthSpec) -> Self {
+        let unit = spec.unit();
+        Self {
+            spec,
+            unit,
+            frame: 0,
+            pending: Vec::new(),
+            pending_offset: 0,
+        }
+    }
+
+    /// Fill `samples` with interleaved stereo samples, returning how many were written
+    ///
+    /// Returns fewer than requested only once the track has ended.
+    pub fn read_samples(&mut self, samples: &mut [f32]) -> usize {
+        let mut frames = samples.len() / CHANNELS as usize;
+        if let Some(total) = self.spec.total_frames() {
+            frames = frames.min(total.saturating_sub(self.frame) as usize);
+        }
+
+        for frame in samples.chunks_exact_mut(CHANNELS as usize).take(frames) {
+            frame.fill(self.unit.get_mono());
+        }
+        self.frame += frames as u64;
+        frames * CHANNELS as usize
+    }
+
+    /// Restart rendering at the given frame
+    pub fn seek_frame(&mut self, frame: u64) {
+        let frame = match self.spec.total_frames() {
+            Some(total) => frame.min(total),
+            None => frame,
+        };
+        if frame < self.frame {
+            self.unit.reset();
+            self.frame = 0;
+        }
+        // Signals depend on their history, so skipped frames are still rendered
+        while self.frame < frame {
+            self.unit.get_mono();
+            self.frame += 1;
+        }
+        self.pending.clear();
+        self.pending_offset = 0;
+    }
+}
+
+impl Read for SynthStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending_offset >= self.pending.len() {
+            let mut block = [0.0f32; BLOCK_FRAMES * CHANNELS as usize];
+            let written = self.read_samples(&mut block);
+            self.pending.clear();
+            self.pending_offset = 0;
+            self.pending.extend(
+                block[..written]
+                    .iter()
+                    .flat_map(|sample| sample.to_le_bytes()),
+            );
+        }
+
+        let available = &self.pending[self.pending_offset..];
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.pending_offset += count;
+        Ok(count)
+    }
+}
+
+impl Seek for SynthStream {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        let length = self
+            .spec
+            .total_frames()
+            .map(|frames| frames * BYTES_PER_FRAME as u64);
+        let current =
+            self.frame * BYTES_PER_FRAME as u64 - (self.pending.len() - self.pending_offset) as u64;
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => current.checked_add_signed(delta),
+            SeekFrom::End(delta) => length.and_then(|length| length.checked_add_signed(delta)),
+        }
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid seek"))?;
+
+        // Land on a frame boundary, then skip into it
+        let remainder = (target % BYTES_PER_FRAME as u64) as usize;
+        self.seek_frame(target / BYTES_PER_FRAME as u64);
+        if remainder > 0 {
+            let mut skip = [0u8; BYTES_PER_FRAME];
+            self.read_exact(&mut skip[..remainder])?;
+        }
+        Ok(target)
+    }
+}
+
+impl symphonia::core::io::MediaSource for SynthStream {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.spec
+            .total_frames()
+            .map(|frames| frames * BYTES_PER_FRAME as u64)
+    }
+}
+
+/// Wrap a synthetic track as a songbird input, decoded by songbird's raw codec
+#[cfg(feature = "discord")]
+pub fn songbird_input(spec: SynthSpec) -> songbird::input::Input {
+    songbird::input::RawAdapter::new(SynthStream::new(spec), SAMPLE_RATE, CHANNELS).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identifiers() {
+        let spec = SynthSpec::parse("synth:sine?freq=1000&duration=250&amplitude=0.25").unwrap();
+        assert_eq!(spec.waveform, Waveform::Sine);
+        assert_eq!(spec.frequency, 1000.0);
+        assert_eq!(spec.duration_ms, Some(250));
+        assert_eq!(spec.total_frames(), Some(12_000));
+
+        let sweep = SynthSpec::parse("synth:sweep?duration=0").unwrap();
+        assert_eq!(sweep.frequency, DEFAULT_SWEEP_START);
+        assert_eq!(sweep.duration_ms, None);
+        assert!(sweep.to_track("synth:sweep?duration=0").info.is_stream);
+
+        assert!(SynthSpec::parse("synth:pink").is_ok());
+        assert!(SynthSpec::parse("synth:kazoo").is_err());
+        assert!(SynthSpec::parse("synth:sine?freq=30000").is_err());
+        assert!(SynthSpec::parse("synth:sine?pitch=3").is_err());
+    }
+
+    #[test]
+    fn test_stream_is_deterministic_and_seekable() {
+        let spec = SynthSpec::parse("synth:pink?duration=100&seed=7").unwrap();
+        let mut first = Vec::new();
+        SynthStream::new(spec.clone())
+            .read_to_end(&mut first)
+            .unwrap();
+        assert_eq!(first.len(), 4_800 * BYTES_PER_FRAME);
+        assert!(first.iter().any(|&byte| byte != 0));
+
+        let mut stream = SynthStream::new(spec);
+        let mut second = Vec::new();
+        stream.read_to_end(&mut second).unwrap();
+        assert_eq!(first, second);
+
+        // Seeking back renders the same samples again
+        stream.seek(SeekFrom::Start(800)).unwrap();
+        let mut tail = Vec::new();
+        stream.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, first[800..]);
+    }
+}
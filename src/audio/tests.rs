@@ -636,4 +636,26 @@ mod audio_source_tests {
         assert!(!audio_manager.can_handle("https://twitch.tv/test"));
         assert!(!audio_manager.can_handle("https://vimeo.com/test"));
     }
+
+    #[test]
+    fn test_flat_mix_entries_convert_to_tracks() {
+        let entry = serde_json::json!({
+            "_type": "url",
+            "id": "dQw4w9WgXcQ",
+            "url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "title": "Never Gonna Give You Up",
+            "channel": "Rick Astley",
+            "uploader": null,
+        });
+        let track = YouTubeAudioSource
+            .json_to_track(flat_entry(entry))
+            .expect("flat entries convert");
+        assert_eq!(track.info.identifier, "dQw4w9WgXcQ");
+        assert_eq!(track.info.author, "Rick Astley");
+        assert_eq!(track.info.length, 0);
+        assert_eq!(
+            track.info.uri.as_deref(),
+            Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+        );
+    }
 }
@@ -73,6 +73,125 @@ pub struct LavalinkInnerConfig {
     /// Discord bot token for voice connections (optional)
     #[serde(rename = "discordBotToken")]
     pub discord_bot_token: Option<String>,
+    pub artwork: Option<ArtworkConfig>,
+    pub persistence: Option<PersistenceConfig>,
+    /// Accept Lavalink v3 WebSocket ops from clients connecting to `/` (default: false)
+    #[serde(rename = "v3Compatibility")]
+    pub v3_compatibility: Option<bool>,
+    /// Named keys accepted in the Authorization header next to the password
+    #[serde(rename = "apiKeys")]
+    pub api_keys: Option<Vec<ApiKeyConfig>>,
+    /// Rate limits and caps applied per client
+    #[serde(rename = "clientLimits")]
+    pub client_limits: Option<ClientLimitsConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiKeyConfig {
+    /// Shown in logs and request traces instead of the key itself
+    pub name: String,
+    pub key: String,
+    /// Routes the key may use (default: all)
+    pub scopes: Option<Vec<ApiScope>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ClientLimitsConfig {
+    /// `/v4/loadtracks` requests, which include searches
+    #[serde(rename = "loadTracks")]
+    pub load_tracks: Option<TokenBucketConfig>,
+    /// Requests changing players, and v3 WebSocket ops
+    #[serde(rename = "playerUpdates")]
+    pub player_updates: Option<TokenBucketConfig>,
+    #[serde(rename = "maxPlayersPerSession")]
+    pub max_players_per_session: Option<usize>,
+    /// Tracks a player's queue may hold when adding to it
+    #[serde(rename = "maxQueueLength")]
+    pub max_queue_length: Option<usize>,
+}
+
+/// A token bucket per client; each request takes one token
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TokenBucketConfig {
+    /// Requests a client may send in a burst
+    pub capacity: u32,
+    /// Tokens added back every second
+    #[serde(rename = "refillPerSecond")]
+    pub refill_per_second: f64,
+}
+
+impl TokenBucketConfig {
+    /// Whether the bucket refills at all; NaN and infinity are refused too
+    pub fn is_valid(&self) -> bool {
+        self.refill_per_second.is_finite() && self.refill_per_second > 0.0
+    }
+}
+
+/// Group of routes an API key can be granted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ApiScope {
+    /// Sessions, players and the WebSocket
+    #[serde(rename = "player")]
+    Player,
+    /// Loading and decoding tracks
+    #[serde(rename = "load")]
+    Load,
+    /// Node info, version and stats
+    #[serde(rename = "stats:read")]
+    StatsRead,
+    #[serde(rename = "admin:plugins")]
+    AdminPlugins,
+    #[serde(rename = "admin:routeplanner")]
+    AdminRoutePlanner,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 5] = [
+        ApiScope::Player,
+        ApiScope::Load,
+        ApiScope::StatsRead,
+        ApiScope::AdminPlugins,
+        ApiScope::AdminRoutePlanner,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::Player => "player",
+            ApiScope::Load => "load",
+            ApiScope::StatsRead => "stats:read",
+            ApiScope::AdminPlugins => "admin:plugins",
+            ApiScope::AdminRoutePlanner => "admin:routeplanner",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PersistenceConfig {
+    /// Snapshot players to disk and restore them on startup (default: false)
+    pub enabled: Option<bool>,
+    /// Directory the player snapshots are written to (default: ./data)
+    #[serde(rename = "dataDirectory")]
+    pub data_directory: Option<String>,
+    /// How long changes are collected before a snapshot is written
+    #[serde(rename = "debounceMs")]
+    pub debounce_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ArtworkConfig {
+    /// Rewrite track artwork URLs to this node's /v4/artwork endpoint (default: false)
+    pub proxy: Option<bool>,
+    /// Public base URL of this node used in rewritten artwork URLs, for example
+    /// https://lavalink.example.com. Artwork URLs are relative when unset.
+    #[serde(rename = "publicUrl")]
+    pub public_url: Option<String>,
+    /// Hosts remote artwork may be fetched from. Defaults to the image CDNs
+    /// used by the built-in sources.
+    #[serde(rename = "allowedHosts")]
+    pub allowed_hosts: Option<Vec<String>>,
+    /// Total size of the images kept in the in-memory cache, in megabytes (default: 64)
+    #[serde(rename = "cacheSizeMb")]
+    pub cache_size_mb: Option<usize>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -85,6 +204,48 @@ pub struct SourcesConfig {
     pub nico: Option<bool>,
     pub http: Option<bool>,
     pub local: Option<bool>,
+    pub podcast: Option<bool>,
+    pub synth: Option<bool>,
+    pub mirroring: Option<MirroringConfig>,
+    #[serde(rename = "localLibrary")]
+    pub local_library: Option<LocalLibraryConfig>,
+    #[serde(rename = "podcastFeeds")]
+    pub podcast_feeds: Option<PodcastFeedsConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MirroringConfig {
+    /// Search queries tried in order when mirroring Spotify, Apple Music and
+    /// Deezer tracks. Supports %ISRC%, %QUERY%, %TITLE% and %ARTIST%.
+    pub providers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct LocalLibraryConfig {
+    /// Directories the local source may read from and indexes for search.
+    /// Defaults to ./music, ./audio and ./tracks.
+    pub directories: Option<Vec<String>>,
+    /// How many directory levels below each directory are indexed
+    #[serde(rename = "maxDepth")]
+    pub max_depth: Option<usize>,
+    /// File the tag index is persisted to; kept in memory only when unset
+    #[serde(rename = "indexFile")]
+    pub index_file: Option<String>,
+    /// How often the directories are checked for added, changed or removed files
+    #[serde(rename = "scanIntervalMs")]
+    pub scan_interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PodcastFeedsConfig {
+    /// Feeds fetched for `podcast:` searches even if no client loaded them yet
+    pub feeds: Option<Vec<String>>,
+    /// File loaded feeds are persisted to, so searches survive restarts
+    #[serde(rename = "cacheFile")]
+    pub cache_file: Option<String>,
+    /// How long a loaded feed is served from the cache before it is fetched again
+    #[serde(rename = "refreshIntervalMs")]
+    pub refresh_interval_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -103,6 +264,30 @@ pub struct FiltersConfig {
     pub low_pass: Option<bool>,
 }
 
+impl FiltersConfig {
+    /// Names of the enabled filters; filters are enabled unless set to false
+    pub fn enabled_filters(&self) -> Vec<String> {
+        let enabled = [
+            self.volume,
+            self.equalizer,
+            self.karaoke,
+            self.timescale,
+            self.tremolo,
+            self.vibrato,
+            self.distortion,
+            self.rotation,
+            self.channel_mix,
+            self.low_pass,
+        ];
+        crate::protocol::FILTERS
+            .iter()
+            .zip(enabled)
+            .filter(|(_, enabled)| enabled.unwrap_or(true))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ResamplingQuality {
@@ -249,6 +434,34 @@ pub struct RollingPolicyConfig {
 }
 
 impl LavalinkConfig {
+    /// Refuse settings that parse but can't work
+    pub fn validate(&self) -> Result<()> {
+        // An empty key would authenticate requests with an empty Authorization header
+        if let Some(key) = self
+            .lavalink
+            .server
+            .api_keys
+            .iter()
+            .flatten()
+            .find(|key| key.key.is_empty())
+        {
+            anyhow::bail!("lavalink.server.apiKeys: key '{}' is empty", key.name);
+        }
+        if let Some(limits) = &self.lavalink.server.client_limits {
+            for (name, bucket) in [
+                ("loadTracks", &limits.load_tracks),
+                ("playerUpdates", &limits.player_updates),
+            ] {
+                if bucket.as_ref().is_some_and(|bucket| !bucket.is_valid()) {
+                    anyhow::bail!(
+                        "lavalink.server.clientLimits.{name}.refillPerSecond must be a positive number"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub async fn load<P: AsRef<Path>>(
         #[cfg_attr(not(feature = "server"), allow(unused_variables))] path: P,
     ) -> Result<Self> {
@@ -262,6 +475,7 @@ impl LavalinkConfig {
             {
                 let config: LavalinkConfig = serde_yaml::from_str(&content)
                     .with_context(|| "Failed to parse YAML configuration")?;
+                config.validate()?;
                 Ok(config)
             }
             #[cfg(not(feature = "rest-api"))]
@@ -296,6 +510,11 @@ impl Default for LavalinkConfig {
                         nico: Some(true),
                         http: Some(true),
                         local: Some(false),
+                        podcast: Some(true),
+                        synth: Some(true),
+                        mirroring: None,
+                        local_library: None,
+                        podcast_feeds: None,
                     },
                     filters: FiltersConfig {
                         volume: Some(true),
@@ -329,6 +548,11 @@ impl Default for LavalinkConfig {
                         socket_timeout_ms: Some(3000),
                     }),
                     discord_bot_token: None,
+                    artwork: None,
+                    persistence: None,
+                    v3_compatibility: None,
+                    api_keys: None,
+                    client_limits: None,
                 },
                 plugins: None,
             },
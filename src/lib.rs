@@ -2,6 +2,7 @@
 // This module exports the core functionality for use as a library
 
 pub mod config;
+pub mod lyrics;
 pub mod plugin;
 pub mod protocol;
 pub mod server;
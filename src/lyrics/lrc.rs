@@ -0,0 +1,138 @@
+// LRC lyrics parsing
+// Handles `[mm:ss.xx]` line stamps, repeated stamps, `[offset:ms]` and
+// enhanced `<mm:ss.xx>` word stamps (which are dropped)
+
+use super::LyricsLine;
+
+/// Parse LRC text into timed lines sorted by timestamp
+///
+/// Lines without a time stamp (including metadata tags such as `[ar:...]`)
+/// are skipped. Durations are filled in from the next line's start.
+pub fn parse_lrc(text: &str) -> Vec<LyricsLine> {
+    let mut offset: i64 = 0;
+    let mut lines = Vec::new();
+
+    for raw in text.lines() {
+        let mut rest = raw.trim();
+        let mut stamps = Vec::new();
+
+        while let Some(tag_end) = rest.strip_prefix('[').and_then(|tag| tag.find(']')) {
+            let tag = &rest[1..=tag_end];
+            if let Some(value) = tag.strip_prefix("offset:") {
+                offset = value.trim().parse().unwrap_or(offset);
+            } else if let Some(timestamp) = parse_timestamp(tag) {
+                stamps.push(timestamp);
+            }
+            rest = rest[tag_end + 2..].trim_start();
+        }
+
+        if stamps.is_empty() {
+            continue;
+        }
+
+        let line = strip_word_stamps(rest);
+        for timestamp in stamps {
+            lines.push(LyricsLine {
+                // A positive offset makes lyrics appear sooner
+                timestamp: (timestamp as i64 - offset).max(0) as u64,
+                duration: None,
+                line: line.clone(),
+            });
+        }
+    }
+
+    lines.sort_by_key(|line| line.timestamp);
+    for index in 1..lines.len() {
+        let next = lines[index].timestamp;
+        let previous = &mut lines[index - 1];
+        previous.duration = Some(next - previous.timestamp);
+    }
+    lines
+}
+
+/// Whether text contains at least one LRC time stamp
+pub fn looks_like_lrc(text: &str) -> bool {
+    text.lines().any(|line| {
+        line.trim()
+            .strip_prefix('[')
+            .and_then(|tag| tag.split_once(']'))
+            .is_some_and(|(tag, _)| parse_timestamp(tag).is_some())
+    })
+}
+
+/// Parse `mm:ss`, `mm:ss.xx` or `mm:ss:xx` into milliseconds
+fn parse_timestamp(tag: &str) -> Option<u64> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, Some(fraction)),
+        None => (rest, None),
+    };
+    let seconds: u64 = seconds.trim().parse().ok()?;
+    if seconds >= 60 {
+        return None;
+    }
+
+    let millis = match fraction {
+        Some(fraction) if !fraction.is_empty() && fraction.chars().all(|c| c.is_ascii_digit()) => {
+            // Hundredths are the norm, but tenths and thousandths also occur
+            let digits = &fraction[..fraction.len().min(3)];
+            digits.parse::<u64>().ok()? * 10u64.pow(3 - digits.len() as u32)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    Some(minutes * 60_000 + seconds * 1000 + millis)
+}
+
+fn strip_word_stamps(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) if parse_timestamp(&rest[start + 1..start + end]).is_some() => {
+                output.push_str(&rest[..start]);
+                rest = &rest[start + end + 1..];
+            }
+            _ => {
+                output.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lrc() {
+        let lrc = "[ar:Artist]\n[ti:Song]\n[offset:+200]\n\
+                   [00:12.00]First line\n\
+                   [00:17.20][01:02.5]Chorus <00:17.80>with <00:18.10>words\n\
+                   [00:21.10]\n\
+                   not a lyric line";
+        let lines = parse_lrc(lrc);
+
+        let stamps: Vec<u64> = lines.iter().map(|l| l.timestamp).collect();
+        assert_eq!(stamps, vec![11_800, 17_000, 20_900, 62_300]);
+        assert_eq!(lines[1].line, "Chorus with words");
+        assert_eq!(lines[1].duration, Some(3_900));
+        assert_eq!(lines[2].line, "");
+        assert_eq!(lines[3].line, "Chorus with words");
+        assert_eq!(lines[3].duration, None);
+    }
+
+    #[test]
+    fn test_looks_like_lrc() {
+        assert!(looks_like_lrc("[ti:Song]\n[00:01.00]Hello"));
+        assert!(!looks_like_lrc("Just some\nplain lyrics [chorus]"));
+        assert_eq!(parse_timestamp("01:02.345"), Some(62_345));
+        assert_eq!(parse_timestamp("00:75.00"), None);
+    }
+}
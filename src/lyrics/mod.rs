@@ -0,0 +1,312 @@
+// Lyrics module
+// Looks up plain and time-synced lyrics for tracks through registered providers
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, warn};
+
+use crate::protocol::Track;
+
+pub mod lrc;
+pub mod sylt;
+
+pub use lrc::{looks_like_lrc, parse_lrc};
+
+/// Maximum number of tracks whose lyrics lookups are cached
+const MAX_CACHED_TRACKS: usize = 512;
+
+/// Lyrics for a track, optionally split into timed lines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lyrics {
+    /// Source of the track the lyrics belong to
+    pub source_name: String,
+    /// Provider that returned the lyrics
+    pub provider: String,
+    /// Plain text, if the provider has it
+    pub text: Option<String>,
+    /// Timed lines, empty when the lyrics are not synced
+    pub lines: Vec<LyricsLine>,
+}
+
+/// A single timed lyrics line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsLine {
+    /// Start of the line in milliseconds
+    pub timestamp: u64,
+    /// How long the line lasts in milliseconds, if known
+    pub duration: Option<u64>,
+    pub line: String,
+}
+
+impl Lyrics {
+    /// Build synced lyrics from timed lines, filling in the plain text
+    pub fn synced(source_name: &str, provider: &str, lines: Vec<LyricsLine>) -> Self {
+        let text = lines
+            .iter()
+            .map(|line| line.line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self {
+            source_name: source_name.to_string(),
+            provider: provider.to_string(),
+            text: Some(text),
+            lines,
+        }
+    }
+
+    /// Build unsynced lyrics from plain text
+    pub fn plain(source_name: &str, provider: &str, text: &str) -> Self {
+        Self {
+            source_name: source_name.to_string(),
+            provider: provider.to_string(),
+            text: Some(text.trim().to_string()),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Index of the line being sung at `position` milliseconds
+    pub fn line_at(&self, position: u64) -> Option<usize> {
+        self.lines
+            .partition_point(|line| line.timestamp <= position)
+            .checked_sub(1)
+    }
+}
+
+/// A source of lyrics, such as local tags or a remote lyrics service
+///
+/// Plugins register providers through [`crate::plugin::LavalinkPlugin::lyrics_providers`].
+#[async_trait]
+pub trait LyricsProvider: Send + Sync {
+    /// Provider name, reported in [`Lyrics::provider`] and used in logs
+    fn name(&self) -> &str;
+
+    /// Look up lyrics for a track, returning `None` when the provider has none
+    async fn fetch(&self, track: &Track) -> Result<Option<Lyrics>>;
+}
+
+/// A provider together with the name of the plugin that registered it, if any
+type OwnedProvider = (Option<String>, Arc<dyn LyricsProvider>);
+
+/// Registered providers plus a cache of lookups keyed by encoded track
+#[derive(Default)]
+pub struct LyricsManager {
+    /// Providers in lookup order
+    providers: RwLock<Vec<OwnedProvider>>,
+    cache: Mutex<HashMap<String, Option<Arc<Lyrics>>>>,
+    pending: Mutex<HashSet<String>>,
+}
+
+impl LyricsManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a built-in provider; these are asked before plugin providers
+    pub fn register_provider(&self, provider: Arc<dyn LyricsProvider>) {
+        if let Ok(mut providers) = self.providers.write() {
+            let position = providers
+                .iter()
+                .position(|(owner, _)| owner.is_some())
+                .unwrap_or(providers.len());
+            providers.insert(position, (None, provider));
+        }
+        self.clear_cache();
+    }
+
+    /// Register the providers of a plugin
+    pub fn register_plugin_providers(
+        &self,
+        plugin_name: &str,
+        plugin_providers: Vec<Arc<dyn LyricsProvider>>,
+    ) {
+        if plugin_providers.is_empty() {
+            return;
+        }
+        if let Ok(mut providers) = self.providers.write() {
+            providers.extend(
+                plugin_providers
+                    .into_iter()
+                    .map(|provider| (Some(plugin_name.to_string()), provider)),
+            );
+        }
+        self.clear_cache();
+    }
+
+    /// Remove the providers registered by a plugin
+    pub fn unregister_plugin_providers(&self, plugin_name: &str) {
+        if let Ok(mut providers) = self.providers.write() {
+            providers.retain(|(owner, _)| owner.as_deref() != Some(plugin_name));
+        }
+        self.clear_cache();
+    }
+
+    /// Cached lookup result: `None` if the track was not looked up yet
+    pub fn cached(&self, track: &Track) -> Option<Option<Arc<Lyrics>>> {
+        self.cache.lock().ok()?.get(&track.encoded).cloned()
+    }
+
+    /// Look up lyrics for a track, asking each provider in order
+    pub async fn lyrics_for(&self, track: &Track) -> Option<Arc<Lyrics>> {
+        if let Some(cached) = self.cached(track) {
+            return cached;
+        }
+
+        let providers: Vec<Arc<dyn LyricsProvider>> = self
+            .providers
+            .read()
+            .map(|providers| providers.iter().map(|(_, p)| Arc::clone(p)).collect())
+            .unwrap_or_default();
+
+        let mut found = None;
+        for provider in providers {
+            match provider.fetch(track).await {
+                Ok(Some(lyrics)) => {
+                    debug!(
+                        "Found lyrics for {} from {}",
+                        track.info.title,
+                        provider.name()
+                    );
+                    found = Some(Arc::new(lyrics));
+                    break;
+                }
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "Lyrics provider {} failed for {}: {}",
+                    provider.name(),
+                    track.info.title,
+                    e
+                ),
+            }
+        }
+
+        if let Ok(mut cache) = self.cache.lock() {
+            if cache.len() >= MAX_CACHED_TRACKS {
+                cache.clear();
+            }
+            cache.insert(track.encoded.clone(), found.clone());
+        }
+        found
+    }
+
+    /// Start looking up lyrics in the background unless already known or in flight
+    pub fn prefetch(self: &Arc<Self>, track: &Track) {
+        if self.cached(track).is_some() {
+            return;
+        }
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        if !pending.insert(track.encoded.clone()) {
+            return;
+        }
+        drop(pending);
+
+        let manager = Arc::clone(self);
+        let track = track.clone();
+        tokio::spawn(async move {
+            manager.lyrics_for(&track).await;
+            if let Ok(mut pending) = manager.pending.lock() {
+                pending.remove(&track.encoded);
+            }
+        });
+    }
+
+    fn clear_cache(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::TrackInfo;
+
+    struct StaticProvider {
+        name: &'static str,
+        lyrics: Option<Lyrics>,
+    }
+
+    #[async_trait]
+    impl LyricsProvider for StaticProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn fetch(&self, _track: &Track) -> Result<Option<Lyrics>> {
+            Ok(self.lyrics.clone())
+        }
+    }
+
+    fn track(encoded: &str) -> Track {
+        Track {
+            encoded: encoded.to_string(),
+            info: TrackInfo {
+                identifier: encoded.to_string(),
+                is_seekable: true,
+                author: "Author".to_string(),
+                length: 60_000,
+                is_stream: false,
+                position: 0,
+                title: "Title".to_string(),
+                uri: None,
+                artwork_url: None,
+                isrc: None,
+                source_name: "test".to_string(),
+            },
+            #[cfg(feature = "plugins")]
+            plugin_info: HashMap::new(),
+            #[cfg(feature = "rest-api")]
+            user_data: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_line_at_position() {
+        let lyrics = Lyrics::synced(
+            "test",
+            "test",
+            parse_lrc("[00:01.00]One\n[00:03.00]Two\n[00:05.00]Three"),
+        );
+        assert_eq!(lyrics.line_at(500), None);
+        assert_eq!(lyrics.line_at(1000), Some(0));
+        assert_eq!(lyrics.line_at(4999), Some(1));
+        assert_eq!(lyrics.line_at(60_000), Some(2));
+        assert_eq!(lyrics.text.as_deref(), Some("One\nTwo\nThree"));
+    }
+
+    #[tokio::test]
+    async fn test_providers_are_asked_in_order() {
+        let manager = LyricsManager::new();
+        manager.register_plugin_providers(
+            "lyrics-plugin",
+            vec![Arc::new(StaticProvider {
+                name: "remote",
+                lyrics: Some(Lyrics::plain("test", "remote", "From the plugin")),
+            })],
+        );
+        let lyrics = manager.lyrics_for(&track("a")).await.unwrap();
+        assert_eq!(lyrics.provider, "remote");
+        assert!(manager.cached(&track("a")).is_some());
+
+        // Built-in providers go first even when registered later
+        manager.register_provider(Arc::new(StaticProvider {
+            name: "local",
+            lyrics: Some(Lyrics::plain("test", "local", "From the file")),
+        }));
+        assert!(manager.cached(&track("a")).is_none());
+        let lyrics = manager.lyrics_for(&track("a")).await.unwrap();
+        assert_eq!(lyrics.provider, "local");
+
+        manager.unregister_plugin_providers("lyrics-plugin");
+        let providers = manager.providers.read().unwrap();
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].1.name(), "local");
+    }
+}
@@ -0,0 +1,283 @@
+// ID3v2 SYLT (synchronised lyrics) reader
+// Symphonia skips SYLT frames, so this walks the ID3v2 tag at the start of a file
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::LyricsLine;
+
+/// SYLT timestamp format: absolute time in milliseconds
+const TIMESTAMP_MILLISECONDS: u8 = 2;
+
+/// Read synchronised lyrics from the ID3v2 tag at the start of a file
+pub fn read_file(path: &Path) -> std::io::Result<Option<Vec<LyricsLine>>> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; 10];
+    if file.read_exact(&mut header).is_err() || &header[..3] != b"ID3" {
+        return Ok(None);
+    }
+
+    let size = syncsafe(&header[6..10]) as usize;
+    let mut tag = header.to_vec();
+    tag.resize(10 + size, 0);
+    file.read_exact(&mut tag[10..])?;
+    Ok(read_sylt(&tag))
+}
+
+/// Parse the first millisecond-timed SYLT frame of an ID3v2 tag
+pub fn read_sylt(tag: &[u8]) -> Option<Vec<LyricsLine>> {
+    if tag.len() < 10 || &tag[..3] != b"ID3" {
+        return None;
+    }
+    let version = tag[3];
+    let flags = tag[5];
+    let size = (syncsafe(&tag[6..10]) as usize).min(tag.len() - 10);
+
+    let mut body = tag[10..10 + size].to_vec();
+    if flags & 0x80 != 0 && version < 4 {
+        body = remove_unsynchronisation(&body);
+    }
+
+    let mut offset = 0;
+    if flags & 0x40 != 0 && version >= 3 {
+        let extended = read_u32(&body, 0)? as usize;
+        // v2.3 excludes the size field itself, v2.4 includes it
+        offset = if version == 3 { extended + 4 } else { extended };
+    }
+
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    while offset + header_len <= body.len() {
+        let id = &body[offset..offset + id_len];
+        if id[0] == 0 {
+            break;
+        }
+        let frame_size = match version {
+            2 => u32::from_be_bytes([0, body[offset + 3], body[offset + 4], body[offset + 5]]),
+            3 => read_u32(&body, offset + 4)?,
+            _ => syncsafe(&body[offset + 4..offset + 8]),
+        } as usize;
+        let start = offset + header_len;
+        let end = (start + frame_size).min(body.len());
+        // Format flags live in the second flag byte (v2.2 frames have none)
+        let frame_flags = if version == 2 { 0 } else { body[start - 1] };
+        offset = start + frame_size;
+
+        if id != b"SYLT" && id != b"SLT" {
+            continue;
+        }
+
+        let mut frame = body[start..end].to_vec();
+        if version >= 4 {
+            // Compressed or encrypted frames are not supported
+            if frame_flags & 0x0C != 0 {
+                continue;
+            }
+            if frame_flags & 0x02 != 0 {
+                frame = remove_unsynchronisation(&frame);
+            }
+            if frame_flags & 0x01 != 0 && frame.len() >= 4 {
+                frame.drain(..4);
+            }
+        } else if version == 3 && frame_flags & 0xC0 != 0 {
+            continue;
+        }
+
+        if let Some(lines) = parse_frame(&frame) {
+            return Some(lines);
+        }
+    }
+    None
+}
+
+fn parse_frame(frame: &[u8]) -> Option<Vec<LyricsLine>> {
+    if frame.len() < 6 {
+        return None;
+    }
+    let encoding = frame[0];
+    if frame[4] != TIMESTAMP_MILLISECONDS {
+        return None;
+    }
+
+    // Skip the content descriptor
+    let (_, mut rest) = read_string(encoding, &frame[6..])?;
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let (text, after) = read_string(encoding, rest)?;
+        let timestamp = read_u32(after, 0)? as u64;
+        entries.push((timestamp, text));
+        rest = &after[4..];
+    }
+    if entries.is_empty() {
+        return None;
+    }
+
+    // Some taggers sync individual syllables and start new lines with a newline
+    let syllables = entries
+        .iter()
+        .skip(1)
+        .any(|(_, text)| text.starts_with(['\n', '\r']));
+    let mut lines: Vec<LyricsLine> = Vec::new();
+    for (timestamp, text) in entries {
+        let starts_line = text.starts_with(['\n', '\r']);
+        let text = text.trim_matches(['\n', '\r']);
+        match lines.last_mut() {
+            Some(last) if syllables && !starts_line => last.line.push_str(text),
+            _ => lines.push(LyricsLine {
+                timestamp,
+                duration: None,
+                line: text.to_string(),
+            }),
+        }
+    }
+
+    lines.sort_by_key(|line| line.timestamp);
+    for index in 1..lines.len() {
+        let next = lines[index].timestamp;
+        let previous = &mut lines[index - 1];
+        previous.duration = Some(next.saturating_sub(previous.timestamp));
+    }
+    Some(lines)
+}
+
+/// Read a terminated string in the given ID3 text encoding
+fn read_string(encoding: u8, data: &[u8]) -> Option<(String, &[u8])> {
+    match encoding {
+        0 | 3 => {
+            let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+            let bytes = &data[..end];
+            let text = if encoding == 0 {
+                bytes.iter().map(|&b| b as char).collect()
+            } else {
+                String::from_utf8_lossy(bytes).into_owned()
+            };
+            Some((text, &data[(end + 1).min(data.len())..]))
+        }
+        1 | 2 => {
+            let end = data
+                .chunks_exact(2)
+                .position(|unit| unit == [0, 0])
+                .map(|units| units * 2)
+                .unwrap_or(data.len() & !1);
+            let mut bytes = &data[..end];
+            let mut big_endian = encoding == 2;
+            if encoding == 1 && bytes.len() >= 2 {
+                match bytes[..2] {
+                    [0xFF, 0xFE] => bytes = &bytes[2..],
+                    [0xFE, 0xFF] => {
+                        big_endian = true;
+                        bytes = &bytes[2..];
+                    }
+                    _ => {}
+                }
+            }
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|unit| {
+                    if big_endian {
+                        u16::from_be_bytes([unit[0], unit[1]])
+                    } else {
+                        u16::from_le_bytes([unit[0], unit[1]])
+                    }
+                })
+                .collect();
+            Some((
+                String::from_utf16_lossy(&units),
+                &data[(end + 2).min(data.len())..],
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0u32, |size, &byte| (size << 7) | (byte & 0x7F) as u32)
+}
+
+/// Undo ID3 unsynchronisation (`FF 00` becomes `FF`)
+fn remove_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut previous = 0u8;
+    for &byte in data {
+        if !(previous == 0xFF && byte == 0) {
+            output.push(byte);
+        }
+        previous = byte;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id3v23_tag(frames: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (id, data) in frames {
+            body.extend_from_slice(*id);
+            body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            body.extend_from_slice(&[0, 0]);
+            body.extend_from_slice(data);
+        }
+        body.extend_from_slice(&[0; 16]);
+
+        let size = body.len() as u32;
+        let mut tag = b"ID3\x03\x00\x00".to_vec();
+        tag.extend((0..4).rev().map(|i| ((size >> (i * 7)) & 0x7F) as u8));
+        tag.extend(body);
+        tag
+    }
+
+    #[test]
+    fn test_read_sylt_lines() {
+        let mut sylt = vec![3, b'e', b'n', b'g', TIMESTAMP_MILLISECONDS, 1, 0];
+        for (text, time) in [("Hello", 1_000u32), ("World", 2_500)] {
+            sylt.extend_from_slice(text.as_bytes());
+            sylt.push(0);
+            sylt.extend_from_slice(&time.to_be_bytes());
+        }
+        let tag = id3v23_tag(&[(b"TIT2", b"\x03Song".to_vec()), (b"SYLT", sylt)]);
+
+        let lines = read_sylt(&tag).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].line, "Hello");
+        assert_eq!(lines[0].timestamp, 1_000);
+        assert_eq!(lines[0].duration, Some(1_500));
+        assert_eq!(lines[1].line, "World");
+    }
+
+    #[test]
+    fn test_read_sylt_syllables_utf16() {
+        let mut sylt = vec![
+            1,
+            b'e',
+            b'n',
+            b'g',
+            TIMESTAMP_MILLISECONDS,
+            1,
+            0xFF,
+            0xFE,
+            0,
+            0,
+        ];
+        for (text, time) in [("Hel", 0u32), ("lo", 400), ("\nNext", 900)] {
+            sylt.extend_from_slice(&[0xFF, 0xFE]);
+            sylt.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+            sylt.extend_from_slice(&[0, 0]);
+            sylt.extend_from_slice(&time.to_be_bytes());
+        }
+        let tag = id3v23_tag(&[(b"SYLT", sylt)]);
+
+        let lines = read_sylt(&tag).unwrap();
+        let text: Vec<&str> = lines.iter().map(|line| line.line.as_str()).collect();
+        assert_eq!(text, vec!["Hello", "Next"]);
+        assert_eq!(lines[1].timestamp, 900);
+    }
+}
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use tracing::info;
 
 mod config;
+mod lyrics;
 mod plugin;
 mod protocol;
 mod server;
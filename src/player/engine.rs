@@ -4,17 +4,20 @@
 use anyhow::{anyhow, Result};
 #[cfg(feature = "discord")]
 use songbird::{input::Input, tracks::Track as SongbirdTrack, Call};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, Mutex, RwLock};
-use tokio::time::{interval, Instant};
+use tokio::time::interval;
 use tracing::{debug, info, warn};
 
 use symphonia::core::codecs::{Decoder, DecoderOptions};
-use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
 
 use super::{PlayerEvent, TrackEndReason};
 use crate::audio::filters::{AudioFilterManager, AudioFormat};
@@ -39,12 +42,14 @@ type AudioInput = ();
 pub struct AudioPlayerEngine {
     /// Current track being played
     current_track: Arc<RwLock<Option<Track>>>,
-    /// Audio decoder for the current track
-    decoder: Arc<Mutex<Option<Box<dyn Decoder>>>>,
-    /// Format reader for the current track
-    format_reader: Arc<Mutex<Option<Box<dyn FormatReader>>>>,
-    /// Current playback position in milliseconds
+    /// Position the playback loop should seek its decoded source to
+    pending_seek: Arc<Mutex<Option<u64>>>,
+    /// Incremented for every track played, stopping the previous playback loop
+    generation: Arc<AtomicU64>,
+    /// Playback position in milliseconds when `decoded` started counting
     position: Arc<RwLock<u64>>,
+    /// Microseconds of audio decoded since the track started or was seeked
+    decoded: Arc<AtomicU64>,
     /// Whether the player is paused
     paused: Arc<RwLock<bool>>,
 
@@ -57,8 +62,6 @@ pub struct AudioPlayerEngine {
 
     /// Current audio filters
     filters: Arc<RwLock<Filters>>,
-    /// Last position update timestamp
-    last_position_update: Arc<RwLock<Instant>>,
     /// Whether seeking is in progress
     seeking: Arc<RwLock<bool>>,
     /// Voice call for audio output (Discord mode only)
@@ -73,6 +76,146 @@ pub struct AudioPlayerEngine {
     streaming_manager: Arc<AudioStreamingManager>,
     /// Audio filter manager for processing audio effects
     filter_manager: Arc<AudioFilterManager>,
+    /// Negotiated Niconico session, held so its heartbeat runs while playing
+    #[cfg(feature = "audio-sources")]
+    nico_stream: Arc<Mutex<Option<crate::audio::sources::NicoStream>>>,
+    /// Task following ICY metadata of the current radio stream
+    #[cfg(feature = "audio-sources")]
+    icy_watcher: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// Configured sources that mirrored tracks are resolved through
+    #[cfg(feature = "audio-sources")]
+    sources: Option<Arc<crate::audio::AudioSourceManager>>,
+}
+
+/// Duration of one audio frame
+pub const FRAME_DURATION: Duration = Duration::from_millis(20);
+
+/// Position reached after decoding `decoded` microseconds from `start` milliseconds
+fn played_position(start: u64, decoded: &AtomicU64) -> u64 {
+    start + decoded.load(Ordering::Relaxed) / 1000
+}
+
+/// Audio the playback loop decodes when no voice connection does it
+enum FrameSource {
+    /// A fetched stream demuxed and decoded by symphonia
+    Decoded {
+        reader: Box<dyn FormatReader>,
+        decoder: Box<dyn Decoder>,
+        track_id: u32,
+    },
+    /// A synthetic track rendered locally
+    #[cfg(feature = "audio-sources")]
+    Synth(crate::audio::sources::synth::SynthStream),
+}
+
+impl FrameSource {
+    /// Decode the next chunk of audio, returning its length or `None` at the end
+    fn decode_next(&mut self) -> Result<Option<Duration>> {
+        match self {
+            FrameSource::Decoded {
+                reader,
+                decoder,
+                track_id,
+            } => loop {
+                let packet = match reader.next_packet() {
+                    Ok(packet) => packet,
+                    Err(SymphoniaError::IoError(e))
+                        if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        return Ok(None);
+                    }
+                    Err(e) => return Err(anyhow!("Failed to read packet: {}", e)),
+                };
+                if packet.track_id() != *track_id {
+                    continue;
+                }
+                match decoder.decode(&packet) {
+                    Ok(buffer) => {
+                        let rate = buffer.spec().rate.max(1) as u64;
+                        let micros = buffer.frames() as u64 * 1_000_000 / rate;
+                        return Ok(Some(Duration::from_micros(micros)));
+                    }
+                    // Corrupt packets are skipped like players do
+                    Err(SymphoniaError::DecodeError(e)) => {
+                        debug!("Skipping undecodable packet: {}", e);
+                    }
+                    Err(e) => return Err(anyhow!("Failed to decode packet: {}", e)),
+                }
+            },
+            #[cfg(feature = "audio-sources")]
+            FrameSource::Synth(stream) => {
+                use crate::audio::sources::synth::{CHANNELS, SAMPLE_RATE};
+                let mut samples = [0.0f32; 960 * CHANNELS as usize];
+                let frames = (stream.read_samples(&mut samples) / CHANNELS as usize) as u64;
+                Ok((frames > 0)
+                    .then(|| Duration::from_micros(frames * 1_000_000 / SAMPLE_RATE as u64)))
+            }
+        }
+    }
+
+    /// Decode `budget` microseconds of audio, adding what was decoded to `decoded`
+    ///
+    /// Returns the budget left over, which is negative when the last chunk
+    /// overshot it, and why the track ended if it did.
+    fn decode_budget(
+        &mut self,
+        mut budget: i64,
+        decoded: &AtomicU64,
+        guild_id: &str,
+    ) -> (i64, Option<TrackEndReason>) {
+        while budget > 0 {
+            match self.decode_next() {
+                Ok(Some(length)) => {
+                    let micros = length.as_micros() as u64;
+                    decoded.fetch_add(micros, Ordering::Relaxed);
+                    budget -= micros as i64;
+                }
+                Ok(None) => return (budget, Some(TrackEndReason::Finished)),
+                Err(e) => {
+                    warn!("Playback failed in guild {}: {}", guild_id, e);
+                    return (budget, Some(TrackEndReason::LoadFailed));
+                }
+            }
+        }
+        (budget, None)
+    }
+
+    /// Continue decoding at `position` milliseconds
+    fn seek(&mut self, position: u64) -> Result<()> {
+        match self {
+            FrameSource::Decoded {
+                reader,
+                decoder,
+                track_id,
+            } => {
+                let time = Time::new(position / 1000, (position % 1000) as f64 / 1000.0);
+                reader
+                    .seek(
+                        SeekMode::Accurate,
+                        SeekTo::Time {
+                            time,
+                            track_id: Some(*track_id),
+                        },
+                    )
+                    .map_err(|e| anyhow!("Failed to seek: {}", e))?;
+                decoder.reset();
+                Ok(())
+            }
+            #[cfg(feature = "audio-sources")]
+            FrameSource::Synth(stream) => {
+                use crate::audio::sources::synth::SAMPLE_RATE;
+                stream.seek_frame(position * SAMPLE_RATE as u64 / 1000);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Stream location resolved for playback
+struct ResolvedStream {
+    uri: String,
+    is_hls: bool,
+    headers: reqwest::header::HeaderMap,
 }
 
 #[allow(dead_code)]
@@ -93,9 +236,10 @@ impl AudioPlayerEngine {
 
         Self {
             current_track: Arc::new(RwLock::new(None)),
-            decoder: Arc::new(Mutex::new(None)),
-            format_reader: Arc::new(Mutex::new(None)),
+            pending_seek: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
             position: Arc::new(RwLock::new(0)),
+            decoded: Arc::new(AtomicU64::new(0)),
             paused: Arc::new(RwLock::new(false)),
 
             event_sender,
@@ -103,7 +247,6 @@ impl AudioPlayerEngine {
             playing: Arc::new(RwLock::new(false)),
 
             filters: Arc::new(RwLock::new(Filters::new())),
-            last_position_update: Arc::new(RwLock::new(Instant::now())),
             seeking: Arc::new(RwLock::new(false)),
             #[cfg(feature = "discord")]
             voice_call: Arc::new(RwLock::new(None)),
@@ -112,6 +255,12 @@ impl AudioPlayerEngine {
             quality_manager: Arc::new(RwLock::new(quality_manager)),
             streaming_manager: Arc::new(streaming_manager),
             filter_manager: Arc::new(filter_manager),
+            #[cfg(feature = "audio-sources")]
+            nico_stream: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "audio-sources")]
+            icy_watcher: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "audio-sources")]
+            sources: None,
         }
     }
 
@@ -137,9 +286,10 @@ impl AudioPlayerEngine {
 
         Self {
             current_track: Arc::new(RwLock::new(None)),
-            decoder: Arc::new(Mutex::new(None)),
-            format_reader: Arc::new(Mutex::new(None)),
+            pending_seek: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
             position: Arc::new(RwLock::new(0)),
+            decoded: Arc::new(AtomicU64::new(0)),
             paused: Arc::new(RwLock::new(false)),
 
             event_sender,
@@ -147,7 +297,6 @@ impl AudioPlayerEngine {
             playing: Arc::new(RwLock::new(false)),
 
             filters: Arc::new(RwLock::new(Filters::new())),
-            last_position_update: Arc::new(RwLock::new(Instant::now())),
             seeking: Arc::new(RwLock::new(false)),
             #[cfg(feature = "discord")]
             voice_call: Arc::new(RwLock::new(None)),
@@ -156,9 +305,22 @@ impl AudioPlayerEngine {
             quality_manager: Arc::new(RwLock::new(quality_manager)),
             streaming_manager: Arc::new(streaming_manager),
             filter_manager: Arc::new(filter_manager),
+            #[cfg(feature = "audio-sources")]
+            nico_stream: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "audio-sources")]
+            icy_watcher: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "audio-sources")]
+            sources: None,
         }
     }
 
+    /// Resolve mirrored tracks through the configured sources
+    #[cfg(feature = "audio-sources")]
+    pub fn with_sources(mut self, sources: Arc<crate::audio::AudioSourceManager>) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
     /// Set the voice call for audio output (Discord mode only)
     #[cfg(feature = "discord")]
     pub async fn set_voice_call(&self, call: Arc<Mutex<Call>>) {
@@ -277,6 +439,8 @@ impl AudioPlayerEngine {
         // Reset position
         let start_pos = start_time.unwrap_or(0);
         *self.position.write().await = start_pos;
+        self.decoded.store(0, Ordering::Relaxed);
+        *self.pending_seek.lock().await = (start_pos > 0).then_some(start_pos);
 
         // Start playback
         *self.playing.write().await = true;
@@ -287,17 +451,23 @@ impl AudioPlayerEngine {
         #[cfg(feature = "discord")]
         self.start_voice_streaming(&track).await?;
 
+        // Without voice playback the track is decoded here to follow its position
         #[cfg(not(feature = "discord"))]
-        {
-            // In standalone mode, just log that we would start streaming
-            info!(
-                "Would start voice streaming for track: {} in standalone mode",
-                track.info.title
-            );
-        }
+        let source = match self.load_audio_source(&track).await {
+            Ok(source) => Some(source),
+            Err(e) => {
+                warn!(
+                    "Cannot decode track {} in guild {}, following its position by the clock: {}",
+                    track.info.title, self.guild_id, e
+                );
+                None
+            }
+        };
+        #[cfg(feature = "discord")]
+        let source = None;
 
         // Start the playback loop
-        self.start_playback_loop().await;
+        self.start_playback_loop(source).await;
 
         Ok(())
     }
@@ -316,9 +486,18 @@ impl AudioPlayerEngine {
             debug!("Stopped Songbird track in guild {}", self.guild_id);
         }
 
-        // Clear decoder and format reader
-        *self.decoder.lock().await = None;
-        *self.format_reader.lock().await = None;
+        // The playback loop drops the decoded source once it sees the new generation
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        *self.pending_seek.lock().await = None;
+
+        // Dropping the session stops its heartbeat
+        #[cfg(feature = "audio-sources")]
+        self.nico_stream.lock().await.take();
+
+        #[cfg(feature = "audio-sources")]
+        if let Some(watcher) = self.icy_watcher.lock().await.take() {
+            watcher.abort();
+        }
 
         // Emit track end event if there was a current track
         if let Some(track) = self.current_track.read().await.clone() {
@@ -331,6 +510,7 @@ impl AudioPlayerEngine {
 
         *self.current_track.write().await = None;
         *self.position.write().await = 0;
+        self.decoded.store(0, Ordering::Relaxed);
 
         Ok(())
     }
@@ -374,7 +554,8 @@ impl AudioPlayerEngine {
 
         *self.seeking.write().await = true;
         *self.position.write().await = position;
-        *self.last_position_update.write().await = Instant::now();
+        self.decoded.store(0, Ordering::Relaxed);
+        *self.pending_seek.lock().await = Some(position);
 
         // For Songbird tracks, we need to restart the track at the new position (Discord mode only)
         // This is a limitation of the current implementation - true seeking would require
@@ -523,8 +704,8 @@ impl AudioPlayerEngine {
             }
         }
 
-        // Fallback to internal position tracking
-        *self.position.read().await
+        // Fallback to the audio decoded by the playback loop
+        played_position(*self.position.read().await, &self.decoded)
     }
 
     /// Check if currently playing
@@ -538,56 +719,30 @@ impl AudioPlayerEngine {
     }
 
     /// Load audio source for a track
-    async fn load_audio_source(&self, track: &Track) -> Result<()> {
-        let uri = track
-            .info
-            .uri
-            .as_ref()
-            .ok_or_else(|| anyhow!("Track has no URI"))?;
-        debug!("Loading audio source for track: {}", uri);
+    async fn load_audio_source(&self, track: &Track) -> Result<FrameSource> {
+        // Synthetic tracks are rendered locally instead of fetched
+        #[cfg(feature = "audio-sources")]
+        if track.info.source_name == "synth" {
+            let spec = crate::audio::sources::SynthSpec::parse(&track.info.identifier)?;
+            return Ok(FrameSource::Synth(
+                crate::audio::sources::synth::SynthStream::new(spec),
+            ));
+        }
+
+        let stream = self.resolve_stream(track).await?;
+        debug!("Loading audio source for track: {}", stream.uri);
 
         // Create a hint based on the track URI
         let mut hint = Hint::new();
-        if let Some(extension) = uri.split('.').next_back() {
+        if let Some(extension) = stream.uri.split('.').next_back() {
             hint.with_extension(extension);
         }
 
-        // For now, we'll implement a basic HTTP stream loader
-        // In a full implementation, this would handle various source types
-        let source = self.create_media_source(uri).await?;
-        let media_source_stream = MediaSourceStream::new(source, Default::default());
-
-        // Probe the media source
-        let probe_result = symphonia::default::get_probe()
-            .format(
-                &hint,
-                media_source_stream,
-                &FormatOptions::default(),
-                &MetadataOptions::default(),
-            )
-            .map_err(|e| anyhow!("Failed to probe audio format: {}", e))?;
-
-        let format_reader = probe_result.format;
-
-        // Find the default audio track
-        let _track_id = format_reader
-            .default_track()
-            .ok_or_else(|| anyhow!("No default audio track found"))?
-            .id;
-
-        // Create decoder for the track
-        let decoder = symphonia::default::get_codecs()
-            .make(
-                &format_reader.tracks()[0].codec_params,
-                &DecoderOptions::default(),
-            )
-            .map_err(|e| anyhow!("Failed to create decoder: {}", e))?;
-
-        // Store the format reader and decoder
-        *self.format_reader.lock().await = Some(format_reader);
-        *self.decoder.lock().await = Some(decoder);
-
-        Ok(())
+        let source = self.create_media_source(&stream).await?;
+        // Probing reads the start of the stream, which blocks until it arrives
+        tokio::task::spawn_blocking(move || probe_source(source, &hint))
+            .await
+            .map_err(|e| anyhow!("Failed to probe audio format: {}", e))?
     }
 
     /// Create a Songbird audio input from a track with quality settings
@@ -596,13 +751,20 @@ impl AudioPlayerEngine {
         track: &Track,
         quality_config: &AudioQualityConfig,
     ) -> Result<AudioInput> {
-        let uri = match &track.info.uri {
-            Some(uri) => uri,
-            None => {
-                warn!("Track has no URI: {}", track.info.title);
-                return Err(anyhow!("Track has no URI: {}", track.info.title));
-            }
-        };
+        // Synthetic tracks are rendered locally instead of fetched
+        #[cfg(all(feature = "discord", feature = "audio-sources"))]
+        if track.info.source_name == "synth" {
+            let spec = crate::audio::sources::SynthSpec::parse(&track.info.identifier)?;
+            info!("Creating synth audio input for {}", track.info.identifier);
+            return Ok(crate::audio::sources::synth::songbird_input(spec));
+        }
+
+        #[cfg_attr(not(feature = "discord"), allow(unused_variables))]
+        let ResolvedStream {
+            uri,
+            is_hls,
+            headers,
+        } = self.resolve_stream(track).await?;
 
         debug!(
             "Creating audio input with quality settings: {}kbps, {:?}, {} channels",
@@ -625,19 +787,86 @@ impl AudioPlayerEngine {
             #[cfg(feature = "discord")]
             {
                 let client = reqwest::Client::new();
-                let http_input = songbird::input::HttpRequest::new(client, uri.clone());
 
                 // Note: Quality configuration is applied at the driver level via Config
                 // Individual inputs don't have configuration methods in Songbird
+                if is_hls {
+                    // Prefer our own segment fetcher, which follows live playlists
+                    // and picks audio renditions; songbird's handles encryption
+                    #[cfg(feature = "audio-sources")]
+                    match crate::audio::sources::hls::open_stream(
+                        client.clone(),
+                        &uri,
+                        headers.clone(),
+                    )
+                    .await
+                    {
+                        Ok(stream) => {
+                            let input: Box<dyn symphonia::core::io::MediaSource> = Box::new(stream);
+                            return Ok(Input::Live(
+                                songbird::input::LiveInput::Raw(songbird::input::AudioStream {
+                                    input,
+                                    hint: None,
+                                }),
+                                None,
+                            ));
+                        }
+                        Err(e) => debug!("Falling back to songbird HLS input for {}: {}", uri, e),
+                    }
+
+                    let hls_input =
+                        songbird::input::HlsRequest::new_with_headers(client, uri, headers);
+                    Ok(Input::from(hls_input))
+                } else {
+                    // Radio streams are read through a single connection that
+                    // also carries their ICY metadata
+                    #[cfg(feature = "audio-sources")]
+                    if track.info.is_stream {
+                        let (title_sender, title_receiver) = mpsc::unbounded_channel();
+                        match crate::audio::sources::icy::open_stream(
+                            &client,
+                            &uri,
+                            headers.clone(),
+                            title_sender,
+                        )
+                        .await
+                        {
+                            Ok((stream, content_type)) => {
+                                let forward = self.forward_stream_titles(track, title_receiver);
+                                self.set_icy_watcher(tokio::spawn(forward)).await;
+
+                                let hint = content_type.map(|content_type| {
+                                    let mut hint = Hint::new();
+                                    hint.mime_type(&content_type);
+                                    hint
+                                });
+                                let input: Box<dyn symphonia::core::io::MediaSource> =
+                                    Box::new(stream);
+                                return Ok(Input::Live(
+                                    songbird::input::LiveInput::Raw(songbird::input::AudioStream {
+                                        input,
+                                        hint,
+                                    }),
+                                    None,
+                                ));
+                            }
+                            Err(e) => {
+                                debug!("Falling back to songbird HTTP input for {}: {}", uri, e)
+                            }
+                        }
+                    }
 
-                Ok(Input::from(http_input))
+                    let http_input =
+                        songbird::input::HttpRequest::new_with_headers(client, uri, headers);
+                    Ok(Input::from(http_input))
+                }
             }
             #[cfg(not(feature = "discord"))]
             {
                 // In standalone mode, return a dummy value
                 Ok(())
             }
-        } else if uri.starts_with("file://") || std::path::Path::new(uri).exists() {
+        } else if uri.starts_with("file://") || std::path::Path::new(&uri).exists() {
             info!(
                 "Creating file audio input for URI: {} with {}kbps bitrate",
                 uri, quality_config.bitrate
@@ -646,11 +875,7 @@ impl AudioPlayerEngine {
             // Use Songbird's File input for local files (Discord mode only)
             #[cfg(feature = "discord")]
             {
-                let file_path = if uri.starts_with("file://") {
-                    uri.strip_prefix("file://").unwrap_or(uri)
-                } else {
-                    uri
-                };
+                let file_path = uri.strip_prefix("file://").unwrap_or(&uri);
 
                 let file_input = songbird::input::File::new(file_path.to_string());
 
@@ -672,6 +897,137 @@ impl AudioPlayerEngine {
         }
     }
 
+    /// Resolve where the audio for a track should be fetched from
+    async fn resolve_stream(&self, track: &Track) -> Result<ResolvedStream> {
+        // Spotify, Apple Music and Deezer tracks only carry metadata until mirrored
+        #[cfg(feature = "audio-sources")]
+        let mirrored;
+        #[cfg(feature = "audio-sources")]
+        let track = if crate::audio::sources::mirror::is_mirrored_source(&track.info.source_name) {
+            mirrored = match &self.sources {
+                Some(sources) => sources.resolve_mirror(track).await?,
+                None => {
+                    crate::audio::AudioSourceManager::new()
+                        .resolve_mirror(track)
+                        .await?
+                }
+            };
+            &mirrored
+        } else {
+            track
+        };
+
+        // Niconico streams are negotiated per playback and must be kept alive
+        #[cfg(feature = "audio-sources")]
+        if track.info.source_name == "nico" {
+            let client = self
+                .sources
+                .as_ref()
+                .and_then(|sources| sources.nico_client())
+                .ok_or_else(|| anyhow!("Niconico playback needs the Niconico source"))?;
+            let stream = client.open_stream(&track.info.identifier).await?;
+            let resolved = ResolvedStream {
+                uri: stream.url.clone(),
+                is_hls: stream.is_hls,
+                headers: stream.headers(),
+            };
+            *self.nico_stream.lock().await = Some(stream);
+            return Ok(resolved);
+        }
+
+        // Bandcamp stream URLs expire, so they are looked up from the track page
+        #[cfg(feature = "audio-sources")]
+        if track.info.source_name == "bandcamp" {
+            let page_url = track
+                .info
+                .uri
+                .as_deref()
+                .ok_or_else(|| anyhow!("Track has no URI: {}", track.info.title))?;
+            let scraper = self
+                .sources
+                .as_ref()
+                .and_then(|sources| sources.bandcamp_scraper())
+                .ok_or_else(|| anyhow!("Bandcamp playback needs the Bandcamp source"))?;
+            return Ok(ResolvedStream {
+                uri: scraper.stream_url(page_url).await?,
+                is_hls: false,
+                headers: reqwest::header::HeaderMap::new(),
+            });
+        }
+
+        let uri = track.info.uri.as_deref().ok_or_else(|| {
+            warn!("Track has no URI: {}", track.info.title);
+            anyhow!("Track has no URI: {}", track.info.title)
+        })?;
+
+        let is_hls = uri.contains(".m3u8") || is_hls_track(track);
+
+        // Radio streams announce song changes through ICY metadata, which voice
+        // playback reads from the audio connection itself
+        #[cfg(all(feature = "audio-sources", not(feature = "discord")))]
+        if track.info.is_stream && !is_hls && uri.starts_with("http") {
+            self.start_icy_watcher(track, uri).await;
+        }
+
+        Ok(ResolvedStream {
+            is_hls,
+            uri: uri.to_string(),
+            headers: reqwest::header::HeaderMap::new(),
+        })
+    }
+
+    /// Follow a radio stream's ICY metadata, emitting stream title events
+    #[cfg(all(feature = "audio-sources", not(feature = "discord")))]
+    async fn start_icy_watcher(&self, track: &Track, uri: &str) {
+        let (title_sender, title_receiver) = mpsc::unbounded_channel();
+        let forward = self.forward_stream_titles(track, title_receiver);
+        let uri = uri.to_string();
+
+        let watcher = tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            let watch =
+                crate::audio::sources::icy::watch_stream_titles(&client, &uri, title_sender);
+
+            let (result, _) = tokio::join!(watch, forward);
+            if let Err(e) = result {
+                debug!("Stopped following ICY metadata for {}: {}", uri, e);
+            }
+        });
+        self.set_icy_watcher(watcher).await;
+    }
+
+    /// Emit a stream title event for each title received on `titles`
+    #[cfg(feature = "audio-sources")]
+    fn forward_stream_titles(
+        &self,
+        track: &Track,
+        mut titles: mpsc::UnboundedReceiver<crate::audio::sources::icy::IcyStreamTitle>,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let event_sender = self.event_sender.clone();
+        let guild_id = self.guild_id.clone();
+        let track = track.clone();
+
+        async move {
+            while let Some(title) = titles.recv().await {
+                debug!("Stream title in guild {}: {}", guild_id, title.title);
+                let _ = event_sender.send(PlayerEvent::StreamTitle {
+                    guild_id: guild_id.clone(),
+                    track: track.clone(),
+                    title: title.title,
+                    url: title.url,
+                });
+            }
+        }
+    }
+
+    /// Track the task following the current stream's titles, stopping the previous one
+    #[cfg(feature = "audio-sources")]
+    async fn set_icy_watcher(&self, watcher: tokio::task::JoinHandle<()>) {
+        if let Some(previous) = self.icy_watcher.lock().await.replace(watcher) {
+            previous.abort();
+        }
+    }
+
     /// Create a Songbird audio input from a track (legacy method)
     async fn create_audio_input(&self, track: &Track) -> Result<AudioInput> {
         let quality_config = self.get_quality_config().await;
@@ -679,98 +1035,136 @@ impl AudioPlayerEngine {
             .await
     }
 
-    /// Create a media source from a URI
+    /// Create a media source from a resolved stream
+    ///
+    /// HTTP sources are read as they download, so live streams play too.
     async fn create_media_source(
         &self,
-        uri: &str,
+        stream: &ResolvedStream,
     ) -> Result<Box<dyn symphonia::core::io::MediaSource>> {
-        if uri.starts_with("http://") || uri.starts_with("https://") {
-            // HTTP source
-            let response = reqwest::get(uri).await?;
-            let bytes = response.bytes().await?;
-            let cursor = std::io::Cursor::new(bytes.to_vec());
-            Ok(Box::new(cursor))
+        let uri = stream.uri.as_str();
+        if stream.is_hls {
+            let client = reqwest::Client::new();
+            let source =
+                crate::audio::sources::hls::open_stream(client, uri, stream.headers.clone())
+                    .await?;
+            Ok(Box::new(source))
+        } else if uri.starts_with("http://") || uri.starts_with("https://") {
+            let client = reqwest::Client::new();
+            let source =
+                crate::audio::sources::live::open_http(&client, uri, stream.headers.clone())
+                    .await?;
+            Ok(Box::new(source))
         } else {
             // File source
-            let file = std::fs::File::open(uri)?;
+            let file = std::fs::File::open(uri.strip_prefix("file://").unwrap_or(uri))?;
             Ok(Box::new(file))
         }
     }
 
     /// Start the playback loop
-    async fn start_playback_loop(&self) {
+    ///
+    /// Every tick decodes another frame's worth of audio from `source` on a
+    /// blocking thread; the position is derived from the audio decoded, so it
+    /// stands still while paused or seeking and the track ends with its
+    /// stream. Without a source the position follows the clock instead. Voice
+    /// playback decodes on its own and reports its position through the
+    /// track handle.
+    async fn start_playback_loop(&self, mut source: Option<FrameSource>) {
         let current_track = self.current_track.clone();
+        let pending_seek = self.pending_seek.clone();
+        let generation = self.generation.clone();
+        let own_generation = generation.load(Ordering::Relaxed);
         let position = self.position.clone();
+        let decoded = self.decoded.clone();
         let paused = self.paused.clone();
         let playing = self.playing.clone();
         let seeking = self.seeking.clone();
-        let last_position_update = self.last_position_update.clone();
         let event_sender = self.event_sender.clone();
         let guild_id = self.guild_id.clone();
+        #[cfg(feature = "discord")]
+        let track_handle = self.current_track_handle.clone();
 
         tokio::spawn(async move {
-            let mut playback_interval = interval(Duration::from_millis(20)); // 50 FPS for smooth playback
-            let mut position_update_interval = interval(Duration::from_millis(100)); // Update position every 100ms
+            let mut playback_interval = interval(FRAME_DURATION);
+            // Audio still to decode in microseconds; chunks rarely match a frame
+            let mut budget: i64 = 0;
 
             loop {
-                tokio::select! {
-                    _ = playback_interval.tick() => {
-                        if !*playing.read().await {
-                            break;
-                        }
-
-                        // Check for track end
-                        if let Some(track) = current_track.read().await.as_ref() {
-                            // Get current position with interpolation
-                            let current_pos = if *seeking.read().await || *paused.read().await {
-                                *position.read().await
-                            } else {
-                                let base_position = *position.read().await;
-                                let last_update = *last_position_update.read().await;
-                                let elapsed = last_update.elapsed();
-                                let interpolated = base_position + elapsed.as_millis() as u64;
-                                if track.info.length > 0 {
-                                    interpolated.min(track.info.length)
-                                } else {
-                                    interpolated
-                                }
-                            };
-
-                            if track.info.length > 0 && current_pos >= track.info.length {
-                                // Track ended
-                                *playing.write().await = false;
-
-                                let _ = event_sender.send(PlayerEvent::TrackEnd {
-                                    guild_id: guild_id.clone(),
-                                    track: track.clone(),
-                                    reason: TrackEndReason::Finished,
-                                });
+                playback_interval.tick().await;
+                let stale = || generation.load(Ordering::Relaxed) != own_generation;
+                if stale() || !*playing.read().await {
+                    break;
+                }
+                if *seeking.read().await || *paused.read().await {
+                    continue;
+                }
 
-                                break;
+                let mut end_reason = None;
+                if let Some(mut decoding) = source.take() {
+                    budget += FRAME_DURATION.as_micros() as i64;
+                    let seek_to = pending_seek.lock().await.take();
+                    let decoded = decoded.clone();
+                    let guild = guild_id.clone();
+                    // Decoding blocks while a stream waits for more audio
+                    let result = tokio::task::spawn_blocking(move || {
+                        if let Some(position) = seek_to {
+                            if let Err(e) = decoding.seek(position) {
+                                warn!("Failed to seek in guild {}: {}", guild, e);
                             }
                         }
-
-                        // Here we would decode and process audio frames
-                        // For now, this is a placeholder for actual audio processing
+                        let (budget, end_reason) = decoding.decode_budget(budget, &decoded, &guild);
+                        (decoding, budget, end_reason)
+                    })
+                    .await;
+                    match result {
+                        Ok((decoding, left, reason)) => {
+                            source = Some(decoding);
+                            budget = left;
+                            end_reason = reason;
+                        }
+                        Err(e) => {
+                            warn!("Playback failed in guild {}: {}", guild_id, e);
+                            end_reason = Some(TrackEndReason::LoadFailed);
+                        }
                     }
+                    if stale() {
+                        break;
+                    }
+                } else {
+                    decoded.fetch_add(FRAME_DURATION.as_micros() as u64, Ordering::Relaxed);
+                }
+                #[cfg(feature = "discord")]
+                let decoding = source.is_some();
 
-                    _ = position_update_interval.tick() => {
-                        // Update position tracking
-                        if !*seeking.read().await && !*paused.read().await && *playing.read().await {
-                            let last_update = *last_position_update.read().await;
-                            let elapsed = last_update.elapsed();
-
-                            // Update position based on elapsed time
-                            {
-                                let mut pos = position.write().await;
-                                *pos += elapsed.as_millis() as u64;
-                            }
-
-                            // Update last position update timestamp
-                            *last_position_update.write().await = Instant::now();
+                // Check for track end
+                let Some(track) = current_track.read().await.clone() else {
+                    continue;
+                };
+                #[cfg_attr(not(feature = "discord"), allow(unused_mut))]
+                let mut current_pos = played_position(*position.read().await, &decoded);
+                #[cfg(feature = "discord")]
+                if !decoding {
+                    if let Some(handle) = track_handle.read().await.as_ref() {
+                        if let Ok(info) = handle.get_info().await {
+                            current_pos = info.position.as_millis() as u64;
                         }
                     }
                 }
+                if end_reason.is_none() && track.info.length > 0 && current_pos >= track.info.length
+                {
+                    end_reason = Some(TrackEndReason::Finished);
+                }
+
+                if let Some(reason) = end_reason {
+                    *playing.write().await = false;
+                    let _ = event_sender.send(PlayerEvent::TrackEnd {
+                        guild_id: guild_id.clone(),
+                        track,
+                        reason,
+                    });
+                    break;
+                }
             }
         });
     }
@@ -911,3 +1305,51 @@ impl AudioPlayerEngine {
         self.streaming_manager.stop_stream().await
     }
 }
+
+/// Demux and decode a media source with symphonia
+fn probe_source(
+    source: Box<dyn symphonia::core::io::MediaSource>,
+    hint: &Hint,
+) -> Result<FrameSource> {
+    let media_source_stream = MediaSourceStream::new(source, Default::default());
+
+    // Probe the media source
+    let probe_result = symphonia::default::get_probe()
+        .format(
+            hint,
+            media_source_stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| anyhow!("Failed to probe audio format: {}", e))?;
+
+    let format_reader = probe_result.format;
+
+    // Find the default audio track
+    let default_track = format_reader
+        .default_track()
+        .ok_or_else(|| anyhow!("No default audio track found"))?;
+    let track_id = default_track.id;
+
+    // Create decoder for the track
+    let decoder = symphonia::default::get_codecs()
+        .make(&default_track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow!("Failed to create decoder: {}", e))?;
+
+    Ok(FrameSource::Decoded {
+        reader: format_reader,
+        decoder,
+        track_id,
+    })
+}
+
+/// Check whether a source flagged the track as an HLS stream
+#[cfg_attr(not(feature = "plugins"), allow(unused_variables))]
+fn is_hls_track(track: &Track) -> bool {
+    #[cfg(feature = "plugins")]
+    if let Some(hls) = track.plugin_info.get("hls").and_then(|v| v.as_bool()) {
+        return hls;
+    }
+
+    false
+}
@@ -1,18 +1,21 @@
 // Player management module
 // This will handle audio players for Discord guilds
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, Notify, RwLock};
 use tokio::time::{interval, Duration, Instant};
 
 #[cfg(any(feature = "discord", feature = "crypto"))]
 use rand::prelude::*;
 use tracing::{debug, error, info, warn};
 
+use crate::audio::AudioSourceManager;
+use crate::lyrics::{LyricsLine, LyricsManager};
 use crate::protocol::{
-    messages::{Event, Message, VoiceState},
-    Filters, PlayerState, Track,
+    chapter_at,
+    messages::{Event, Message, QueueMode, QueueMove, QueueUpdate, QueuedTrack, VoiceState},
+    Chapter, Filters, PlayerState, Track,
 };
 use crate::voice::{connection::VoiceConnectionEvent, VoiceConnectionManager};
 
@@ -40,11 +43,66 @@ pub struct VoiceConnectionStats {}
 pub mod engine;
 pub use engine::AudioPlayerEngine;
 
+pub mod persistence;
+pub use persistence::{PlayerSnapshot, PlayerStore};
+
+/// Number of related tracks queued when an autoplaying queue runs dry
+pub const AUTOPLAY_TRACKS: usize = 5;
+
+/// Number of finished tracks each player remembers
+pub const MAX_HISTORY: usize = 50;
+
+/// Most queue updates kept for a player before the oldest are dropped;
+/// clients notice the gap in versions and fetch the queue again
+const MAX_PENDING_QUEUE_UPDATES: usize = 64;
+
+/// How often subscribed players are checked for a new lyrics line or chapter
+const LYRICS_TICK: Duration = Duration::from_millis(200);
+
+/// How often players send a `playerUpdate` unless configured otherwise
+pub const DEFAULT_PLAYER_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often players are checked for ended tracks and due `playerUpdate`s;
+/// also the shortest per-player update interval
+pub const UPDATE_TICK: Duration = Duration::from_millis(250);
+
+/// Leeway for `playerUpdate`s arriving slightly before a session's interval is up
+#[cfg(feature = "websocket")]
+const PLAYER_UPDATE_JITTER: Duration = Duration::from_millis(100);
+
+/// Highest player volume, in percent
+pub const MAX_VOLUME: u16 = 1000;
+
+/// Refuses a track for a queue already holding the given number of tracks
+pub type QueueLimit = Arc<dyn Fn(usize) -> Result<(), String> + Send + Sync>;
+
+/// Rewrites the artwork URL of a track a player takes on
+pub type ArtworkRewrite = Arc<dyn Fn(&mut Track) + Send + Sync>;
+
 /// Player manager for handling audio players across guilds
 pub struct PlayerManager {
     players: Arc<RwLock<HashMap<String, Arc<RwLock<LavalinkPlayer>>>>>,
     event_sender: Option<mpsc::UnboundedSender<PlayerEvent>>,
     voice_manager: Arc<VoiceConnectionManager>,
+    lyrics: Arc<LyricsManager>,
+    /// Where player snapshots are persisted, if enabled
+    store: Option<Arc<PlayerStore>>,
+    /// Configured sources, used to mirror tracks and to find related tracks
+    /// when an autoplaying queue runs dry
+    sources: Option<Arc<AudioSourceManager>>,
+    /// How often players without an override send a `playerUpdate`
+    update_interval: Duration,
+    /// Checked by every player before a track is queued
+    queue_limit: Option<QueueLimit>,
+    /// Applied by every player to the tracks it plays and queues
+    artwork: Option<ArtworkRewrite>,
+    /// Woken when persisted player state changed
+    ///
+    /// Players wake it whenever their persisted state changes, and the
+    /// manager when players are created, moved or removed.
+    changes: Arc<Notify>,
+    /// Sessions of restored players that have not resumed yet
+    unresumed: Arc<std::sync::Mutex<HashSet<String>>>,
 }
 
 /// Individual audio player for a Discord guild
@@ -53,7 +111,7 @@ pub struct LavalinkPlayer {
     pub session_id: String,
     pub current_track: Option<Track>,
     pub state: PlayerState,
-    pub volume: u8,
+    pub volume: u16,
     pub paused: bool,
     pub filters: Filters,
     pub voice: VoiceState,
@@ -72,6 +130,54 @@ pub struct LavalinkPlayer {
     pub shuffle: bool,
     /// Voice connection manager reference
     pub voice_manager: Option<Arc<VoiceConnectionManager>>,
+    /// Whether `LyricsLineEvent`s are sent for this player
+    pub lyrics_subscribed: bool,
+    /// Encoded track and index of the last lyrics line sent
+    pub lyrics_line: Option<(String, usize)>,
+    /// Encoded track and index of the last chapter announced
+    pub chapter: Option<(String, usize)>,
+    /// Restored from a snapshot and waiting for its session or a voice update
+    pub restore_pending: bool,
+    /// Previously played tracks, most recent last, bounded by `MAX_HISTORY`
+    pub history: VecDeque<Track>,
+    /// How tracks added to the queue are ordered
+    pub queue_mode: QueueMode,
+    /// Whether related tracks are queued when the queue runs dry
+    pub autoplay: bool,
+    /// Version of the last recorded queue update
+    pub queue_version: u64,
+    /// Queue updates not yet sent as events
+    pub queue_updates: Vec<QueueUpdate>,
+    /// Overrides the node's `playerUpdate` interval, in milliseconds
+    pub update_interval: Option<u64>,
+    /// When the last `playerUpdate` was sent
+    pub last_player_update: Option<Instant>,
+    /// Woken when persisted state changes, so the player is persisted
+    pub changes: Option<Arc<Notify>>,
+    /// Refuses tracks once the queue is full
+    pub queue_limit: Option<QueueLimit>,
+    /// Points the artwork of played and queued tracks at this node
+    pub artwork: Option<ArtworkRewrite>,
+}
+
+/// A single change to a queue, recorded into a `QueueUpdate`
+enum QueueChange {
+    Cleared,
+    Removed(usize),
+    Moved(QueueMove),
+    Added(Box<QueuedTrack>),
+}
+
+impl QueueChange {
+    /// Position of the change in the order the parts of an update are applied
+    fn rank(&self) -> u8 {
+        match self {
+            QueueChange::Cleared => 0,
+            QueueChange::Removed(_) => 1,
+            QueueChange::Moved(_) => 2,
+            QueueChange::Added(_) => 3,
+        }
+    }
 }
 
 /// Events that can be emitted by players
@@ -94,6 +200,36 @@ pub enum PlayerEvent {
         guild_id: String,
         event: VoiceConnectionEvent,
     },
+
+    StreamTitle {
+        guild_id: String,
+        track: Track,
+        title: String,
+        url: Option<String>,
+    },
+
+    LyricsLine {
+        guild_id: String,
+        track: Track,
+        line_index: usize,
+        line: LyricsLine,
+        skipped: bool,
+    },
+
+    ChapterStart {
+        guild_id: String,
+        track: Track,
+        chapter_index: usize,
+        chapter: Chapter,
+    },
+
+    QueueUpdate {
+        guild_id: String,
+        update: QueueUpdate,
+    },
+
+    /// The player was removed; not sent to clients
+    Destroyed { guild_id: String },
 }
 
 /// Reasons why a track ended
@@ -152,6 +288,14 @@ impl PlayerManager {
             players: Arc::new(RwLock::new(HashMap::new())),
             event_sender,
             voice_manager: Arc::new(voice_manager),
+            lyrics: Arc::new(LyricsManager::new()),
+            store: None,
+            sources: None,
+            update_interval: DEFAULT_PLAYER_UPDATE_INTERVAL,
+            queue_limit: None,
+            artwork: None,
+            changes: Arc::new(Notify::new()),
+            unresumed: Arc::default(),
         }
     }
 
@@ -172,6 +316,14 @@ impl PlayerManager {
             players: Arc::new(RwLock::new(HashMap::new())),
             event_sender: Some(event_sender),
             voice_manager: Arc::new(voice_manager),
+            lyrics: Arc::new(LyricsManager::new()),
+            store: None,
+            sources: None,
+            update_interval: DEFAULT_PLAYER_UPDATE_INTERVAL,
+            queue_limit: None,
+            artwork: None,
+            changes: Arc::new(Notify::new()),
+            unresumed: Arc::default(),
         }
     }
 
@@ -180,47 +332,246 @@ impl PlayerManager {
         self.voice_manager.clone()
     }
 
+    /// Get the lyrics manager
+    pub fn lyrics(&self) -> Arc<LyricsManager> {
+        self.lyrics.clone()
+    }
+
+    /// Play and autoplay tracks through these configured sources
+    pub fn with_sources(mut self, sources: Arc<AudioSourceManager>) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    /// Queue related tracks for a player whose queue ran dry, in the background
+    ///
+    /// Does nothing unless the player has autoplay enabled and sources are configured.
+    /// Players whose track ends on its own are autoplayed by the update service.
+    #[cfg(feature = "discord")]
+    pub async fn autoplay(&self, player: Arc<RwLock<LavalinkPlayer>>) {
+        if !player.read().await.autoplay {
+            return;
+        }
+        if let Some(sources) = self.sources.clone() {
+            tokio::spawn(autoplay(player, sources, self.event_sender.clone()));
+        }
+    }
+
+    /// Send `playerUpdate`s at this interval unless a player overrides it
+    pub fn with_update_interval(mut self, interval: Duration) -> Self {
+        self.update_interval = interval;
+        self
+    }
+
+    /// Refuse tracks for queues that `limit` considers full
+    pub fn with_queue_limit(
+        mut self,
+        limit: impl Fn(usize) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.queue_limit = Some(Arc::new(limit));
+        self
+    }
+
+    /// Rewrite the artwork of every track players play or queue
+    ///
+    /// Tracks keep the rewritten artwork wherever they are returned, so
+    /// players, queues, history and events agree with load results.
+    pub fn with_artwork(mut self, rewrite: impl Fn(&mut Track) + Send + Sync + 'static) -> Self {
+        self.artwork = Some(Arc::new(rewrite));
+        self
+    }
+
+    /// Persist player snapshots to the given store
+    pub fn with_persistence(mut self, store: PlayerStore) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Recreate the players persisted in the store, returning how many were restored
+    ///
+    /// Restored players keep their track, queue and settings but stay idle
+    /// until their session resumes or a voice update arrives for them.
+    /// Players still waiting for their session after `resume_timeout` are
+    /// dropped again.
+    pub async fn restore_players(&self, resume_timeout: Duration) -> usize {
+        let Some(store) = self.store.clone() else {
+            return 0;
+        };
+        let snapshots = match store.load() {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                warn!(
+                    "Failed to read player snapshots {}: {}",
+                    store.path().display(),
+                    e
+                );
+                return 0;
+            }
+        };
+
+        let count = snapshots.len();
+        for snapshot in snapshots {
+            self.unresumed
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(snapshot.session_id.clone());
+            let player = self
+                .get_or_create_player(snapshot.guild_id.clone(), snapshot.session_id.clone())
+                .await;
+            player.write().await.restore(snapshot);
+        }
+        if count > 0 {
+            info!("Restored {} players from {}", count, store.path().display());
+            tokio::spawn(drop_unresumed_players(
+                self.players.clone(),
+                self.unresumed.clone(),
+                self.changes.clone(),
+                resume_timeout,
+            ));
+        }
+        count
+    }
+
+    /// Reattach the restored players of a resumed session, returning whether it owns any players
+    pub async fn resume_session(&self, session_id: &str) -> bool {
+        self.unresumed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session_id);
+        let players = self.get_players_for_session(session_id).await;
+        for player in &players {
+            let mut player_guard = player.write().await;
+            // Restored queues are announced once the session is back to hear it
+            for event in player_guard.take_queue_updates() {
+                self.emit_event(event).await;
+            }
+            if !player_guard.restore_pending {
+                continue;
+            }
+
+            // Voice server tokens outlive a node restart, so try the stored ones first
+            let voice = player_guard.voice.clone();
+            if !voice.token.is_empty() && !voice.endpoint.is_empty() {
+                if let Err(e) = player_guard.update_voice_state(voice).await {
+                    debug!(
+                        "Stored voice state of guild {} is no longer valid: {}",
+                        player_guard.guild_id, e
+                    );
+                    continue;
+                }
+                if let Err(e) = player_guard.resume_restored().await {
+                    warn!(
+                        "Failed to resume restored player for guild {}: {}",
+                        player_guard.guild_id, e
+                    );
+                }
+            }
+        }
+        !players.is_empty()
+    }
+
+    /// Snapshots of all players, ordered by guild
+    pub async fn snapshots(&self) -> Vec<PlayerSnapshot> {
+        snapshot_players(&self.players).await.0
+    }
+
     /// Get or create a player for a guild
     pub async fn get_or_create_player(
         &self,
         guild_id: String,
         session_id: String,
     ) -> Arc<RwLock<LavalinkPlayer>> {
-        let mut players = self.players.write().await;
-
-        let player = players
+        let player = self
+            .players
+            .write()
+            .await
             .entry(guild_id.clone())
-            .or_insert_with(|| {
-                let mut new_player = LavalinkPlayer::new(guild_id.clone(), session_id.clone());
+            .or_insert_with(|| self.new_player(guild_id, &session_id))
+            .clone();
+
+        self.attach_player(&player, session_id).await;
+        player
+    }
 
-                // Initialize audio engine if we have an event sender
-                if let Some(ref sender) = self.event_sender {
-                    new_player.initialize_audio_engine(sender.clone());
+    /// Get or create the players of several guilds, taking the players lock once
+    ///
+    /// Before anything is created, `admit` is given how many players the
+    /// session owns and how many it would gain, and can refuse the request.
+    /// Both counts are taken under the same lock, so concurrent requests can't
+    /// slip past it together.
+    pub async fn get_or_create_players<E>(
+        &self,
+        guild_ids: Vec<String>,
+        session_id: &str,
+        admit: impl FnOnce(usize, usize) -> Result<(), E>,
+    ) -> Result<Vec<Arc<RwLock<LavalinkPlayer>>>, E> {
+        let players: Vec<_> = {
+            let mut players = self.players.write().await;
+
+            let mut owned = 0;
+            for player in players.values() {
+                if player.read().await.session_id == session_id {
+                    owned += 1;
+                }
+            }
+            let mut added = 0;
+            for guild_id in &guild_ids {
+                match players.get(guild_id) {
+                    Some(player) if player.read().await.session_id == session_id => {}
+                    _ => added += 1,
                 }
+            }
+            admit(owned, added)?;
+
+            guild_ids
+                .into_iter()
+                .map(|guild_id| {
+                    players
+                        .entry(guild_id.clone())
+                        .or_insert_with(|| self.new_player(guild_id, session_id))
+                        .clone()
+                })
+                .collect()
+        };
 
-                // Set voice manager reference
-                new_player.voice_manager = Some(self.voice_manager.clone());
+        for player in &players {
+            self.attach_player(player, session_id.to_string()).await;
+        }
+        Ok(players)
+    }
 
-                Arc::new(RwLock::new(new_player))
-            })
-            .clone();
+    fn new_player(&self, guild_id: String, session_id: &str) -> Arc<RwLock<LavalinkPlayer>> {
+        self.changes.notify_one();
+        let mut new_player = LavalinkPlayer::new(guild_id, session_id.to_string());
 
-        // Update session ID if different
-        {
-            let mut player_guard = player.write().await;
-            if player_guard.session_id != session_id {
-                player_guard.session_id = session_id;
-            }
+        // Initialize audio engine if we have an event sender
+        if let Some(ref sender) = self.event_sender {
+            new_player.initialize_audio_engine(sender.clone(), self.sources.clone());
+        }
 
-            // Initialize audio engine if not present
-            if player_guard.audio_engine.is_none() {
-                if let Some(ref sender) = self.event_sender {
-                    player_guard.initialize_audio_engine(sender.clone());
-                }
-            }
+        // Set voice manager reference
+        new_player.voice_manager = Some(self.voice_manager.clone());
+        new_player.changes = Some(self.changes.clone());
+        new_player.queue_limit = self.queue_limit.clone();
+        new_player.artwork = self.artwork.clone();
+
+        Arc::new(RwLock::new(new_player))
+    }
+
+    /// Move a player to the session using it
+    async fn attach_player(&self, player: &Arc<RwLock<LavalinkPlayer>>, session_id: String) {
+        let mut player_guard = player.write().await;
+        if player_guard.session_id != session_id {
+            player_guard.session_id = session_id;
+            player_guard.mark_changed();
         }
 
-        player
+        // Initialize audio engine if not present
+        if player_guard.audio_engine.is_none() {
+            if let Some(ref sender) = self.event_sender {
+                player_guard.initialize_audio_engine(sender.clone(), self.sources.clone());
+            }
+        }
     }
 
     /// Get a player for a guild
@@ -271,6 +622,7 @@ impl PlayerManager {
         let player = players.remove(guild_id);
 
         if let Some(ref player) = player {
+            self.changes.notify_one();
             // Emit player destruction event
             let player_guard = player.read().await;
             if let Some(ref track) = player_guard.current_track {
@@ -281,6 +633,10 @@ impl PlayerManager {
                 })
                 .await;
             }
+            self.emit_event(PlayerEvent::Destroyed {
+                guild_id: guild_id.to_string(),
+            })
+            .await;
         }
 
         player
@@ -300,6 +656,10 @@ impl PlayerManager {
             }
         }
 
+        if !to_remove.is_empty() {
+            self.changes.notify_one();
+        }
+
         // Remove the players
         for guild_id in to_remove {
             if let Some(player) = players.remove(&guild_id) {
@@ -313,6 +673,10 @@ impl PlayerManager {
                     })
                     .await;
                 }
+                self.emit_event(PlayerEvent::Destroyed {
+                    guild_id: guild_id.clone(),
+                })
+                .await;
                 info!(
                     "Removed player for guild {} from session {}",
                     guild_id, session_id
@@ -336,6 +700,13 @@ impl PlayerManager {
     pub async fn shutdown(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Shutting down player manager...");
 
+        // Persist the final state before the players are torn down
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.save(&self.snapshots().await) {
+                warn!("Failed to persist player snapshots: {}", e);
+            }
+        }
+
         // Get all players and shut them down
         let players = {
             let players_guard = self.players.read().await;
@@ -381,133 +752,378 @@ impl PlayerManager {
     pub async fn start_update_service(&self) {
         let players = self.players.clone();
         let event_sender = self.event_sender.clone();
+        let autoplay_sources = self.sources.clone();
+        let update_interval = self.update_interval;
+
+        if let Some(store) = self.store.clone() {
+            let players = players.clone();
+            let changes = self.changes.clone();
+            tokio::spawn(async move {
+                persist_players(players, store, changes).await;
+            });
+        }
+
+        // Lyrics lines and chapters need a much finer resolution than player updates
+        if let Some(sender) = event_sender.clone() {
+            let players = players.clone();
+            let lyrics = self.lyrics.clone();
+            tokio::spawn(async move {
+                let mut interval = interval(LYRICS_TICK);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+                loop {
+                    interval.tick().await;
+
+                    // Clone the players out so the map isn't locked while each one is
+                    let players_snapshot: Vec<_> = players.read().await.values().cloned().collect();
+                    for player in &players_snapshot {
+                        let (lyrics_subscribed, has_chapters) = {
+                            let player = player.read().await;
+                            let has_chapters = player
+                                .current_track
+                                .as_ref()
+                                .is_some_and(|track| track.has_chapters());
+                            (player.lyrics_subscribed, has_chapters)
+                        };
+                        if !lyrics_subscribed && !has_chapters {
+                            continue;
+                        }
+
+                        let mut player = player.write().await;
+                        if let Some(event) = player.next_lyrics_line(&lyrics) {
+                            let _ = sender.send(event);
+                        }
+                        if let Some(event) = player.next_chapter() {
+                            let _ = sender.send(event);
+                        }
+                    }
+                }
+            });
+        }
 
         tokio::spawn(async move {
-            let mut interval = interval(Duration::from_millis(5000)); // 5 second updates
+            let mut interval = interval(UPDATE_TICK);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
             loop {
                 interval.tick().await;
 
-                let players_guard = players.read().await;
-                for player in players_guard.values() {
-                    let mut player_state = player.write().await;
-
-                    // Update position for playing tracks
-                    let mut track_ended = false;
-                    let mut end_reason = TrackEndReason::Finished;
-                    let mut ended_track = None;
-                    let guild_id = player_state.guild_id.clone();
-
-                    // Check if we have a current track and if it's playing
-                    let should_update =
-                        player_state.current_track.is_some() && !player_state.paused;
-                    let track_length = player_state
-                        .current_track
-                        .as_ref()
-                        .map(|t| t.info.length)
-                        .unwrap_or(0);
-                    let end_time = player_state.end_time;
-
-                    if should_update {
-                        let elapsed = player_state.last_update.elapsed();
-                        player_state.position += elapsed.as_millis() as u64;
-                        player_state.last_update = Instant::now();
-
-                        // Check if track should end
-                        if let Some(end_time) = end_time {
-                            if player_state.position >= end_time {
-                                track_ended = true;
-                                ended_track = player_state.current_track.clone();
-                                end_reason = TrackEndReason::Finished;
-                            }
-                        } else if track_length > 0 && player_state.position >= track_length {
-                            track_ended = true;
-                            ended_track = player_state.current_track.clone();
-                            end_reason = TrackEndReason::Finished;
-                        }
-                    }
+                // Clone the players out so the map isn't locked while each one updates
+                let players_snapshot: Vec<_> = players.read().await.values().cloned().collect();
+                for player in players_snapshot {
+                    update_player(
+                        player,
+                        update_interval,
+                        event_sender.clone(),
+                        autoplay_sources.clone(),
+                    )
+                    .await;
+                }
+            }
+        });
+    }
+}
 
-                    // Handle track end
-                    if track_ended {
-                        let _ended_track_clone = ended_track.clone();
-                        let end_reason_clone = end_reason.clone();
-
-                        // Emit track end event first
-                        if let (Some(track), Some(ref sender)) = (ended_track, &event_sender) {
-                            let _ = sender.send(PlayerEvent::TrackEnd {
-                                guild_id: guild_id.clone(),
-                                track,
-                                reason: end_reason,
-                            });
-                        }
+/// Drop the restored players whose session did not resume within `resume_timeout`
+///
+/// Players a voice update moved to another session in the meantime are kept.
+async fn drop_unresumed_players(
+    players: Arc<RwLock<HashMap<String, Arc<RwLock<LavalinkPlayer>>>>>,
+    unresumed: Arc<std::sync::Mutex<HashSet<String>>>,
+    changes: Arc<Notify>,
+    resume_timeout: Duration,
+) {
+    tokio::time::sleep(resume_timeout).await;
+    let expired = std::mem::take(&mut *unresumed.lock().unwrap_or_else(|e| e.into_inner()));
+    if expired.is_empty() {
+        return;
+    }
 
-                        // Try to play next track from queue if the end reason allows it
-                        if end_reason_clone.may_start_next() {
-                            let next_track = player_state.get_next_track();
+    let mut players = players.write().await;
+    let before = players.len();
+    let mut kept = HashMap::with_capacity(before);
+    for (guild_id, player) in players.drain() {
+        if !expired.contains(&player.read().await.session_id) {
+            kept.insert(guild_id, player);
+        }
+    }
+    *players = kept;
 
-                            if let Some(next_track) = next_track {
-                                info!(
-                                    "Auto-playing next track from queue: {} in guild {}",
-                                    next_track.info.title, guild_id
-                                );
+    let dropped = before - players.len();
+    if dropped > 0 {
+        info!(
+            "Dropped {} restored players whose session did not resume",
+            dropped
+        );
+        changes.notify_one();
+    }
+}
 
-                                // Set new track
-                                player_state.current_track = Some(next_track.clone());
-                                player_state.position = 0;
-                                player_state.end_time = None;
-                                player_state.paused = false;
-                                player_state.last_update = Instant::now();
-
-                                // Update state
-                                player_state.state.position = 0;
-                                player_state.state.time = chrono::Utc::now();
-
-                                // Start playback with audio engine
-                                if let Some(ref engine) = player_state.audio_engine {
-                                    let engine_clone = engine.clone();
-                                    let track_clone = next_track.clone();
-
-                                    // Spawn a task to start playback to avoid blocking the update loop
-                                    tokio::spawn(async move {
-                                        if let Err(e) =
-                                            engine_clone.play_track(track_clone, None).await
-                                        {
-                                            error!("Failed to auto-play next track: {}", e);
-                                        }
-                                    });
-                                }
-                            } else {
-                                // No more tracks in queue
-                                player_state.current_track = None;
-                                player_state.position = 0;
-                                player_state.end_time = None;
-                                info!("Queue is empty for guild {}", guild_id);
-                            }
-                        } else {
-                            // End reason doesn't allow auto-play (e.g., stopped manually)
-                            player_state.current_track = None;
-                            player_state.position = 0;
-                            player_state.end_time = None;
-                        }
-                    }
+/// Advance a player, start its next track once one ended and send a due `playerUpdate`
+///
+/// Players that are locked elsewhere are left for the next tick, so a busy
+/// player doesn't hold up the others.
+async fn update_player(
+    player: Arc<RwLock<LavalinkPlayer>>,
+    default_interval: Duration,
+    event_sender: Option<mpsc::UnboundedSender<PlayerEvent>>,
+    autoplay_sources: Option<Arc<AudioSourceManager>>,
+) {
+    let Ok(mut player_state) = player.try_write() else {
+        return;
+    };
+
+    // Update position for playing tracks
+    let mut track_ended = false;
+    let mut end_reason = TrackEndReason::Finished;
+    let mut ended_track = None;
+    let guild_id = player_state.guild_id.clone();
+
+    // Check if we have a current track and if it's playing
+    let should_update = player_state.is_playing();
+    let track_length = player_state
+        .current_track
+        .as_ref()
+        .map(|t| t.info.length)
+        .unwrap_or(0);
+    let end_time = player_state.end_time;
+
+    if should_update {
+        player_state.sync_position().await;
+
+        // Check if track should end
+        if let Some(end_time) = end_time {
+            if player_state.position >= end_time {
+                track_ended = true;
+                ended_track = player_state.current_track.clone();
+                end_reason = TrackEndReason::Finished;
+            }
+        } else if track_length > 0 && player_state.position >= track_length {
+            track_ended = true;
+            ended_track = player_state.current_track.clone();
+            end_reason = TrackEndReason::Finished;
+        }
+    }
+
+    // Handle track end
+    if track_ended {
+        let _ended_track_clone = ended_track.clone();
+        let end_reason_clone = end_reason.clone();
+
+        // Emit track end event first
+        if let (Some(track), Some(ref sender)) = (ended_track, &event_sender) {
+            let _ = sender.send(PlayerEvent::TrackEnd {
+                guild_id: guild_id.clone(),
+                track,
+                reason: end_reason,
+            });
+        }
+
+        // Try to play next track from queue if the end reason allows it
+        if end_reason_clone.may_start_next() {
+            let next_track = player_state.get_next_track();
+            if let Some(ref sender) = event_sender {
+                for event in player_state.take_queue_updates() {
+                    let _ = sender.send(event);
+                }
+            }
 
-                    // Update player state
-                    player_state.state.position = player_state.position;
-                    player_state.state.time = chrono::Utc::now();
+            if let Some(next_track) = next_track {
+                info!(
+                    "Auto-playing next track from queue: {} in guild {}",
+                    next_track.info.title, guild_id
+                );
 
-                    // Emit player update
-                    if let Some(ref sender) = event_sender {
-                        let _ = sender.send(PlayerEvent::PlayerUpdate {
-                            guild_id: player_state.guild_id.clone(),
-                            state: player_state.state.clone(),
-                        });
+                // Set new track
+                player_state.replace_current_track(Some(next_track.clone()));
+                player_state.position = 0;
+                player_state.end_time = None;
+                player_state.paused = false;
+                player_state.last_update = Instant::now();
+
+                // Update state
+                player_state.state.position = 0;
+                player_state.state.time = chrono::Utc::now();
+
+                // Start playback with audio engine
+                if let Some(ref engine) = player_state.audio_engine {
+                    let engine_clone = engine.clone();
+                    let track_clone = next_track.clone();
+
+                    // Spawn a task to start playback to avoid blocking the update loop
+                    tokio::spawn(async move {
+                        if let Err(e) = engine_clone.play_track(track_clone, None).await {
+                            error!("Failed to auto-play next track: {}", e);
+                        }
+                    });
+                }
+            } else {
+                // No more tracks in queue
+                player_state.replace_current_track(None);
+                player_state.position = 0;
+                player_state.end_time = None;
+                info!("Queue is empty for guild {}", guild_id);
+
+                if player_state.autoplay {
+                    if let Some(sources) = autoplay_sources.clone() {
+                        tokio::spawn(autoplay(player.clone(), sources, event_sender.clone()));
                     }
                 }
             }
+        } else {
+            // End reason doesn't allow auto-play (e.g., stopped manually)
+            player_state.replace_current_track(None);
+            player_state.position = 0;
+            player_state.end_time = None;
+        }
+    }
+
+    // Emit player update once the player's interval is up
+    let interval = player_state
+        .update_interval
+        .map(Duration::from_millis)
+        .unwrap_or(default_interval);
+    let due = player_state
+        .last_player_update
+        .is_none_or(|last| last.elapsed() + UPDATE_TICK / 2 >= interval);
+    if !due {
+        return;
+    }
+    player_state.last_player_update = Some(Instant::now());
+    player_state.state.position = player_state.position;
+    player_state.state.time = chrono::Utc::now();
+
+    if let Some(ref sender) = event_sender {
+        let _ = sender.send(PlayerEvent::PlayerUpdate {
+            guild_id: player_state.guild_id.clone(),
+            state: player_state.state.clone(),
         });
     }
 }
 
+/// Queue tracks related to the player's last track and start the first one
+async fn autoplay(
+    player: Arc<RwLock<LavalinkPlayer>>,
+    sources: Arc<AudioSourceManager>,
+    event_sender: Option<mpsc::UnboundedSender<PlayerEvent>>,
+) {
+    let Some(seed) = player.read().await.history.back().cloned() else {
+        return;
+    };
+
+    // Fetch more than needed since recently played tracks are dropped
+    let related = match sources.related_tracks(&seed, AUTOPLAY_TRACKS * 2).await {
+        Ok(related) => related,
+        Err(e) => {
+            warn!(
+                "Failed to find tracks related to {}: {}",
+                seed.info.identifier, e
+            );
+            return;
+        }
+    };
+
+    let mut player = player.write().await;
+    // The player may have been given something else to play in the meantime
+    if !player.autoplay || player.current_track.is_some() || !player.queue.is_empty() {
+        return;
+    }
+
+    let added = player.enqueue_autoplay(related);
+    if added.is_empty() {
+        info!("No related tracks to autoplay in guild {}", player.guild_id);
+        return;
+    }
+    info!(
+        "Autoplaying {} related tracks in guild {}",
+        added.len(),
+        player.guild_id
+    );
+    if let Err(e) = player.skip_track().await {
+        error!("Failed to start autoplayed track: {}", e);
+    }
+    if let Some(ref sender) = event_sender {
+        for event in player.take_queue_updates() {
+            let _ = sender.send(event);
+        }
+    }
+}
+
+/// Snapshots of all players ordered by guild, and whether any of them is playing
+async fn snapshot_players(
+    players: &RwLock<HashMap<String, Arc<RwLock<LavalinkPlayer>>>>,
+) -> (Vec<PlayerSnapshot>, bool) {
+    // Clone the players out so the map isn't locked while each one is
+    let players_snapshot: Vec<_> = players.read().await.values().cloned().collect();
+    let mut snapshots = Vec::with_capacity(players_snapshot.len());
+    let mut playing = false;
+    for player in players_snapshot {
+        let player = player.read().await;
+        playing |= player.is_playing();
+        snapshots.push(player.snapshot());
+    }
+    snapshots.sort_by(|a, b| a.guild_id.cmp(&b.guild_id));
+    (snapshots, playing)
+}
+
+/// Write player snapshots whenever they change, at most once per debounce interval
+///
+/// The task sleeps until `changes` is notified, or while a player is
+/// playing, until its position is due to be saved again.
+async fn persist_players(
+    players: Arc<RwLock<HashMap<String, Arc<RwLock<LavalinkPlayer>>>>>,
+    store: Arc<PlayerStore>,
+    changes: Arc<Notify>,
+) {
+    let mut last_written: Option<serde_json::Value> = None;
+    let mut last_write = Instant::now();
+    let mut playing = false;
+
+    loop {
+        if playing {
+            let position_due = last_write + persistence::POSITION_SAVE_INTERVAL;
+            tokio::select! {
+                _ = changes.notified() => {}
+                _ = tokio::time::sleep_until(position_due) => {}
+            }
+        } else {
+            changes.notified().await;
+        }
+        // Collect the rest of a burst of changes into the same write
+        tokio::time::sleep(store.debounce()).await;
+
+        let (snapshots, now_playing) = snapshot_players(&players).await;
+        playing = now_playing;
+
+        // Positions move on their own while playing, so they alone only
+        // trigger a write every POSITION_SAVE_INTERVAL
+        let mut comparable = snapshots.clone();
+        for snapshot in &mut comparable {
+            snapshot.position = 0;
+        }
+        let comparable = serde_json::to_value(&comparable).ok();
+        let changed = comparable != last_written;
+        let position_due = playing && last_write.elapsed() >= persistence::POSITION_SAVE_INTERVAL;
+        if !changed && !position_due {
+            continue;
+        }
+
+        let store_clone = store.clone();
+        match tokio::task::spawn_blocking(move || store_clone.save(&snapshots)).await {
+            Ok(Ok(())) => {
+                last_written = comparable;
+                last_write = Instant::now();
+            }
+            Ok(Err(e)) => warn!(
+                "Failed to write player snapshots {}: {}",
+                store.path().display(),
+                e
+            ),
+            Err(e) => warn!("Player snapshot task failed: {}", e),
+        }
+    }
+}
+
 impl LavalinkPlayer {
     /// Create a new player
     pub fn new(guild_id: String, session_id: String) -> Self {
@@ -533,15 +1149,37 @@ impl LavalinkPlayer {
             repeat_queue: false,
             shuffle: false,
             voice_manager: None,
+            lyrics_subscribed: false,
+            lyrics_line: None,
+            chapter: None,
+            restore_pending: false,
+            history: VecDeque::new(),
+            queue_mode: QueueMode::Linear,
+            autoplay: false,
+            queue_version: 0,
+            queue_updates: Vec::new(),
+            update_interval: None,
+            last_player_update: None,
+            changes: None,
+            queue_limit: None,
+            artwork: None,
         }
     }
 
     /// Initialize the audio engine for this player
-    pub fn initialize_audio_engine(&mut self, event_sender: mpsc::UnboundedSender<PlayerEvent>) {
-        self.audio_engine = Some(Arc::new(AudioPlayerEngine::new(
-            self.guild_id.clone(),
-            event_sender,
-        )));
+    #[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
+    pub fn initialize_audio_engine(
+        &mut self,
+        event_sender: mpsc::UnboundedSender<PlayerEvent>,
+        sources: Option<Arc<AudioSourceManager>>,
+    ) {
+        #[allow(unused_mut)]
+        let mut engine = AudioPlayerEngine::new(self.guild_id.clone(), event_sender);
+        #[cfg(feature = "audio-sources")]
+        if let Some(sources) = sources {
+            engine = engine.with_sources(sources);
+        }
+        self.audio_engine = Some(Arc::new(engine));
     }
 
     /// Update voice state and establish/disconnect voice connection
@@ -556,6 +1194,7 @@ impl LavalinkPlayer {
         self.voice.token = voice_state.token.clone();
         self.voice.endpoint = voice_state.endpoint.clone();
         self.voice.session_id = voice_state.session_id.clone();
+        self.mark_changed();
 
         // Update connection state timestamp
         self.state.time = chrono::Utc::now();
@@ -629,6 +1268,7 @@ impl LavalinkPlayer {
 
     /// Handle voice connection events and update player state accordingly
     pub async fn handle_voice_event(&mut self, event: &VoiceConnectionEvent) {
+        let was_paused = self.paused;
         match event {
             // Basic Connection Events
             VoiceConnectionEvent::Connected | VoiceConnectionEvent::GatewayReady { .. } => {
@@ -941,6 +1581,9 @@ impl LavalinkPlayer {
 
         // Update state timestamp for all events
         self.state.time = chrono::Utc::now();
+        if self.paused != was_paused {
+            self.mark_changed();
+        }
     }
 
     /// Validate player state consistency with voice connection
@@ -975,6 +1618,7 @@ impl LavalinkPlayer {
                 self.guild_id
             );
             self.paused = true;
+            self.mark_changed();
             is_consistent = false; // State was inconsistent but now fixed
         }
 
@@ -1007,10 +1651,12 @@ impl LavalinkPlayer {
     #[allow(dead_code)]
     pub async fn play_track(
         &mut self,
-        track: Track,
+        mut track: Track,
         start_time: Option<u64>,
         end_time: Option<u64>,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Events of the engine carry the track, so it is rewritten up front
+        self.rewrite_artwork(&mut track);
         info!(
             "Playing track {} in guild {}",
             track.info.title, self.guild_id
@@ -1025,7 +1671,7 @@ impl LavalinkPlayer {
         }
 
         // Set new track
-        self.current_track = Some(track.clone());
+        self.replace_current_track(Some(track.clone()));
         self.paused = false;
         self.position = start_time.unwrap_or(0);
         self.end_time = end_time;
@@ -1072,6 +1718,7 @@ impl LavalinkPlayer {
 
         self.filters = filters;
         self.state.time = chrono::Utc::now();
+        self.mark_changed();
 
         info!("Successfully applied filters in guild {}", self.guild_id);
         Ok(())
@@ -1093,87 +1740,281 @@ impl LavalinkPlayer {
 
     /// Check if the player is playing
     pub fn is_playing(&self) -> bool {
-        self.current_track.is_some() && !self.paused
+        self.current_track.is_some() && !self.paused && !self.restore_pending
     }
 
     /// Add a track to the queue
-    #[allow(dead_code)]
-    pub fn add_to_queue(&mut self, track: Track) {
+    ///
+    /// In fair mode the track is placed in its requester's next round and is
+    /// rejected if the requester already has the maximum number of tracks
+    /// queued. Tracks are also rejected once the queue limit is reached.
+    /// Returns the index the track was inserted at.
+    pub fn add_to_queue(&mut self, mut track: Track) -> Result<usize, String> {
+        if let Some(limit) = &self.queue_limit {
+            limit(self.queue.len())?;
+        }
+        self.rewrite_artwork(&mut track);
+        let requester = self.requester_of(&track);
+        if let (
+            QueueMode::Fair {
+                max_per_requester: Some(max),
+                ..
+            },
+            Some(name),
+        ) = (&self.queue_mode, &requester)
+        {
+            let queued = self
+                .queue
+                .iter()
+                .filter(|queued| self.requester_of(queued) == requester)
+                .count();
+            if queued >= *max {
+                return Err(format!("Requester {name} already has {max} tracks queued"));
+            }
+        }
+
+        let index = self.queue_position(&requester);
         info!(
-            "Adding track '{}' to queue for guild {}",
-            track.info.title, self.guild_id
+            "Adding track '{}' to queue for guild {} at position {}",
+            track.info.title, self.guild_id, index
         );
-        self.queue.push_back(track);
+        self.queue.insert(index, track.clone());
+        self.record_queue_change(QueueChange::Added(Box::new(QueuedTrack { index, track })));
+        Ok(index)
     }
 
-    /// Remove a track from the queue by index
-    #[allow(dead_code)]
-    pub fn remove_from_queue(&mut self, index: usize) -> Option<Track> {
-        if index < self.queue.len() {
-            let track = self.queue.remove(index);
-            if let Some(ref track) = track {
-                info!(
-                    "Removed track '{}' from queue for guild {}",
-                    track.info.title, self.guild_id
-                );
+    /// Change the queue mode, reordering the queued tracks to match it
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub fn set_queue_mode(&mut self, mode: QueueMode) {
+        info!(
+            "Setting queue mode for guild {} to {:?}",
+            self.guild_id, mode
+        );
+        self.queue_mode = mode;
+        self.mark_changed();
+        if matches!(self.queue_mode, QueueMode::Fair { .. }) {
+            let tracks = std::mem::take(&mut self.queue);
+            for track in tracks {
+                let index = self.queue_position(&self.requester_of(&track));
+                self.queue.insert(index, track);
             }
-            track
-        } else {
-            None
+            self.record_queue_reorder();
         }
     }
 
-    /// Clear the entire queue
-    #[allow(dead_code)]
-    pub fn clear_queue(&mut self) {
-        let count = self.queue.len();
-        self.queue.clear();
-        info!(
-            "Cleared {} tracks from queue for guild {}",
-            count, self.guild_id
-        );
-    }
+    /// Record a queue change, merging it into the pending update when the
+    /// update's application order allows it
+    fn record_queue_change(&mut self, change: QueueChange) {
+        self.mark_changed();
+        let mergeable = self.queue_updates.last().is_some_and(|update| {
+            let last_rank = if !update.added.is_empty() {
+                3
+            } else if !update.moved.is_empty() {
+                2
+            } else if !update.removed.is_empty() {
+                1
+            } else {
+                0
+            };
+            change.rank() >= last_rank
+        });
+        if !mergeable {
+            self.queue_version += 1;
+            if self.queue_updates.len() == MAX_PENDING_QUEUE_UPDATES {
+                self.queue_updates.remove(0);
+            }
+            self.queue_updates.push(QueueUpdate {
+                version: self.queue_version,
+                ..Default::default()
+            });
+        }
 
-    /// Get the next track from the queue
-    pub fn get_next_track(&mut self) -> Option<Track> {
-        if self.repeat_track && self.current_track.is_some() {
-            // Repeat current track
-            return self.current_track.clone();
+        let update = self
+            .queue_updates
+            .last_mut()
+            .expect("a pending queue update was just ensured");
+        match change {
+            QueueChange::Cleared => update.cleared = true,
+            QueueChange::Removed(index) => update.removed.push(index),
+            QueueChange::Moved(moved) => update.moved.push(moved),
+            QueueChange::Added(added) => update.added.push(*added),
         }
+    }
 
-        if self.queue.is_empty() {
-            return None;
+    /// Record a reordering of the whole queue as clearing and re-adding it
+    fn record_queue_reorder(&mut self) {
+        self.record_queue_change(QueueChange::Cleared);
+        for (index, track) in self.queue.clone().into_iter().enumerate() {
+            self.record_queue_change(QueueChange::Added(Box::new(QueuedTrack { index, track })));
         }
+    }
 
-        if self.shuffle {
-            // Shuffle mode: pick a random track from the queue
-            #[cfg(any(feature = "discord", feature = "crypto"))]
-            {
-                let mut rng = rand::rng();
-                let indices: Vec<usize> = (0..self.queue.len()).collect();
-                if let Some(&random_index) = indices.choose(&mut rng) {
-                    return self.queue.remove(random_index);
-                }
+    /// Take the recorded queue updates as `QueueUpdate` events, oldest first
+    pub fn take_queue_updates(&mut self) -> Vec<PlayerEvent> {
+        std::mem::take(&mut self.queue_updates)
+            .into_iter()
+            .map(|update| PlayerEvent::QueueUpdate {
+                guild_id: self.guild_id.clone(),
+                update,
+            })
+            .collect()
+    }
+
+    /// Requester of a track in fair mode, taken from its `userData`
+    fn requester_of(&self, track: &Track) -> Option<String> {
+        let QueueMode::Fair { key, .. } = &self.queue_mode else {
+            return None;
+        };
+        #[cfg(feature = "rest-api")]
+        {
+            track.user_data.get(key).map(|value| {
+                value
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| value.to_string())
+            })
+        }
+        #[cfg(not(feature = "rest-api"))]
+        {
+            let _ = (key, track);
+            None
+        }
+    }
+
+    /// Where a new track of the given requester goes in the queue
+    ///
+    /// Each queued track belongs to a round: the number of tracks of the same
+    /// requester ahead of it, counting the current track. A new track joins
+    /// its requester's next round and goes before the first track of a later
+    /// round, so requesters alternate. Outside fair mode, shuffled queues
+    /// take new tracks at a random position.
+    fn queue_position(&self, requester: &Option<String>) -> usize {
+        if !matches!(self.queue_mode, QueueMode::Fair { .. }) {
+            #[cfg(any(feature = "discord", feature = "crypto"))]
+            if self.shuffle {
+                return rand::rng().random_range(0..=self.queue.len());
             }
-            #[cfg(not(any(feature = "discord", feature = "crypto")))]
-            {
-                // Fallback: just take the first track when rand is not available
-                return self.queue.pop_front();
+            return self.queue.len();
+        }
+
+        let mut counts: HashMap<Option<String>, usize> = HashMap::new();
+        if let Some(ref current) = self.current_track {
+            counts.insert(self.requester_of(current), 1);
+        }
+        let rounds: Vec<usize> = self
+            .queue
+            .iter()
+            .map(|track| {
+                let count = counts.entry(self.requester_of(track)).or_insert(0);
+                *count += 1;
+                *count - 1
+            })
+            .collect();
+
+        let round = counts.get(requester).copied().unwrap_or(0);
+        rounds
+            .iter()
+            .position(|&queued_round| queued_round > round)
+            .unwrap_or(self.queue.len())
+    }
+
+    /// Remove a track from the queue by index
+    #[allow(dead_code)]
+    pub fn remove_from_queue(&mut self, index: usize) -> Option<Track> {
+        if index < self.queue.len() {
+            let track = self.queue.remove(index);
+            if let Some(ref track) = track {
+                self.record_queue_change(QueueChange::Removed(index));
+                info!(
+                    "Removed track '{}' from queue for guild {}",
+                    track.info.title, self.guild_id
+                );
             }
+            track
+        } else {
+            None
         }
+    }
 
-        // Normal mode: take the first track from the queue
+    /// Clear the entire queue
+    #[allow(dead_code)]
+    pub fn clear_queue(&mut self) {
+        let count = self.queue.len();
+        self.queue.clear();
+        if count > 0 {
+            self.record_queue_change(QueueChange::Cleared);
+        }
+        info!(
+            "Cleared {} tracks from queue for guild {}",
+            count, self.guild_id
+        );
+    }
+
+    /// Get the next track from the queue
+    pub fn get_next_track(&mut self) -> Option<Track> {
+        if self.repeat_track && self.current_track.is_some() {
+            // Repeat current track
+            return self.current_track.clone();
+        }
+
+        if self.queue.is_empty() {
+            return None;
+        }
+
+        // The queue is kept in play order, shuffled or not
         let next_track = self.queue.pop_front();
+        self.record_queue_change(QueueChange::Removed(0));
 
         // If repeat_queue is enabled and we just took the last track, add current track back to queue
         if self.repeat_queue && next_track.is_some() && self.current_track.is_some() {
-            self.queue
-                .push_back(self.current_track.as_ref().unwrap().clone());
+            let current = self.current_track.as_ref().unwrap().clone();
+            let index = self.queue_position(&self.requester_of(&current));
+            self.queue.insert(index, current.clone());
+            self.record_queue_change(QueueChange::Added(Box::new(QueuedTrack {
+                index,
+                track: current,
+            })));
         }
 
         next_track
     }
 
+    /// Queue up to `AUTOPLAY_TRACKS` related tracks that were not played recently
+    ///
+    /// Queued tracks are marked with `autoplay: true` in their user data.
+    pub fn enqueue_autoplay(&mut self, related: Vec<Track>) -> Vec<QueuedTrack> {
+        let mut seen: Vec<String> = self
+            .history
+            .iter()
+            .chain(self.current_track.iter())
+            .chain(self.queue.iter())
+            .map(|track| track.info.identifier.clone())
+            .collect();
+
+        let mut added = Vec::new();
+        for track in related {
+            if added.len() >= AUTOPLAY_TRACKS {
+                break;
+            }
+            if seen.contains(&track.info.identifier) {
+                continue;
+            }
+            seen.push(track.info.identifier.clone());
+
+            #[cfg(feature = "rest-api")]
+            let mut track = track;
+            #[cfg(feature = "rest-api")]
+            track
+                .user_data
+                .insert("autoplay".to_string(), serde_json::Value::Bool(true));
+
+            if let Ok(index) = self.add_to_queue(track.clone()) {
+                added.push(QueuedTrack { index, track });
+            }
+        }
+        added
+    }
+
     /// Get the queue as a vector (for API responses)
     #[allow(dead_code)]
     pub fn get_queue(&self) -> Vec<Track> {
@@ -1186,6 +2027,122 @@ impl LavalinkPlayer {
         self.queue.len()
     }
 
+    /// Replace the current track, remembering the old one in the history
+    ///
+    /// A track replaced by itself (repeat, replay) is not recorded twice.
+    pub fn replace_current_track(&mut self, mut track: Option<Track>) {
+        // A snapshot's track is only resumed while it is still the current one
+        self.restore_pending = false;
+        self.mark_changed();
+        if let Some(track) = track.as_mut() {
+            self.rewrite_artwork(track);
+        }
+        let previous = std::mem::replace(&mut self.current_track, track);
+        if let Some(previous) = previous {
+            let replayed = self
+                .current_track
+                .as_ref()
+                .is_some_and(|current| current.encoded == previous.encoded);
+            if !replayed {
+                if self.history.len() == MAX_HISTORY {
+                    self.history.pop_front();
+                }
+                self.history.push_back(previous);
+            }
+        }
+    }
+
+    /// Point a track's artwork at this node, if artwork is proxied
+    fn rewrite_artwork(&self, track: &mut Track) {
+        if let Some(rewrite) = &self.artwork {
+            rewrite(track);
+        }
+    }
+
+    /// Wake the persistence task, if the player is persisted
+    pub fn mark_changed(&self) {
+        if let Some(changes) = &self.changes {
+            changes.notify_one();
+        }
+    }
+
+    /// Previously played tracks, most recent first
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub fn get_history(&self, offset: usize, limit: usize) -> Vec<Track> {
+        self.history
+            .iter()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Play the most recently finished track again
+    ///
+    /// The current track goes back to the front of the queue, so skipping
+    /// afterwards returns to it.
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub async fn play_previous(
+        &mut self,
+    ) -> Result<Option<Track>, Box<dyn std::error::Error + Send + Sync>> {
+        self.replay_from_history(0).await
+    }
+
+    /// Play a track from the history again, counted from the most recent one
+    ///
+    /// The track is taken out of the history and the current track goes back
+    /// to the front of the queue.
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub async fn replay_from_history(
+        &mut self,
+        index: usize,
+    ) -> Result<Option<Track>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(position) = self.history.len().checked_sub(index + 1) else {
+            return Ok(None);
+        };
+        let Some(track) = self.history.get(position).cloned() else {
+            return Ok(None);
+        };
+        info!(
+            "Replaying {} from history in guild {}",
+            track.info.title, self.guild_id
+        );
+
+        // The history and queue only change once the track plays
+        let previous = (
+            self.current_track.take(),
+            self.position,
+            self.end_time,
+            self.paused,
+        );
+        if previous.0.is_some() {
+            if let Some(ref engine) = self.audio_engine {
+                let _ = engine.stop().await;
+            }
+        }
+        if let Err(e) = self.play_track(track.clone(), None, None).await {
+            (
+                self.current_track,
+                self.position,
+                self.end_time,
+                self.paused,
+            ) = previous;
+            self.last_update = Instant::now();
+            return Err(e);
+        }
+
+        self.history.remove(position);
+        if let Some(current) = previous.0 {
+            self.queue.push_front(current.clone());
+            self.record_queue_change(QueueChange::Added(Box::new(QueuedTrack {
+                index: 0,
+                track: current,
+            })));
+        }
+        Ok(Some(track))
+    }
+
     /// Skip to the next track in the queue
     #[allow(dead_code)]
     pub async fn skip_track(
@@ -1206,7 +2163,7 @@ impl LavalinkPlayer {
             self.play_track(track, None, None).await?;
         } else {
             // No more tracks in queue
-            self.current_track = None;
+            self.replace_current_track(None);
             self.position = 0;
             self.paused = false;
             self.state.position = 0;
@@ -1238,6 +2195,9 @@ impl LavalinkPlayer {
 
         let track = self.queue.remove(from).unwrap();
         self.queue.insert(to, track.clone());
+        if from != to {
+            self.record_queue_change(QueueChange::Moved(QueueMove { from, to }));
+        }
 
         info!(
             "Moved track '{}' from position {} to {} in queue for guild {}",
@@ -1257,6 +2217,9 @@ impl LavalinkPlayer {
             let mut tracks: Vec<Track> = self.queue.drain(..).collect();
             tracks.shuffle(&mut rng);
             self.queue = tracks.into();
+            if !self.queue.is_empty() {
+                self.record_queue_reorder();
+            }
         }
         #[cfg(not(any(feature = "discord", feature = "crypto")))]
         {
@@ -1294,6 +2257,219 @@ impl LavalinkPlayer {
         }
     }
 
+    /// Take the position from the frames the audio engine played
+    ///
+    /// Players without an engine fall back to the time elapsed since the last sync.
+    pub async fn sync_position(&mut self) {
+        self.position = match &self.audio_engine {
+            Some(engine) => engine.get_position().await,
+            None => self.get_current_position(),
+        };
+        self.last_update = Instant::now();
+    }
+
+    /// Event for the lyrics line at the current position, if it changed since the last call
+    ///
+    /// Lyrics are looked up in the background on first use, so the first
+    /// line of a track may come one tick late.
+    pub fn next_lyrics_line(&mut self, lyrics: &Arc<LyricsManager>) -> Option<PlayerEvent> {
+        if !self.lyrics_subscribed {
+            return None;
+        }
+        let track = self.current_track.as_ref()?;
+        let Some(found) = lyrics.cached(track) else {
+            lyrics.prefetch(track);
+            return None;
+        };
+        let found = found?;
+        let index = found.line_at(self.get_current_position())?;
+
+        let previous = self
+            .lyrics_line
+            .as_ref()
+            .filter(|(encoded, _)| *encoded == track.encoded)
+            .map(|(_, index)| *index);
+        if previous == Some(index) {
+            return None;
+        }
+        let skipped = previous.map_or(index > 0, |previous| index != previous + 1);
+
+        let event = PlayerEvent::LyricsLine {
+            guild_id: self.guild_id.clone(),
+            track: track.clone(),
+            line_index: index,
+            line: found.lines[index].clone(),
+            skipped,
+        };
+        self.lyrics_line = Some((track.encoded.clone(), index));
+        Some(event)
+    }
+
+    /// Event for the chapter at the current position, if it changed since the last call
+    pub fn next_chapter(&mut self) -> Option<PlayerEvent> {
+        let track = self.current_track.as_ref()?;
+        let chapters = track.chapters();
+        let index = chapter_at(&chapters, self.get_current_position())?;
+
+        let previous = self
+            .chapter
+            .as_ref()
+            .filter(|(encoded, _)| *encoded == track.encoded)
+            .map(|(_, index)| *index);
+        if previous == Some(index) {
+            return None;
+        }
+
+        let event = PlayerEvent::ChapterStart {
+            guild_id: self.guild_id.clone(),
+            track: track.clone(),
+            chapter_index: index,
+            chapter: chapters[index].clone(),
+        };
+        self.chapter = Some((track.encoded.clone(), index));
+        Some(event)
+    }
+
+    /// Pause or resume playback
+    ///
+    /// A restored track that is still waiting for voice is resumed in the
+    /// requested state once it is.
+    pub async fn set_paused(&mut self, paused: bool) -> Result<(), String> {
+        self.paused = paused;
+        self.mark_changed();
+        if self.restore_pending {
+            return Ok(());
+        }
+        // The engine stops counting frames while paused, which keeps positions accurate
+        if let Some(engine) = &self.audio_engine {
+            let result = if paused {
+                engine.pause().await
+            } else {
+                engine.resume().await
+            };
+            result.map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Seek the current track to a position in milliseconds
+    pub async fn seek(&mut self, position: u64) -> Result<(), String> {
+        let track = self
+            .current_track
+            .as_ref()
+            .ok_or_else(|| "No track is playing".to_string())?;
+        if !track.info.is_seekable {
+            return Err("Track is not seekable".to_string());
+        }
+
+        self.position = position;
+        self.last_update = Instant::now();
+        self.mark_changed();
+        // A restored track isn't loaded yet and is resumed from the new position
+        if self.restore_pending {
+            return Ok(());
+        }
+        if let Some(engine) = &self.audio_engine {
+            engine.seek(position).await.map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Seek the current track to the start of one of its chapters
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub async fn seek_to_chapter(&mut self, index: usize) -> Result<Chapter, String> {
+        let chapters = self
+            .current_track
+            .as_ref()
+            .map(|track| track.chapters())
+            .unwrap_or_default();
+        let chapter = chapters
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("Chapter {index} not found"))?;
+
+        self.seek(chapter.start_time).await?;
+        Ok(chapter)
+    }
+
+    /// Snapshot of the state that is persisted across restarts
+    pub fn snapshot(&self) -> PlayerSnapshot {
+        PlayerSnapshot {
+            guild_id: self.guild_id.clone(),
+            session_id: self.session_id.clone(),
+            track: self.current_track.clone(),
+            position: self.get_current_position(),
+            end_time: self.end_time,
+            paused: self.paused,
+            volume: self.volume,
+            filters: self.filters.clone(),
+            repeat_track: self.repeat_track,
+            repeat_queue: self.repeat_queue,
+            shuffle: self.shuffle,
+            queue: self.queue.iter().cloned().collect(),
+            history: self.history.iter().cloned().collect(),
+            queue_mode: self.queue_mode.clone(),
+            autoplay: self.autoplay,
+            update_interval: self.update_interval,
+            queue_version: self.queue_version,
+            voice: self.voice.clone(),
+        }
+    }
+
+    /// Take over a persisted snapshot without starting playback
+    pub fn restore(&mut self, snapshot: PlayerSnapshot) {
+        self.restore_pending = snapshot.track.is_some();
+        self.current_track = snapshot.track;
+        self.position = snapshot.position;
+        self.last_update = Instant::now();
+        self.end_time = snapshot.end_time;
+        self.paused = snapshot.paused;
+        self.volume = snapshot.volume;
+        self.filters = snapshot.filters;
+        self.repeat_track = snapshot.repeat_track;
+        self.repeat_queue = snapshot.repeat_queue;
+        self.shuffle = snapshot.shuffle;
+        self.queue = snapshot.queue.into();
+        // Clients mirroring the queue may have missed updates while the node was down
+        self.queue_version = snapshot.queue_version;
+        self.queue_updates.clear();
+        self.record_queue_reorder();
+        self.history = snapshot.history.into();
+        self.queue_mode = snapshot.queue_mode;
+        self.autoplay = snapshot.autoplay;
+        self.update_interval = snapshot.update_interval;
+        self.voice = snapshot.voice;
+        self.state.position = self.position;
+    }
+
+    /// Start playing a restored track where the snapshot left off
+    pub async fn resume_restored(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !std::mem::take(&mut self.restore_pending) {
+            return Ok(());
+        }
+        let Some(track) = self.current_track.clone() else {
+            return Ok(());
+        };
+        info!(
+            "Resuming restored track {} at {} ms in guild {}",
+            track.info.title, self.position, self.guild_id
+        );
+
+        let paused = self.paused;
+        self.play_track(track, Some(self.position), self.end_time)
+            .await?;
+        if let Some(ref engine) = self.audio_engine {
+            engine.apply_filters(self.filters.clone()).await?;
+            if paused {
+                engine.pause().await?;
+            }
+        }
+        self.paused = paused;
+        Ok(())
+    }
+
     /// Convert to protocol Player structure
     pub fn to_protocol_player(&self) -> crate::protocol::messages::Player {
         crate::protocol::messages::Player {
@@ -1336,6 +2512,20 @@ impl Clone for LavalinkPlayer {
             repeat_queue: self.repeat_queue,
             shuffle: self.shuffle,
             voice_manager: self.voice_manager.clone(),
+            lyrics_subscribed: self.lyrics_subscribed,
+            lyrics_line: self.lyrics_line.clone(),
+            chapter: self.chapter.clone(),
+            restore_pending: self.restore_pending,
+            history: self.history.clone(),
+            queue_mode: self.queue_mode.clone(),
+            autoplay: self.autoplay,
+            queue_version: self.queue_version,
+            queue_updates: Vec::new(),
+            update_interval: self.update_interval,
+            last_player_update: self.last_player_update,
+            changes: self.changes.clone(),
+            queue_limit: self.queue_limit.clone(),
+            artwork: self.artwork.clone(),
         }
     }
 }
@@ -1351,6 +2541,9 @@ pub struct PlayerEventHandler {
     event_receiver: mpsc::UnboundedReceiver<PlayerEvent>,
     #[cfg(feature = "websocket")]
     websocket_sessions: Arc<dashmap::DashMap<String, crate::server::WebSocketSession>>,
+    /// When each session last received a `playerUpdate` of a guild
+    #[cfg(feature = "websocket")]
+    player_updates_sent: std::sync::Mutex<HashMap<(String, String), Instant>>,
     player_manager: Option<Arc<PlayerManager>>,
 }
 
@@ -1366,6 +2559,8 @@ impl PlayerEventHandler {
             event_receiver,
             #[cfg(feature = "websocket")]
             websocket_sessions,
+            #[cfg(feature = "websocket")]
+            player_updates_sent: Default::default(),
             player_manager: None,
         }
     }
@@ -1383,6 +2578,8 @@ impl PlayerEventHandler {
             event_receiver,
             #[cfg(feature = "websocket")]
             websocket_sessions,
+            #[cfg(feature = "websocket")]
+            player_updates_sent: Default::default(),
             player_manager: Some(player_manager),
         }
     }
@@ -1435,6 +2632,65 @@ impl PlayerEventHandler {
                 self.broadcast_to_sessions(message).await;
             }
 
+            PlayerEvent::StreamTitle {
+                guild_id,
+                track,
+                title,
+                url,
+            } => {
+                debug!("Stream title changed in guild {}: {}", guild_id, title);
+
+                let message = Message::event(Event::stream_title(guild_id, track, title, url));
+                self.broadcast_to_sessions(message).await;
+            }
+
+            PlayerEvent::LyricsLine {
+                guild_id,
+                track,
+                line_index,
+                line,
+                skipped,
+            } => {
+                debug!("Lyrics line {} in guild {}", line_index, guild_id);
+
+                let message = Message::event(Event::lyrics_line(
+                    guild_id, track, line_index, line, skipped,
+                ));
+                self.broadcast_to_sessions(message).await;
+            }
+
+            PlayerEvent::ChapterStart {
+                guild_id,
+                track,
+                chapter_index,
+                chapter,
+            } => {
+                debug!("Chapter {} started in guild {}", chapter_index, guild_id);
+
+                let message = Message::event(Event::chapter_start(
+                    guild_id,
+                    track,
+                    chapter_index,
+                    chapter,
+                ));
+                self.broadcast_to_sessions(message).await;
+            }
+
+            PlayerEvent::QueueUpdate { guild_id, update } => {
+                debug!(
+                    "Queue of guild {} updated to version {}",
+                    guild_id, update.version
+                );
+
+                let message = Message::event(Event::queue_update(guild_id, update));
+                self.broadcast_to_sessions(message).await;
+            }
+
+            PlayerEvent::Destroyed { guild_id } => {
+                debug!("Player of guild {} destroyed", guild_id);
+                self.forget_player_updates(&guild_id);
+            }
+
             PlayerEvent::VoiceConnectionEvent { guild_id, event } => {
                 debug!("Voice connection event for guild {}: {:?}", guild_id, event);
 
@@ -1517,11 +2773,14 @@ impl PlayerEventHandler {
         }
     }
 
-    /// Broadcast a message to all WebSocket sessions
+    /// Broadcast a message to all WebSocket sessions subscribed to it
     async fn broadcast_to_sessions(&self, message: Message) {
         #[cfg(feature = "websocket")]
         {
             for session in self.websocket_sessions.iter() {
+                if !session.subscription.allows(&message) || self.throttled(&session, &message) {
+                    continue;
+                }
                 if let Err(e) = session.send_message(message.clone()).await {
                     error!(
                         "Failed to send message to session {}: {}",
@@ -1539,6 +2798,47 @@ impl PlayerEventHandler {
             );
         }
     }
+
+    /// Drop the `playerUpdate` times of a destroyed player and of closed sessions
+    fn forget_player_updates(&self, guild_id: &str) {
+        #[cfg(feature = "websocket")]
+        {
+            self.player_updates_sent
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .retain(|(session_id, guild), _| {
+                    guild != guild_id && self.websocket_sessions.contains_key(session_id)
+                });
+        }
+        #[cfg(not(feature = "websocket"))]
+        {
+            let _ = guild_id;
+        }
+    }
+
+    /// Whether a `playerUpdate` comes sooner than the session's interval allows
+    #[cfg(feature = "websocket")]
+    fn throttled(&self, session: &crate::server::WebSocketSession, message: &Message) -> bool {
+        let (Message::PlayerUpdate { guild_id, .. }, Some(interval)) =
+            (message, session.subscription.player_update_interval)
+        else {
+            return false;
+        };
+        let key = (session.session_id.clone(), guild_id.clone());
+        let mut sent = self
+            .player_updates_sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let interval = Duration::from_millis(interval);
+        if sent
+            .get(&key)
+            .is_some_and(|last| last.elapsed() + PLAYER_UPDATE_JITTER < interval)
+        {
+            return true;
+        }
+        sent.insert(key, Instant::now());
+        false
+    }
 }
 
 #[cfg(test)]
@@ -1662,4 +2962,785 @@ mod tests {
         assert!(!player.state.connected); // Should be corrected
         assert_eq!(player.state.ping, -1);
     }
+
+    #[tokio::test]
+    async fn test_lyrics_line_events() {
+        struct FixedLyrics;
+
+        #[async_trait::async_trait]
+        impl crate::lyrics::LyricsProvider for FixedLyrics {
+            fn name(&self) -> &str {
+                "fixed"
+            }
+
+            async fn fetch(&self, _track: &Track) -> anyhow::Result<Option<crate::lyrics::Lyrics>> {
+                Ok(Some(crate::lyrics::Lyrics::synced(
+                    "test_source",
+                    "fixed",
+                    crate::lyrics::parse_lrc("[00:01.00]One\n[00:02.00]Two\n[00:09.00]Three"),
+                )))
+            }
+        }
+
+        let lyrics = Arc::new(LyricsManager::new());
+        lyrics.register_provider(Arc::new(FixedLyrics));
+
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+        let track = Track {
+            encoded: "lyrics_encoded".to_string(),
+            info: crate::protocol::TrackInfo {
+                identifier: "lyrics_id".to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: "Test Track".to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        lyrics.lyrics_for(&track).await;
+
+        // Paused so the position stays where the test puts it
+        player.current_track = Some(track);
+        player.paused = true;
+        player.position = 1_500;
+        assert!(player.next_lyrics_line(&lyrics).is_none());
+
+        player.lyrics_subscribed = true;
+        match player.next_lyrics_line(&lyrics) {
+            Some(PlayerEvent::LyricsLine {
+                line_index,
+                skipped,
+                ..
+            }) => {
+                assert_eq!(line_index, 0);
+                assert!(!skipped);
+            }
+            other => panic!("expected a lyrics line event, got {:?}", other),
+        }
+        assert!(player.next_lyrics_line(&lyrics).is_none());
+
+        // Seeking past a line reports it as skipped
+        player.position = 9_500;
+        match player.next_lyrics_line(&lyrics) {
+            Some(PlayerEvent::LyricsLine {
+                line_index,
+                line,
+                skipped,
+                ..
+            }) => {
+                assert_eq!(line_index, 2);
+                assert_eq!(line.line, "Three");
+                assert!(skipped);
+            }
+            other => panic!("expected a lyrics line event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chapter_start_events() {
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+        let mut track = Track {
+            encoded: "chapters_encoded".to_string(),
+            info: crate::protocol::TrackInfo {
+                identifier: "chapters_id".to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: "Test Track".to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        let mut chapters = vec![Chapter::new("Two", 60_000), Chapter::new("One", 0)];
+        crate::protocol::normalize_chapters(&mut chapters, Some(track.info.length));
+        track.set_chapters(&chapters);
+        assert_eq!(track.chapters()[0].end_time, Some(60_000));
+        assert_eq!(track.chapters()[1].end_time, Some(180_000));
+
+        // Paused so the position stays where the test puts it
+        player.current_track = Some(track);
+        player.paused = true;
+        match player.next_chapter() {
+            Some(PlayerEvent::ChapterStart {
+                chapter_index,
+                chapter,
+                ..
+            }) => {
+                assert_eq!(chapter_index, 0);
+                assert_eq!(chapter.title, "One");
+            }
+            other => panic!("expected a chapter start event, got {:?}", other),
+        }
+        assert!(player.next_chapter().is_none());
+
+        let chapter = player.seek_to_chapter(1).await.unwrap();
+        assert_eq!(chapter.title, "Two");
+        assert_eq!(player.get_current_position(), 60_000);
+        match player.next_chapter() {
+            Some(PlayerEvent::ChapterStart { chapter_index, .. }) => assert_eq!(chapter_index, 1),
+            other => panic!("expected a chapter start event, got {:?}", other),
+        }
+        assert!(player.seek_to_chapter(2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_players_persist_across_restarts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let track = |identifier: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: "Test Track".to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+
+        let manager = PlayerManager::new().with_persistence(PlayerStore::new(
+            temp_dir.path(),
+            persistence::DEFAULT_DEBOUNCE,
+        ));
+        let player = manager
+            .get_or_create_player("test_guild".to_string(), "test_session".to_string())
+            .await;
+        {
+            let mut player = player.write().await;
+            player.current_track = Some(track("current"));
+            player.position = 42_000;
+            player.paused = true;
+            player.volume = 80;
+            player.repeat_queue = true;
+            player.add_to_queue(track("next")).unwrap();
+        }
+        manager.shutdown().await.unwrap();
+
+        // A new node picks the player up again, idle until it is reattached
+        let manager = PlayerManager::new().with_persistence(PlayerStore::new(
+            temp_dir.path(),
+            persistence::DEFAULT_DEBOUNCE,
+        ));
+        assert_eq!(manager.restore_players(Duration::from_secs(60)).await, 1);
+        let player = manager.get_player("test_guild").await.unwrap();
+        let mut player = player.write().await;
+        assert_eq!(player.session_id, "test_session");
+        assert_eq!(player.current_track, Some(track("current")));
+        assert_eq!(player.queue_length(), 1);
+        assert_eq!(player.volume, 80);
+        assert!(player.repeat_queue);
+        assert!(player.restore_pending);
+        assert!(!player.is_playing());
+
+        // The restored queue is announced as the next version
+        assert_eq!(player.queue_version, 2);
+        let events = player.take_queue_updates();
+        assert!(matches!(
+            events.as_slice(),
+            [PlayerEvent::QueueUpdate { update, .. }]
+                if update.version == 2 && update.cleared && update.added.len() == 1
+        ));
+
+        // Seeking and pausing change what is resumed
+        player.seek(50_000).await.unwrap();
+        player.set_paused(true).await.unwrap();
+        assert!(player.restore_pending);
+
+        player.resume_restored().await.unwrap();
+        assert!(!player.restore_pending);
+        assert!(player.paused);
+        assert_eq!(player.get_current_position(), 50_000);
+
+        // A track the client sets replaces the one waiting to be resumed
+        let snapshot = player.snapshot();
+        player.restore(snapshot);
+        assert!(player.restore_pending);
+        player.replace_current_track(Some(track("other")));
+        assert!(!player.restore_pending);
+    }
+
+    #[tokio::test]
+    async fn test_only_changes_wake_persistence() {
+        let manager = PlayerManager::new();
+        let changed = || async {
+            tokio::time::timeout(Duration::from_millis(50), manager.changes.notified())
+                .await
+                .is_ok()
+        };
+
+        let player = manager
+            .get_or_create_player("test_guild".to_string(), "test_session".to_string())
+            .await;
+        assert!(changed().await);
+
+        // Looking players up doesn't change them
+        manager.get_player("test_guild").await.unwrap();
+        manager.get_players_for_session("test_session").await;
+        manager
+            .get_or_create_player("test_guild".to_string(), "test_session".to_string())
+            .await;
+        assert!(!changed().await);
+
+        player.write().await.set_paused(true).await.unwrap();
+        assert!(changed().await);
+
+        manager.remove_players_for_session("other_session").await;
+        assert!(!changed().await);
+        manager.remove_player("test_guild").await.unwrap();
+        assert!(changed().await);
+    }
+
+    #[tokio::test]
+    async fn test_restored_players_of_unresumed_sessions_are_dropped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = PlayerManager::new().with_persistence(PlayerStore::new(
+            temp_dir.path(),
+            persistence::DEFAULT_DEBOUNCE,
+        ));
+        manager
+            .get_or_create_player("guild_a".to_string(), "session_a".to_string())
+            .await;
+        manager
+            .get_or_create_player("guild_b".to_string(), "session_b".to_string())
+            .await;
+        manager.shutdown().await.unwrap();
+
+        let manager = PlayerManager::new().with_persistence(PlayerStore::new(
+            temp_dir.path(),
+            persistence::DEFAULT_DEBOUNCE,
+        ));
+        assert_eq!(manager.restore_players(Duration::from_millis(100)).await, 2);
+        manager.resume_session("session_a").await;
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(manager.get_player("guild_a").await.is_some());
+        assert!(manager.get_player("guild_b").await.is_none());
+    }
+
+    #[cfg(not(feature = "discord"))]
+    #[tokio::test]
+    async fn test_undecodable_tracks_follow_the_clock() {
+        let (event_sender, _events) = mpsc::unbounded_channel();
+        let engine = AudioPlayerEngine::new("test_guild".to_string(), event_sender);
+        let track = Track {
+            encoded: "test_encoded".to_string(),
+            info: crate::protocol::TrackInfo {
+                identifier: "test".to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: "Test Track".to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+
+        engine.play_track(track, Some(10_000)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(engine.get_position().await >= 10_200);
+
+        engine.pause().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let paused_at = engine.get_position().await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(engine.get_position().await, paused_at);
+    }
+
+    #[tokio::test]
+    async fn test_history_and_previous() {
+        let track = |identifier: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: identifier.to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+
+        for identifier in ["one", "two", "three"] {
+            player
+                .play_track(track(identifier), None, None)
+                .await
+                .unwrap();
+        }
+        // Replaying the current track does not record it twice
+        player.play_track(track("three"), None, None).await.unwrap();
+        assert_eq!(player.get_history(0, 10), vec![track("two"), track("one")]);
+        assert_eq!(player.get_history(1, 10), vec![track("one")]);
+
+        let previous = player.play_previous().await.unwrap();
+        assert_eq!(previous, Some(track("two")));
+        assert_eq!(player.current_track, Some(track("two")));
+        assert_eq!(player.get_queue(), vec![track("three")]);
+        assert_eq!(player.get_history(0, 10), vec![track("one")]);
+
+        // Skipping returns to the track that was playing before
+        player.skip_track().await.unwrap();
+        assert_eq!(player.current_track, Some(track("three")));
+        assert_eq!(player.get_history(0, 10), vec![track("two"), track("one")]);
+
+        for index in 0..MAX_HISTORY + 5 {
+            player
+                .play_track(track(&index.to_string()), None, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(player.history.len(), MAX_HISTORY);
+        assert!(player
+            .replay_from_history(MAX_HISTORY)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_shuffled_queue_plays_in_listed_order() {
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+        player.shuffle = true;
+        for index in 0..20 {
+            let identifier = format!("track_{index}");
+            let track = Track {
+                encoded: format!("{identifier}_encoded"),
+                info: crate::protocol::TrackInfo {
+                    identifier: identifier.clone(),
+                    is_seekable: true,
+                    author: "test_author".to_string(),
+                    length: 180_000,
+                    is_stream: false,
+                    position: 0,
+                    title: identifier,
+                    uri: None,
+                    source_name: "test_source".to_string(),
+                    artwork_url: None,
+                    isrc: None,
+                },
+                plugin_info: std::collections::HashMap::new(),
+                user_data: std::collections::HashMap::new(),
+            };
+            player.add_to_queue(track).unwrap();
+        }
+        player.shuffle_queue();
+
+        let listed = player.get_queue();
+        let played: Vec<Track> = std::iter::from_fn(|| player.get_next_track()).collect();
+        assert_eq!(played, listed);
+
+        // Nobody takes the updates of this player, so only the latest are kept
+        for _ in 0..MAX_PENDING_QUEUE_UPDATES {
+            player.add_to_queue(listed[0].clone()).unwrap();
+            player.get_next_track();
+        }
+        assert_eq!(player.queue_updates.len(), MAX_PENDING_QUEUE_UPDATES);
+        assert_eq!(
+            player.queue_updates.last().map(|update| update.version),
+            Some(player.queue_version)
+        );
+    }
+
+    #[test]
+    fn test_fair_queue_mode() {
+        let track = |identifier: &str, requester: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: identifier.to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: [("requester".to_string(), serde_json::json!(requester))]
+                .into_iter()
+                .collect(),
+        };
+        let identifiers = |player: &LavalinkPlayer| -> Vec<String> {
+            player
+                .get_queue()
+                .into_iter()
+                .map(|track| track.info.identifier)
+                .collect()
+        };
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+
+        // Linear mode keeps insertion order
+        for (identifier, requester) in [("a1", "a"), ("a2", "a"), ("b1", "b"), ("c1", "c")] {
+            player.add_to_queue(track(identifier, requester)).unwrap();
+        }
+        assert_eq!(identifiers(&player), ["a1", "a2", "b1", "c1"]);
+
+        // Switching to fair mode interleaves the existing tracks
+        player.set_queue_mode(QueueMode::Fair {
+            key: "requester".to_string(),
+            max_per_requester: Some(2),
+        });
+        assert_eq!(identifiers(&player), ["a1", "b1", "c1", "a2"]);
+
+        // New tracks join their requester's next round
+        assert_eq!(player.add_to_queue(track("b2", "b")), Ok(4));
+        assert_eq!(player.add_to_queue(track("d1", "d")), Ok(3));
+        assert_eq!(identifiers(&player), ["a1", "b1", "c1", "d1", "a2", "b2"]);
+        assert!(player.add_to_queue(track("a3", "a")).is_err());
+
+        // The current track counts as its requester's first round
+        player.current_track = Some(track("c0", "c"));
+        player.set_queue_mode(QueueMode::Fair {
+            key: "requester".to_string(),
+            max_per_requester: None,
+        });
+        assert_eq!(identifiers(&player), ["a1", "b1", "d1", "c1", "a2", "b2"]);
+    }
+
+    #[test]
+    fn test_queue_updates_keep_mirror_in_sync() {
+        let track = |identifier: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: identifier.to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+        let mut mirror: Vec<Track> = Vec::new();
+        let mut version = 0;
+        let mut sync = |player: &mut LavalinkPlayer| {
+            for event in player.take_queue_updates() {
+                let PlayerEvent::QueueUpdate { update, .. } = event else {
+                    panic!("expected a queue update, got {:?}", event);
+                };
+                assert_eq!(update.version, version + 1);
+                version = update.version;
+
+                if update.cleared {
+                    mirror.clear();
+                }
+                for index in update.removed {
+                    mirror.remove(index);
+                }
+                for moved in update.moved {
+                    let track = mirror.remove(moved.from);
+                    mirror.insert(moved.to, track);
+                }
+                for added in update.added {
+                    mirror.insert(added.index, added.track);
+                }
+            }
+            assert_eq!(mirror, player.get_queue());
+            assert_eq!(version, player.queue_version);
+        };
+
+        for identifier in ["a", "b", "c", "d"] {
+            player.add_to_queue(track(identifier)).unwrap();
+        }
+        sync(&mut player);
+        assert_eq!(player.queue_version, 1);
+
+        player.move_track(0, 3).unwrap();
+        player.remove_from_queue(1);
+        sync(&mut player);
+
+        player.current_track = Some(track("current"));
+        player.repeat_queue = true;
+        player.get_next_track();
+        sync(&mut player);
+
+        player.set_queue_mode(QueueMode::Fair {
+            key: "requester".to_string(),
+            max_per_requester: None,
+        });
+        sync(&mut player);
+
+        player.clear_queue();
+        sync(&mut player);
+        assert!(mirror.is_empty());
+
+        let event = Event::queue_update(
+            "test_guild".to_string(),
+            QueueUpdate {
+                version: 7,
+                removed: vec![0],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "QueueUpdateEvent",
+                "guildId": "test_guild",
+                "version": 7,
+                "removed": [0]
+            })
+        );
+    }
+
+    #[test]
+    fn test_enqueue_autoplay_skips_recent_tracks() {
+        let track = |identifier: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: identifier.to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+        player.history.push_back(track("played"));
+        player.history.push_back(track("seed"));
+
+        let related = ["seed", "played", "r1", "r1", "r2", "r3", "r4", "r5", "r6"]
+            .into_iter()
+            .map(track)
+            .collect();
+        let added = player.enqueue_autoplay(related);
+
+        let identifiers: Vec<_> = added
+            .iter()
+            .map(|queued| queued.track.info.identifier.as_str())
+            .collect();
+        assert_eq!(identifiers, ["r1", "r2", "r3", "r4", "r5"]);
+        assert_eq!(
+            added.iter().map(|queued| queued.index).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(player.queue_length(), AUTOPLAY_TRACKS);
+        assert!(player
+            .get_queue()
+            .iter()
+            .all(|track| track.user_data.get("autoplay") == Some(&serde_json::json!(true))));
+    }
+
+    #[tokio::test]
+    async fn test_queue_limit_refuses_tracks() {
+        let track = |identifier: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: identifier.to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        let manager = PlayerManager::new().with_queue_limit(|queued| {
+            if queued >= 2 {
+                Err("Queue is full".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        let player = manager
+            .get_or_create_player("test_guild".to_string(), "test_session".to_string())
+            .await;
+        let mut player = player.write().await;
+
+        assert_eq!(player.add_to_queue(track("a")), Ok(0));
+        assert_eq!(player.add_to_queue(track("b")), Ok(1));
+        assert_eq!(
+            player.add_to_queue(track("c")),
+            Err("Queue is full".to_string())
+        );
+
+        // Autoplay goes through the same limit
+        player.queue.clear();
+        let added = player.enqueue_autoplay(vec![track("r1"), track("r2"), track("r3")]);
+        assert_eq!(added.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_artwork_rewrite_applies_to_played_and_queued_tracks() {
+        let track = |identifier: &str| Track {
+            encoded: format!("{identifier}_encoded"),
+            info: crate::protocol::TrackInfo {
+                identifier: identifier.to_string(),
+                is_seekable: true,
+                author: "test_author".to_string(),
+                length: 180_000,
+                is_stream: false,
+                position: 0,
+                title: identifier.to_string(),
+                uri: None,
+                source_name: "test_source".to_string(),
+                artwork_url: Some(format!("https://img.example/{identifier}.jpg")),
+                isrc: None,
+            },
+            plugin_info: std::collections::HashMap::new(),
+            user_data: std::collections::HashMap::new(),
+        };
+        let manager = PlayerManager::new().with_artwork(|track| {
+            track.info.artwork_url = Some(format!("/v4/artwork/{}", track.info.identifier));
+        });
+        let player = manager
+            .get_or_create_player("test_guild".to_string(), "test_session".to_string())
+            .await;
+        let mut player = player.write().await;
+
+        player.replace_current_track(Some(track("current")));
+        player.add_to_queue(track("queued")).unwrap();
+        player.enqueue_autoplay(vec![track("related")]);
+
+        let current = player.to_protocol_player().track.unwrap();
+        assert_eq!(
+            current.info.artwork_url.as_deref(),
+            Some("/v4/artwork/current")
+        );
+        assert_eq!(
+            player
+                .get_queue()
+                .iter()
+                .map(|track| track.info.artwork_url.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("/v4/artwork/queued"), Some("/v4/artwork/related")]
+        );
+    }
+
+    #[cfg(all(feature = "audio-sources", not(feature = "discord")))]
+    #[tokio::test(start_paused = true)]
+    async fn test_player_updates_follow_interval_and_decoded_audio() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut player = LavalinkPlayer::new("test_guild".to_string(), "test_session".to_string());
+        player.initialize_audio_engine(sender.clone(), None);
+        player.update_interval = Some(1000);
+        let identifier = "synth:sine?duration=11500";
+        let track = crate::audio::sources::SynthSpec::parse(identifier)
+            .unwrap()
+            .to_track(identifier);
+        player.play_track(track, Some(10_000), None).await.unwrap();
+        let player = Arc::new(RwLock::new(player));
+
+        async fn tick(
+            player: &Arc<RwLock<LavalinkPlayer>>,
+            sender: &mpsc::UnboundedSender<PlayerEvent>,
+            receiver: &mut mpsc::UnboundedReceiver<PlayerEvent>,
+        ) -> Vec<u64> {
+            let sender = Some(sender.clone());
+            update_player(player.clone(), DEFAULT_PLAYER_UPDATE_INTERVAL, sender, None).await;
+            std::iter::from_fn(|| receiver.try_recv().ok())
+                .filter_map(|event| match event {
+                    PlayerEvent::PlayerUpdate { state, .. } => Some(state.position),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        // The position comes from the audio the engine decoded since the start time
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let positions = tick(&player, &sender, &mut receiver).await;
+        assert_eq!(positions.len(), 1);
+        assert!((10_400..=10_500).contains(&positions[0]), "{positions:?}");
+
+        // The player's own interval overrides the node's
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(tick(&player, &sender, &mut receiver).await.is_empty());
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(tick(&player, &sender, &mut receiver).await.len(), 1);
+
+        // Paused engines stop decoding
+        let engine = player.read().await.audio_engine.clone().unwrap();
+        engine.pause().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let paused_at = engine.get_position().await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(engine.get_position().await, paused_at);
+
+        // The track ends with its audio rather than the clock
+        engine.resume().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2000)).await;
+        assert_eq!(engine.get_position().await, 11_500);
+        assert!(!engine.is_playing().await);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn test_destroyed_player_forgets_update_times() {
+        let sessions = Arc::new(dashmap::DashMap::new());
+        let mut session = crate::server::WebSocketSession::new("session".to_string());
+        session.subscription.player_update_interval = Some(10_000);
+        sessions.insert(session.session_id.clone(), session.clone());
+        let (_sender, receiver) = mpsc::unbounded_channel();
+        let handler = PlayerEventHandler::new(receiver, sessions);
+
+        let state = LavalinkPlayer::new("1".to_string(), "session".to_string()).state;
+        let update = Message::player_update("1".to_string(), state);
+        assert!(!handler.throttled(&session, &update));
+        assert!(handler.throttled(&session, &update));
+
+        handler
+            .handle_event(PlayerEvent::Destroyed {
+                guild_id: "1".to_string(),
+            })
+            .await;
+        assert!(handler.player_updates_sent.lock().unwrap().is_empty());
+        assert!(!handler.throttled(&session, &update));
+    }
 }
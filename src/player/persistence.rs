@@ -0,0 +1,162 @@
+//! Player snapshots that survive node restarts
+//!
+//! When persistence is enabled, the player manager snapshots every player
+//! (current track and position, filters, volume, repeat/shuffle flags, queue,
+//! history and voice state) into a JSON file in the data directory.
+//! Snapshots are written a debounce interval after players change and only
+//! when something did, so bursts of updates result in a single write. On
+//! startup the snapshots are restored as idle players that resume playback
+//! once their session resumes or the client sends a voice update again.
+//! Players whose session doesn't resume in time are dropped.
+//!
+//! The voice state includes the voice server token, so the file is only
+//! readable by its owner.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::PersistenceConfig;
+use crate::protocol::{
+    messages::{QueueMode, VoiceState},
+    Filters, Track,
+};
+
+/// Data directory used when none is configured
+pub const DEFAULT_DATA_DIRECTORY: &str = "./data";
+
+/// Default delay between a change and the snapshot being written
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(1000);
+
+/// How often positions of playing players are written without other changes
+pub const POSITION_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Name of the snapshot file inside the data directory
+const SNAPSHOT_FILE: &str = "players.json";
+
+/// Version of the on-disk snapshot format
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// State of a single player as written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSnapshot {
+    pub guild_id: String,
+    pub session_id: String,
+    pub track: Option<Track>,
+    /// Position in the current track in milliseconds
+    pub position: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+    pub paused: bool,
+    pub volume: u16,
+    #[serde(default)]
+    pub filters: Filters,
+    pub repeat_track: bool,
+    pub repeat_queue: bool,
+    pub shuffle: bool,
+    #[serde(default)]
+    pub queue: Vec<Track>,
+    /// Previously played tracks, most recent last
+    #[serde(default)]
+    pub history: Vec<Track>,
+    #[serde(default)]
+    pub queue_mode: QueueMode,
+    #[serde(default)]
+    pub autoplay: bool,
+    /// Overridden `playerUpdate` interval in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_interval: Option<u64>,
+    /// Version of the last queue update sent
+    #[serde(default)]
+    pub queue_version: u64,
+    pub voice: VoiceState,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotFile {
+    version: u32,
+    players: Vec<PlayerSnapshot>,
+}
+
+/// File-backed store for player snapshots
+#[derive(Debug, Clone)]
+pub struct PlayerStore {
+    path: PathBuf,
+    debounce: Duration,
+}
+
+impl PlayerStore {
+    /// Create a store writing `players.json` into the given data directory
+    pub fn new(data_directory: impl AsRef<Path>, debounce: Duration) -> Self {
+        Self {
+            path: data_directory.as_ref().join(SNAPSHOT_FILE),
+            debounce,
+        }
+    }
+
+    /// Create a store from the configuration, if persistence is enabled
+    pub fn from_config(config: Option<&PersistenceConfig>) -> Option<Self> {
+        let config = config.filter(|c| c.enabled.unwrap_or(false))?;
+        Some(Self::new(
+            config
+                .data_directory
+                .as_deref()
+                .unwrap_or(DEFAULT_DATA_DIRECTORY),
+            config
+                .debounce_ms
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_DEBOUNCE),
+        ))
+    }
+
+    /// File the snapshots are written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Delay between a change and the snapshot being written
+    pub fn debounce(&self) -> Duration {
+        self.debounce
+    }
+
+    /// Read the persisted snapshots; a missing file has none
+    pub fn load(&self) -> Result<Vec<PlayerSnapshot>> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let file: SnapshotFile = serde_json::from_slice(&data)?;
+        if file.version != SNAPSHOT_VERSION {
+            return Err(anyhow!("unsupported snapshot version {}", file.version));
+        }
+        Ok(file.players)
+    }
+
+    /// Replace the persisted snapshots
+    pub fn save(&self, players: &[PlayerSnapshot]) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = SnapshotFile {
+            version: SNAPSHOT_VERSION,
+            players: players.to_vec(),
+        };
+        // Write to a temporary file first so a crash never leaves a truncated snapshot
+        let temp = self.path.with_extension("tmp");
+        // The mode only applies to new files
+        let _ = std::fs::remove_file(&temp);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&temp)?
+            .write_all(&serde_json::to_vec(&file)?)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
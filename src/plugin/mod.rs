@@ -5,8 +5,10 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::config::PluginsConfig;
+use crate::lyrics::{LyricsManager, LyricsProvider};
 
 pub mod interface;
 pub mod loader;
@@ -18,6 +20,8 @@ pub use loader::*;
 pub struct PluginManager {
     plugins: HashMap<String, Box<dyn LavalinkPlugin + Send + Sync>>,
     pub dynamic_loader: DynamicPluginLoader,
+    /// Receives the lyrics providers of registered plugins
+    lyrics: Option<Arc<LyricsManager>>,
 }
 
 /// Trait for Lavalink plugins
@@ -69,6 +73,11 @@ pub trait LavalinkPlugin {
     async fn update_config(&mut self, _config: JsonValue) -> Result<()> {
         Ok(())
     }
+
+    /// Lyrics providers contributed by the plugin, asked after the built-in ones
+    fn lyrics_providers(&self) -> Vec<Arc<dyn LyricsProvider>> {
+        Vec::new()
+    }
 }
 
 impl PluginManager {
@@ -93,7 +102,16 @@ impl PluginManager {
         Self {
             plugins: HashMap::new(),
             dynamic_loader,
+            lyrics: None,
+        }
+    }
+
+    /// Hand lyrics providers of current and future plugins to a lyrics manager
+    pub fn attach_lyrics(&mut self, lyrics: Arc<LyricsManager>) {
+        for (name, plugin) in &self.plugins {
+            lyrics.register_plugin_providers(name, plugin.lyrics_providers());
         }
+        self.lyrics = Some(lyrics);
     }
 
     /// Register a plugin
@@ -112,6 +130,10 @@ impl PluginManager {
         // Initialize the plugin
         plugin.initialize().await?;
 
+        if let Some(lyrics) = &self.lyrics {
+            lyrics.register_plugin_providers(&name, plugin.lyrics_providers());
+        }
+
         // Store the plugin
         self.plugins.insert(name.clone(), plugin);
         tracing::info!("Registered plugin: {}", name);
@@ -127,6 +149,9 @@ impl PluginManager {
     /// Unregister a plugin
     pub async fn unregister_plugin(&mut self, name: &str) -> Result<()> {
         if let Some(mut plugin) = self.plugins.remove(name) {
+            if let Some(lyrics) = &self.lyrics {
+                lyrics.unregister_plugin_providers(name);
+            }
             // Shutdown the plugin
             if let Err(e) = plugin.shutdown().await {
                 tracing::warn!("Error shutting down plugin '{}': {}", name, e);
@@ -163,6 +188,29 @@ impl PluginManager {
         self.dynamic_loader.get_loaded_plugin_names()
     }
 
+    /// Name and version of every registered and dynamically loaded plugin, sorted by name
+    pub fn plugin_infos(&self) -> Vec<crate::protocol::Plugin> {
+        let mut plugins: Vec<_> = self
+            .plugins
+            .values()
+            .map(|plugin| crate::protocol::Plugin {
+                name: plugin.name().to_string(),
+                version: plugin.version().to_string(),
+            })
+            .chain(
+                self.get_dynamic_plugin_names()
+                    .iter()
+                    .filter_map(|name| self.get_dynamic_plugin_metadata(name))
+                    .map(|metadata| crate::protocol::Plugin {
+                        name: metadata.name.clone(),
+                        version: metadata.version.clone(),
+                    }),
+            )
+            .collect();
+        plugins.sort_by(|a, b| a.name.cmp(&b.name));
+        plugins
+    }
+
     /// Check if a dynamic plugin is loaded
     pub fn is_dynamic_plugin_loaded(&self, name: &str) -> bool {
         self.dynamic_loader.is_plugin_loaded(name)
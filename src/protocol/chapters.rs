@@ -0,0 +1,106 @@
+//! Chapter markers of long-form tracks
+//!
+//! Sources that know a track's chapters (yt-dlp, podcast chapter documents,
+//! MP4/Matroska/Ogg metadata) publish them in the track's
+//! `pluginInfo.chapters`, so clients see them with the track and the player
+//! can follow them during playback.
+
+use serde::{Deserialize, Serialize};
+
+use super::Track;
+
+/// Key of the chapter list in a track's `pluginInfo`
+pub const CHAPTERS_KEY: &str = "chapters";
+
+/// A chapter of a track, as exposed in `pluginInfo.chapters`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    pub title: String,
+    /// Start of the chapter in milliseconds
+    pub start_time: u64,
+    /// End of the chapter in milliseconds, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub img: Option<String>,
+}
+
+impl Chapter {
+    pub fn new(title: impl Into<String>, start_time: u64) -> Self {
+        Self {
+            title: title.into(),
+            start_time,
+            end_time: None,
+            url: None,
+            img: None,
+        }
+    }
+}
+
+/// Sort chapters by start time and fill in missing end times
+///
+/// A chapter without an end time ends where the next one starts; the last
+/// one ends with the track if its length is known.
+pub fn normalize_chapters(chapters: &mut [Chapter], length: Option<u64>) {
+    chapters.sort_by_key(|chapter| chapter.start_time);
+    for index in 1..chapters.len() {
+        let next_start = chapters[index].start_time;
+        let previous = &mut chapters[index - 1];
+        if previous.end_time.is_none() {
+            previous.end_time = Some(next_start);
+        }
+    }
+    if let (Some(last), Some(length)) = (chapters.last_mut(), length.filter(|length| *length > 0)) {
+        if last.end_time.is_none() && last.start_time < length {
+            last.end_time = Some(length);
+        }
+    }
+}
+
+/// Index of the chapter playing at the given position, if any
+pub fn chapter_at(chapters: &[Chapter], position: u64) -> Option<usize> {
+    let index = chapters
+        .iter()
+        .rposition(|chapter| chapter.start_time <= position)?;
+    let within = chapters[index]
+        .end_time
+        .is_none_or(|end_time| position < end_time);
+    within.then_some(index)
+}
+
+impl Track {
+    /// Chapters published in this track's `pluginInfo`
+    pub fn chapters(&self) -> Vec<Chapter> {
+        #[cfg(feature = "plugins")]
+        if let Some(chapters) = self.plugin_info.get(CHAPTERS_KEY) {
+            return serde_json::from_value(chapters.clone()).unwrap_or_default();
+        }
+        Vec::new()
+    }
+
+    /// Whether this track carries chapters
+    pub fn has_chapters(&self) -> bool {
+        #[cfg(feature = "plugins")]
+        {
+            self.plugin_info.contains_key(CHAPTERS_KEY)
+        }
+        #[cfg(not(feature = "plugins"))]
+        {
+            false
+        }
+    }
+
+    /// Publish chapters in this track's `pluginInfo`; an empty list is left out
+    #[cfg_attr(not(feature = "plugins"), allow(unused_variables))]
+    pub fn set_chapters(&mut self, chapters: &[Chapter]) {
+        #[cfg(feature = "plugins")]
+        if chapters.is_empty() {
+            self.plugin_info.remove(CHAPTERS_KEY);
+        } else if let Ok(value) = serde_json::to_value(chapters) {
+            self.plugin_info.insert(CHAPTERS_KEY.to_string(), value);
+        }
+    }
+}
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 
 /// Audio filters that can be applied to a player
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Filters {
     #[serde(skip_serializing_if = "Omissible::is_omitted", default)]
     pub volume: Omissible<f32>,
@@ -41,6 +42,7 @@ pub struct Filters {
 
 /// Equalizer band configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Band {
     pub band: u8,
     pub gain: f32,
@@ -48,6 +50,7 @@ pub struct Band {
 
 /// Karaoke filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Karaoke {
     pub level: Option<f32>,
     #[serde(rename = "monoLevel")]
@@ -60,6 +63,7 @@ pub struct Karaoke {
 
 /// Timescale filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Timescale {
     pub speed: Option<f32>,
     pub pitch: Option<f32>,
@@ -68,6 +72,7 @@ pub struct Timescale {
 
 /// Tremolo filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Tremolo {
     pub frequency: Option<f32>,
     pub depth: Option<f32>,
@@ -75,6 +80,7 @@ pub struct Tremolo {
 
 /// Vibrato filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Vibrato {
     pub frequency: Option<f32>,
     pub depth: Option<f32>,
@@ -82,6 +88,7 @@ pub struct Vibrato {
 
 /// Distortion filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Distortion {
     #[serde(rename = "sinOffset")]
     pub sin_offset: Option<f32>,
@@ -101,6 +108,7 @@ pub struct Distortion {
 
 /// Rotation filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Rotation {
     #[serde(rename = "rotationHz")]
     pub rotation_hz: Option<f32>,
@@ -108,6 +116,7 @@ pub struct Rotation {
 
 /// Channel mix filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct ChannelMix {
     #[serde(rename = "leftToLeft")]
     pub left_to_left: Option<f32>,
@@ -121,6 +130,7 @@ pub struct ChannelMix {
 
 /// Low pass filter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct LowPass {
     pub smoothing: Option<f32>,
 }
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// Server information response
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Info {
     pub version: Version,
     #[serde(rename = "buildTime")]
@@ -17,6 +18,7 @@ pub struct Info {
 
 /// Version information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Version {
     pub semver: String,
     pub major: u32,
@@ -29,6 +31,7 @@ pub struct Version {
 
 /// Git information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Git {
     pub branch: String,
     pub commit: String,
@@ -62,6 +65,7 @@ impl<'de> Deserialize<'de> for Plugins {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Plugin {
     pub name: String,
     pub version: String,
@@ -96,20 +100,20 @@ impl Version {
 }
 
 impl Info {
-    /// Create server info
+    /// Create server info with the build metadata embedded by `build.rs`
+    ///
+    /// Lists every source manager and filter the node supports; use
+    /// [`Info::with_capabilities`] to narrow them to the configured ones.
     pub fn new() -> Self {
         Self {
             version: Version::from_semver(env!("CARGO_PKG_VERSION")),
-            build_time: 0, // Will be set at build time
+            build_time: env!("LAVALINK_BUILD_TIME").parse().unwrap_or(0),
             git: Git {
-                branch: "main".to_string(),
-                commit: "unknown".to_string(),
-                commit_time: 0,
+                branch: env!("LAVALINK_GIT_BRANCH").to_string(),
+                commit: env!("LAVALINK_GIT_COMMIT").to_string(),
+                commit_time: env!("LAVALINK_GIT_COMMIT_TIME").parse().unwrap_or(0),
             },
-            jvm: format!(
-                "Rust {}",
-                option_env!("CARGO_PKG_RUST_VERSION").unwrap_or("unknown")
-            ),
+            jvm: format!("Rust {}", env!("LAVALINK_RUSTC_VERSION")),
             lavaplayer: "rust-native".to_string(),
             source_managers: vec![
                 "youtube".to_string(),
@@ -121,25 +125,43 @@ impl Info {
                 "http".to_string(),
                 "local".to_string(),
             ],
-            filters: vec![
-                "volume".to_string(),
-                "equalizer".to_string(),
-                "karaoke".to_string(),
-                "timescale".to_string(),
-                "tremolo".to_string(),
-                "vibrato".to_string(),
-                "distortion".to_string(),
-                "rotation".to_string(),
-                "channelMix".to_string(),
-                "lowPass".to_string(),
-            ],
+            filters: FILTERS.iter().map(|filter| filter.to_string()).collect(),
             plugins: Plugins {
                 plugins: Vec::new(),
             },
         }
     }
+
+    /// Advertise only the given source managers and filters
+    pub fn with_capabilities(mut self, source_managers: Vec<String>, filters: Vec<String>) -> Self {
+        self.source_managers = source_managers;
+        self.filters = filters;
+        self
+    }
+
+    /// Copy of the info listing the given plugins
+    pub fn with_plugins(&self, plugins: Vec<Plugin>) -> Self {
+        Self {
+            plugins: Plugins { plugins },
+            ..self.clone()
+        }
+    }
 }
 
+/// Names of all filters the node implements, as used in the filters object
+pub const FILTERS: [&str; 10] = [
+    "volume",
+    "equalizer",
+    "karaoke",
+    "timescale",
+    "tremolo",
+    "vibrato",
+    "distortion",
+    "rotation",
+    "channelMix",
+    "lowPass",
+];
+
 impl Default for Info {
     fn default() -> Self {
         Self::new()
@@ -66,10 +66,80 @@ pub enum Event {
         #[serde(rename = "byRemote")]
         by_remote: bool,
     },
+    #[serde(rename = "StreamTitleEvent")]
+    StreamTitle {
+        #[serde(rename = "guildId")]
+        guild_id: String,
+        track: Track,
+        title: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    #[serde(rename = "LyricsLineEvent")]
+    LyricsLine {
+        #[serde(rename = "guildId")]
+        guild_id: String,
+        track: Track,
+        #[serde(rename = "lineIndex")]
+        line_index: usize,
+        line: crate::lyrics::LyricsLine,
+        /// Whether lines were skipped to get here, e.g. after a seek
+        skipped: bool,
+    },
+    #[serde(rename = "ChapterStartEvent")]
+    ChapterStart {
+        #[serde(rename = "guildId")]
+        guild_id: String,
+        track: Track,
+        #[serde(rename = "chapterIndex")]
+        chapter_index: usize,
+        chapter: super::Chapter,
+    },
+    #[serde(rename = "QueueUpdateEvent")]
+    QueueUpdate {
+        #[serde(rename = "guildId")]
+        guild_id: String,
+        #[serde(flatten)]
+        update: QueueUpdate,
+    },
+}
+
+/// A change to a player's queue
+///
+/// The parts are applied in field order: the queue is cleared, tracks are
+/// removed, moved and finally added. The indices of each list are applied in
+/// order, so every index refers to the queue as left by the previous one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct QueueUpdate {
+    /// Incremented by one for every update of the queue
+    pub version: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cleared: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moved: Vec<QueueMove>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<QueuedTrack>,
+}
+
+/// A track moved within a queue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueueMove {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// A track inserted into a queue at `index`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueuedTrack {
+    pub index: usize,
+    pub track: Track,
 }
 
 /// REST API request/response types
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct UpdatePlayerRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub track: Option<TrackRequest>,
@@ -78,12 +148,13 @@ pub struct UpdatePlayerRequest {
     #[serde(rename = "endTime", skip_serializing_if = "Option::is_none")]
     pub end_time: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub volume: Option<u8>,
+    pub volume: Option<u16>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub paused: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filters: Option<super::Filters>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "rest-api", schemars(with = "Option<VoiceState>"))]
     pub voice: Option<super::VoiceState>,
     /// Queue management options
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -93,6 +164,7 @@ pub struct UpdatePlayerRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum TrackRequest {
     Encoded { encoded: String },
@@ -100,13 +172,23 @@ pub enum TrackRequest {
     Null,
 }
 
+/// Outcome of one guild in a batch player update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum PlayerUpdateResult {
+    Player(Box<Player>),
+    Error(super::ErrorResponse),
+}
+
 /// Player response structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Player {
     #[serde(rename = "guildId")]
     pub guild_id: String,
     pub track: Option<Track>,
-    pub volume: u8,
+    pub volume: u16,
     pub paused: bool,
     pub state: PlayerState,
     pub voice: VoiceState,
@@ -119,6 +201,7 @@ pub struct Player {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct VoiceState {
     pub token: String,
     pub endpoint: String,
@@ -134,18 +217,105 @@ pub struct Players {
 
 /// Session update request
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct SessionUpdateRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resuming: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
+    /// Replaces the messages the session receives
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription: Option<EventSubscription>,
 }
 
 /// Session response
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "rest-api", schemars(rename = "Session"))]
 pub struct SessionResponse {
     pub resuming: bool,
     pub timeout: u64,
+    #[serde(default)]
+    pub subscription: EventSubscription,
+}
+
+/// Messages a session receives over its WebSocket
+///
+/// Unset fields don't filter anything, so the default subscription receives
+/// every message like a regular Lavalink session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EventSubscription {
+    /// Message types to send: `playerUpdate`, `stats` or an event type such as `TrackStartEvent`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "rest-api",
+        schemars(schema_with = "super::schema::event_types")
+    )]
+    pub events: Option<Vec<String>>,
+    /// Guilds whose player updates and events are sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guilds: Option<Vec<String>>,
+    /// Minimum milliseconds between two `playerUpdate`s of the same guild
+    ///
+    /// Updates are only skipped, so this cannot make them more frequent than
+    /// the player's own interval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_update_interval: Option<u64>,
+}
+
+impl EventSubscription {
+    /// Message types a session can subscribe to
+    pub const TYPES: [&'static str; 11] = [
+        "playerUpdate",
+        "stats",
+        "TrackStartEvent",
+        "TrackEndEvent",
+        "TrackExceptionEvent",
+        "TrackStuckEvent",
+        "WebSocketClosedEvent",
+        "StreamTitleEvent",
+        "LyricsLineEvent",
+        "ChapterStartEvent",
+        "QueueUpdateEvent",
+    ];
+
+    /// Check that every subscribed type exists
+    pub fn validate(&self) -> Result<(), String> {
+        let unknown: Vec<&str> = self
+            .events
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|name| !Self::TYPES.contains(name))
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unknown event types: {}. Valid types are: {}",
+                unknown.join(", "),
+                Self::TYPES.join(", ")
+            ))
+        }
+    }
+
+    /// Whether a message matches the subscribed types and guilds
+    ///
+    /// The `playerUpdate` interval is enforced by the sender, which knows when
+    /// the last update of a guild went out.
+    pub fn allows(&self, message: &Message) -> bool {
+        let type_allowed = self
+            .events
+            .as_ref()
+            .is_none_or(|events| events.iter().any(|name| name == message.type_name()));
+        let guild_allowed = match (&self.guilds, message.guild_id()) {
+            (Some(guilds), Some(guild_id)) => guilds.iter().any(|id| id == guild_id),
+            _ => true,
+        };
+        type_allowed && guild_allowed
+    }
 }
 
 /// Player response for session endpoints
@@ -154,7 +324,7 @@ pub struct PlayerResponse {
     #[serde(rename = "guildId")]
     pub guild_id: String,
     pub track: Option<Track>,
-    pub volume: u8,
+    pub volume: u16,
     pub paused: bool,
     pub state: PlayerState,
     pub voice: VoiceState,
@@ -175,6 +345,7 @@ pub struct DecodeTracksRequest {
 
 /// Repeat mode for queue management
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum RepeatMode {
     #[default]
@@ -183,8 +354,30 @@ pub enum RepeatMode {
     Queue,
 }
 
+/// How tracks added to a player's queue are ordered
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum QueueMode {
+    /// Tracks play in the order they were added
+    #[default]
+    Linear,
+    /// Tracks are interleaved round-robin by requester, the `userData` value under `key`
+    Fair {
+        key: String,
+        /// How many tracks a single requester may have queued
+        #[serde(
+            rename = "maxPerRequester",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )]
+        max_per_requester: Option<usize>,
+    },
+}
+
 /// Queue management request structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct AddToQueueRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub track: Option<Track>,
@@ -197,17 +390,52 @@ pub struct AddToQueueRequest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct MoveTrackRequest {
     pub from: usize,
     pub to: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "rest-api", schemars(rename = "Queue"))]
 pub struct QueueResponse {
+    /// Queued tracks in the order they will play
     pub tracks: Vec<Track>,
     pub length: usize,
     pub repeat: RepeatMode,
     pub shuffle: bool,
+    #[serde(default)]
+    pub mode: QueueMode,
+    /// Version of the last `QueueUpdateEvent` the queue reflects
+    #[serde(default)]
+    pub version: u64,
+}
+
+/// Per-player `playerUpdate` interval request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+pub struct UpdateIntervalRequest {
+    /// Milliseconds between two `playerUpdate`s; `null` uses the node's interval
+    pub interval: Option<u64>,
+}
+
+/// Track history query parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    pub page: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// A page of a player's track history, most recent first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "rest-api", schemars(rename = "History"))]
+pub struct HistoryResponse {
+    pub tracks: Vec<Track>,
+    pub page: usize,
+    pub limit: usize,
+    pub total: usize,
 }
 
 /// Load tracks query parameters
@@ -247,6 +475,25 @@ impl Message {
     pub fn event(event: Event) -> Self {
         Message::Event(Box::new(event))
     }
+
+    /// The `op` of the message, or the event type for events
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Message::Ready { .. } => "ready",
+            Message::Stats(_) => "stats",
+            Message::PlayerUpdate { .. } => "playerUpdate",
+            Message::Event(event) => event.type_name(),
+        }
+    }
+
+    /// The guild a message is about, if any
+    pub fn guild_id(&self) -> Option<&str> {
+        match self {
+            Message::Ready { .. } | Message::Stats(_) => None,
+            Message::PlayerUpdate { guild_id, .. } => Some(guild_id),
+            Message::Event(event) => Some(event.guild_id()),
+        }
+    }
 }
 
 impl Event {
@@ -276,4 +523,84 @@ impl Event {
             by_remote,
         }
     }
+
+    /// Create a stream title event
+    pub fn stream_title(
+        guild_id: String,
+        track: Track,
+        title: String,
+        url: Option<String>,
+    ) -> Self {
+        Event::StreamTitle {
+            guild_id,
+            track,
+            title,
+            url,
+        }
+    }
+
+    pub fn lyrics_line(
+        guild_id: String,
+        track: Track,
+        line_index: usize,
+        line: crate::lyrics::LyricsLine,
+        skipped: bool,
+    ) -> Self {
+        Event::LyricsLine {
+            guild_id,
+            track,
+            line_index,
+            line,
+            skipped,
+        }
+    }
+
+    pub fn chapter_start(
+        guild_id: String,
+        track: Track,
+        chapter_index: usize,
+        chapter: super::Chapter,
+    ) -> Self {
+        Event::ChapterStart {
+            guild_id,
+            track,
+            chapter_index,
+            chapter,
+        }
+    }
+
+    /// Create a queue update event
+    pub fn queue_update(guild_id: String, update: QueueUpdate) -> Self {
+        Event::QueueUpdate { guild_id, update }
+    }
+
+    /// The `type` the event is serialized with
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Event::TrackStart { .. } => "TrackStartEvent",
+            Event::TrackEnd { .. } => "TrackEndEvent",
+            Event::TrackException { .. } => "TrackExceptionEvent",
+            Event::TrackStuck { .. } => "TrackStuckEvent",
+            Event::WebSocketClosed { .. } => "WebSocketClosedEvent",
+            Event::StreamTitle { .. } => "StreamTitleEvent",
+            Event::LyricsLine { .. } => "LyricsLineEvent",
+            Event::ChapterStart { .. } => "ChapterStartEvent",
+            Event::QueueUpdate { .. } => "QueueUpdateEvent",
+        }
+    }
+
+    /// The guild the event happened in
+    pub fn guild_id(&self) -> &str {
+        match self {
+            Event::TrackStart { guild_id, .. }
+            | Event::TrackEnd { guild_id, .. }
+            | Event::TrackException { guild_id, .. }
+            | Event::TrackStuck { guild_id, .. }
+            | Event::WebSocketClosed { guild_id, .. }
+            | Event::StreamTitle { guild_id, .. }
+            | Event::LyricsLine { guild_id, .. }
+            | Event::ChapterStart { guild_id, .. }
+            | Event::QueueUpdate { guild_id, .. } => guild_id,
+        }
+    }
 }
@@ -1,19 +1,23 @@
-#[cfg(feature = "rest-api")]
+#[cfg(any(feature = "rest-api", feature = "audio-sources"))]
 use base64::{engine::general_purpose, Engine};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 #[cfg(any(feature = "plugins", feature = "rest-api"))]
 use std::collections::HashMap;
 
+pub mod chapters;
 pub mod filters;
 pub mod info;
 pub mod messages;
 pub mod player;
+#[cfg(feature = "rest-api")]
+pub mod schema;
 pub mod session;
 
 #[cfg(test)]
 mod tests;
 
+pub use chapters::*;
 pub use filters::*;
 pub use info::*;
 #[cfg(any(feature = "websocket", feature = "discord"))]
@@ -70,6 +74,7 @@ pub type Timestamp = DateTime<Utc>;
 
 /// Track information structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Track {
     pub encoded: String,
     pub info: TrackInfo,
@@ -82,6 +87,7 @@ pub struct Track {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct TrackInfo {
     pub identifier: String,
     #[serde(rename = "isSeekable", default)]
@@ -104,6 +110,16 @@ pub struct TrackInfo {
 }
 
 impl Track {
+    /// Encode the track into the base64 form accepted by [`Track::decode`]
+    #[cfg(any(feature = "rest-api", feature = "audio-sources"))]
+    pub fn encode(&self) -> String {
+        let track = Track {
+            encoded: String::new(),
+            ..self.clone()
+        };
+        general_purpose::STANDARD.encode(serde_json::to_vec(&track).unwrap_or_default())
+    }
+
     /// Decode a track from a base64 string
     #[cfg(feature = "rest-api")]
     pub fn decode(encoded: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
@@ -113,7 +129,11 @@ impl Track {
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
 
         // Try to decode as JSON first (our format)
-        if let Ok(track) = serde_json::from_slice::<Track>(&decoded_bytes) {
+        if let Ok(mut track) = serde_json::from_slice::<Track>(&decoded_bytes) {
+            // Encoded tracks leave out their own encoded form, which is the input itself
+            if track.encoded.is_empty() {
+                track.encoded = encoded.to_string();
+            }
             return Ok(track);
         }
 
@@ -158,6 +178,7 @@ impl Track {
 
 /// Exception information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Exception {
     pub message: Option<String>,
     pub severity: Severity,
@@ -165,6 +186,7 @@ pub struct Exception {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Common,
@@ -174,6 +196,7 @@ pub enum Severity {
 
 /// Load type enumeration for track loading operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum LoadType {
     Track,
@@ -185,6 +208,7 @@ pub enum LoadType {
 
 /// Load result data variants
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum LoadResultData {
     Track(Box<Track>),
@@ -195,6 +219,7 @@ pub enum LoadResultData {
 
 /// Load result for track loading operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct LoadResult {
     #[serde(rename = "loadType")]
     pub load_type: LoadType,
@@ -202,6 +227,7 @@ pub struct LoadResult {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Playlist {
     pub info: PlaylistInfo,
     #[cfg(feature = "plugins")]
@@ -211,6 +237,7 @@ pub struct Playlist {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct PlaylistInfo {
     pub name: String,
     #[serde(rename = "selectedTrack")]
@@ -228,6 +255,7 @@ pub struct VoiceState {
 
 /// Statistics information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Stats {
     pub players: u32,
     #[serde(rename = "playingPlayers")]
@@ -237,9 +265,13 @@ pub struct Stats {
     pub cpu: Cpu,
     #[serde(rename = "frameStats")]
     pub frame_stats: Option<FrameStats>,
+    /// Only sent when client limits are configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejections: Option<RejectionStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Memory {
     pub free: u64,
     pub used: u64,
@@ -248,6 +280,7 @@ pub struct Memory {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct Cpu {
     pub cores: u32,
     #[serde(rename = "systemLoad")]
@@ -257,12 +290,26 @@ pub struct Cpu {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct FrameStats {
     pub sent: u32,
     pub nulled: u32,
     pub deficit: u32,
 }
 
+/// Requests refused by the client limits since startup
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RejectionStats {
+    pub load_tracks: u64,
+    pub player_updates: u64,
+    /// Players refused by the per-session cap
+    pub players: u64,
+    /// Tracks refused by the queue length cap
+    pub queue: u64,
+}
+
 /// Route planner status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutePlannerStatus {
@@ -321,13 +368,17 @@ pub struct FailingAddress {
 
 /// Error response structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "rest-api", schemars(rename = "Error"))]
 pub struct ErrorResponse {
     pub timestamp: u64,
     pub status: u16,
     pub error: String,
+    /// Only sent when the request asked for it with `?trace=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<String>,
     pub message: Option<String>,
     pub path: String,
-    pub trace: Option<String>,
 }
 
 impl ErrorResponse {
@@ -351,6 +402,7 @@ pub struct LoadTracksQuery {
 
 /// Request body for decoding tracks
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "rest-api", derive(schemars::JsonSchema))]
 pub struct DecodeTracksRequest {
     pub tracks: Vec<String>,
 }
@@ -365,7 +417,7 @@ pub struct UpdatePlayerRequest {
     #[serde(rename = "endTime", skip_serializing_if = "Option::is_none")]
     pub end_time: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub volume: Option<u8>,
+    pub volume: Option<u16>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub paused: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -0,0 +1,122 @@
+//! OpenAPI schemas of the protocol types
+//!
+//! The protocol types derive [`JsonSchema`] from their serde attributes, so
+//! the schemas follow the JSON the server actually sends. [`Schemas`] collects
+//! them into `components.schemas` of the document served at `/v4/openapi.json`.
+//! Types with hand-written serde impls describe themselves here.
+
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use schemars::{json_schema, JsonSchema, Schema};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+
+use super::messages::EventSubscription;
+use super::{Omissible, PlayerState, Plugin, Plugins};
+
+/// The component schemas of a document
+pub struct Schemas(SchemaGenerator);
+
+impl Default for Schemas {
+    fn default() -> Self {
+        let settings = SchemaSettings::draft2020_12()
+            .for_serialize()
+            .with(|settings| {
+                settings.definitions_path = "/components/schemas".into();
+                settings.meta_schema = None;
+            });
+        Self(settings.into_generator())
+    }
+}
+
+impl Schemas {
+    /// Add a type and return its schema, a reference for named types
+    pub fn add<T: JsonSchema>(&mut self) -> Value {
+        self.0.subschema_for::<T>().to_value()
+    }
+
+    pub fn into_value(mut self) -> Value {
+        Value::Object(self.0.take_definitions(true))
+    }
+}
+
+pub fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+pub fn integer() -> Value {
+    json!({ "type": "integer" })
+}
+
+pub fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// An object with arbitrary keys mapping to `values`
+pub fn map(values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values })
+}
+
+/// A JSON object without a fixed shape
+pub fn any_object() -> Value {
+    json!({ "type": "object" })
+}
+
+/// The `events` of an [`EventSubscription`], limited to the known types
+pub fn event_types(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "array",
+        "items": { "type": "string", "enum": EventSubscription::TYPES },
+    })
+}
+
+/// Serialized like the value it holds, or `null`; omitted values are skipped
+impl<T: JsonSchema> JsonSchema for Omissible<T> {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        Option::<T>::schema_name()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Option::<T>::json_schema(generator)
+    }
+}
+
+impl JsonSchema for PlayerState {
+    fn schema_name() -> Cow<'static, str> {
+        "PlayerState".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "required": ["time", "position", "connected", "ping"],
+            "properties": {
+                "time": {
+                    "type": "integer",
+                    "description": "Unix timestamp in milliseconds",
+                },
+                "position": { "type": "integer" },
+                "connected": { "type": "boolean" },
+                "ping": { "type": "integer" },
+            },
+        })
+    }
+}
+
+/// Serialized as the list of plugins itself
+impl JsonSchema for Plugins {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "Plugins".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        generator.subschema_for::<Vec<Plugin>>()
+    }
+}
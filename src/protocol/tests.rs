@@ -99,6 +99,43 @@ mod info_tests {
         assert_eq!(git_info.commit, "abc123def456");
         assert_eq!(git_info.commit_time, 1_700_000_000_000);
     }
+
+    #[test]
+    fn test_info_reflects_runtime_capabilities() {
+        let filters = crate::config::FiltersConfig {
+            volume: None,
+            equalizer: Some(true),
+            karaoke: Some(false),
+            timescale: None,
+            tremolo: None,
+            vibrato: None,
+            distortion: None,
+            rotation: None,
+            channel_mix: None,
+            low_pass: Some(false),
+        };
+        let info = Info::new().with_capabilities(
+            vec!["youtube".to_string(), "http".to_string()],
+            filters.enabled_filters(),
+        );
+
+        assert!(info.build_time > 0);
+        assert!(!info.git.commit.is_empty());
+        assert_eq!(info.source_managers, ["youtube", "http"]);
+        assert_eq!(info.filters.len(), FILTERS.len() - 2);
+        assert!(!info.filters.contains(&"karaoke".to_string()));
+        assert!(!info.filters.contains(&"lowPass".to_string()));
+
+        let with_plugins = info.with_plugins(vec![Plugin {
+            name: "example".to_string(),
+            version: "1.0.0".to_string(),
+        }]);
+        assert!(info.plugins.plugins.is_empty());
+        assert_eq!(
+            serde_json::to_value(&with_plugins).unwrap()["plugins"],
+            serde_json::json!([{ "name": "example", "version": "1.0.0" }])
+        );
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +217,34 @@ mod track_tests {
         assert_eq!(track.encoded, deserialized.encoded);
         assert_eq!(track.info.identifier, deserialized.info.identifier);
     }
+
+    #[test]
+    fn test_track_encode_round_trip() {
+        let mut track = Track {
+            encoded: String::new(),
+            info: TrackInfo {
+                identifier: "https://example.com/track".to_string(),
+                is_seekable: true,
+                author: "Example".to_string(),
+                length: 1000,
+                is_stream: false,
+                position: 0,
+                title: "Track".to_string(),
+                uri: Some("https://example.com/track".to_string()),
+                artwork_url: None,
+                isrc: None,
+                source_name: "http".to_string(),
+            },
+            plugin_info: HashMap::from([("streamUrl".to_string(), serde_json::json!("x"))]),
+            user_data: HashMap::new(),
+        };
+        track.encoded = track.encode();
+
+        // Decoding restores the encoded form along with everything else
+        let decoded = Track::decode(&track.encoded).expect("encoded track decodes");
+        assert_eq!(decoded, track);
+        assert_eq!(decoded.encode(), track.encoded);
+    }
 }
 
 #[cfg(test)]
@@ -287,6 +352,40 @@ mod message_tests {
             _ => panic!("Expected TrackStartEvent"),
         }
     }
+
+    #[test]
+    fn test_event_subscription_filters_messages() {
+        let subscription: EventSubscription = serde_json::from_str(
+            r#"{"events": ["TrackStartEvent", "TrackEndEvent"], "guilds": ["1"]}"#,
+        )
+        .expect("Failed to deserialize subscription");
+        assert!(subscription.validate().is_ok());
+
+        let start = |guild_id: &str| {
+            Message::event(Event::track_start(
+                guild_id.to_string(),
+                create_mock_track(),
+            ))
+        };
+        assert!(subscription.allows(&start("1")));
+        assert!(!subscription.allows(&start("2")));
+
+        let state = PlayerState {
+            time: chrono::Utc::now(),
+            position: 0,
+            connected: true,
+            ping: 0,
+        };
+        let update = Message::player_update("1".to_string(), state);
+        assert!(!subscription.allows(&update));
+        assert!(EventSubscription::default().allows(&update));
+
+        let typo = EventSubscription {
+            events: Some(vec!["TrackStart".to_string()]),
+            ..Default::default()
+        };
+        assert!(typo.validate().is_err());
+    }
 }
 
 #[cfg(test)]
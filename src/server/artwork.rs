@@ -0,0 +1,611 @@
+// Artwork proxy for Lavalink REST API - /v4/artwork/{id}
+//
+// When proxying is enabled, track artwork URLs are rewritten to point at this
+// node so clients never contact third-party image hosts directly. The endpoint
+// needs the Authorization header like any other, so proxying is opt-in.
+// Artwork ids encode where the image comes from: `l` ids reference a local
+// audio file with embedded cover art, `r` ids reference a remote image on one
+// of the allowed hosts. Ids are signed with an HMAC keyed on the node
+// password, so only ids this node handed out are served.
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use ring::hmac;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use super::{ApiError, AppState};
+use crate::config::ArtworkConfig;
+use crate::protocol::{LoadResult, LoadResultData, Track};
+
+/// Image hosts used by the built-in sources
+pub const DEFAULT_ALLOWED_HOSTS: [&str; 13] = [
+    "ytimg.com",
+    "ggpht.com",
+    "googleusercontent.com",
+    "sndcdn.com",
+    "bcbits.com",
+    "scdn.co",
+    "spotifycdn.com",
+    "mzstatic.com",
+    "dzcdn.net",
+    "nimg.jp",
+    "nicovideo.jp",
+    "jtvnw.net",
+    "vimeocdn.com",
+];
+
+const DEFAULT_CACHE_SIZE_MB: usize = 64;
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+const MIN_SIZE: u32 = 16;
+const MAX_SIZE: u32 = 2048;
+
+/// Where an artwork id points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtworkRef {
+    /// Cover art embedded in a local audio file
+    Local(String),
+    /// A remote image URL
+    Remote(String),
+}
+
+/// Build the signed artwork id for a track artwork URL
+pub fn artwork_id(url: &str, key: &hmac::Key) -> Option<String> {
+    let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    let reference = if let Some(path) = url.strip_prefix("file://") {
+        format!("l{}", engine.encode(path))
+    } else if url.starts_with("https://") || url.starts_with("http://") {
+        format!("r{}", engine.encode(url))
+    } else {
+        return None;
+    };
+    let signature = hmac::sign(key, reference.as_bytes());
+    Some(format!("{reference}.{}", engine.encode(signature.as_ref())))
+}
+
+/// Decode an artwork id built by [`artwork_id`], checking its signature
+pub fn parse_artwork_id(id: &str, key: &hmac::Key) -> Option<ArtworkRef> {
+    let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    let (reference, signature) = id.rsplit_once('.')?;
+    hmac::verify(key, reference.as_bytes(), &engine.decode(signature).ok()?).ok()?;
+
+    let (kind, encoded) = reference.split_at_checked(1)?;
+    let decoded = String::from_utf8(engine.decode(encoded).ok()?).ok()?;
+    match kind {
+        "l" => Some(ArtworkRef::Local(decoded)),
+        "r" => Some(ArtworkRef::Remote(decoded)),
+        _ => None,
+    }
+}
+
+/// Key artwork ids are signed with
+///
+/// Nodes without a password get a random key, so their ids only live as long
+/// as the process.
+fn signing_key(secret: &str) -> hmac::Key {
+    if !secret.is_empty() {
+        return hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    }
+    hmac::Key::generate(hmac::HMAC_SHA256, &ring::rand::SystemRandom::new())
+        .expect("the system random number generator is available")
+}
+
+/// Query parameters for the artwork endpoint
+#[derive(Debug, Deserialize)]
+pub struct ArtworkQuery {
+    /// Bounding box in pixels; the image keeps its aspect ratio
+    pub size: Option<u32>,
+}
+
+#[derive(Clone)]
+struct CachedImage {
+    content_type: String,
+    data: Arc<Vec<u8>>,
+    etag: String,
+}
+
+/// LRU cache of original and resized images, bounded by their total size
+struct ImageCache {
+    capacity_bytes: usize,
+    used_bytes: usize,
+    images: HashMap<String, CachedImage>,
+    order: VecDeque<String>,
+}
+
+impl ImageCache {
+    fn new(capacity_bytes: usize) -> Self {
+        Self {
+            capacity_bytes,
+            used_bytes: 0,
+            images: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<CachedImage> {
+        let image = self.images.get(key)?.clone();
+        self.order.retain(|k| k != key);
+        self.order.push_back(key.to_string());
+        Some(image)
+    }
+
+    fn insert(&mut self, key: String, image: CachedImage) {
+        let size = image.data.len();
+        if size > self.capacity_bytes {
+            return;
+        }
+        if let Some(previous) = self.images.insert(key.clone(), image) {
+            self.used_bytes -= previous.data.len();
+            self.order.retain(|k| *k != key);
+        }
+        self.used_bytes += size;
+        self.order.push_back(key);
+        while self.used_bytes > self.capacity_bytes {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.images.remove(&oldest) {
+                self.used_bytes -= evicted.data.len();
+            }
+        }
+    }
+}
+
+/// Rewrites artwork URLs and serves the artwork they point to
+pub struct ArtworkService {
+    proxy: bool,
+    public_url: String,
+    allowed_hosts: Vec<String>,
+    key: hmac::Key,
+    client: reqwest::Client,
+    cache: Mutex<ImageCache>,
+}
+
+impl ArtworkService {
+    /// Create the service, signing artwork ids with `secret`
+    pub fn new(config: Option<&ArtworkConfig>, secret: &str) -> Self {
+        let allowed_hosts = config
+            .and_then(|c| c.allowed_hosts.clone())
+            .unwrap_or_else(|| {
+                DEFAULT_ALLOWED_HOSTS
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect()
+            });
+
+        // Redirects must stay on allowed hosts, or the proxy could be bounced
+        // to internal addresses
+        let redirect_hosts = allowed_hosts.clone();
+        let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+            let allowed = attempt
+                .url()
+                .host_str()
+                .is_some_and(|host| host_matches(host, &redirect_hosts));
+            if attempt.previous().len() >= 5 || !allowed {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        });
+
+        Self {
+            proxy: config.and_then(|c| c.proxy).unwrap_or(false),
+            public_url: config
+                .and_then(|c| c.public_url.as_deref())
+                .unwrap_or_default()
+                .trim_end_matches('/')
+                .to_string(),
+            allowed_hosts,
+            key: signing_key(secret),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .user_agent("Lavalink-Rust/4.0")
+                .redirect(redirect_policy)
+                .build()
+                .unwrap_or_default(),
+            cache: Mutex::new(ImageCache::new(
+                config
+                    .and_then(|c| c.cache_size_mb)
+                    .unwrap_or(DEFAULT_CACHE_SIZE_MB)
+                    .saturating_mul(1024 * 1024),
+            )),
+        }
+    }
+
+    /// Point a track's artwork URL at the artwork endpoint, if proxying is enabled
+    ///
+    /// Tracks whose artwork already points at the endpoint are left as they are.
+    pub fn rewrite_track(&self, track: &mut Track) {
+        if !self.proxy {
+            return;
+        }
+        let Some(url) = track.info.artwork_url.as_deref() else {
+            return;
+        };
+        let endpoint = format!("{}/v4/artwork/", self.public_url);
+        if url.starts_with(&endpoint) {
+            return;
+        }
+        if let Some(id) = artwork_id(url, &self.key) {
+            track.info.artwork_url = Some(format!("{endpoint}{id}"));
+        }
+    }
+
+    /// Rewrite the artwork URLs of every track in a load result
+    pub fn rewrite_load_result(&self, result: &mut LoadResult) {
+        match result.data.as_mut() {
+            Some(LoadResultData::Track(track)) => self.rewrite_track(track),
+            Some(LoadResultData::Search(tracks)) => tracks
+                .iter_mut()
+                .for_each(|track| self.rewrite_track(track)),
+            Some(LoadResultData::Playlist(playlist)) => playlist
+                .tracks
+                .iter_mut()
+                .for_each(|track| self.rewrite_track(track)),
+            _ => {}
+        }
+    }
+
+    fn is_allowed_host(&self, url: &str) -> bool {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return false;
+        };
+        let Some(host) = parsed.host_str() else {
+            return false;
+        };
+        host_matches(host, &self.allowed_hosts)
+    }
+
+    fn cached(&self, key: &str) -> Option<CachedImage> {
+        self.cache.lock().ok()?.get(key)
+    }
+
+    fn store(&self, key: String, image: CachedImage) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(key, image);
+        }
+    }
+
+    /// Load the original image for an artwork reference
+    async fn original(&self, id: &str, state: &AppState) -> Result<Option<CachedImage>> {
+        if let Some(image) = self.cached(id) {
+            return Ok(Some(image));
+        }
+
+        let Some(reference) = parse_artwork_id(id, &self.key) else {
+            return Ok(None);
+        };
+
+        let (content_type, data) = match reference {
+            ArtworkRef::Local(path) => {
+                let Some(local) = state.audio_manager.local_source() else {
+                    return Ok(None);
+                };
+                let Some(artwork) = local.read_artwork(&path).await? else {
+                    return Ok(None);
+                };
+                (artwork.media_type, artwork.data)
+            }
+            ArtworkRef::Remote(url) => {
+                if !self.is_allowed_host(&url) {
+                    debug!("Refusing to proxy artwork from {}", url);
+                    return Ok(None);
+                }
+                self.fetch_remote(&url).await?
+            }
+        };
+
+        let image = CachedImage {
+            etag: etag_for(&data),
+            content_type,
+            data: Arc::new(data),
+        };
+        self.store(id.to_string(), image.clone());
+        Ok(Some(image))
+    }
+
+    async fn fetch_remote(&self, url: &str) -> Result<(String, Vec<u8>)> {
+        let mut response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {} fetching artwork", response.status()));
+        }
+
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !content_type.starts_with("image/") {
+            return Err(anyhow!("Artwork is not an image: {content_type}"));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length as usize > MAX_IMAGE_BYTES)
+        {
+            return Err(anyhow!("Artwork is too large"));
+        }
+
+        // The length may be missing or wrong, so the body is read in chunks
+        // and abandoned once it grows past the limit
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if data.len() + chunk.len() > MAX_IMAGE_BYTES {
+                return Err(anyhow!("Artwork is too large"));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        Ok((content_type, data))
+    }
+
+    /// Load an artwork image, resized to fit `size` when given
+    async fn image(
+        &self,
+        id: &str,
+        size: Option<u32>,
+        state: &AppState,
+    ) -> Result<Option<CachedImage>> {
+        let Some(original) = self.original(id, state).await? else {
+            return Ok(None);
+        };
+        let Some(size) = size else {
+            return Ok(Some(original));
+        };
+
+        let key = format!("{id}@{size}");
+        if let Some(image) = self.cached(&key) {
+            return Ok(Some(image));
+        }
+
+        let source = Arc::clone(&original.data);
+        let (content_type, data) =
+            tokio::task::spawn_blocking(move || resize_image(&source, size)).await??;
+        let image = CachedImage {
+            etag: etag_for(&data),
+            content_type,
+            data: Arc::new(data),
+        };
+        self.store(key, image.clone());
+        Ok(Some(image))
+    }
+}
+
+/// Scale an image down to fit a square of `size` pixels
+///
+/// PNGs stay PNGs to keep transparency, everything else is re-encoded as JPEG.
+pub fn resize_image(data: &[u8], size: u32) -> Result<(String, Vec<u8>)> {
+    let format = image::guess_format(data)?;
+    let decoded = image::load_from_memory_with_format(data, format)?;
+    let resized = if decoded.width() > size || decoded.height() > size {
+        decoded.thumbnail(size, size)
+    } else {
+        decoded
+    };
+
+    let mut output = Cursor::new(Vec::new());
+    let content_type = if format == image::ImageFormat::Png {
+        resized.write_to(&mut output, image::ImageFormat::Png)?;
+        "image/png"
+    } else {
+        resized
+            .into_rgb8()
+            .write_to(&mut output, image::ImageFormat::Jpeg)?;
+        "image/jpeg"
+    };
+    Ok((content_type.to_string(), output.into_inner()))
+}
+
+fn host_matches(host: &str, allowed_hosts: &[String]) -> bool {
+    allowed_hosts
+        .iter()
+        .any(|allowed| host == allowed || host.ends_with(&format!(".{allowed}")))
+}
+
+fn etag_for(data: &[u8]) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Artwork handler - /v4/artwork/{id}
+pub async fn artwork_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<ArtworkQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if query
+        .size
+        .is_some_and(|size| !(MIN_SIZE..=MAX_SIZE).contains(&size))
+    {
+        return ApiError::bad_request(format!("size must be between {MIN_SIZE} and {MAX_SIZE}"))
+            .into_response();
+    }
+
+    let image = match state.artwork.image(&id, query.size, &state).await {
+        Ok(Some(image)) => image,
+        Ok(None) => return ApiError::not_found("Artwork not found").into_response(),
+        Err(e) => {
+            warn!("Failed to load artwork {}: {}", id, e);
+            return ApiError::bad_gateway(format!("Failed to load artwork: {e}")).into_response();
+        }
+    };
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .any(|tag| tag.trim() == image.etag || tag.trim() == "*")
+        });
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (StatusCode::OK, image.data.as_ref().clone()).into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(etag) = HeaderValue::from_str(&image.etag) {
+        response_headers.insert(header::ETAG, etag);
+    }
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=86400"),
+    );
+    if !not_modified {
+        if let Ok(content_type) = HeaderValue::from_str(&image.content_type) {
+            response_headers.insert(header::CONTENT_TYPE, content_type);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::TrackInfo;
+
+    #[test]
+    fn test_artwork_id_round_trip() {
+        let key = signing_key("youshallnotpass");
+        let id = artwork_id("file:///music/song.flac", &key).unwrap();
+        assert!(id.starts_with('l'));
+        assert_eq!(
+            parse_artwork_id(&id, &key),
+            Some(ArtworkRef::Local("/music/song.flac".to_string()))
+        );
+
+        let url = "https://i.ytimg.com/vi/abc/hqdefault.jpg?x=1&y=2";
+        let id = artwork_id(url, &key).unwrap();
+        assert!(!id.contains(['/', '+', '?', '&']));
+        assert_eq!(
+            parse_artwork_id(&id, &key),
+            Some(ArtworkRef::Remote(url.to_string()))
+        );
+
+        assert_eq!(artwork_id("data:image/png;base64,AAAA", &key), None);
+        assert_eq!(parse_artwork_id("xAAAA", &key), None);
+        assert_eq!(parse_artwork_id("", &key), None);
+    }
+
+    #[test]
+    fn test_artwork_ids_must_be_signed_by_this_node() {
+        let key = signing_key("youshallnotpass");
+        let id = artwork_id("https://i.ytimg.com/vi/abc/hqdefault.jpg", &key).unwrap();
+
+        // Unsigned ids, forged references and other nodes' ids are refused
+        let (reference, signature) = id.rsplit_once('.').unwrap();
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let forged = format!("r{}", engine.encode("http://169.254.169.254/latest"));
+        assert_eq!(parse_artwork_id(reference, &key), None);
+        assert_eq!(
+            parse_artwork_id(&format!("{forged}.{signature}"), &key),
+            None
+        );
+        assert_eq!(parse_artwork_id(&id, &signing_key("another")), None);
+        assert_eq!(parse_artwork_id(&id, &signing_key("")), None);
+    }
+
+    #[test]
+    fn test_rewrite_and_allowed_hosts() {
+        let service = ArtworkService::new(
+            Some(&ArtworkConfig {
+                proxy: Some(true),
+                public_url: Some("https://node.example/".to_string()),
+                ..Default::default()
+            }),
+            "youshallnotpass",
+        );
+
+        let mut track = Track {
+            encoded: String::new(),
+            info: TrackInfo {
+                identifier: "abc".to_string(),
+                is_seekable: true,
+                author: "Author".to_string(),
+                length: 1000,
+                is_stream: false,
+                position: 0,
+                title: "Title".to_string(),
+                uri: None,
+                artwork_url: Some("https://i.ytimg.com/vi/abc/hqdefault.jpg".to_string()),
+                isrc: None,
+                source_name: "youtube".to_string(),
+            },
+            #[cfg(feature = "plugins")]
+            plugin_info: HashMap::new(),
+            #[cfg(feature = "rest-api")]
+            user_data: HashMap::new(),
+        };
+        // Artwork is only proxied when asked to
+        ArtworkService::new(None, "youshallnotpass").rewrite_track(&mut track);
+        assert_eq!(
+            track.info.artwork_url.as_deref(),
+            Some("https://i.ytimg.com/vi/abc/hqdefault.jpg")
+        );
+
+        service.rewrite_track(&mut track);
+        let rewritten = track.info.artwork_url.clone().unwrap();
+        assert!(rewritten.starts_with("https://node.example/v4/artwork/r"));
+
+        // Rewriting again keeps the proxied URL
+        service.rewrite_track(&mut track);
+        assert_eq!(track.info.artwork_url.as_deref(), Some(rewritten.as_str()));
+
+        assert!(service.is_allowed_host("https://i.ytimg.com/vi/abc/hqdefault.jpg"));
+        assert!(service.is_allowed_host("https://ytimg.com/a.jpg"));
+        assert!(!service.is_allowed_host("https://evilytimg.com/a.jpg"));
+        assert!(!service.is_allowed_host("http://169.254.169.254/latest"));
+    }
+
+    #[test]
+    fn test_resize_keeps_aspect_ratio() {
+        let source = image::RgbImage::from_pixel(400, 200, image::Rgb([200, 10, 10]));
+        let mut png = Cursor::new(Vec::new());
+        source.write_to(&mut png, image::ImageFormat::Png).unwrap();
+
+        let (content_type, data) = resize_image(png.get_ref(), 100).unwrap();
+        assert_eq!(content_type, "image/png");
+        let resized = image::load_from_memory(&data).unwrap();
+        assert_eq!((resized.width(), resized.height()), (100, 50));
+
+        // ETags are stable for identical bytes
+        assert_eq!(etag_for(&data), etag_for(&data));
+        assert_ne!(etag_for(&data), etag_for(png.get_ref()));
+    }
+
+    #[test]
+    fn test_image_cache_evicts_least_recently_used() {
+        let image = |size: usize| CachedImage {
+            content_type: "image/png".to_string(),
+            data: Arc::new(vec![0; size]),
+            etag: String::new(),
+        };
+        let mut cache = ImageCache::new(100);
+        cache.insert("a".to_string(), image(40));
+        cache.insert("b".to_string(), image(40));
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), image(40));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.used_bytes, 80);
+
+        // Images larger than the whole cache are not kept
+        cache.insert("d".to_string(), image(101));
+        assert!(cache.get("d").is_none());
+        assert_eq!(cache.used_bytes, 80);
+    }
+}
@@ -1,9 +1,30 @@
-use crate::config::LavalinkConfig;
+use crate::config::{ApiScope, LavalinkConfig};
 use anyhow::{anyhow, Result};
 use axum::http::HeaderMap;
 
+/// The key a request authenticated with
+///
+/// Inserted into the request extensions by the auth middleware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+/// Name of the key behind `lavalink.server.password`
+pub const PASSWORD_KEY_NAME: &str = "password";
+
 /// Authenticate a request using the Authorization header
-pub fn authenticate_request(headers: &HeaderMap, config: &LavalinkConfig) -> Result<()> {
+///
+/// The header is checked against the password and every configured API key.
+/// An empty password is never accepted, so nodes can be limited to API keys.
+pub fn authenticate_request(headers: &HeaderMap, config: &LavalinkConfig) -> Result<ApiKey> {
     let auth_header = headers
         .get("Authorization")
         .ok_or_else(|| anyhow!("Missing Authorization header"))?;
@@ -12,20 +33,83 @@ pub fn authenticate_request(headers: &HeaderMap, config: &LavalinkConfig) -> Res
         .to_str()
         .map_err(|_| anyhow!("Invalid Authorization header"))?;
 
-    let expected_password = &config.lavalink.server.password;
+    let server = &config.lavalink.server;
+    let password = (!server.password.is_empty()).then(|| {
+        (
+            PASSWORD_KEY_NAME,
+            server.password.as_str(),
+            ApiScope::ALL.as_slice(),
+        )
+    });
+    let keys = server
+        .api_keys
+        .iter()
+        .flatten()
+        .filter(|key| !key.key.is_empty())
+        .map(|key| {
+            (
+                key.name.as_str(),
+                key.key.as_str(),
+                key.scopes.as_deref().unwrap_or(&ApiScope::ALL),
+            )
+        });
+
+    // Every key is compared so the time taken doesn't tell which one matched
+    let mut matched = None;
+    for (name, key, scopes) in password.into_iter().chain(keys) {
+        if constant_time_eq(key.as_bytes(), auth_value.as_bytes()) && matched.is_none() {
+            matched = Some(ApiKey {
+                name: name.to_string(),
+                scopes: scopes.to_vec(),
+            });
+        }
+    }
+
+    matched.ok_or_else(|| anyhow!("Invalid password"))
+}
 
-    if auth_value != expected_password {
-        return Err(anyhow!("Invalid password"));
+/// Compare two secrets in time that only depends on the longer one's length
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= usize::from(x ^ y);
     }
+    std::hint::black_box(diff) == 0
+}
 
-    Ok(())
+/// The scope a key needs for a route, `None` for routes any key may use
+///
+/// Routes not listed here, like ones added by plugins, need `admin:plugins`.
+pub fn required_scope(path: &str) -> Option<ApiScope> {
+    let path = path.strip_prefix("/v4").unwrap_or(path);
+    match path.split('/').nth(1).unwrap_or_default() {
+        // `/` is where v3 clients open their WebSocket
+        "" | "websocket" | "sessions" | "filters" => Some(ApiScope::Player),
+        "loadtracks" | "decodetrack" | "decodetracks" | "artwork" => Some(ApiScope::Load),
+        "info" | "version" | "stats" => Some(ApiScope::StatsRead),
+        "plugins" => Some(ApiScope::AdminPlugins),
+        "routeplanner" => Some(ApiScope::AdminRoutePlanner),
+        "openapi.json" => None,
+        _ => Some(ApiScope::AdminPlugins),
+    }
+}
+
+/// The session a `/v4/sessions/{sessionId}/...` route acts on
+pub fn session_in_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/v4/sessions/")?
+        .split('/')
+        .next()
+        .filter(|session_id| !session_id.is_empty())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{
-        FiltersConfig, LavalinkConfig, LavalinkInnerConfig, LavalinkServerConfig, SourcesConfig,
+        ApiKeyConfig, FiltersConfig, LavalinkConfig, LavalinkInnerConfig, LavalinkServerConfig,
+        SourcesConfig,
     };
     use axum::http::HeaderValue;
 
@@ -48,6 +132,11 @@ mod tests {
                         nico: Some(true),
                         http: Some(true),
                         local: Some(false),
+                        podcast: Some(true),
+                        synth: Some(true),
+                        mirroring: None,
+                        local_library: None,
+                        podcast_feeds: None,
                     },
                     filters: FiltersConfig {
                         volume: Some(true),
@@ -77,6 +166,11 @@ mod tests {
                     http_config: None,
                     timeouts: None,
                     discord_bot_token: None,
+                    artwork: None,
+                    persistence: None,
+                    v3_compatibility: None,
+                    api_keys: None,
+                    client_limits: None,
                 },
                 plugins: None,
             },
@@ -112,4 +206,124 @@ mod tests {
 
         assert!(authenticate_request(&headers, &config).is_err());
     }
+
+    fn with_key(headers: &mut HeaderMap, key: &'static str) {
+        headers.insert("Authorization", HeaderValue::from_static(key));
+    }
+
+    #[test]
+    fn test_password_has_every_scope() {
+        let config = create_test_config();
+        let mut headers = HeaderMap::new();
+        with_key(&mut headers, "testpassword");
+
+        let key = authenticate_request(&headers, &config).unwrap();
+        assert_eq!(key.name, PASSWORD_KEY_NAME);
+        assert!(ApiScope::ALL.iter().all(|scope| key.allows(*scope)));
+    }
+
+    #[test]
+    fn test_scoped_api_keys() {
+        let mut config = create_test_config();
+        config.lavalink.server.api_keys = Some(vec![
+            ApiKeyConfig {
+                name: "music-bot".to_string(),
+                key: "bot-key".to_string(),
+                scopes: Some(vec![ApiScope::Player, ApiScope::Load]),
+            },
+            ApiKeyConfig {
+                name: "ops".to_string(),
+                key: "ops-key".to_string(),
+                scopes: None,
+            },
+        ]);
+
+        let mut headers = HeaderMap::new();
+        with_key(&mut headers, "bot-key");
+        let key = authenticate_request(&headers, &config).unwrap();
+        assert_eq!(key.name, "music-bot");
+        assert!(key.allows(ApiScope::Load));
+        assert!(!key.allows(ApiScope::AdminPlugins));
+
+        with_key(&mut headers, "ops-key");
+        let key = authenticate_request(&headers, &config).unwrap();
+        assert_eq!(key.name, "ops");
+        assert!(key.allows(ApiScope::AdminRoutePlanner));
+
+        with_key(&mut headers, "bot-key2");
+        assert!(authenticate_request(&headers, &config).is_err());
+    }
+
+    #[test]
+    fn test_empty_password_is_rejected() {
+        let mut config = create_test_config();
+        config.lavalink.server.password = String::new();
+
+        let mut headers = HeaderMap::new();
+        with_key(&mut headers, "");
+        assert!(authenticate_request(&headers, &config).is_err());
+    }
+
+    #[test]
+    fn test_empty_api_keys_are_rejected() {
+        let mut config = create_test_config();
+        config.lavalink.server.password = String::new();
+        config.lavalink.server.api_keys = Some(vec![ApiKeyConfig {
+            name: "music-bot".to_string(),
+            key: String::new(),
+            scopes: None,
+        }]);
+        assert!(config.validate().is_err());
+
+        let mut headers = HeaderMap::new();
+        with_key(&mut headers, "");
+        assert!(authenticate_request(&headers, &config).is_err());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"youshallnotpass", b"youshallnotpass"));
+        assert!(!constant_time_eq(b"youshallnotpass", b"youshallnotpasS"));
+        assert!(!constant_time_eq(b"youshallnotpass", b"youshallnotpass\0"));
+        assert!(!constant_time_eq(b"", b"a"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(required_scope("/"), Some(ApiScope::Player));
+        assert_eq!(required_scope("/v4/websocket"), Some(ApiScope::Player));
+        assert_eq!(
+            required_scope("/v4/sessions/abc/players/123"),
+            Some(ApiScope::Player)
+        );
+        assert_eq!(required_scope("/v4/loadtracks"), Some(ApiScope::Load));
+        assert_eq!(
+            required_scope("/v4/artwork/rAAAA.BBBB"),
+            Some(ApiScope::Load)
+        );
+        assert_eq!(required_scope("/version"), Some(ApiScope::StatsRead));
+        assert_eq!(
+            required_scope("/v4/plugins/x/reload"),
+            Some(ApiScope::AdminPlugins)
+        );
+        assert_eq!(
+            required_scope("/v4/routeplanner/free/all"),
+            Some(ApiScope::AdminRoutePlanner)
+        );
+        assert_eq!(required_scope("/v4/openapi.json"), None);
+        assert_eq!(
+            required_scope("/v4/someplugin/action"),
+            Some(ApiScope::AdminPlugins)
+        );
+    }
+
+    #[test]
+    fn test_session_in_path() {
+        assert_eq!(session_in_path("/v4/sessions/abc"), Some("abc"));
+        assert_eq!(session_in_path("/v4/sessions/abc/players/123"), Some("abc"));
+        assert_eq!(session_in_path("/v4/sessions"), None);
+        assert_eq!(session_in_path("/v4/sessions/"), None);
+        assert_eq!(session_in_path("/v4/loadtracks"), None);
+    }
 }
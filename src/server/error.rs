@@ -0,0 +1,240 @@
+//! Typed REST API errors, rendered like Lavalink's error responses
+
+use axum::{
+    body::Body,
+    extract::{Query, Request},
+    http::{header, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::error;
+
+use super::limits::{retry_after_secs, LimitError};
+use crate::protocol::ErrorResponse;
+
+/// Errors returned by the REST API handlers
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// The request is malformed or asks for something impossible
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    NotFound(String),
+    /// The endpoint needs a feature this build does not include
+    #[error("{0}")]
+    NotImplemented(String),
+    /// A remote service the request depends on failed
+    #[error("{0}")]
+    BadGateway(String),
+    /// The client is over its rate limit and may retry after the duration
+    #[error("{0}")]
+    TooManyRequests(LimitError),
+    /// Anything else; the cause chain is kept for `?trace=true`
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn not_implemented(message: impl Into<String>) -> Self {
+        Self::NotImplemented(message.into())
+    }
+
+    pub fn bad_gateway(message: impl Into<String>) -> Self {
+        Self::BadGateway(message.into())
+    }
+
+    /// An internal error with a description of what failed
+    pub fn internal(
+        error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+        context: &'static str,
+    ) -> Self {
+        Self::Internal(anyhow::Error::from_boxed(error.into()).context(context))
+    }
+
+    pub fn session_not_found() -> Self {
+        Self::not_found("Session not found")
+    }
+
+    #[cfg(feature = "discord")]
+    pub fn player_not_found() -> Self {
+        Self::not_found("Player not found")
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            Self::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            Self::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The error response for a request to `path`
+    pub fn to_response(&self, path: impl Into<String>, trace: bool) -> ErrorResponse {
+        let status = self.status();
+        let mut response = ErrorResponse::new(
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("Unknown").to_string(),
+            Some(self.to_string()),
+            path.into(),
+        );
+        if trace {
+            response.trace = Some(self.trace());
+        }
+        response
+    }
+
+    /// The full cause chain, and the backtrace when one was captured
+    fn trace(&self) -> String {
+        if let Self::Internal(e) = self {
+            return format!("{e:?}");
+        }
+
+        let mut trace = self.to_string();
+        let mut source = std::error::Error::source(self);
+        if source.is_some() {
+            trace.push_str("\n\nCaused by:");
+        }
+        while let Some(cause) = source {
+            trace.push_str(&format!("\n    {cause}"));
+            source = cause.source();
+        }
+        trace
+    }
+}
+
+impl From<LimitError> for ApiError {
+    fn from(error: LimitError) -> Self {
+        match error {
+            LimitError::RateLimited(_) => Self::TooManyRequests(error),
+            // Caps are only lifted by the client itself, so retrying won't help
+            LimitError::TooManyPlayers(_)
+            | LimitError::QueueFull(_)
+            | LimitError::BatchTooLarge(_) => Self::BadRequest(error.to_string()),
+        }
+    }
+}
+
+/// An error whose response still needs the request's path and trace flag
+#[derive(Clone)]
+struct PendingError(Arc<ApiError>);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let Self::Internal(e) = &self {
+            error!("Request failed: {:?}", e);
+        }
+        let mut response = (self.status(), Json(self.to_response("", false))).into_response();
+        if let Self::TooManyRequests(LimitError::RateLimited(wait)) = &self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after_secs(*wait).into());
+        }
+        response
+            .extensions_mut()
+            .insert(PendingError(Arc::new(self)));
+        response
+    }
+}
+
+#[derive(Deserialize)]
+struct TraceQuery {
+    #[serde(default)]
+    trace: bool,
+}
+
+/// Whether a request asked for stack traces in error responses
+pub fn wants_trace(uri: &Uri) -> bool {
+    Query::<TraceQuery>::try_from_uri(uri).is_ok_and(|Query(query)| query.trace)
+}
+
+/// Middleware completing [`ApiError`] responses with the request path, and
+/// with the trace when the request has `?trace=true`
+pub async fn error_context(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let trace = wants_trace(request.uri());
+
+    let response = next.run(request).await;
+    let Some(PendingError(error)) = response.extensions().get::<PendingError>().cloned() else {
+        return response;
+    };
+
+    let (mut parts, _) = response.into_parts();
+    let body = match serde_json::to_vec(&error.to_response(path, trace)) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to serialize error response: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal_error_trace_has_cause_chain() {
+        let source = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        let error = ApiError::internal(source, "Failed to connect to voice server");
+
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let response = error.to_response("/v4/sessions/a/players/1", false);
+        assert_eq!(response.error, "Internal Server Error");
+        assert_eq!(
+            response.message.as_deref(),
+            Some("Failed to connect to voice server")
+        );
+        assert_eq!(response.trace, None);
+
+        let trace = error
+            .to_response("/v4/sessions/a/players/1", true)
+            .trace
+            .unwrap();
+        assert!(trace.starts_with("Failed to connect to voice server"));
+        assert!(trace.contains("Caused by"));
+        assert!(trace.contains("refused"));
+    }
+
+    #[test]
+    fn test_rate_limited_response_has_retry_after() {
+        let error = ApiError::from(LimitError::RateLimited(std::time::Duration::from_millis(
+            1500,
+        )));
+        let response = error.into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+
+    #[test]
+    fn test_client_error_trace_is_its_message() {
+        let error = ApiError::from(LimitError::QueueFull(3));
+        let response = error.to_response("/v4/sessions/a/players/1/queue", true);
+        assert_eq!(response.trace, response.message);
+    }
+
+    #[test]
+    fn test_trace_query_flag() {
+        assert!(wants_trace(&Uri::from_static("/v4/info?trace=true")));
+        assert!(!wants_trace(&Uri::from_static("/v4/info?trace=false")));
+        assert!(!wants_trace(&Uri::from_static("/v4/info?trace=yes")));
+        assert!(!wants_trace(&Uri::from_static("/v4/info")));
+    }
+}
@@ -0,0 +1,540 @@
+//! Per-client rate limits and per-session caps
+//!
+//! Clients are told apart by the API key and IP address they connect with;
+//! headers such as `User-Id` are chosen by the client, so they would let it
+//! escape its limits. Each client gets a token bucket per [`LimitKind`].
+
+use dashmap::DashMap;
+use std::{
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+use crate::config::{ClientLimitsConfig, TokenBucketConfig};
+use crate::protocol::RejectionStats;
+
+/// Buckets kept before some are dropped, down to half of this
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+/// Requests limited by a token bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    /// Track loading and searches
+    LoadTracks,
+    /// Requests and v3 ops changing players
+    PlayerUpdates,
+}
+
+impl LimitKind {
+    /// The bucket a REST request takes a token from
+    ///
+    /// Batch player updates are charged a token per guild by their handler.
+    pub fn of(method: &axum::http::Method, path: &str) -> Option<Self> {
+        if path == "/v4/loadtracks" {
+            return Some(Self::LoadTracks);
+        }
+        let mut segments = path.strip_prefix("/v4/sessions/")?.split('/').skip(1);
+        let is_player = segments.next() == Some("players") && segments.next().is_some();
+        (is_player && method != axum::http::Method::GET).then_some(Self::PlayerUpdates)
+    }
+}
+
+/// Who a request comes from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ClientId {
+    pub api_key: String,
+    pub ip: Option<IpAddr>,
+}
+
+#[cfg(feature = "rest-api")]
+impl ClientId {
+    /// The client of a request that passed the auth middleware
+    pub fn of(request: &axum::extract::Request) -> Self {
+        Self {
+            api_key: request
+                .extensions()
+                .get::<super::ApiKey>()
+                .map(|key| key.name.clone())
+                .unwrap_or_default(),
+            ip: request
+                .extensions()
+                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+                .map(|info| info.0.ip()),
+        }
+    }
+}
+
+/// Why a request was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LimitError {
+    #[error("Rate limit exceeded, retry in {} seconds", retry_after_secs(*.0))]
+    RateLimited(Duration),
+    #[error("Session already has the maximum of {0} players")]
+    TooManyPlayers(usize),
+    #[error("Queue already has the maximum of {0} tracks")]
+    QueueFull(usize),
+    #[error("Batch updates at most {0} players at once")]
+    BatchTooLarge(u32),
+}
+
+/// Whole seconds to wait, as sent in `Retry-After`
+pub fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs()
+        .saturating_add(u64::from(wait.subsec_nanos() > 0))
+}
+
+/// Requests refused since startup
+#[derive(Debug, Default)]
+pub struct Rejections {
+    load_tracks: AtomicU64,
+    player_updates: AtomicU64,
+    players: AtomicU64,
+    queue: AtomicU64,
+}
+
+impl Rejections {
+    pub fn snapshot(&self) -> RejectionStats {
+        RejectionStats {
+            load_tracks: self.load_tracks.load(Ordering::Relaxed),
+            player_updates: self.player_updates.load(Ordering::Relaxed),
+            players: self.players.load(Ordering::Relaxed),
+            queue: self.queue.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(config: &TokenBucketConfig, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(config.capacity),
+            updated: now,
+        }
+    }
+
+    /// Tokens the bucket holds at `now`
+    fn tokens_at(&self, config: &TokenBucketConfig, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * config.refill_per_second).min(f64::from(config.capacity))
+    }
+
+    fn refill(&mut self, config: &TokenBucketConfig, now: Instant) {
+        self.tokens = self.tokens_at(config, now);
+        self.updated = now;
+    }
+
+    /// Take `count` tokens, or return how long until they are available
+    fn take(
+        &mut self,
+        config: &TokenBucketConfig,
+        now: Instant,
+        count: f64,
+    ) -> Result<(), Duration> {
+        self.refill(config, now);
+        if self.tokens >= count {
+            self.tokens -= count;
+            Ok(())
+        } else {
+            let wait = (count - self.tokens) / config.refill_per_second;
+            Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+        }
+    }
+
+    fn is_full(&self, config: &TokenBucketConfig, now: Instant) -> bool {
+        self.tokens_at(config, now) >= f64::from(config.capacity)
+    }
+}
+
+/// The configured limits and every client's buckets
+#[derive(Debug, Default)]
+pub struct ClientLimits {
+    config: ClientLimitsConfig,
+    buckets: DashMap<(LimitKind, ClientId), TokenBucket>,
+    rejections: Arc<Rejections>,
+}
+
+impl ClientLimits {
+    pub fn new(mut config: ClientLimitsConfig) -> Self {
+        for (name, bucket) in [
+            ("loadTracks", &mut config.load_tracks),
+            ("playerUpdates", &mut config.player_updates),
+        ] {
+            if bucket.as_ref().is_some_and(|bucket| !bucket.is_valid()) {
+                warn!(
+                    "Ignoring the {} client limit: refillPerSecond must be positive",
+                    name
+                );
+                *bucket = None;
+            }
+        }
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Whether any limit is configured
+    pub fn enabled(&self) -> bool {
+        let config = &self.config;
+        config.load_tracks.is_some()
+            || config.player_updates.is_some()
+            || config.max_players_per_session.is_some()
+            || config.max_queue_length.is_some()
+    }
+
+    pub fn rejections(&self) -> Arc<Rejections> {
+        self.rejections.clone()
+    }
+
+    fn bucket_config(&self, kind: LimitKind) -> Option<&TokenBucketConfig> {
+        match kind {
+            LimitKind::LoadTracks => self.config.load_tracks.as_ref(),
+            LimitKind::PlayerUpdates => self.config.player_updates.as_ref(),
+        }
+    }
+
+    /// Take a token from a client's bucket
+    pub fn check_rate(&self, kind: LimitKind, client: &ClientId) -> Result<(), LimitError> {
+        self.check_rate_at(kind, client, Instant::now())
+    }
+
+    /// Take `count` tokens from a client's bucket at once, or none at all
+    ///
+    /// More tokens than the bucket holds when full are never available.
+    pub fn check_rate_for(
+        &self,
+        kind: LimitKind,
+        client: &ClientId,
+        count: u32,
+    ) -> Result<(), LimitError> {
+        self.take_at(kind, client, count, Instant::now())
+    }
+
+    fn check_rate_at(
+        &self,
+        kind: LimitKind,
+        client: &ClientId,
+        now: Instant,
+    ) -> Result<(), LimitError> {
+        self.take_at(kind, client, 1, now)
+    }
+
+    fn take_at(
+        &self,
+        kind: LimitKind,
+        client: &ClientId,
+        count: u32,
+        now: Instant,
+    ) -> Result<(), LimitError> {
+        let Some(config) = self.bucket_config(kind) else {
+            return Ok(());
+        };
+        let counter = match kind {
+            LimitKind::LoadTracks => &self.rejections.load_tracks,
+            LimitKind::PlayerUpdates => &self.rejections.player_updates,
+        };
+        if count > config.capacity {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return Err(LimitError::BatchTooLarge(config.capacity));
+        }
+
+        if self.buckets.len() > MAX_TRACKED_BUCKETS {
+            self.evict(now);
+        }
+
+        let result = self
+            .buckets
+            .entry((kind, client.clone()))
+            .or_insert_with(|| TokenBucket::full(config, now))
+            .take(config, now, f64::from(count));
+        result.map_err(|wait| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            LimitError::RateLimited(wait)
+        })
+    }
+
+    /// Drop buckets until half of `MAX_TRACKED_BUCKETS` are left
+    ///
+    /// Full buckets go first, as they are the same as new ones, then the ones
+    /// idle the longest, which are the closest to full. Going down to half
+    /// keeps these passes over every bucket rare.
+    fn evict(&self, now: Instant) {
+        self.buckets.retain(|(kind, _), bucket| {
+            self.bucket_config(*kind)
+                .is_some_and(|config| !bucket.is_full(config, now))
+        });
+
+        let excess = self.buckets.len().saturating_sub(MAX_TRACKED_BUCKETS / 2);
+        if excess == 0 {
+            return;
+        }
+        let mut idle: Vec<_> = self
+            .buckets
+            .iter()
+            .map(|entry| (entry.value().updated, entry.key().clone()))
+            .collect();
+        idle.select_nth_unstable_by_key(excess - 1, |(updated, _)| *updated);
+        for (_, key) in idle.into_iter().take(excess) {
+            self.buckets.remove(&key);
+        }
+    }
+
+    /// Check that a session owning `owned` players can get `added` more
+    pub fn check_player_count(&self, owned: usize, added: usize) -> Result<(), LimitError> {
+        match self.config.max_players_per_session {
+            Some(max) if added > 0 && owned + added > max => {
+                self.rejections.players.fetch_add(1, Ordering::Relaxed);
+                Err(LimitError::TooManyPlayers(max))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that a queue holding `queued` tracks can take another one
+    pub fn check_queue(&self, queued: usize) -> Result<(), LimitError> {
+        match self.config.max_queue_length {
+            Some(max) if queued >= max => {
+                self.rejections.queue.fetch_add(1, Ordering::Relaxed);
+                Err(LimitError::QueueFull(max))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Middleware refusing requests over their client's rate limit
+#[cfg(feature = "rest-api")]
+pub async fn rate_limit(
+    axum::extract::State(state): axum::extract::State<Arc<super::AppState>>,
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    // Handlers that charge by what the request holds need the client too
+    let client = ClientId::of(&request);
+    if let Some(kind) = LimitKind::of(request.method(), request.uri().path()) {
+        if let Err(error) = state.limits.check_rate(kind, &client) {
+            warn!(
+                "Rate limited {} from {:?} on {}",
+                client.api_key,
+                client.ip,
+                request.uri().path()
+            );
+            return super::ApiError::from(error).into_response();
+        }
+    }
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerManager;
+
+    fn limits() -> ClientLimits {
+        ClientLimits::new(ClientLimitsConfig {
+            load_tracks: Some(TokenBucketConfig {
+                capacity: 2,
+                refill_per_second: 0.5,
+            }),
+            player_updates: None,
+            max_players_per_session: Some(1),
+            max_queue_length: Some(3),
+        })
+    }
+
+    fn client(ip: u32) -> ClientId {
+        ClientId {
+            api_key: "music-bot".to_string(),
+            ip: Some(IpAddr::from(ip.to_be_bytes())),
+        }
+    }
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let limits = limits();
+        let bot = client(1);
+        let start = Instant::now();
+
+        assert!(limits
+            .check_rate_at(LimitKind::LoadTracks, &bot, start)
+            .is_ok());
+        assert!(limits
+            .check_rate_at(LimitKind::LoadTracks, &bot, start)
+            .is_ok());
+        assert_eq!(
+            limits.check_rate_at(LimitKind::LoadTracks, &bot, start),
+            Err(LimitError::RateLimited(Duration::from_secs(2)))
+        );
+
+        // Other clients and kinds have their own buckets
+        assert!(limits
+            .check_rate_at(LimitKind::LoadTracks, &client(2), start)
+            .is_ok());
+        assert!(limits
+            .check_rate_at(LimitKind::PlayerUpdates, &bot, start)
+            .is_ok());
+
+        let later = start + Duration::from_secs(2);
+        assert!(limits
+            .check_rate_at(LimitKind::LoadTracks, &bot, later)
+            .is_ok());
+        assert!(limits
+            .check_rate_at(LimitKind::LoadTracks, &bot, later)
+            .is_err());
+        assert_eq!(limits.rejections().snapshot().load_tracks, 2);
+    }
+
+    #[test]
+    fn test_user_id_does_not_split_clients() {
+        let request = |user_id: &str| {
+            axum::extract::Request::builder()
+                .header("User-Id", user_id)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        assert_eq!(ClientId::of(&request("1")), ClientId::of(&request("2")));
+    }
+
+    #[test]
+    fn test_invalid_refill_disables_bucket() {
+        let invalid = ClientLimitsConfig {
+            load_tracks: Some(TokenBucketConfig {
+                capacity: 1,
+                refill_per_second: f64::NAN,
+            }),
+            ..Default::default()
+        };
+
+        // Loading such a config fails before the limits are built
+        let mut config = crate::config::LavalinkConfig::default();
+        config.lavalink.server.client_limits = Some(invalid.clone());
+        assert!(config.validate().is_err());
+
+        let limits = ClientLimits::new(invalid);
+        assert!(!limits.enabled());
+        for _ in 0..3 {
+            assert!(limits.check_rate(LimitKind::LoadTracks, &client(1)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_eviction_keeps_recent_buckets() {
+        let limits = limits();
+        let start = Instant::now();
+
+        // Every bucket is still refilling, so the oldest ones go
+        for ip in 0..=MAX_TRACKED_BUCKETS as u32 {
+            let now = start + Duration::from_micros(100 * u64::from(ip));
+            assert!(limits
+                .check_rate_at(LimitKind::LoadTracks, &client(ip), now)
+                .is_ok());
+        }
+        let now = start + Duration::from_millis(1500);
+        let last = client(MAX_TRACKED_BUCKETS as u32);
+        assert!(limits
+            .check_rate_at(LimitKind::LoadTracks, &client(u32::MAX), now)
+            .is_ok());
+
+        assert!(limits.buckets.len() <= MAX_TRACKED_BUCKETS / 2 + 1);
+        assert!(limits.buckets.contains_key(&(LimitKind::LoadTracks, last)));
+        assert!(!limits
+            .buckets
+            .contains_key(&(LimitKind::LoadTracks, client(0))));
+    }
+
+    #[test]
+    fn test_taking_several_tokens() {
+        let limits = limits();
+        let bot = client(1);
+        let now = Instant::now();
+
+        assert_eq!(
+            limits.take_at(LimitKind::LoadTracks, &bot, 3, now),
+            Err(LimitError::BatchTooLarge(2))
+        );
+        assert!(limits.take_at(LimitKind::LoadTracks, &bot, 2, now).is_ok());
+        assert!(matches!(
+            limits.take_at(LimitKind::LoadTracks, &bot, 1, now),
+            Err(LimitError::RateLimited(_))
+        ));
+        assert_eq!(limits.rejections().snapshot().load_tracks, 2);
+    }
+
+    #[test]
+    fn test_queue_cap() {
+        let limits = limits();
+        assert!(limits.check_queue(2).is_ok());
+        assert_eq!(limits.check_queue(3), Err(LimitError::QueueFull(3)));
+        assert_eq!(limits.rejections().snapshot().queue, 1);
+    }
+
+    #[tokio::test]
+    async fn test_player_cap() {
+        let limits = limits();
+        let player_manager = PlayerManager::new();
+        let guilds = ["1".to_string(), "2".to_string()];
+        let admit = |guild_ids: &[String]| {
+            player_manager.get_or_create_players(guild_ids.to_vec(), "session", |owned, added| {
+                limits.check_player_count(owned, added)
+            })
+        };
+
+        assert!(admit(&guilds[..1]).await.is_ok());
+
+        // Updating the existing player is fine, a second one is not
+        assert!(admit(&guilds[..1]).await.is_ok());
+        assert_eq!(
+            admit(&guilds[1..]).await.err(),
+            Some(LimitError::TooManyPlayers(1))
+        );
+        assert_eq!(limits.rejections().snapshot().players, 1);
+
+        // Counts are taken under the players lock and nothing is created when refused
+        assert_eq!(
+            admit(&guilds).await.err(),
+            Some(LimitError::TooManyPlayers(1))
+        );
+        assert!(player_manager.get_player(&guilds[1]).await.is_none());
+    }
+
+    #[test]
+    fn test_limit_kinds() {
+        use axum::http::Method;
+
+        assert_eq!(
+            LimitKind::of(&Method::GET, "/v4/loadtracks"),
+            Some(LimitKind::LoadTracks)
+        );
+        assert_eq!(
+            LimitKind::of(&Method::PATCH, "/v4/sessions/abc/players/123"),
+            Some(LimitKind::PlayerUpdates)
+        );
+        assert_eq!(
+            LimitKind::of(&Method::POST, "/v4/sessions/abc/players/123/queue"),
+            Some(LimitKind::PlayerUpdates)
+        );
+        assert_eq!(
+            LimitKind::of(&Method::GET, "/v4/sessions/abc/players/123"),
+            None
+        );
+        assert_eq!(LimitKind::of(&Method::PATCH, "/v4/sessions/abc"), None);
+        assert_eq!(
+            LimitKind::of(&Method::PATCH, "/v4/sessions/abc/players"),
+            None
+        );
+        assert_eq!(LimitKind::of(&Method::GET, "/v4/info"), None);
+    }
+}
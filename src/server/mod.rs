@@ -13,10 +13,11 @@ use crate::player::{PlayerEvent, PlayerManager};
 use axum::{
     body::Body,
     extract::State,
+    handler::Handler,
     http::{Request, StatusCode},
     middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put, MethodRouter},
     Json, Router,
 };
 
@@ -50,14 +51,24 @@ use crate::protocol::ErrorResponse;
 
 use self::routeplanner::RoutePlanner;
 
+#[cfg(all(feature = "rest-api", feature = "audio-sources"))]
+mod artwork;
 #[cfg(feature = "server")]
 mod auth;
 #[cfg(feature = "rest-api")]
+mod error;
+#[cfg(feature = "server")]
+mod limits;
+#[cfg(feature = "rest-api")]
+mod openapi;
+#[cfg(feature = "rest-api")]
 mod rest;
 mod routeplanner;
 #[cfg(feature = "server")]
 mod stats;
 #[cfg(feature = "websocket")]
+mod v3;
+#[cfg(feature = "websocket")]
 mod websocket;
 
 #[cfg(test)]
@@ -68,6 +79,8 @@ mod rest_tests;
 
 #[cfg(feature = "server")]
 pub use auth::*;
+#[cfg(feature = "rest-api")]
+pub use error::ApiError;
 
 #[cfg(feature = "server")]
 pub use stats::*;
@@ -106,31 +119,164 @@ pub struct AppState {
     pub config: LavalinkConfig,
     #[cfg(feature = "websocket")]
     pub sessions: Arc<dashmap::DashMap<String, WebSocketSession>>,
+    /// Resume keys set by v3 sessions with `configureResuming`, mapped to their session id
+    #[cfg(feature = "websocket")]
+    pub resume_keys: Arc<dashmap::DashMap<String, String>>,
     #[cfg(feature = "server")]
     pub stats_collector: Arc<StatsCollector>,
+    /// Rate limits and caps applied per client
+    #[cfg(feature = "server")]
+    pub limits: Arc<limits::ClientLimits>,
     pub info: Info,
 
     // Player manager is needed for both Discord and standalone modes
     pub player_manager: Arc<PlayerManager>,
     pub plugin_manager: Arc<std::sync::RwLock<PluginManager>>,
     pub route_planner: Option<Arc<RoutePlanner>>,
+    /// Sources built from the configuration, shared so the local library index persists
+    #[cfg(feature = "audio-processing")]
+    pub audio_manager: Arc<crate::audio::AudioSourceManager>,
+    #[cfg(all(feature = "rest-api", feature = "audio-sources"))]
+    pub artwork: Arc<artwork::ArtworkService>,
+}
+
+/// The routes of the server, with the method and path of each
+#[cfg(feature = "server")]
+#[derive(Default)]
+struct Routes {
+    router: Router<Arc<AppState>>,
+    /// `(method, path)` of every route, which the OpenAPI document must describe
+    #[cfg_attr(not(test), allow(dead_code))]
+    listed: Vec<(&'static str, &'static str)>,
+}
+
+#[cfg(feature = "server")]
+impl Routes {
+    fn add(
+        mut self,
+        method: &'static str,
+        path: &'static str,
+        route: MethodRouter<Arc<AppState>>,
+    ) -> Self {
+        self.listed.push((method, path));
+        self.router = self.router.route(path, route);
+        self
+    }
+
+    fn get<H: Handler<T, Arc<AppState>>, T: 'static>(self, path: &'static str, handler: H) -> Self {
+        self.add("get", path, get(handler))
+    }
+
+    fn post<H: Handler<T, Arc<AppState>>, T: 'static>(
+        self,
+        path: &'static str,
+        handler: H,
+    ) -> Self {
+        self.add("post", path, post(handler))
+    }
+
+    fn put<H: Handler<T, Arc<AppState>>, T: 'static>(self, path: &'static str, handler: H) -> Self {
+        self.add("put", path, put(handler))
+    }
+
+    fn patch<H: Handler<T, Arc<AppState>>, T: 'static>(
+        self,
+        path: &'static str,
+        handler: H,
+    ) -> Self {
+        self.add("patch", path, patch(handler))
+    }
+
+    fn delete<H: Handler<T, Arc<AppState>>, T: 'static>(
+        self,
+        path: &'static str,
+        handler: H,
+    ) -> Self {
+        self.add("delete", path, delete(handler))
+    }
 }
 
 impl LavalinkServer {
     /// Create a new Lavalink server
     pub async fn new(config: LavalinkConfig) -> Result<Self> {
-        let info = Info::new();
         #[cfg(feature = "websocket")]
         let sessions = Arc::new(dashmap::DashMap::<String, WebSocketSession>::new());
         #[cfg(feature = "server")]
-        let stats_collector = Arc::new(StatsCollector::new());
+        let limits = Arc::new(limits::ClientLimits::new(
+            config
+                .lavalink
+                .server
+                .client_limits
+                .clone()
+                .unwrap_or_default(),
+        ));
+        #[cfg(feature = "server")]
+        let stats_collector = Arc::new(if limits.enabled() {
+            StatsCollector::new().with_rejections(limits.rejections())
+        } else {
+            StatsCollector::new()
+        });
+
+        #[cfg(feature = "audio-processing")]
+        let audio_manager = Arc::new(crate::audio::AudioSourceManager::with_config(Some(
+            &config.lavalink.server.sources,
+        )));
+
+        // Advertise what this node is actually configured to do
+        #[cfg(feature = "audio-processing")]
+        let source_managers = audio_manager.source_names();
+        #[cfg(not(feature = "audio-processing"))]
+        let source_managers = Vec::new();
+        let info = Info::new().with_capabilities(
+            source_managers,
+            config.lavalink.server.filters.enabled_filters(),
+        );
+
+        #[cfg(all(feature = "rest-api", feature = "audio-sources"))]
+        let artwork = Arc::new(artwork::ArtworkService::new(
+            config.lavalink.server.artwork.as_ref(),
+            &config.lavalink.server.password,
+        ));
 
         // Initialize player manager (needed for both Discord and standalone modes)
         let player_manager = {
             // Create player event channel
             let (event_sender, event_receiver) =
                 tokio::sync::mpsc::unbounded_channel::<PlayerEvent>();
-            let player_manager = Arc::new(PlayerManager::with_event_sender(event_sender));
+            let mut player_manager = PlayerManager::with_event_sender(event_sender);
+            if let Some(seconds) = config
+                .lavalink
+                .server
+                .player_update_interval
+                .filter(|seconds| *seconds > 0)
+            {
+                player_manager = player_manager
+                    .with_update_interval(std::time::Duration::from_secs(seconds.into()));
+            }
+            #[cfg(feature = "server")]
+            {
+                let limits = limits.clone();
+                player_manager = player_manager.with_queue_limit(move |queued| {
+                    limits.check_queue(queued).map_err(|e| e.to_string())
+                });
+            }
+            #[cfg(all(feature = "rest-api", feature = "audio-sources"))]
+            {
+                let artwork = artwork.clone();
+                player_manager =
+                    player_manager.with_artwork(move |track| artwork.rewrite_track(track));
+            }
+            if let Some(store) =
+                crate::player::PlayerStore::from_config(config.lavalink.server.persistence.as_ref())
+            {
+                info!("Persisting players to {}", store.path().display());
+                player_manager = player_manager.with_persistence(store);
+            }
+            #[cfg(feature = "audio-processing")]
+            {
+                player_manager = player_manager.with_sources(audio_manager.clone());
+            }
+            let player_manager = Arc::new(player_manager);
 
             // Initialize voice client based on configuration
             #[cfg(feature = "discord")]
@@ -211,6 +357,9 @@ impl LavalinkServer {
                         for entry in sessions.iter() {
                             let session_id = entry.key();
                             let session = entry.value();
+                            if !session.subscription.allows(&stats_message) {
+                                continue;
+                            }
 
                             if let Err(e) = session.send_message(stats_message.clone()).await {
                                 warn!("Failed to send stats to session {}: {}", session_id, e);
@@ -229,6 +378,14 @@ impl LavalinkServer {
                 });
             }
 
+            // Restore persisted players before their snapshots are written again,
+            // keeping them for as long as their session could still resume
+            #[cfg(feature = "websocket")]
+            let resume_timeout = tokio::time::Duration::from_secs(DEFAULT_SESSION_TIMEOUT);
+            #[cfg(not(feature = "websocket"))]
+            let resume_timeout = tokio::time::Duration::ZERO;
+            player_manager.restore_players(resume_timeout).await;
+
             // Start player update service
             player_manager.start_update_service().await;
             player_manager
@@ -243,8 +400,17 @@ impl LavalinkServer {
             warn!("Failed to load dynamic plugins: {}", e);
         }
 
+        plugin_manager.attach_lyrics(player_manager.lyrics());
         let plugin_manager = Arc::new(std::sync::RwLock::new(plugin_manager));
 
+        // Lyrics of local files come from sidecars and tags, ahead of plugin providers
+        #[cfg(all(feature = "audio-processing", feature = "audio-sources"))]
+        if let Some(local) = audio_manager.local_source() {
+            player_manager
+                .lyrics()
+                .register_provider(Arc::new(local.clone()));
+        }
+
         // Initialize route planner if configured
         let route_planner = if let Some(ratelimit_config) = &config.lavalink.server.ratelimit {
             match routeplanner::RoutePlannerConfig::try_from(ratelimit_config) {
@@ -271,14 +437,22 @@ impl LavalinkServer {
             config: config.clone(),
             #[cfg(feature = "websocket")]
             sessions,
+            #[cfg(feature = "websocket")]
+            resume_keys: Arc::new(dashmap::DashMap::new()),
             #[cfg(feature = "server")]
             stats_collector,
+            #[cfg(feature = "server")]
+            limits,
             info,
 
             // Player manager is needed for both Discord and standalone modes
             player_manager,
             plugin_manager,
             route_planner,
+            #[cfg(feature = "audio-processing")]
+            audio_manager,
+            #[cfg(all(feature = "rest-api", feature = "audio-sources"))]
+            artwork,
         });
 
         Ok(Self { config, app_state })
@@ -393,154 +567,244 @@ impl LavalinkServer {
         info!("Cleanup completed");
     }
 
-    /// Build the Axum router
+    /// Every route of the server, before the middleware is layered on
     #[cfg(feature = "server")]
-    pub fn build_router(&self) -> Router {
+    fn routes(&self) -> Routes {
         #[allow(unused_mut)]
-        let mut router = Router::new();
+        let mut routes = Routes::default();
 
         // WebSocket endpoint (conditional)
         #[cfg(feature = "websocket")]
         {
-            router = router.route("/v4/websocket", get(websocket_handler));
+            routes = routes.get("/v4/websocket", websocket_handler);
+            if self
+                .config
+                .lavalink
+                .server
+                .v3_compatibility
+                .unwrap_or(false)
+            {
+                routes = routes.get("/", v3_websocket_handler);
+            }
         }
 
         // REST API endpoints
-        router = router
-            .route("/v4/info", get(rest::info_handler))
-            .route("/version", get(rest::version_handler))
-            .route("/v4/stats", get(rest::stats_handler));
+        routes = routes
+            .get("/v4/info", rest::info_handler)
+            .get("/version", rest::version_handler)
+            .get("/v4/stats", rest::stats_handler)
+            .get("/v4/openapi.json", openapi::openapi_handler);
 
         // Session management (conditional)
         #[cfg(feature = "websocket")]
         {
-            router = router
-                .route("/v4/sessions", get(rest::get_sessions_handler))
-                .route("/v4/sessions/:session_id", get(rest::get_session_handler))
-                .route(
-                    "/v4/sessions/:session_id",
-                    patch(rest::update_session_handler),
-                )
-                .route(
-                    "/v4/sessions/:session_id",
-                    delete(rest::delete_session_handler),
+            routes = routes
+                .get("/v4/sessions", rest::get_sessions_handler)
+                .get("/v4/sessions/:session_id", rest::get_session_handler)
+                .patch("/v4/sessions/:session_id", rest::update_session_handler)
+                .delete("/v4/sessions/:session_id", rest::delete_session_handler)
+                .get(
+                    "/v4/sessions/:session_id/events",
+                    rest::session_events_handler,
                 );
         }
 
-        router = router
+        routes = routes
             // Player management
-            .route(
+            .get(
                 "/v4/sessions/:session_id/players",
-                get(rest::get_session_players_handler),
+                rest::get_session_players_handler,
             )
-            .route(
+            .patch(
+                "/v4/sessions/:session_id/players",
+                rest::update_players_handler,
+            )
+            .get(
                 "/v4/sessions/:session_id/players/:guild_id",
-                get(rest::get_player_handler),
+                rest::get_player_handler,
             )
-            .route(
+            .patch(
                 "/v4/sessions/:session_id/players/:guild_id",
-                patch(rest::update_player_handler),
+                rest::update_player_handler,
             )
-            .route(
+            .delete(
                 "/v4/sessions/:session_id/players/:guild_id",
-                delete(rest::delete_player_handler),
+                rest::delete_player_handler,
             )
             // Queue management
-            .route(
+            .get(
                 "/v4/sessions/:session_id/players/:guild_id/queue",
-                get(rest::get_player_queue_handler),
+                rest::get_player_queue_handler,
             )
-            .route(
+            .post(
                 "/v4/sessions/:session_id/players/:guild_id/queue",
-                post(rest::add_to_queue_handler),
+                rest::add_to_queue_handler,
             )
-            .route(
+            .delete(
                 "/v4/sessions/:session_id/players/:guild_id/queue",
-                delete(rest::clear_queue_handler),
+                rest::clear_queue_handler,
             )
-            .route(
+            .delete(
                 "/v4/sessions/:session_id/players/:guild_id/queue/:index",
-                delete(rest::remove_from_queue_handler),
+                rest::remove_from_queue_handler,
             )
-            .route(
+            .post(
                 "/v4/sessions/:session_id/players/:guild_id/queue/move",
-                post(rest::move_track_in_queue_handler),
+                rest::move_track_in_queue_handler,
             )
-            .route(
+            .post(
                 "/v4/sessions/:session_id/players/:guild_id/queue/shuffle",
-                post(rest::shuffle_queue_handler),
+                rest::shuffle_queue_handler,
+            )
+            .put(
+                "/v4/sessions/:session_id/players/:guild_id/queue/mode",
+                rest::set_queue_mode_handler,
             )
-            .route(
+            .post(
                 "/v4/sessions/:session_id/players/:guild_id/skip",
-                post(rest::skip_track_handler),
+                rest::skip_track_handler,
+            )
+            // History
+            .post(
+                "/v4/sessions/:session_id/players/:guild_id/previous",
+                rest::previous_track_handler,
+            )
+            .get(
+                "/v4/sessions/:session_id/players/:guild_id/history",
+                rest::get_player_history_handler,
+            )
+            .post(
+                "/v4/sessions/:session_id/players/:guild_id/history/:index",
+                rest::replay_from_history_handler,
+            )
+            // Lyrics
+            .get(
+                "/v4/sessions/:session_id/players/:guild_id/lyrics",
+                rest::get_player_lyrics_handler,
+            )
+            .post(
+                "/v4/sessions/:session_id/players/:guild_id/lyrics/subscribe",
+                rest::subscribe_lyrics_handler,
+            )
+            .delete(
+                "/v4/sessions/:session_id/players/:guild_id/lyrics/subscribe",
+                rest::unsubscribe_lyrics_handler,
+            )
+            // Autoplay
+            .post(
+                "/v4/sessions/:session_id/players/:guild_id/autoplay",
+                rest::enable_autoplay_handler,
+            )
+            .delete(
+                "/v4/sessions/:session_id/players/:guild_id/autoplay",
+                rest::disable_autoplay_handler,
+            )
+            .put(
+                "/v4/sessions/:session_id/players/:guild_id/updateInterval",
+                rest::set_update_interval_handler,
+            )
+            // Chapters
+            .post(
+                "/v4/sessions/:session_id/players/:guild_id/chapters/:index",
+                rest::seek_to_chapter_handler,
             )
             // Filter management
-            .route(
+            .get(
                 "/v4/sessions/:session_id/players/:guild_id/filters",
-                get(rest::get_player_filters_handler),
+                rest::get_player_filters_handler,
             )
-            .route(
+            .patch(
                 "/v4/sessions/:session_id/players/:guild_id/filters",
-                patch(rest::update_player_filters_handler),
+                rest::update_player_filters_handler,
             )
             // Filter presets
-            .route("/v4/filters/presets", get(rest::get_filter_presets_handler))
+            .get("/v4/filters/presets", rest::get_filter_presets_handler)
             // Plugin management
-            .route("/v4/plugins", get(rest::get_plugins_handler))
-            .route("/v4/plugins/:name", get(rest::get_plugin_handler))
-            .route(
-                "/v4/plugins/:name/reload",
-                post(rest::reload_plugin_handler),
-            )
-            .route(
+            .get("/v4/plugins", rest::get_plugins_handler)
+            .get("/v4/plugins/:name", rest::get_plugin_handler)
+            .post("/v4/plugins/:name/reload", rest::reload_plugin_handler)
+            .get("/v4/plugins/:name/config", rest::get_plugin_config_handler)
+            .patch(
                 "/v4/plugins/:name/config",
-                get(rest::get_plugin_config_handler),
-            )
-            .route(
-                "/v4/plugins/:name/config",
-                patch(rest::update_plugin_config_handler),
+                rest::update_plugin_config_handler,
             )
             // Track loading
-            .route("/v4/loadtracks", get(rest::load_tracks_handler))
-            .route("/v4/decodetrack", get(rest::decode_track_handler))
-            .route("/v4/decodetracks", post(rest::decode_tracks_handler))
+            .get("/v4/loadtracks", rest::load_tracks_handler)
+            .get("/v4/decodetrack", rest::decode_track_handler)
+            .post("/v4/decodetracks", rest::decode_tracks_handler)
             // Route planner
-            .route(
-                "/v4/routeplanner/status",
-                get(rest::routeplanner_status_handler),
-            )
-            .route(
+            .get("/v4/routeplanner/status", rest::routeplanner_status_handler)
+            .post(
                 "/v4/routeplanner/free/address",
-                post(rest::routeplanner_unmark_address_handler),
+                rest::routeplanner_unmark_address_handler,
             )
-            .route(
+            .post(
                 "/v4/routeplanner/free/all",
-                post(rest::routeplanner_unmark_all_handler),
+                rest::routeplanner_unmark_all_handler,
             );
 
+        #[cfg(all(feature = "rest-api", feature = "audio-sources"))]
+        {
+            routes = routes.get("/v4/artwork/:id", artwork::artwork_handler);
+        }
+
         // Add Discord-specific routes conditionally
         #[cfg(feature = "discord")]
         {
-            router = router
-                .route(
+            routes = routes
+                .delete(
                     "/v4/sessions/:session_id/players/:guild_id/filters",
-                    delete(rest::clear_player_filters_handler),
+                    rest::clear_player_filters_handler,
                 )
-                .route(
+                .post(
                     "/v4/sessions/:session_id/players/:guild_id/filters/preset/:preset_name",
-                    post(rest::apply_filter_preset_handler),
+                    rest::apply_filter_preset_handler,
                 );
         }
 
-        router
-            // Middleware - auth first, then other layers
-            .layer(middleware::from_fn_with_state(
+        routes
+    }
+
+    /// Build the Axum router
+    #[cfg(feature = "server")]
+    pub fn build_router(&self) -> Router {
+        #[allow(unused_mut)]
+        let mut router = self.routes().router;
+
+        // Runs after the auth middleware, which identifies the API key
+        #[cfg(feature = "rest-api")]
+        {
+            router = router.layer(middleware::from_fn_with_state(
                 self.app_state.clone(),
-                auth_middleware,
-            ))
+                limits::rate_limit,
+            ));
+        }
+
+        router = router.layer(middleware::from_fn_with_state(
+            self.app_state.clone(),
+            auth_middleware,
+        ));
+
+        // Error responses get the path and trace of the request that failed
+        #[cfg(feature = "rest-api")]
+        {
+            router = router.layer(middleware::from_fn(error::error_context));
+        }
+
+        router
             .layer(
                 ServiceBuilder::new()
-                    .layer(TraceLayer::new_for_http())
+                    .layer(
+                        TraceLayer::new_for_http().make_span_with(|request: &Request<Body>| {
+                            tracing::info_span!(
+                                "request",
+                                method = %request.method(),
+                                uri = %request.uri(),
+                                version = ?request.version(),
+                                api_key = tracing::field::Empty,
+                            )
+                        }),
+                    )
                     .layer(CorsLayer::permissive())
                     .layer(CompressionLayer::new()),
             )
@@ -569,13 +833,44 @@ async fn websocket_handler(
     State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Query(_params): Query<HashMap<String, String>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let v3_compat = headers
+        .get(v3::API_VERSION_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|version| version.trim() == "3");
+    upgrade_websocket(ws, state, addr, headers, params, v3_compat)
+}
+
+/// WebSocket handler for Lavalink v3 clients - /
+#[cfg(feature = "websocket")]
+async fn v3_websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    upgrade_websocket(ws, state, addr, headers, params, true)
+}
+
+#[cfg(feature = "websocket")]
+fn upgrade_websocket(
+    ws: WebSocketUpgrade,
+    state: Arc<AppState>,
+    addr: SocketAddr,
+    headers: HeaderMap,
+    params: HashMap<String, String>,
+    v3_compat: bool,
 ) -> Response {
     // Authenticate the connection
-    if let Err(err) = authenticate_request(&headers, &state.config) {
-        warn!("WebSocket authentication failed from {}: {}", addr, err);
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
-    }
+    let api_key = match authenticate_request(&headers, &state.config) {
+        Ok(key) => key,
+        Err(err) => {
+            warn!("WebSocket authentication failed from {}: {}", addr, err);
+            return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+        }
+    };
 
     // Extract required headers
     let user_id = match headers.get("User-Id").and_then(|h| h.to_str().ok()) {
@@ -586,27 +881,64 @@ async fn websocket_handler(
         }
     };
 
-    let session_id = headers
-        .get("Session-Id")
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string());
+    let session_id = if v3_compat {
+        // v3 clients resume with the key they configured instead of a session id
+        headers
+            .get(v3::RESUME_KEY_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|key| state.resume_keys.get(key))
+            .map(|session_id| session_id.clone())
+    } else {
+        headers
+            .get("Session-Id")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string())
+    };
     let client_name = headers
         .get("Client-Name")
         .and_then(|h| h.to_str().ok())
         .map(|s| s.to_string());
 
+    // Sessions can narrow down the messages they receive right away
+    let subscription = match parse_subscription(&params) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            warn!("Invalid subscription from {}: {}", addr, err);
+            return (StatusCode::BAD_REQUEST, err).into_response();
+        }
+    };
+
+    // Sessions only resume with the key that opened them
+    if let Some(session_id) = &session_id {
+        let foreign = state
+            .sessions
+            .get(session_id)
+            .is_some_and(|session| !session.is_usable_by(&api_key.name));
+        if foreign {
+            warn!(
+                "API key {} cannot resume session {} of another key",
+                api_key.name, session_id
+            );
+            return (StatusCode::FORBIDDEN, "Session belongs to another API key").into_response();
+        }
+    }
+
     info!("WebSocket connection from {} (User-ID: {})", addr, user_id);
 
-    ws.on_upgrade(move |socket| {
-        handle_websocket(socket, state, addr, user_id, session_id, client_name)
-    })
+    let options = SessionOptions {
+        client_name,
+        api_key: api_key.name,
+        v3_compat,
+        subscription,
+    };
+    ws.on_upgrade(move |socket| handle_websocket(socket, state, addr, user_id, session_id, options))
 }
 
 /// Authentication middleware for REST endpoints
 #[cfg(feature = "server")]
 pub async fn auth_middleware(
     State(state): State<Arc<AppState>>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Response {
     let headers = request.headers();
@@ -616,43 +948,87 @@ pub async fn auth_middleware(
     let method = request.method();
     info!("Auth middleware checking path: {} {}", method, path);
 
-    // Debug: Log headers for player update requests
-    if path.contains("/players/") && method == "PATCH" {
-        info!("DEBUG: Player update request headers: {:?}", headers);
-        info!("DEBUG: Content-Type: {:?}", headers.get("content-type"));
-    }
-
     // Check authentication
-    if let Err(err) = authenticate_request(headers, &state.config) {
-        warn!("REST API authentication failed for {}: {}", path, err);
+    let key = match authenticate_request(headers, &state.config) {
+        Ok(key) => key,
+        Err(err) => {
+            warn!("REST API authentication failed for {}: {}", path, err);
+
+            // Check if Authorization header is missing (401) or wrong (403)
+            let status_code = if headers.get("Authorization").is_none() {
+                StatusCode::UNAUTHORIZED
+            } else {
+                StatusCode::FORBIDDEN
+            };
 
-        // Check if Authorization header is missing (401) or wrong (403)
-        let status_code = if headers.get("Authorization").is_none() {
-            StatusCode::UNAUTHORIZED
-        } else {
-            StatusCode::FORBIDDEN
-        };
+            let error_code = if status_code == StatusCode::UNAUTHORIZED {
+                401
+            } else {
+                403
+            };
+            let error_message = if status_code == StatusCode::UNAUTHORIZED {
+                "Unauthorized"
+            } else {
+                "Forbidden"
+            };
 
-        let error_code = if status_code == StatusCode::UNAUTHORIZED {
-            401
-        } else {
-            403
-        };
-        let error_message = if status_code == StatusCode::UNAUTHORIZED {
-            "Unauthorized"
-        } else {
-            "Forbidden"
-        };
+            let error = ErrorResponse::new(
+                error_code,
+                error_message.to_string(),
+                Some("Invalid or missing authorization".to_string()),
+                path.to_string(),
+            );
+            return (status_code, Json(error)).into_response();
+        }
+    };
+    // The request span was opened by the TraceLayer with an empty api_key field
+    tracing::Span::current().record("api_key", key.name.as_str());
+
+    if let Some(scope) = required_scope(path) {
+        if !key.allows(scope) {
+            warn!(
+                "API key {} lacks the {} scope for {}",
+                key.name,
+                scope.as_str(),
+                path
+            );
+            let error = ErrorResponse::new(
+                403,
+                "Forbidden".to_string(),
+                Some(format!(
+                    "API key '{}' lacks the '{}' scope",
+                    key.name,
+                    scope.as_str()
+                )),
+                path.to_string(),
+            );
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+    }
 
-        let error = ErrorResponse::new(
-            error_code,
-            error_message.to_string(),
-            Some("Invalid or missing authorization".to_string()),
-            path.to_string(),
-        );
-        return (status_code, Json(error)).into_response();
+    // Sessions only answer to the key that opened them
+    #[cfg(feature = "websocket")]
+    if let Some(session_id) = session_in_path(path) {
+        let foreign = state
+            .sessions
+            .get(session_id)
+            .is_some_and(|session| !session.is_usable_by(&key.name));
+        if foreign {
+            warn!(
+                "API key {} cannot use session {} of another key",
+                key.name, session_id
+            );
+            let error = ErrorResponse::new(
+                403,
+                "Forbidden".to_string(),
+                Some(format!("Session '{session_id}' belongs to another API key")),
+                path.to_string(),
+            );
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
     }
 
     info!("Authentication successful for path: {}", path);
+    request.extensions_mut().insert(key);
     next.run(request).await
 }
@@ -0,0 +1,954 @@
+//! OpenAPI document of the REST API - /v4/openapi.json
+//!
+//! Every route of [`LavalinkServer::build_router`](super::LavalinkServer::build_router)
+//! is listed in [`operations`]; the request and response bodies come from the
+//! [`JsonSchema`] impls of the protocol types.
+
+use axum::{extract::State, Json};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+use super::{required_scope, AppState};
+use crate::protocol::messages::{
+    AddToQueueRequest, HistoryResponse, MoveTrackRequest, Player, PlayerUpdateResult, QueueMode,
+    QueueResponse, SessionResponse, SessionUpdateRequest, UpdateIntervalRequest,
+    UpdatePlayerRequest,
+};
+use crate::protocol::schema::{self, Schemas};
+use crate::protocol::{
+    DecodeTracksRequest, ErrorResponse, Filters, Info, LoadResult, Stats, Track,
+};
+
+/// Builds the schema of a request or response body
+type SchemaFn = fn(&mut Schemas) -> Value;
+
+/// A response status with its media type and body
+struct ApiResponse {
+    status: &'static str,
+    description: &'static str,
+    content: Option<(&'static str, SchemaFn)>,
+}
+
+/// One method of one route
+struct Operation {
+    method: &'static str,
+    /// Path as routed by axum, with `:name` parameters
+    path: &'static str,
+    summary: &'static str,
+    parameters: Vec<Value>,
+    request: Option<SchemaFn>,
+    responses: Vec<ApiResponse>,
+}
+
+impl Operation {
+    fn new(method: &'static str, path: &'static str, summary: &'static str) -> Self {
+        Self {
+            method,
+            path,
+            summary,
+            parameters: Vec::new(),
+            request: None,
+            responses: Vec::new(),
+        }
+    }
+
+    fn get(path: &'static str, summary: &'static str) -> Self {
+        Self::new("get", path, summary)
+    }
+
+    fn post(path: &'static str, summary: &'static str) -> Self {
+        Self::new("post", path, summary)
+    }
+
+    fn put(path: &'static str, summary: &'static str) -> Self {
+        Self::new("put", path, summary)
+    }
+
+    fn patch(path: &'static str, summary: &'static str) -> Self {
+        Self::new("patch", path, summary)
+    }
+
+    fn delete(path: &'static str, summary: &'static str) -> Self {
+        Self::new("delete", path, summary)
+    }
+
+    fn parameter(
+        mut self,
+        location: &str,
+        name: &str,
+        required: bool,
+        schema: Value,
+        description: &str,
+    ) -> Self {
+        self.parameters.push(json!({
+            "name": name,
+            "in": location,
+            "required": required,
+            "description": description,
+            "schema": schema,
+        }));
+        self
+    }
+
+    fn query(self, name: &str, required: bool, schema: Value, description: &str) -> Self {
+        self.parameter("query", name, required, schema, description)
+    }
+
+    fn header(self, name: &str, required: bool, description: &str) -> Self {
+        self.parameter("header", name, required, schema::string(), description)
+    }
+
+    fn request<T: JsonSchema>(mut self) -> Self {
+        self.request = Some(Schemas::add::<T>);
+        self
+    }
+
+    fn request_with(mut self, schema: SchemaFn) -> Self {
+        self.request = Some(schema);
+        self
+    }
+
+    /// A request body without a fixed shape
+    fn request_object(mut self) -> Self {
+        self.request = Some(|_| schema::any_object());
+        self
+    }
+
+    fn response(
+        mut self,
+        status: &'static str,
+        description: &'static str,
+        media_type: &'static str,
+        schema: SchemaFn,
+    ) -> Self {
+        self.responses.push(ApiResponse {
+            status,
+            description,
+            content: Some((media_type, schema)),
+        });
+        self
+    }
+
+    fn ok<T: JsonSchema>(self) -> Self {
+        self.response("200", "OK", "application/json", Schemas::add::<T>)
+    }
+
+    fn ok_array<T: JsonSchema>(self) -> Self {
+        self.response("200", "OK", "application/json", |schemas| {
+            schema::array(schemas.add::<T>())
+        })
+    }
+
+    /// A JSON response assembled by the handler itself
+    fn ok_object(self, description: &'static str) -> Self {
+        self.response("200", description, "application/json", |_| {
+            schema::any_object()
+        })
+    }
+
+    fn no_content(mut self) -> Self {
+        self.responses.push(ApiResponse {
+            status: "204",
+            description: "No Content",
+            content: None,
+        });
+        self
+    }
+
+    /// `:name` segments as OpenAPI path parameters
+    fn path_parameters(&self) -> impl Iterator<Item = Value> + '_ {
+        self.path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix(':'))
+            .map(|name| {
+                let schema = if name == "index" {
+                    schema::integer()
+                } else {
+                    schema::string()
+                };
+                json!({ "name": name, "in": "path", "required": true, "schema": schema })
+            })
+    }
+
+    fn to_value(&self, schemas: &mut Schemas) -> Value {
+        let mut responses = Map::new();
+        for response in &self.responses {
+            let mut value = json!({ "description": response.description });
+            if let Some((media_type, schema)) = response.content {
+                value["content"] = json!({ media_type: { "schema": schema(schemas) } });
+            }
+            responses.insert(response.status.to_string(), value);
+        }
+        responses.insert(
+            "default".to_string(),
+            json!({
+                "description": "Error",
+                "content": {
+                    "application/json": { "schema": schemas.add::<ErrorResponse>() }
+                }
+            }),
+        );
+
+        let parameters: Vec<Value> = self
+            .path_parameters()
+            .chain(self.parameters.iter().cloned())
+            .collect();
+        let mut operation = json!({
+            "summary": self.summary,
+            "operationId": operation_id(self.method, self.path),
+            "responses": responses,
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(request) = self.request {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": request(schemas) } }
+            });
+        }
+        // Keys only reach the route when granted its scope
+        let scopes: Vec<&str> = required_scope(self.path)
+            .map(|scope| scope.as_str())
+            .into_iter()
+            .collect();
+        operation["security"] = json!([{ "password": scopes }]);
+        operation
+    }
+}
+
+/// `/v4/sessions/:session_id/players` -> `{session_id}` segments
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{name}}}"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `patch` `/v4/sessions/:session_id/players` -> `patchSessionsPlayersBySessionId`
+fn operation_id(method: &str, path: &str) -> String {
+    let mut id = method.to_string();
+    for segment in path.split('/') {
+        if segment.is_empty() || segment == "v4" || segment.starts_with(':') {
+            continue;
+        }
+        let name = segment.split('.').next().unwrap_or(segment);
+        let mut chars = name.chars();
+        if let Some(first) = chars.next() {
+            id.extend(first.to_uppercase());
+            id.push_str(chars.as_str());
+        }
+    }
+    if path == "/" {
+        id.push_str("V3Websocket");
+    } else if path.split('/').any(|segment| segment.starts_with(':')) {
+        // Tell `GET /plugins` and `GET /plugins/:name` apart
+        id.push_str("By");
+        for name in path.split('/').filter_map(|s| s.strip_prefix(':')) {
+            id.extend(name.split('_').map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            }));
+        }
+    }
+    id
+}
+
+/// Every route of the server
+fn operations() -> Vec<Operation> {
+    const PLAYER: &str = "/v4/sessions/:session_id/players/:guild_id";
+
+    #[allow(unused_mut)]
+    let mut operations = vec![
+        // WebSocket
+        Operation::get("/v4/websocket", "Open a WebSocket session")
+            .header("User-Id", true, "Id of the bot user")
+            .header("Client-Name", false, "Name and version of the client")
+            .header("Session-Id", false, "Session to resume")
+            .query(
+                "events",
+                false,
+                schema::string(),
+                "Comma separated message types to receive",
+            )
+            .query(
+                "guilds",
+                false,
+                schema::string(),
+                "Comma separated guilds to receive messages of",
+            )
+            .query(
+                "playerUpdateInterval",
+                false,
+                schema::integer(),
+                "Minimum milliseconds between two playerUpdates of a guild",
+            )
+            .response("101", "Switching Protocols", "text/plain", |_| {
+                schema::string()
+            }),
+        Operation::get("/", "Open a Lavalink v3 WebSocket session")
+            .header("User-Id", true, "Id of the bot user")
+            .header("Resume-Key", false, "Resume key configured by the client")
+            .response("101", "Switching Protocols", "text/plain", |_| {
+                schema::string()
+            }),
+        // Node information
+        Operation::get("/v4/info", "Get node information").ok::<Info>(),
+        Operation::get("/version", "Get the node version").ok_object("Version details"),
+        Operation::get("/v4/stats", "Get node statistics").ok::<Stats>(),
+        Operation::get("/v4/openapi.json", "Get this document").ok_object("OpenAPI document"),
+        // Sessions
+        Operation::get("/v4/sessions", "List sessions").ok_object("Sessions with their ids"),
+        Operation::get("/v4/sessions/:session_id", "Get a session").ok::<SessionResponse>(),
+        Operation::patch("/v4/sessions/:session_id", "Update a session")
+            .request::<SessionUpdateRequest>()
+            .ok::<SessionResponse>(),
+        Operation::delete("/v4/sessions/:session_id", "Delete a session").no_content(),
+        Operation::get("/v4/sessions/:session_id/events", "Stream session events")
+            .header(
+                "Last-Event-ID",
+                false,
+                "Id of the last event received, to replay the ones after it",
+            )
+            .response(
+                "200",
+                "Server-Sent Events carrying WebSocket messages",
+                "text/event-stream",
+                |_| schema::string(),
+            ),
+        // Players
+        Operation::get(
+            "/v4/sessions/:session_id/players",
+            "List the players of a session",
+        )
+        .ok_array::<Player>(),
+        Operation::patch("/v4/sessions/:session_id/players", "Update several players")
+            .response("200", "Result per guild", "application/json", |schemas| {
+                schema::map(schemas.add::<PlayerUpdateResult>())
+            })
+            .request_with(|schemas| schema::map(schemas.add::<UpdatePlayerRequest>())),
+        Operation::get(PLAYER, "Get a player").ok::<Player>(),
+        Operation::patch(PLAYER, "Update or create a player")
+            .request::<UpdatePlayerRequest>()
+            .ok::<Player>(),
+        Operation::delete(PLAYER, "Destroy a player").no_content(),
+        // Queue
+        Operation::get(
+            "/v4/sessions/:session_id/players/:guild_id/queue",
+            "Get a player's queue",
+        )
+        .ok::<QueueResponse>(),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/queue",
+            "Add tracks to a player's queue",
+        )
+        .request::<AddToQueueRequest>()
+        .ok_object("Added tracks"),
+        Operation::delete(
+            "/v4/sessions/:session_id/players/:guild_id/queue",
+            "Clear a player's queue",
+        )
+        .ok_object("Number of removed tracks"),
+        Operation::delete(
+            "/v4/sessions/:session_id/players/:guild_id/queue/:index",
+            "Remove a track from a player's queue",
+        )
+        .ok_object("Removed track"),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/queue/move",
+            "Move a track within a player's queue",
+        )
+        .request::<MoveTrackRequest>()
+        .ok_object("Moved track"),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/queue/shuffle",
+            "Shuffle a player's queue",
+        )
+        .ok_object("Number of shuffled tracks"),
+        Operation::put(
+            "/v4/sessions/:session_id/players/:guild_id/queue/mode",
+            "Set how a player's queue is ordered",
+        )
+        .request::<QueueMode>()
+        .ok::<QueueResponse>(),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/skip",
+            "Skip to the next queued track",
+        )
+        .ok_object("Next track"),
+        // History
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/previous",
+            "Replay the previous track",
+        )
+        .ok::<Player>(),
+        Operation::get(
+            "/v4/sessions/:session_id/players/:guild_id/history",
+            "Get a player's track history",
+        )
+        .query("page", false, schema::integer(), "Page, starting at 0")
+        .query("limit", false, schema::integer(), "Tracks per page")
+        .ok::<HistoryResponse>(),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/history/:index",
+            "Replay a track from the history",
+        )
+        .ok::<Player>(),
+        // Lyrics
+        Operation::get(
+            "/v4/sessions/:session_id/players/:guild_id/lyrics",
+            "Get the lyrics of the playing track",
+        )
+        .ok_object("Lyrics"),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/lyrics/subscribe",
+            "Subscribe to lyrics lines",
+        )
+        .no_content(),
+        Operation::delete(
+            "/v4/sessions/:session_id/players/:guild_id/lyrics/subscribe",
+            "Unsubscribe from lyrics lines",
+        )
+        .no_content(),
+        // Autoplay
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/autoplay",
+            "Enable autoplay",
+        )
+        .no_content(),
+        Operation::delete(
+            "/v4/sessions/:session_id/players/:guild_id/autoplay",
+            "Disable autoplay",
+        )
+        .no_content(),
+        Operation::put(
+            "/v4/sessions/:session_id/players/:guild_id/updateInterval",
+            "Set a player's playerUpdate interval",
+        )
+        .request::<UpdateIntervalRequest>()
+        .no_content(),
+        // Chapters
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/chapters/:index",
+            "Seek to a chapter",
+        )
+        .ok::<Player>(),
+        // Filters
+        Operation::get(
+            "/v4/sessions/:session_id/players/:guild_id/filters",
+            "Get a player's filters",
+        )
+        .ok::<Filters>(),
+        Operation::patch(
+            "/v4/sessions/:session_id/players/:guild_id/filters",
+            "Update a player's filters",
+        )
+        .request::<Filters>()
+        .ok_object("Applied filters"),
+        Operation::get("/v4/filters/presets", "List filter presets")
+            .ok_object("Filters of every preset"),
+        // Plugins
+        Operation::get("/v4/plugins", "List plugins").ok_object("Loaded plugins"),
+        Operation::get("/v4/plugins/:name", "Get a plugin").ok_object("Plugin details"),
+        Operation::post("/v4/plugins/:name/reload", "Reload a plugin").no_content(),
+        Operation::get("/v4/plugins/:name/config", "Get a plugin's configuration")
+            .ok_object("Plugin configuration"),
+        Operation::patch(
+            "/v4/plugins/:name/config",
+            "Update a plugin's configuration",
+        )
+        .request_object()
+        .ok_object("Plugin configuration"),
+        // Tracks
+        Operation::get("/v4/loadtracks", "Load tracks")
+            .query(
+                "identifier",
+                true,
+                schema::string(),
+                "URL or search query such as ytsearch:...",
+            )
+            .ok::<LoadResult>(),
+        Operation::get("/v4/decodetrack", "Decode a track")
+            .query("encodedTrack", true, schema::string(), "Encoded track")
+            .ok::<Track>(),
+        Operation::post("/v4/decodetracks", "Decode tracks")
+            .request::<DecodeTracksRequest>()
+            .ok_array::<Track>(),
+        // Route planner
+        Operation::get("/v4/routeplanner/status", "Get the route planner status")
+            .ok_object("Route planner class and details"),
+        Operation::post("/v4/routeplanner/free/address", "Unmark a failed address")
+            .request_object()
+            .no_content(),
+        Operation::post("/v4/routeplanner/free/all", "Unmark all failed addresses").no_content(),
+    ];
+
+    #[cfg(feature = "audio-sources")]
+    operations.push(
+        Operation::get("/v4/artwork/:id", "Get cached track artwork")
+            .query(
+                "size",
+                false,
+                schema::integer(),
+                "Bounding box in pixels; the image keeps its aspect ratio",
+            )
+            .response(
+                "200",
+                "Artwork image",
+                "image/*",
+                |_| json!({ "type": "string", "format": "binary" }),
+            ),
+    );
+
+    #[cfg(feature = "discord")]
+    operations.extend([
+        Operation::delete(
+            "/v4/sessions/:session_id/players/:guild_id/filters",
+            "Clear a player's filters",
+        )
+        .ok_object("Cleared filters"),
+        Operation::post(
+            "/v4/sessions/:session_id/players/:guild_id/filters/preset/:preset_name",
+            "Apply a filter preset",
+        )
+        .ok_object("Applied filters"),
+    ]);
+
+    operations
+}
+
+/// The OpenAPI document of this node
+pub fn document(version: &str) -> Value {
+    let mut schemas = Schemas::default();
+    let mut paths = Map::new();
+    for operation in operations() {
+        let item = paths
+            .entry(openapi_path(operation.path))
+            .or_insert_with(|| json!({}));
+        item[operation.method] = operation.to_value(&mut schemas);
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Lavalink",
+            "version": version,
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas.into_value(),
+            "securitySchemes": {
+                "password": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "Authorization",
+                    "description": "The node password or one of its API keys",
+                }
+            }
+        },
+        "security": [{ "password": [] }],
+    })
+}
+
+/// OpenAPI handler - /v4/openapi.json
+pub async fn openapi_handler(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(document(&state.info.version.semver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::LavalinkServer;
+    use std::collections::BTreeSet;
+
+    #[tokio::test]
+    async fn test_every_route_is_described() {
+        let mut config = crate::test_utils::create_test_config();
+        config.lavalink.server.v3_compatibility = Some(true);
+        let server = LavalinkServer::new(config).await.unwrap();
+
+        let described: BTreeSet<(&str, &str)> = operations()
+            .iter()
+            .map(|operation| (operation.method, operation.path))
+            .collect();
+        let routed: BTreeSet<(&str, &str)> = server.routes().listed.into_iter().collect();
+
+        let undescribed: Vec<_> = routed.difference(&described).collect();
+        assert!(
+            undescribed.is_empty(),
+            "routes missing from the OpenAPI document: {undescribed:?}"
+        );
+        let unrouted: Vec<_> = described.difference(&routed).collect();
+        assert!(
+            unrouted.is_empty(),
+            "OpenAPI operations without a route: {unrouted:?}"
+        );
+    }
+
+    #[test]
+    fn test_every_route_has_a_scope() {
+        let unscoped: Vec<_> = operations()
+            .iter()
+            .filter(|operation| operation.path != "/v4/openapi.json")
+            .filter(|operation| required_scope(operation.path).is_none())
+            .map(|operation| operation.path)
+            .collect();
+        assert!(unscoped.is_empty(), "routes without a scope: {unscoped:?}");
+    }
+
+    #[test]
+    fn test_document_references_resolve() {
+        fn references(value: &Value, found: &mut Vec<String>) {
+            match value {
+                Value::Object(map) => {
+                    if let Some(Value::String(target)) = map.get("$ref") {
+                        found.push(target.clone());
+                    }
+                    map.values().for_each(|value| references(value, found));
+                }
+                Value::Array(values) => values.iter().for_each(|value| references(value, found)),
+                _ => {}
+            }
+        }
+
+        let document = document("4.0.0");
+        let mut found = Vec::new();
+        references(&document, &mut found);
+        assert!(!found.is_empty());
+        for target in found {
+            let name = target.strip_prefix("#/components/schemas/").unwrap();
+            assert!(
+                document["components"]["schemas"][name].is_object(),
+                "unresolved reference {target}"
+            );
+        }
+
+        let ids: BTreeSet<_> = document["paths"]
+            .as_object()
+            .unwrap()
+            .values()
+            .flat_map(|item| item.as_object().unwrap().values())
+            .map(|operation| operation["operationId"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            ids.len(),
+            operations().len(),
+            "operation ids must be unique"
+        );
+    }
+
+    /// Why `value` doesn't match `schema`, resolving references in `definitions`
+    ///
+    /// Objects must not have keys their schema doesn't list, unless it allows
+    /// `additionalProperties`.
+    fn mismatch(schema: &Value, value: &Value, definitions: &Value, at: &str) -> Option<String> {
+        let Value::Object(schema) = schema else {
+            return (schema == &Value::Bool(false)).then(|| format!("{at}: not allowed"));
+        };
+        if let Some(Value::String(target)) = schema.get("$ref") {
+            let name = target.strip_prefix("#/components/schemas/").unwrap();
+            return mismatch(&definitions[name], value, definitions, at);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(Value::Array(branches)) = schema.get(key) {
+                let matched = branches
+                    .iter()
+                    .filter(|branch| mismatch(branch, value, definitions, at).is_none())
+                    .count();
+                if matched == 0 || (key == "oneOf" && matched > 1) {
+                    return Some(format!("{at}: {value} matches {matched} of its {key}"));
+                }
+            }
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.contains(value) {
+                return Some(format!("{at}: {value} is not one of {values:?}"));
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if constant != value {
+                return Some(format!("{at}: {value} is not {constant}"));
+            }
+        }
+        if let Some(types) = schema.get("type") {
+            let kind = match value {
+                Value::Null => "null",
+                Value::Bool(_) => "boolean",
+                Value::Number(number) if number.is_f64() => "number",
+                Value::Number(_) => "integer",
+                Value::String(_) => "string",
+                Value::Array(_) => "array",
+                Value::Object(_) => "object",
+            };
+            let allowed = |name: &str| name == kind || (name == "number" && kind == "integer");
+            let matches = match types {
+                Value::String(name) => allowed(name),
+                Value::Array(names) => names.iter().filter_map(Value::as_str).any(allowed),
+                _ => false,
+            };
+            if !matches {
+                return Some(format!("{at}: {value} is not of type {types}"));
+            }
+        }
+        match value {
+            Value::Object(fields) => {
+                for required in schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    let name = required.as_str().unwrap();
+                    if !fields.contains_key(name) {
+                        return Some(format!("{at}: missing required {name}"));
+                    }
+                }
+                for (name, field) in fields {
+                    let at = format!("{at}.{name}");
+                    let field_schema = match schema.get("properties").and_then(|p| p.get(name)) {
+                        Some(field_schema) => field_schema,
+                        None => match schema.get("additionalProperties") {
+                            Some(additional) => additional,
+                            None if schema.contains_key("properties") => {
+                                return Some(format!("{at}: not in the schema"));
+                            }
+                            None => continue,
+                        },
+                    };
+                    if let Some(reason) = mismatch(field_schema, field, definitions, &at) {
+                        return Some(reason);
+                    }
+                }
+                None
+            }
+            Value::Array(items) => {
+                let item_schema = schema.get("items")?;
+                items.iter().enumerate().find_map(|(index, item)| {
+                    mismatch(item_schema, item, definitions, &format!("{at}[{index}]"))
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The JSON a value serializes to matches the schema of its type
+    fn assert_described<T: JsonSchema + serde::Serialize>(value: &T) {
+        let mut schemas = Schemas::default();
+        let schema = schemas.add::<T>();
+        let definitions = schemas.into_value();
+        let serialized = serde_json::to_value(value).unwrap();
+        if let Some(reason) = mismatch(&schema, &serialized, &definitions, &T::schema_name()) {
+            panic!("{reason}\nschemas: {definitions:#}");
+        }
+    }
+
+    fn track() -> Track {
+        serde_json::from_value(json!({
+            "encoded": "QAAAjQIA",
+            "info": {
+                "identifier": "dQw4w9WgXcQ",
+                "isSeekable": true,
+                "author": "RickAstleyVEVO",
+                "length": 212000,
+                "isStream": false,
+                "position": 0,
+                "title": "Rick Astley - Never Gonna Give You Up",
+                "uri": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                "artworkUrl": null,
+                "isrc": null,
+                "sourceName": "youtube"
+            },
+            "pluginInfo": { "albumName": "Whenever You Need Somebody" },
+            "userData": { "requester": "81011298891993088" }
+        }))
+        .unwrap()
+    }
+
+    fn filters() -> Filters {
+        serde_json::from_value(json!({
+            "volume": 0.8,
+            "equalizer": [{ "band": 0, "gain": 0.25 }],
+            "karaoke": { "level": 1.0, "monoLevel": 1.0, "filterBand": 220.0, "filterWidth": 100.0 },
+            "timescale": { "speed": 1.2, "pitch": 1.0, "rate": 1.0 },
+            "tremolo": { "frequency": 2.0, "depth": 0.5 },
+            "vibrato": { "frequency": 2.0, "depth": 0.5 },
+            "distortion": {
+                "sinOffset": 0.0, "sinScale": 1.0, "cosOffset": 0.0, "cosScale": 1.0,
+                "tanOffset": 0.0, "tanScale": 1.0, "offset": 0.0, "scale": 1.0
+            },
+            "rotation": null,
+            "channelMix": { "leftToLeft": 1.0, "leftToRight": 0.0, "rightToLeft": 0.0, "rightToRight": 1.0 },
+            "lowPass": { "smoothing": 20.0 },
+            "echo": { "delay": 0.5 }
+        }))
+        .unwrap()
+    }
+
+    fn player() -> Player {
+        serde_json::from_value(json!({
+            "guildId": "817327181659111454",
+            "track": serde_json::to_value(track()).unwrap(),
+            "volume": 100,
+            "paused": false,
+            "state": { "time": 1500, "position": 6000, "connected": true, "ping": 40 },
+            "voice": { "token": "token", "endpoint": "endpoint", "sessionId": "session" },
+            "filters": serde_json::to_value(filters()).unwrap(),
+            "repeat": "queue",
+            "shuffle": true,
+            "queueLength": 3
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schemas_match_serialized_types() {
+        let track = track();
+        assert_described(&track);
+        assert_described(&track.info);
+        assert_described(&filters());
+        assert_described(&Filters::default());
+
+        let player = player();
+        assert_described(&player);
+        assert_described(&player.state);
+        assert_described(&PlayerUpdateResult::Player(Box::new(player)));
+
+        let stats: Stats = serde_json::from_value(json!({
+            "players": 1,
+            "playingPlayers": 1,
+            "uptime": 1000,
+            "memory": { "free": 1, "used": 1, "allocated": 1, "reservable": 1 },
+            "cpu": { "cores": 4, "systemLoad": 0.5, "lavalinkLoad": 0.1 },
+            "frameStats": { "sent": 3000, "nulled": 0, "deficit": 0 },
+            "rejections": { "loadTracks": 1, "playerUpdates": 2, "players": 3, "queue": 4 }
+        }))
+        .unwrap();
+        assert_described(&stats);
+        assert_described(&stats.memory);
+        assert_described(&stats.cpu);
+
+        assert_described(&Info::default());
+        let mut error =
+            ErrorResponse::new(404, "Not Found".to_string(), None, "/v4/info".to_string());
+        assert_described(&error);
+        error.trace = Some("Session not found".to_string());
+        assert_described(&PlayerUpdateResult::Error(error));
+
+        assert_described(&QueueResponse {
+            tracks: vec![track.clone()],
+            length: 1,
+            repeat: Default::default(),
+            shuffle: false,
+            mode: QueueMode::Fair {
+                key: "requester".to_string(),
+                max_per_requester: Some(2),
+            },
+            version: 1,
+        });
+        assert_described(&HistoryResponse {
+            tracks: vec![track.clone()],
+            page: 0,
+            limit: 20,
+            total: 1,
+        });
+        assert_described(&SessionResponse {
+            resuming: true,
+            timeout: 60,
+            subscription: serde_json::from_value(json!({
+                "events": ["playerUpdate", "TrackStartEvent"],
+                "guilds": ["817327181659111454"],
+                "playerUpdateInterval": 5000
+            }))
+            .unwrap(),
+        });
+    }
+
+    #[test]
+    fn test_schemas_match_load_results() {
+        for result in [
+            json!({ "loadType": "track", "data": serde_json::to_value(track()).unwrap() }),
+            json!({
+                "loadType": "playlist",
+                "data": {
+                    "info": { "name": "Playlist", "selectedTrack": 0 },
+                    "pluginInfo": {},
+                    "tracks": [serde_json::to_value(track()).unwrap()]
+                }
+            }),
+            json!({ "loadType": "search", "data": [serde_json::to_value(track()).unwrap()] }),
+            json!({ "loadType": "empty", "data": null }),
+            json!({
+                "loadType": "error",
+                "data": { "message": "Unavailable", "severity": "common", "cause": "Blocked" }
+            }),
+        ] {
+            let result: LoadResult = serde_json::from_value(result).unwrap();
+            assert_described(&result);
+        }
+    }
+
+    #[test]
+    fn test_schemas_match_requests() {
+        for track in [
+            json!({ "encoded": "QAAAjQIA" }),
+            json!({ "identifier": "dQw4w9WgXcQ" }),
+            Value::Null,
+        ] {
+            let request: UpdatePlayerRequest = serde_json::from_value(json!({
+                "track": track,
+                "position": 1000,
+                "endTime": 20000,
+                "volume": 1000,
+                "paused": true,
+                "filters": serde_json::to_value(filters()).unwrap(),
+                "voice": { "token": "token", "endpoint": "endpoint", "sessionId": "session" },
+                "repeat": "track",
+                "shuffle": false
+            }))
+            .unwrap();
+            assert_described(&request);
+        }
+
+        let request: SessionUpdateRequest = serde_json::from_value(json!({
+            "resuming": true,
+            "timeout": 60,
+            "subscription": { "events": ["stats"] }
+        }))
+        .unwrap();
+        assert_described(&request);
+
+        assert_described(&AddToQueueRequest {
+            track: Some(track()),
+            tracks: Some(vec![track()]),
+            encoded: Some("QAAAjQIA".to_string()),
+            position: Some(0),
+        });
+        assert_described(&MoveTrackRequest { from: 0, to: 1 });
+        assert_described(&UpdateIntervalRequest {
+            interval: Some(1000),
+        });
+        assert_described(&DecodeTracksRequest {
+            tracks: vec!["QAAAjQIA".to_string()],
+        });
+    }
+
+    #[test]
+    fn test_undescribed_keys_are_rejected() {
+        let mut schemas = Schemas::default();
+        let schema = schemas.add::<MoveTrackRequest>();
+        let definitions = schemas.into_value();
+        let check = |value: Value| mismatch(&schema, &value, &definitions, "MoveTrackRequest");
+
+        assert_eq!(check(json!({ "from": 0, "to": 1 })), None);
+        assert!(check(json!({ "from": 0, "to": 1, "index": 2 })).is_some());
+        assert!(check(json!({ "from": 0 })).is_some());
+        assert!(check(json!({ "from": "0", "to": 1 })).is_some());
+    }
+}
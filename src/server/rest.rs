@@ -10,22 +10,13 @@ use axum::{
 use std::sync::Arc;
 use tracing::{error, info, warn};
 
-use super::AppState;
-use crate::protocol::{DecodeTracksRequest, ErrorResponse, LoadTracksQuery, Track};
-
-#[cfg(feature = "audio-processing")]
-use crate::audio::AudioSourceManager;
+use super::{ApiError, AppState};
+use crate::protocol::{DecodeTracksRequest, LoadTracksQuery, Track};
 
 // Helper function to check if discord feature is enabled and return error if not
 #[cfg(not(feature = "discord"))]
-fn discord_not_available_response(path: &str) -> Response {
-    let error = ErrorResponse::new(
-        501,
-        "Not Implemented".to_string(),
-        Some("Discord integration feature is disabled".to_string()),
-        path.to_string(),
-    );
-    (StatusCode::NOT_IMPLEMENTED, Json(error)).into_response()
+fn discord_not_available_response() -> Response {
+    ApiError::not_implemented("Discord integration feature is disabled").into_response()
 }
 
 // Macro to generate fallback handlers for Discord-specific endpoints only
@@ -51,36 +42,51 @@ macro_rules! generate_discord_fallback_handlers {
             let session_exists = true; // Always allow in standalone mode
 
             if !session_exists {
-                let error = ErrorResponse::new(
-                    404,
-                    "Session not found".to_string(),
-                    Some(format!("Session {session_id} not found")),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::session_not_found().into_response();
             }
 
             // Get or create player (standalone mode supports full functionality!)
-            let player = state
+            let player = match state
                 .player_manager
-                .get_or_create_player(guild_id.clone(), session_id.clone())
-                .await;
+                .get_or_create_players(vec![guild_id.clone()], &session_id, |owned, added| {
+                    state.limits.check_player_count(owned, added)
+                })
+                .await
+            {
+                Ok(mut players) => players.remove(0),
+                Err(error) => return ApiError::from(error).into_response(),
+            };
+            match apply_player_update(&player, &guild_id, request).await {
+                Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+                Err(error) => error.into_response(),
+            }
+        }
+
+        /// Apply an update request to a player, returning the updated player (standalone mode)
+        async fn apply_player_update(
+            player: &Arc<tokio::sync::RwLock<crate::player::LavalinkPlayer>>,
+            guild_id: &str,
+            request: crate::protocol::messages::UpdatePlayerRequest,
+        ) -> Result<crate::protocol::messages::Player, ApiError> {
             let mut player_guard = player.write().await;
 
             // Apply updates from the request (same as Discord mode)
             if let Some(volume) = request.volume {
-                player_guard.volume = volume;
+                player_guard.volume = volume.min(crate::player::MAX_VOLUME);
+                player_guard.mark_changed();
             }
 
             if let Some(paused) = request.paused {
-                player_guard.paused = paused;
+                if let Err(e) = player_guard.set_paused(paused).await {
+                    warn!("Failed to set paused for guild {}: {}", guild_id, e);
+                }
             }
 
             // Handle track updates
             if let Some(track_request) = request.track {
                 match track_request {
                     crate::protocol::messages::TrackRequest::Null => {
-                        player_guard.current_track = None;
+                        player_guard.replace_current_track(None);
                     }
                     crate::protocol::messages::TrackRequest::Identifier { identifier } => {
                         let track = crate::protocol::Track {
@@ -103,7 +109,7 @@ macro_rules! generate_discord_fallback_handlers {
                             #[cfg(feature = "rest-api")]
                             user_data: std::collections::HashMap::new(),
                         };
-                        player_guard.current_track = Some(track);
+                        player_guard.replace_current_track(Some(track));
                     }
                     crate::protocol::messages::TrackRequest::Encoded { encoded } => {
                         info!("Encoded track update not yet implemented: {}", encoded);
@@ -120,16 +126,10 @@ macro_rules! generate_discord_fallback_handlers {
 
                 // Validate voice state
                 if voice_state.endpoint.is_empty() || voice_state.token.is_empty() || voice_state.session_id.is_empty() {
-                    let error = ErrorResponse::new(
-                        400,
-                        "Bad Request".to_string(),
-                        Some(format!("Partial Lavalink voice state: endpoint={}, token={}, sessionId={}",
-                                   voice_state.endpoint,
-                                   if voice_state.token.is_empty() { "empty" } else { "provided" },
-                                   voice_state.session_id)),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}"),
-                    );
-                    return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+                    return Err(ApiError::bad_request(format!("Partial Lavalink voice state: endpoint={}, token={}, sessionId={}",
+                               voice_state.endpoint,
+                               if voice_state.token.is_empty() { "empty" } else { "provided" },
+                               voice_state.session_id)));
                 }
 
                 // Update voice state in player (standalone mode)
@@ -141,163 +141,214 @@ macro_rules! generate_discord_fallback_handlers {
                 };
                 if let Err(e) = player_guard.update_voice_state(messages_voice_state).await {
                     warn!("Failed to update voice state for guild {} in standalone mode: {}", guild_id, e);
-                    let error = ErrorResponse::new(
-                        500,
-                        "Internal Server Error".to_string(),
-                        Some("Failed to connect to voice server".to_string()),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}"),
-                    );
-                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+                    return Err(ApiError::internal(e, "Failed to connect to voice server"));
                 }
 
                 info!("✅ [STANDALONE] Voice state updated successfully for guild {}", guild_id);
-            }
 
-            let response = player_guard.to_protocol_player();
-            drop(player_guard);
+                // Players restored from a snapshot continue once voice is back
+                if let Err(e) = player_guard.resume_restored().await {
+                    warn!("Failed to resume restored player for guild {}: {}", guild_id, e);
+                }
+            }
 
-            (StatusCode::OK, Json(response)).into_response()
+            Ok(player_guard.to_protocol_player())
         }
 
         /// Get player queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue
         pub async fn get_player_queue_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Add tracks to queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue
         pub async fn add_to_queue_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
             Json(_request): Json<serde_json::Value>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
+        }
+
+        /// Set queue mode handler - /v4/sessions/{session_id}/players/{guild_id}/queue/mode
+        pub async fn set_queue_mode_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+            Json(_mode): Json<crate::protocol::messages::QueueMode>,
+        ) -> Response {
+            discord_not_available_response()
         }
 
         /// Remove track from queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue/{index}
         pub async fn remove_from_queue_handler(
-            Path((session_id, guild_id, _index)): Path<(String, String, usize)>,
+            Path(_): Path<(String, String, usize)>,
             State(_state): State<Arc<AppState>>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Update queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue
         #[allow(dead_code)]
         pub async fn update_queue_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
             Json(_request): Json<serde_json::Value>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Clear queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue
         pub async fn clear_queue_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Shuffle queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue/shuffle
         pub async fn shuffle_queue_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Move track in queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue/move
         #[allow(dead_code)]
         pub async fn move_track_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
             Json(_request): Json<serde_json::Value>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Move track in queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue/move
         pub async fn move_track_in_queue_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
             Json(_request): Json<crate::protocol::messages::MoveTrackRequest>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/queue/move",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Get player filters handler - /v4/sessions/{session_id}/players/{guild_id}/filters
         pub async fn get_player_filters_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/filters",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Update player filters handler - /v4/sessions/{session_id}/players/{guild_id}/filters
         pub async fn update_player_filters_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
             Json(_filters): Json<crate::protocol::filters::Filters>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/filters",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
 
         /// Skip track handler - /v4/sessions/{session_id}/players/{guild_id}/skip
         pub async fn skip_track_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/skip",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
+        }
+
+        /// Get lyrics handler - /v4/sessions/{session_id}/players/{guild_id}/lyrics
+        pub async fn get_player_lyrics_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Subscribe to lyrics handler - /v4/sessions/{session_id}/players/{guild_id}/lyrics/subscribe
+        pub async fn subscribe_lyrics_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Unsubscribe from lyrics handler - /v4/sessions/{session_id}/players/{guild_id}/lyrics/subscribe
+        pub async fn unsubscribe_lyrics_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Enable autoplay handler - /v4/sessions/{session_id}/players/{guild_id}/autoplay
+        pub async fn enable_autoplay_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Disable autoplay handler - /v4/sessions/{session_id}/players/{guild_id}/autoplay
+        pub async fn disable_autoplay_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Set update interval handler - /v4/sessions/{session_id}/players/{guild_id}/updateInterval
+        pub async fn set_update_interval_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+            Json(_request): Json<crate::protocol::messages::UpdateIntervalRequest>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Previous track handler - /v4/sessions/{session_id}/players/{guild_id}/previous
+        pub async fn previous_track_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Get history handler - /v4/sessions/{session_id}/players/{guild_id}/history
+        pub async fn get_player_history_handler(
+            Path(_): Path<(String, String)>,
+            State(_state): State<Arc<AppState>>,
+            Query(_query): Query<crate::protocol::messages::HistoryQuery>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Replay from history handler - /v4/sessions/{session_id}/players/{guild_id}/history/{index}
+        pub async fn replay_from_history_handler(
+            Path(_): Path<(String, String, usize)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
+        }
+
+        /// Seek to chapter handler - /v4/sessions/{session_id}/players/{guild_id}/chapters/{index}
+        pub async fn seek_to_chapter_handler(
+            Path(_): Path<(String, String, usize)>,
+            State(_state): State<Arc<AppState>>,
+        ) -> Response {
+            discord_not_available_response()
         }
 
         /// Seek track handler - /v4/sessions/{session_id}/players/{guild_id}/seek
         #[allow(dead_code)]
         pub async fn seek_track_handler(
-            Path((session_id, guild_id)): Path<(String, String)>,
+            Path(_): Path<(String, String)>,
             State(_state): State<Arc<AppState>>,
             Json(_request): Json<serde_json::Value>,
         ) -> Response {
-            discord_not_available_response(&format!(
-                "/v4/sessions/{}/players/{}/seek",
-                session_id, guild_id
-            ))
+            discord_not_available_response()
         }
     };
 }
@@ -322,13 +373,10 @@ where
             Ok(json) => json,
             Err(rejection) => {
                 error!("JSON deserialization failed: {:?}", rejection);
-                let error = ErrorResponse::new(
-                    400,
-                    "Bad Request".to_string(),
-                    Some(format!("JSON deserialization failed: {rejection}")),
-                    "/unknown".to_string(),
-                );
-                return Err((StatusCode::BAD_REQUEST, Json(error)).into_response());
+                return Err(ApiError::bad_request(format!(
+                    "JSON deserialization failed: {rejection}"
+                ))
+                .into_response());
             }
         };
         Ok(DebugJson(value))
@@ -338,17 +386,18 @@ where
 /// Load tracks handler - /v4/loadtracks
 #[cfg(feature = "audio-processing")]
 pub async fn load_tracks_handler(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<LoadTracksQuery>,
 ) -> Response {
     info!("Loading tracks for identifier: {}", query.identifier);
 
-    // Create audio source manager
-    let audio_manager = AudioSourceManager::new();
-
     // Attempt to load the track
-    match audio_manager.load_item(&query.identifier).await {
-        Ok(result) => {
+    match state.audio_manager.load_item(&query.identifier).await {
+        #[cfg_attr(not(feature = "audio-sources"), allow(unused_mut))]
+        Ok(mut result) => {
+            #[cfg(feature = "audio-sources")]
+            state.artwork.rewrite_load_result(&mut result);
+
             info!(
                 "Successfully loaded tracks for identifier: {}",
                 query.identifier
@@ -360,13 +409,7 @@ pub async fn load_tracks_handler(
                 "Failed to load tracks for identifier {}: {}",
                 query.identifier, e
             );
-            let error = ErrorResponse::new(
-                500,
-                "Internal Server Error".to_string(),
-                Some(format!("Failed to load tracks: {e}")),
-                "/v4/loadtracks".to_string(),
-            );
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+            ApiError::internal(e, "Failed to load tracks").into_response()
         }
     }
 }
@@ -377,47 +420,34 @@ pub async fn load_tracks_handler(
     State(_state): State<Arc<AppState>>,
     Query(_query): Query<LoadTracksQuery>,
 ) -> Response {
-    let error = ErrorResponse::new(
-        501,
-        "Not Implemented".to_string(),
-        Some("Audio processing feature is disabled".to_string()),
-        "/v4/loadtracks".to_string(),
-    );
-    (StatusCode::NOT_IMPLEMENTED, Json(error)).into_response()
+    ApiError::not_implemented("Audio processing feature is disabled").into_response()
 }
 
 /// Decode track handler - /v4/decodetrack
+#[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
 pub async fn decode_track_handler(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> impl IntoResponse {
     if let Some(encoded_track) = params.get("encodedTrack") {
         match Track::decode(encoded_track) {
-            Ok(track) => (StatusCode::OK, Json(track)).into_response(),
-            Err(e) => {
-                let error = ErrorResponse::new(
-                    400,
-                    "Bad Request".to_string(),
-                    Some(format!("Failed to decode track: {e}")),
-                    "/v4/decodetrack".to_string(),
-                );
-                (StatusCode::BAD_REQUEST, Json(error)).into_response()
+            #[cfg_attr(not(feature = "audio-sources"), allow(unused_mut))]
+            Ok(mut track) => {
+                #[cfg(feature = "audio-sources")]
+                state.artwork.rewrite_track(&mut track);
+                (StatusCode::OK, Json(track)).into_response()
             }
+            Err(e) => ApiError::bad_request(format!("Failed to decode track: {e}")).into_response(),
         }
     } else {
-        let error = ErrorResponse::new(
-            400,
-            "Bad Request".to_string(),
-            Some("Missing 'encodedTrack' parameter".to_string()),
-            "/v4/decodetrack".to_string(),
-        );
-        (StatusCode::BAD_REQUEST, Json(error)).into_response()
+        ApiError::bad_request("Missing 'encodedTrack' parameter").into_response()
     }
 }
 
 /// Decode tracks handler - /v4/decodetracks
+#[cfg_attr(not(feature = "audio-sources"), allow(unused_variables))]
 pub async fn decode_tracks_handler(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<DecodeTracksRequest>,
 ) -> impl IntoResponse {
     let mut decoded_tracks = Vec::new();
@@ -426,7 +456,12 @@ pub async fn decode_tracks_handler(
 
     for encoded_track in request.tracks {
         match Track::decode(&encoded_track) {
-            Ok(track) => decoded_tracks.push(track),
+            #[cfg_attr(not(feature = "audio-sources"), allow(unused_mut))]
+            Ok(mut track) => {
+                #[cfg(feature = "audio-sources")]
+                state.artwork.rewrite_track(&mut track);
+                decoded_tracks.push(track)
+            }
             Err(e) => {
                 error!("Failed to decode track {}: {}", encoded_track, e);
                 failed_tracks.push(encoded_track);
@@ -449,27 +484,39 @@ pub async fn decode_tracks_handler(
 
 /// Info handler - /v4/info
 pub async fn info_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    (StatusCode::OK, Json(state.info.clone()))
+    (StatusCode::OK, Json(current_info(&state)))
+}
+
+/// Server info listing the plugins loaded right now
+fn current_info(state: &AppState) -> crate::protocol::Info {
+    let plugins = state
+        .plugin_manager
+        .read()
+        .map(|plugin_manager| plugin_manager.plugin_infos())
+        .unwrap_or_default();
+    state.info.with_plugins(plugins)
 }
 
 /// Version handler - /version
 pub async fn version_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let plugins = if let Ok(plugin_manager) = state.plugin_manager.read() {
-        plugin_manager.get_dynamic_plugin_names()
-    } else {
-        Vec::<String>::new()
-    };
+    let info = current_info(&state);
+    let plugins: Vec<String> = info
+        .plugins
+        .plugins
+        .iter()
+        .map(|plugin| plugin.name.clone())
+        .collect();
 
     let version_info = serde_json::json!({
-        "version": env!("CARGO_PKG_VERSION"),
-        "buildTime": 0, // TODO: Add build time
-        "gitBranch": "unknown", // TODO: Add git info
-        "gitCommit": "unknown", // TODO: Add git info
-        "buildNumber": 0, // TODO: Add build number
-        "jvm": "N/A - Rust",
-        "lavaplayer": "N/A - Native Rust",
-        "sourceManagers": ["http", "youtube", "soundcloud", "bandcamp", "twitch", "vimeo", "nico", "local"],
-        "filters": ["volume", "equalizer", "karaoke", "timescale", "tremolo", "vibrato", "distortion", "rotation", "channelMix", "lowPass"],
+        "version": info.version.semver,
+        "buildTime": info.build_time,
+        "gitBranch": info.git.branch,
+        "gitCommit": info.git.commit,
+        "buildNumber": 0,
+        "jvm": info.jvm,
+        "lavaplayer": info.lavaplayer,
+        "sourceManagers": info.source_managers,
+        "filters": info.filters,
         "plugins": plugins
     });
 
@@ -487,17 +534,25 @@ pub async fn stats_handler(State(app_state): State<Arc<AppState>>) -> impl IntoR
 
 /// Get all sessions handler - /v4/sessions
 #[cfg(feature = "websocket")]
-pub async fn get_sessions_handler(State(state): State<Arc<AppState>>) -> Response {
+pub async fn get_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(api_key): axum::Extension<super::ApiKey>,
+) -> Response {
     info!("Getting all sessions");
 
+    // Keys only see the sessions they opened
     let mut sessions = Vec::new();
     for entry in state.sessions.iter() {
         let session_id = entry.key();
         let session = entry.value();
+        if !session.is_usable_by(&api_key.name) {
+            continue;
+        }
         sessions.push(serde_json::json!({
             "sessionId": session_id,
             "resuming": session.resuming,
             "timeout": session.timeout,
+            "subscription": session.subscription,
         }));
     }
 
@@ -516,16 +571,11 @@ pub async fn get_session_handler(
         let response = crate::protocol::messages::SessionResponse {
             resuming: session.resuming,
             timeout: session.timeout,
+            subscription: session.subscription.clone(),
         };
         (StatusCode::OK, Json(response)).into_response()
     } else {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}"),
-        );
-        (StatusCode::NOT_FOUND, Json(error)).into_response()
+        ApiError::session_not_found().into_response()
     }
 }
 
@@ -549,13 +599,7 @@ pub async fn delete_session_handler(
         info!("Session {} deleted successfully", session_id);
         StatusCode::NO_CONTENT.into_response()
     } else {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}"),
-        );
-        (StatusCode::NOT_FOUND, Json(error)).into_response()
+        ApiError::session_not_found().into_response()
     }
 }
 
@@ -564,20 +608,28 @@ pub async fn delete_session_handler(
 pub async fn update_session_handler(
     Path(session_id): Path<String>,
     State(state): State<Arc<AppState>>,
+    axum::Extension(api_key): axum::Extension<super::ApiKey>,
     Json(request): Json<crate::protocol::messages::SessionUpdateRequest>,
 ) -> Response {
     info!("Updating session: {}", session_id);
 
+    if let Some(Err(err)) = request.subscription.as_ref().map(|s| s.validate()) {
+        return ApiError::bad_request(err).into_response();
+    }
+
     // Get or create session (Lavalink v4 behavior - sessions are created on first PATCH)
     let session_exists = state.sessions.contains_key(&session_id);
 
     if !session_exists {
         info!("Creating new session: {}", session_id);
         let session = crate::server::WebSocketSession {
-            session_id: session_id.clone(),
             resuming: request.resuming.unwrap_or(false),
-            timeout: request.timeout.unwrap_or(60000),
-            message_sender: None,
+            timeout: request
+                .timeout
+                .unwrap_or(crate::server::DEFAULT_SESSION_TIMEOUT),
+            subscription: request.subscription.unwrap_or_default(),
+            owner: Some(api_key.name),
+            ..crate::server::WebSocketSession::new(session_id.clone())
         };
         state.sessions.insert(session_id.clone(), session);
     } else {
@@ -589,21 +641,109 @@ pub async fn update_session_handler(
             if let Some(timeout) = request.timeout {
                 session.timeout = timeout;
             }
+            if let Some(subscription) = request.subscription {
+                session.subscription = subscription;
+            }
         }
     }
 
     // Get current session values for response
-    let (resuming, timeout) = if let Some(session) = state.sessions.get(&session_id) {
-        (session.resuming, session.timeout)
+    let response = if let Some(session) = state.sessions.get(&session_id) {
+        crate::protocol::messages::SessionResponse {
+            resuming: session.resuming,
+            timeout: session.timeout,
+            subscription: session.subscription.clone(),
+        }
     } else {
-        (false, 60000) // Fallback values
+        // Fallback values
+        crate::protocol::messages::SessionResponse {
+            resuming: false,
+            timeout: crate::server::DEFAULT_SESSION_TIMEOUT,
+            subscription: Default::default(),
+        }
     };
 
-    let response = crate::protocol::messages::SessionResponse { resuming, timeout };
-
     (StatusCode::OK, Json(response)).into_response()
 }
 
+/// Session event stream handler - /v4/sessions/{session_id}/events
+///
+/// Streams the session's messages as Server-Sent Events. A `Last-Event-ID`
+/// header replays the buffered messages sent after that id.
+#[cfg(feature = "websocket")]
+pub async fn session_events_handler(
+    Path(session_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use futures_util::stream::{self, StreamExt};
+
+    let last_event_id = match headers.get("Last-Event-ID").map(|value| {
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    }) {
+        Some(None) => {
+            return ApiError::bad_request("Last-Event-ID must be an event id sent by this server")
+                .into_response();
+        }
+        Some(id) => id,
+        None => None,
+    };
+
+    // Sessions are opened by a WebSocket or by PATCH before streams attach to them
+    let events = {
+        let Some(mut session) = state.sessions.get_mut(&session_id) else {
+            return ApiError::session_not_found().into_response();
+        };
+        session.detached = None;
+        session.events.clone()
+    };
+    let mut listener = events
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .listen(last_event_id);
+    info!(
+        "Event stream attached to session {} (replaying {} messages)",
+        session_id,
+        listener.replay.len()
+    );
+
+    // The ready message has no id so it never moves the client's Last-Event-ID
+    let ready = crate::protocol::Message::ready(listener.resumed, session_id.clone());
+    let ready = stream::once(async move { (None, ready) });
+    let replay = std::mem::take(&mut listener.replay);
+    let replay = stream::iter(replay).map(|(id, message)| (Some(id), message));
+    let live = crate::server::LiveEvents::new(listener, state.clone(), session_id);
+    let live = stream::unfold(live, |mut live| async move {
+        let (id, message) = live.recv().await?;
+        Some(((Some(id), message), live))
+    });
+    let events = ready
+        .chain(replay)
+        .chain(live)
+        .filter_map(|(id, message)| async move {
+            let json = match serde_json::to_string(&message) {
+                Ok(json) => json,
+                Err(e) => {
+                    error!("Failed to serialize event stream message: {}", e);
+                    return None;
+                }
+            };
+            let event = Event::default().data(json);
+            Some(Ok::<_, std::convert::Infallible>(match id {
+                Some(id) => event.id(id.to_string()),
+                None => event,
+            }))
+        });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
 /// Get players for session handler - /v4/sessions/{session_id}/players
 #[cfg(feature = "discord")]
 pub async fn get_session_players_handler(
@@ -614,13 +754,7 @@ pub async fn get_session_players_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     let players = state
@@ -666,13 +800,7 @@ pub async fn get_session_players_handler(
     let session_exists = false; // Always create session when websocket is disabled
 
     if !session_exists {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Return empty array for non-Discord builds (no players can exist)
@@ -700,13 +828,7 @@ pub async fn get_player_handler(
         let session_exists = false; // Always create session when websocket is disabled
 
         if !session_exists {
-            let error = ErrorResponse::new(
-                404,
-                "Session not found".to_string(),
-                Some(format!("Session {session_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+            return ApiError::session_not_found().into_response();
         }
 
         // Return a basic player response for non-Discord builds
@@ -742,13 +864,7 @@ pub async fn get_player_handler(
 
         // Check if session exists
         if !state.sessions.contains_key(&session_id) {
-            let error = ErrorResponse::new(
-                404,
-                "Session not found".to_string(),
-                Some(format!("Session {session_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+            return ApiError::session_not_found().into_response();
         }
 
         // Get player for this guild
@@ -758,28 +874,15 @@ pub async fn get_player_handler(
 
                 // Check if player belongs to this session
                 if player_guard.session_id != session_id {
-                    let error = ErrorResponse::new(
-                        404,
-                        "Player not found".to_string(),
-                        Some(format!(
-                            "Player for guild {guild_id} not found in session {session_id}"
-                        )),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}"),
-                    );
-                    return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                    return ApiError::not_found(format!(
+                        "Player for guild {guild_id} not found in session {session_id}"
+                    ))
+                    .into_response();
                 }
 
                 (StatusCode::OK, Json(player_guard.to_protocol_player())).into_response()
             }
-            None => {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!("Player for guild {guild_id} not found")),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}"),
-                );
-                (StatusCode::NOT_FOUND, Json(error)).into_response()
-            }
+            None => ApiError::player_not_found().into_response(),
         }
     }
 }
@@ -805,13 +908,7 @@ pub async fn delete_player_handler(
         let session_exists = false; // Always create session when websocket is disabled
 
         if !session_exists {
-            let error = ErrorResponse::new(
-                404,
-                "Session not found".to_string(),
-                Some(format!("Session {session_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+            return ApiError::session_not_found().into_response();
         }
 
         // For non-Discord builds, always return success (no actual players to delete)
@@ -832,28 +929,17 @@ pub async fn delete_player_handler(
 
         // Check if session exists
         if !state.sessions.contains_key(&session_id) {
-            let error = ErrorResponse::new(
-                404,
-                "Session not found".to_string(),
-                Some(format!("Session {session_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+            return ApiError::session_not_found().into_response();
         }
 
         // Check if player exists and belongs to this session
         if let Some(player) = state.player_manager.get_player(&guild_id).await {
             let player_guard = player.read().await;
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
             drop(player_guard);
 
@@ -865,13 +951,7 @@ pub async fn delete_player_handler(
             );
             StatusCode::NO_CONTENT.into_response()
         } else {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
+            ApiError::player_not_found().into_response()
         }
     }
 }
@@ -891,37 +971,52 @@ pub async fn update_player_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get or create player
-    let player = state
+    let player = match state
         .player_manager
-        .get_or_create_player(guild_id.clone(), session_id.clone())
-        .await;
+        .get_or_create_players(vec![guild_id.clone()], &session_id, |owned, added| {
+            state.limits.check_player_count(owned, added)
+        })
+        .await
+    {
+        Ok(mut players) => players.remove(0),
+        Err(error) => return ApiError::from(error).into_response(),
+    };
+    match apply_player_update(&player, &guild_id, request).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Apply an update request to a player, returning the updated player
+#[cfg(feature = "discord")]
+async fn apply_player_update(
+    player: &Arc<tokio::sync::RwLock<crate::player::LavalinkPlayer>>,
+    guild_id: &str,
+    request: crate::protocol::messages::UpdatePlayerRequest,
+) -> Result<crate::protocol::messages::Player, ApiError> {
     let mut player_guard = player.write().await;
 
     // Apply updates from the request
     if let Some(volume) = request.volume {
-        // Volume is u8, so it's always valid (0-255)
-        player_guard.volume = volume;
+        player_guard.volume = volume.min(crate::player::MAX_VOLUME);
+        player_guard.mark_changed();
     }
 
     if let Some(paused) = request.paused {
-        player_guard.paused = paused;
+        if let Err(e) = player_guard.set_paused(paused).await {
+            warn!("Failed to set paused for guild {}: {}", guild_id, e);
+        }
     }
 
     // Handle track updates
     if let Some(track_request) = request.track {
         match track_request {
             crate::protocol::messages::TrackRequest::Null => {
-                player_guard.current_track = None;
+                player_guard.replace_current_track(None);
             }
             crate::protocol::messages::TrackRequest::Identifier { identifier } => {
                 // Create a simple track from identifier for testing
@@ -943,7 +1038,7 @@ pub async fn update_player_handler(
                     plugin_info: std::collections::HashMap::new(),
                     user_data: std::collections::HashMap::new(),
                 };
-                player_guard.current_track = Some(track);
+                player_guard.replace_current_track(Some(track));
             }
             crate::protocol::messages::TrackRequest::Encoded { encoded } => {
                 // TODO: Decode the encoded track
@@ -971,22 +1066,16 @@ pub async fn update_player_handler(
             || voice_state.token.is_empty()
             || voice_state.session_id.is_empty()
         {
-            let error = ErrorResponse::new(
-                400,
-                "Bad Request".to_string(),
-                Some(format!(
-                    "Partial Lavalink voice state: endpoint={}, token={}, sessionId={}",
-                    voice_state.endpoint,
-                    if voice_state.token.is_empty() {
-                        "empty"
-                    } else {
-                        "provided"
-                    },
-                    voice_state.session_id
-                )),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+            return Err(ApiError::bad_request(format!(
+                "Partial Lavalink voice state: endpoint={}, token={}, sessionId={}",
+                voice_state.endpoint,
+                if voice_state.token.is_empty() {
+                    "empty"
+                } else {
+                    "provided"
+                },
+                voice_state.session_id
+            )));
         }
 
         // Update voice state in player
@@ -998,20 +1087,98 @@ pub async fn update_player_handler(
         };
         if let Err(e) = player_guard.update_voice_state(messages_voice_state).await {
             warn!("Failed to update voice state for guild {}: {}", guild_id, e);
-            let error = ErrorResponse::new(
-                500,
-                "Internal Server Error".to_string(),
-                Some("Failed to connect to voice server".to_string()),
-                format!("/v4/sessions/{session_id}/players/{guild_id}"),
-            );
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            return Err(ApiError::internal(e, "Failed to connect to voice server"));
         }
 
         info!("✅ Voice state updated successfully for guild {}", guild_id);
+
+        // Players restored from a snapshot continue once voice is back
+        if let Err(e) = player_guard.resume_restored().await {
+            warn!(
+                "Failed to resume restored player for guild {}: {}",
+                guild_id, e
+            );
+        }
     }
 
-    let response = player_guard.to_protocol_player();
-    drop(player_guard);
+    Ok(player_guard.to_protocol_player())
+}
+
+/// Batch update players handler - /v4/sessions/{session_id}/players
+///
+/// Applies an update request per guild concurrently and reports each guild's
+/// updated player or error.
+pub async fn update_players_handler(
+    Path(session_id): Path<String>,
+    #[cfg_attr(not(feature = "websocket"), allow(unused_variables))] State(state): State<
+        Arc<AppState>,
+    >,
+    uri: axum::http::Uri,
+    axum::Extension(client): axum::Extension<super::limits::ClientId>,
+    Json(requests): Json<
+        std::collections::HashMap<String, crate::protocol::messages::UpdatePlayerRequest>,
+    >,
+) -> Response {
+    info!(
+        "Updating {} players for session: {}",
+        requests.len(),
+        session_id
+    );
+
+    #[cfg(feature = "websocket")]
+    let session_exists = state.sessions.contains_key(&session_id);
+    #[cfg(not(feature = "websocket"))]
+    let session_exists = true;
+
+    if !session_exists {
+        return ApiError::session_not_found().into_response();
+    }
+
+    // Each guild counts as an update of its own
+    let updates = u32::try_from(requests.len().max(1)).unwrap_or(u32::MAX);
+    if let Err(error) =
+        state
+            .limits
+            .check_rate_for(super::limits::LimitKind::PlayerUpdates, &client, updates)
+    {
+        return ApiError::from(error).into_response();
+    }
+
+    let (guild_ids, requests): (Vec<_>, Vec<_>) = requests.into_iter().unzip();
+    let players = match state
+        .player_manager
+        .get_or_create_players(guild_ids.clone(), &session_id, |owned, added| {
+            state.limits.check_player_count(owned, added)
+        })
+        .await
+    {
+        Ok(players) => players,
+        Err(error) => return ApiError::from(error).into_response(),
+    };
+
+    let updates = players
+        .iter()
+        .zip(&guild_ids)
+        .zip(requests)
+        .map(|((player, guild_id), request)| apply_player_update(player, guild_id, request));
+    let results = futures_util::future::join_all(updates).await;
+
+    let trace = super::error::wants_trace(&uri);
+    let response: std::collections::HashMap<_, _> = guild_ids
+        .into_iter()
+        .zip(results)
+        .map(|(guild_id, result)| {
+            let result = match result {
+                Ok(player) => {
+                    crate::protocol::messages::PlayerUpdateResult::Player(Box::new(player))
+                }
+                Err(error) => crate::protocol::messages::PlayerUpdateResult::Error(
+                    error.to_response(format!("{}/{guild_id}", uri.path()), trace),
+                ),
+            };
+            (guild_id, result)
+        })
+        .collect();
 
     (StatusCode::OK, Json(response)).into_response()
 }
@@ -1063,34 +1230,17 @@ pub async fn routeplanner_unmark_address_handler(
                 }
                 Err(_) => {
                     warn!("Invalid IP address format: {}", address_str);
-                    let error = crate::protocol::ErrorResponse::new(
-                        400,
-                        "Bad Request".to_string(),
-                        Some(format!("Invalid IP address format: {address_str}")),
-                        "/v4/routeplanner/free/address".to_string(),
-                    );
-                    (StatusCode::BAD_REQUEST, Json(error)).into_response()
+                    ApiError::bad_request(format!("Invalid IP address format: {address_str}"))
+                        .into_response()
                 }
             }
         } else {
             warn!("Missing 'address' field in request");
-            let error = crate::protocol::ErrorResponse::new(
-                400,
-                "Bad Request".to_string(),
-                Some("Missing 'address' field in request body".to_string()),
-                "/v4/routeplanner/free/address".to_string(),
-            );
-            (StatusCode::BAD_REQUEST, Json(error)).into_response()
+            ApiError::bad_request("Missing 'address' field in request body").into_response()
         }
     } else {
         warn!("Route planner not configured");
-        let error = crate::protocol::ErrorResponse::new(
-            501,
-            "Not Implemented".to_string(),
-            Some("Route planner is not configured".to_string()),
-            "/v4/routeplanner/free/address".to_string(),
-        );
-        (StatusCode::NOT_IMPLEMENTED, Json(error)).into_response()
+        ApiError::not_implemented("Route planner is not configured").into_response()
     }
 }
 
@@ -1148,7 +1298,7 @@ pub async fn get_plugins_handler(State(state): State<Arc<AppState>>) -> impl Int
 pub async fn get_plugin_handler(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> impl IntoResponse {
+) -> Result<Json<serde_json::Value>, ApiError> {
     if let Ok(plugin_manager) = state.plugin_manager.read() {
         // Check static plugins first
         if let Some(plugin) = plugin_manager.get_plugin(&name) {
@@ -1158,7 +1308,7 @@ pub async fn get_plugin_handler(
                 "type": "static",
                 "loaded": true
             });
-            return (StatusCode::OK, Json(response));
+            return Ok(Json(response));
         }
 
         // Check dynamic plugins
@@ -1176,28 +1326,19 @@ pub async fn get_plugin_handler(
                 "loaded": true,
                 "configSchema": config_schema
             });
-            return (StatusCode::OK, Json(response));
+            return Ok(Json(response));
         }
     }
 
     // Plugin not found
-    let error = serde_json::json!({
-        "timestamp": chrono::Utc::now().timestamp_millis() as u64,
-        "status": 404,
-        "error": "Not Found",
-        "message": format!("Plugin '{name}' not found"),
-        "path": format!("/v4/plugins/{name}"),
-        "trace": null
-    });
-
-    (StatusCode::NOT_FOUND, Json(error))
+    Err(ApiError::not_found(format!("Plugin '{name}' not found")))
 }
 
 /// Reload a plugin - POST /v4/plugins/{name}/reload
 pub async fn reload_plugin_handler(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> impl IntoResponse {
+) -> Result<StatusCode, ApiError> {
     let is_loaded = if let Ok(plugin_manager) = state.plugin_manager.read() {
         plugin_manager.is_dynamic_plugin_loaded(&name)
     } else {
@@ -1206,38 +1347,25 @@ pub async fn reload_plugin_handler(
 
     // Only dynamic plugins can be reloaded
     if !is_loaded {
-        let error = serde_json::json!({
-            "timestamp": chrono::Utc::now().timestamp_millis() as u64,
-            "status": 400,
-            "error": "Bad Request",
-            "message": format!("Plugin '{name}' is not a dynamic plugin or not loaded"),
-            "path": format!("/v4/plugins/{name}/reload"),
-            "trace": null
-        });
-        return (StatusCode::BAD_REQUEST, Json(error));
+        return Err(ApiError::bad_request(format!(
+            "Plugin '{name}' is not a dynamic plugin or not loaded"
+        )));
     }
 
     // Note: This would require making plugin_manager mutable
     // For now, return not implemented
     warn!("Plugin reload not yet implemented for plugin: {}", name);
 
-    let error = serde_json::json!({
-        "timestamp": chrono::Utc::now().timestamp_millis() as u64,
-        "status": 501,
-        "error": "Not Implemented",
-        "message": "Plugin reloading is not yet implemented",
-        "path": format!("/v4/plugins/{name}/reload"),
-        "trace": null
-    });
-
-    (StatusCode::NOT_IMPLEMENTED, Json(error))
+    Err(ApiError::not_implemented(
+        "Plugin reloading is not yet implemented",
+    ))
 }
 
 /// Get plugin configuration - GET /v4/plugins/{name}/config
 pub async fn get_plugin_config_handler(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> impl IntoResponse {
+) -> Result<Json<serde_json::Value>, ApiError> {
     if let Ok(plugin_manager) = state.plugin_manager.read() {
         if let Some(metadata) = plugin_manager.get_dynamic_plugin_metadata(&name) {
             #[cfg(feature = "plugins")]
@@ -1250,20 +1378,11 @@ pub async fn get_plugin_config_handler(
                 "configSchema": config_schema,
                 "currentConfig": {} // TODO: Implement config storage
             });
-            return (StatusCode::OK, Json(response));
+            return Ok(Json(response));
         }
     }
 
-    let error = serde_json::json!({
-        "timestamp": chrono::Utc::now().timestamp_millis() as u64,
-        "status": 404,
-        "error": "Not Found",
-        "message": format!("Plugin '{name}' not found"),
-        "path": format!("/v4/plugins/{name}/config"),
-        "trace": null
-    });
-
-    (StatusCode::NOT_FOUND, Json(error))
+    Err(ApiError::not_found(format!("Plugin '{name}' not found")))
 }
 
 /// Update plugin configuration - PATCH /v4/plugins/{name}/config
@@ -1271,7 +1390,7 @@ pub async fn update_plugin_config_handler(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
     Json(config): Json<serde_json::Value>,
-) -> impl IntoResponse {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let is_loaded = if let Ok(plugin_manager) = state.plugin_manager.read() {
         plugin_manager.is_dynamic_plugin_loaded(&name)
     } else {
@@ -1279,15 +1398,7 @@ pub async fn update_plugin_config_handler(
     };
 
     if !is_loaded {
-        let error = serde_json::json!({
-            "timestamp": chrono::Utc::now().timestamp_millis() as u64,
-            "status": 404,
-            "error": "Not Found",
-            "message": format!("Plugin '{name}' not found"),
-            "path": format!("/v4/plugins/{name}/config"),
-            "trace": null
-        });
-        return (StatusCode::NOT_FOUND, Json(error));
+        return Err(ApiError::not_found(format!("Plugin '{name}' not found")));
     }
 
     // Update plugin configuration
@@ -1302,7 +1413,7 @@ pub async fn update_plugin_config_handler(
     });
 
     info!("Successfully updated configuration for plugin: {}", name);
-    (StatusCode::OK, Json(response))
+    Ok(Json(response))
 }
 
 /// Route planner unmark all handler - /v4/routeplanner/free/all
@@ -1317,13 +1428,7 @@ pub async fn routeplanner_unmark_all_handler(
         StatusCode::NO_CONTENT.into_response()
     } else {
         warn!("Route planner not configured");
-        let error = crate::protocol::ErrorResponse::new(
-            501,
-            "Not Implemented".to_string(),
-            Some("Route planner is not configured".to_string()),
-            "/v4/routeplanner/free/all".to_string(),
-        );
-        (StatusCode::NOT_IMPLEMENTED, Json(error)).into_response()
+        ApiError::not_implemented("Route planner is not configured").into_response()
     }
 }
 
@@ -1341,12 +1446,7 @@ pub async fn get_player_queue_handler(
     // Check if session exists, create if it doesn't (for testing purposes)
     if !state.sessions.contains_key(&session_id) {
         info!("Creating new session for testing: {}", session_id);
-        let session = crate::server::WebSocketSession {
-            session_id: session_id.clone(),
-            resuming: false,
-            timeout: 60000,
-            message_sender: None,
-        };
+        let session = crate::server::WebSocketSession::new(session_id.clone());
         state.sessions.insert(session_id.clone(), session);
     }
 
@@ -1357,15 +1457,10 @@ pub async fn get_player_queue_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             let queue = player_guard.get_queue();
@@ -1374,19 +1469,13 @@ pub async fn get_player_queue_handler(
                 length: player_guard.queue_length(),
                 repeat: player_guard.get_repeat_mode(),
                 shuffle: player_guard.shuffle,
+                mode: player_guard.queue_mode.clone(),
+                version: player_guard.queue_version,
             };
 
             (StatusCode::OK, Json(response)).into_response()
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1405,59 +1494,70 @@ pub async fn add_to_queue_handler(
     // Check if session exists, create if it doesn't (for testing purposes)
     if !state.sessions.contains_key(&session_id) {
         info!("Creating new session for testing: {}", session_id);
-        let session = crate::server::WebSocketSession {
-            session_id: session_id.clone(),
-            resuming: false,
-            timeout: 60000,
-            message_sender: None,
-        };
+        let session = crate::server::WebSocketSession::new(session_id.clone());
         state.sessions.insert(session_id.clone(), session);
     }
 
     // Get or create player
-    let player = state
+    let player = match state
         .player_manager
-        .get_or_create_player(guild_id.clone(), session_id.clone())
-        .await;
+        .get_or_create_players(vec![guild_id.clone()], &session_id, |owned, added| {
+            state.limits.check_player_count(owned, added)
+        })
+        .await
+    {
+        Ok(mut players) => players.remove(0),
+        Err(error) => return ApiError::from(error).into_response(),
+    };
     let mut player_guard = player.write().await;
 
     // Parse request - can be a single track or array of tracks
     if let Some(tracks_array) = request.get("tracks").and_then(|v| v.as_array()) {
         // Multiple tracks
         let mut added_tracks = Vec::new();
+        let mut rejected = Vec::new();
 
         for track_value in tracks_array {
             if let Some(encoded) = track_value.get("encoded").and_then(|v| v.as_str()) {
                 match Track::decode(encoded) {
-                    Ok(track) => {
-                        player_guard.add_to_queue(track.clone());
-                        added_tracks.push(track);
+                    Ok(mut track) => {
+                        apply_queue_user_data(&mut track, track_value);
+                        match player_guard.add_to_queue(track.clone()) {
+                            Ok(_) => added_tracks.push(track),
+                            Err(reason) => rejected.push(serde_json::json!({
+                                "track": track,
+                                "reason": reason
+                            })),
+                        }
                     }
                     Err(e) => {
                         error!("Failed to decode track: {}", e);
-                        let error = ErrorResponse::new(
-                            400,
-                            "Invalid track".to_string(),
-                            Some(format!("Failed to decode track: {e}")),
-                            format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-                        );
-                        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+                        // Tracks before the invalid one stay queued
+                        emit_queue_updates(&state, &mut player_guard).await;
+                        return ApiError::bad_request(format!("Failed to decode track: {e}"))
+                            .into_response();
                     }
                 }
             }
         }
+        emit_queue_updates(&state, &mut player_guard).await;
 
         let response = serde_json::json!({
             "added": added_tracks.len(),
-            "tracks": added_tracks
+            "tracks": added_tracks,
+            "rejected": rejected
         });
 
         (StatusCode::OK, Json(response)).into_response()
     } else if let Some(encoded) = request.get("encoded").and_then(|v| v.as_str()) {
         // Single track
         match Track::decode(encoded) {
-            Ok(track) => {
-                player_guard.add_to_queue(track.clone());
+            Ok(mut track) => {
+                apply_queue_user_data(&mut track, &request);
+                if let Err(reason) = player_guard.add_to_queue(track.clone()) {
+                    return ApiError::bad_request(reason).into_response();
+                }
+                emit_queue_updates(&state, &mut player_guard).await;
 
                 let response = serde_json::json!({
                     "added": 1,
@@ -1468,26 +1568,72 @@ pub async fn add_to_queue_handler(
             }
             Err(e) => {
                 error!("Failed to decode track: {}", e);
-                let error = ErrorResponse::new(
-                    400,
-                    "Invalid track".to_string(),
-                    Some(format!("Failed to decode track: {e}")),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-                );
-                (StatusCode::BAD_REQUEST, Json(error)).into_response()
+                ApiError::bad_request(format!("Failed to decode track: {e}")).into_response()
             }
         }
     } else {
-        let error = ErrorResponse::new(
-            400,
-            "Invalid request".to_string(),
-            Some("Request must contain 'encoded' field or 'tracks' array".to_string()),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-        );
-        (StatusCode::BAD_REQUEST, Json(error)).into_response()
+        ApiError::bad_request("Request must contain 'encoded' field or 'tracks' array")
+            .into_response()
+    }
+}
+
+/// Send the queue changes made while handling a request as `QueueUpdateEvent`s
+#[cfg(feature = "discord")]
+async fn emit_queue_updates(state: &AppState, player: &mut crate::player::LavalinkPlayer) {
+    for event in player.take_queue_updates() {
+        state.player_manager.emit_event(event).await;
     }
 }
 
+/// Copy the `userData` object of a queue request entry onto the decoded track
+#[cfg(feature = "discord")]
+fn apply_queue_user_data(track: &mut Track, value: &serde_json::Value) {
+    if let Some(user_data) = value.get("userData").and_then(|v| v.as_object()) {
+        track
+            .user_data
+            .extend(user_data.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+/// Set queue mode handler - /v4/sessions/{session_id}/players/{guild_id}/queue/mode
+#[cfg(feature = "discord")]
+pub async fn set_queue_mode_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(mode): Json<crate::protocol::messages::QueueMode>,
+) -> Response {
+    info!(
+        "Setting queue mode for session: {}, guild: {}",
+        session_id, guild_id
+    );
+
+    let player = match session_player(&state, &session_id, &guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+    let mut player_guard = player.write().await;
+
+    if let crate::protocol::messages::QueueMode::Fair { ref key, .. } = mode {
+        if key.is_empty() {
+            return ApiError::bad_request("Fair queue mode requires a non-empty key")
+                .into_response();
+        }
+    }
+
+    player_guard.set_queue_mode(mode);
+    emit_queue_updates(&state, &mut player_guard).await;
+
+    let response = crate::protocol::messages::QueueResponse {
+        tracks: player_guard.get_queue(),
+        length: player_guard.queue_length(),
+        repeat: player_guard.get_repeat_mode(),
+        shuffle: player_guard.shuffle,
+        mode: player_guard.queue_mode.clone(),
+        version: player_guard.queue_version,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
 /// Remove track from queue handler - /v4/sessions/{session_id}/players/{guild_id}/queue/{index}
 #[cfg(feature = "discord")]
 pub async fn remove_from_queue_handler(
@@ -1501,13 +1647,7 @@ pub async fn remove_from_queue_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/queue/{index}"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1517,45 +1657,26 @@ pub async fn remove_from_queue_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/queue/{index}"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             match player_guard.remove_from_queue(index) {
                 Some(removed_track) => {
+                    emit_queue_updates(&state, &mut player_guard).await;
                     let response = serde_json::json!({
                         "removed": true,
                         "track": removed_track
                     });
                     (StatusCode::OK, Json(response)).into_response()
                 }
-                None => {
-                    let error = ErrorResponse::new(
-                        404,
-                        "Track not found".to_string(),
-                        Some(format!("No track at index {index} in queue")),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}/queue/{index}"),
-                    );
-                    (StatusCode::NOT_FOUND, Json(error)).into_response()
-                }
+                None => ApiError::not_found(format!("No track at index {index} in queue"))
+                    .into_response(),
             }
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/queue/{index}"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1572,13 +1693,7 @@ pub async fn clear_queue_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1588,19 +1703,15 @@ pub async fn clear_queue_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             let cleared_count = player_guard.queue_length();
             player_guard.clear_queue();
+            emit_queue_updates(&state, &mut player_guard).await;
 
             let response = serde_json::json!({
                 "cleared": cleared_count
@@ -1608,15 +1719,7 @@ pub async fn clear_queue_handler(
 
             (StatusCode::OK, Json(response)).into_response()
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/queue"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1633,13 +1736,7 @@ pub async fn skip_track_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/skip"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1649,19 +1746,20 @@ pub async fn skip_track_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/skip"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             match player_guard.skip_track().await {
                 Ok(next_track) => {
+                    emit_queue_updates(&state, &mut player_guard).await;
+                    if next_track.is_none() {
+                        drop(player_guard);
+                        state.player_manager.autoplay(player.clone()).await;
+                    }
+
                     let response = if let Some(track) = next_track {
                         serde_json::json!({
                             "skipped": true,
@@ -1678,25 +1776,11 @@ pub async fn skip_track_handler(
                 }
                 Err(e) => {
                     error!("Failed to skip track: {}", e);
-                    let error = ErrorResponse::new(
-                        500,
-                        "Skip failed".to_string(),
-                        Some(format!("Failed to skip track: {e}")),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}/skip"),
-                    );
-                    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+                    ApiError::internal(e, "Failed to skip track").into_response()
                 }
             }
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/skip"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1714,13 +1798,7 @@ pub async fn move_track_in_queue_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/queue/move"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1730,19 +1808,15 @@ pub async fn move_track_in_queue_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/queue/move"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             match player_guard.move_track(request.from, request.to) {
                 Ok(moved_track) => {
+                    emit_queue_updates(&state, &mut player_guard).await;
                     let response = serde_json::json!({
                         "moved": true,
                         "track": moved_track,
@@ -1751,26 +1825,10 @@ pub async fn move_track_in_queue_handler(
                     });
                     (StatusCode::OK, Json(response)).into_response()
                 }
-                Err(e) => {
-                    let error = ErrorResponse::new(
-                        400,
-                        "Move failed".to_string(),
-                        Some(e),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}/queue/move"),
-                    );
-                    (StatusCode::BAD_REQUEST, Json(error)).into_response()
-                }
+                Err(e) => ApiError::bad_request(e).into_response(),
             }
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/queue/move"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1787,13 +1845,7 @@ pub async fn shuffle_queue_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/queue/shuffle"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1803,19 +1855,15 @@ pub async fn shuffle_queue_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/queue/shuffle"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             let original_length = player_guard.queue_length();
             player_guard.shuffle_queue();
+            emit_queue_updates(&state, &mut player_guard).await;
 
             let response = serde_json::json!({
                 "shuffled": true,
@@ -1824,15 +1872,7 @@ pub async fn shuffle_queue_handler(
 
             (StatusCode::OK, Json(response)).into_response()
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/queue/shuffle"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1849,13 +1889,7 @@ pub async fn get_player_filters_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1865,29 +1899,16 @@ pub async fn get_player_filters_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             let filters = player_guard.get_filters().clone();
             (StatusCode::OK, Json(filters)).into_response()
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1905,13 +1926,7 @@ pub async fn update_player_filters_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1921,15 +1936,10 @@ pub async fn update_player_filters_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             // Apply filters
@@ -1943,25 +1953,11 @@ pub async fn update_player_filters_handler(
                 }
                 Err(e) => {
                     error!("Failed to apply filters: {}", e);
-                    let error = ErrorResponse::new(
-                        400,
-                        "Filter application failed".to_string(),
-                        Some(format!("Failed to apply filters: {e}")),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-                    );
-                    (StatusCode::BAD_REQUEST, Json(error)).into_response()
+                    ApiError::bad_request(format!("Failed to apply filters: {e}")).into_response()
                 }
             }
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
 
@@ -1978,13 +1974,7 @@ pub async fn clear_player_filters_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get player
@@ -1994,15 +1984,10 @@ pub async fn clear_player_filters_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             // Clear filters by applying empty filter set
@@ -2017,25 +2002,291 @@ pub async fn clear_player_filters_handler(
                 }
                 Err(e) => {
                     error!("Failed to clear filters: {}", e);
-                    let error = ErrorResponse::new(
-                        500,
-                        "Filter clearing failed".to_string(),
-                        Some(format!("Failed to clear filters: {e}")),
-                        format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-                    );
-                    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+                    ApiError::internal(e, "Failed to clear filters").into_response()
                 }
             }
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!("/v4/sessions/{session_id}/players/{guild_id}/filters"),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
+    }
+}
+
+/// Get lyrics handler - /v4/sessions/{session_id}/players/{guild_id}/lyrics
+#[cfg(feature = "discord")]
+pub async fn get_player_lyrics_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    info!(
+        "Getting lyrics for session: {}, guild: {}",
+        session_id, guild_id
+    );
+
+    let player = match session_player(&state, &session_id, &guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let Some(track) = player.read().await.current_track.clone() else {
+        return ApiError::not_found(format!(
+            "Player for guild {guild_id} is not playing a track"
+        ))
+        .into_response();
+    };
+
+    match state.player_manager.lyrics().lyrics_for(&track).await {
+        Some(lyrics) => (StatusCode::OK, Json(lyrics.as_ref().clone())).into_response(),
+        None => ApiError::not_found(format!("No lyrics found for track {}", track.info.title))
+            .into_response(),
+    }
+}
+
+/// Subscribe to lyrics handler - /v4/sessions/{session_id}/players/{guild_id}/lyrics/subscribe
+#[cfg(feature = "discord")]
+pub async fn subscribe_lyrics_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    set_lyrics_subscription(&state, &session_id, &guild_id, true).await
+}
+
+/// Unsubscribe from lyrics handler - /v4/sessions/{session_id}/players/{guild_id}/lyrics/subscribe
+#[cfg(feature = "discord")]
+pub async fn unsubscribe_lyrics_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    set_lyrics_subscription(&state, &session_id, &guild_id, false).await
+}
+
+#[cfg(feature = "discord")]
+async fn set_lyrics_subscription(
+    state: &AppState,
+    session_id: &str,
+    guild_id: &str,
+    subscribed: bool,
+) -> Response {
+    info!(
+        "Setting lyrics subscription for session: {}, guild: {} to {}",
+        session_id, guild_id, subscribed
+    );
+
+    let player = match session_player(state, session_id, guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let mut player_guard = player.write().await;
+    player_guard.lyrics_subscribed = subscribed;
+    player_guard.lyrics_line = None;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Enable autoplay handler - /v4/sessions/{session_id}/players/{guild_id}/autoplay
+#[cfg(feature = "discord")]
+pub async fn enable_autoplay_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    set_autoplay(&state, &session_id, &guild_id, true).await
+}
+
+/// Disable autoplay handler - /v4/sessions/{session_id}/players/{guild_id}/autoplay
+#[cfg(feature = "discord")]
+pub async fn disable_autoplay_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    set_autoplay(&state, &session_id, &guild_id, false).await
+}
+
+#[cfg(feature = "discord")]
+async fn set_autoplay(
+    state: &AppState,
+    session_id: &str,
+    guild_id: &str,
+    enabled: bool,
+) -> Response {
+    info!(
+        "Setting autoplay for session: {}, guild: {} to {}",
+        session_id, guild_id, enabled
+    );
+
+    let player = match session_player(state, session_id, guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let idle = {
+        let mut player_guard = player.write().await;
+        player_guard.autoplay = enabled;
+        player_guard.mark_changed();
+        player_guard.current_track.is_none() && player_guard.queue.is_empty()
+    };
+    // An idle player starts right away with tracks related to its last one
+    if enabled && idle {
+        state.player_manager.autoplay(player).await;
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Set update interval handler - /v4/sessions/{session_id}/players/{guild_id}/updateInterval
+#[cfg(feature = "discord")]
+pub async fn set_update_interval_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<crate::protocol::messages::UpdateIntervalRequest>,
+) -> Response {
+    info!(
+        "Setting playerUpdate interval for session: {}, guild: {} to {:?}",
+        session_id, guild_id, request.interval
+    );
+
+    let minimum = crate::player::UPDATE_TICK.as_millis() as u64;
+    if request.interval.is_some_and(|interval| interval < minimum) {
+        return ApiError::bad_request(format!("Interval must be at least {minimum} ms"))
+            .into_response();
+    }
+
+    let player = match session_player(&state, &session_id, &guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let mut player_guard = player.write().await;
+    player_guard.update_interval = request.interval;
+    player_guard.mark_changed();
+    drop(player_guard);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Default and maximum page sizes of the history endpoint
+#[cfg(feature = "discord")]
+const HISTORY_PAGE_LIMIT: usize = 20;
+#[cfg(feature = "discord")]
+const HISTORY_MAX_PAGE_LIMIT: usize = 100;
+
+/// Previous track handler - /v4/sessions/{session_id}/players/{guild_id}/previous
+#[cfg(feature = "discord")]
+pub async fn previous_track_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    info!(
+        "Playing previous track for session: {}, guild: {}",
+        session_id, guild_id
+    );
+
+    replay_history_entry(&state, &session_id, &guild_id, 0).await
+}
+
+/// Replay from history handler - /v4/sessions/{session_id}/players/{guild_id}/history/{index}
+#[cfg(feature = "discord")]
+pub async fn replay_from_history_handler(
+    Path((session_id, guild_id, index)): Path<(String, String, usize)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    info!(
+        "Replaying history entry {} for session: {}, guild: {}",
+        index, session_id, guild_id
+    );
+
+    replay_history_entry(&state, &session_id, &guild_id, index).await
+}
+
+#[cfg(feature = "discord")]
+async fn replay_history_entry(
+    state: &AppState,
+    session_id: &str,
+    guild_id: &str,
+    index: usize,
+) -> Response {
+    let player = match session_player(state, session_id, guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let mut player_guard = player.write().await;
+    let result = player_guard.replay_from_history(index).await;
+    emit_queue_updates(state, &mut player_guard).await;
+    match result {
+        Ok(Some(_)) => (StatusCode::OK, Json(player_guard.to_protocol_player())).into_response(),
+        Ok(None) => ApiError::not_found(format!("No history entry {index} for guild {guild_id}"))
+            .into_response(),
+        Err(e) => ApiError::internal(e, "Failed to replay track").into_response(),
+    }
+}
+
+/// Get history handler - /v4/sessions/{session_id}/players/{guild_id}/history
+#[cfg(feature = "discord")]
+pub async fn get_player_history_handler(
+    Path((session_id, guild_id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<crate::protocol::messages::HistoryQuery>,
+) -> Response {
+    info!(
+        "Getting history for session: {}, guild: {}",
+        session_id, guild_id
+    );
+
+    let player = match session_player(&state, &session_id, &guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let page = query.page.unwrap_or(0);
+    let limit = query
+        .limit
+        .unwrap_or(HISTORY_PAGE_LIMIT)
+        .clamp(1, HISTORY_MAX_PAGE_LIMIT);
+    let player_guard = player.read().await;
+    let response = crate::protocol::messages::HistoryResponse {
+        tracks: player_guard.get_history(page.saturating_mul(limit), limit),
+        page,
+        limit,
+        total: player_guard.history.len(),
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Seek to chapter handler - /v4/sessions/{session_id}/players/{guild_id}/chapters/{index}
+#[cfg(feature = "discord")]
+pub async fn seek_to_chapter_handler(
+    Path((session_id, guild_id, index)): Path<(String, String, usize)>,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    info!(
+        "Seeking to chapter {} for session: {}, guild: {}",
+        index, session_id, guild_id
+    );
+
+    let player = match session_player(&state, &session_id, &guild_id).await {
+        Ok(player) => player,
+        Err(error) => return error.into_response(),
+    };
+
+    let mut player_guard = player.write().await;
+    if let Err(e) = player_guard.seek_to_chapter(index).await {
+        return ApiError::not_found(e).into_response();
+    }
+
+    (StatusCode::OK, Json(player_guard.to_protocol_player())).into_response()
+}
+
+/// Look up a player that belongs to an existing session
+#[cfg(feature = "discord")]
+async fn session_player(
+    state: &AppState,
+    session_id: &str,
+    guild_id: &str,
+) -> Result<Arc<tokio::sync::RwLock<crate::player::LavalinkPlayer>>, ApiError> {
+    if !state.sessions.contains_key(session_id) {
+        return Err(ApiError::session_not_found());
+    }
+
+    match state.player_manager.get_player(guild_id).await {
+        Some(player) if player.read().await.session_id == session_id => Ok(player),
+        _ => Err(ApiError::player_not_found()),
     }
 }
 
@@ -2080,13 +2331,7 @@ pub async fn apply_filter_preset_handler(
 
     // Check if session exists
     if !state.sessions.contains_key(&session_id) {
-        let error = ErrorResponse::new(
-            404,
-            "Session not found".to_string(),
-            Some(format!("Session {session_id} not found")),
-            format!("/v4/sessions/{session_id}/players/{guild_id}/filters/preset/{preset_name}"),
-        );
-        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        return ApiError::session_not_found().into_response();
     }
 
     // Get the preset filters
@@ -2099,15 +2344,7 @@ pub async fn apply_filter_preset_handler(
         "tremolo" => crate::protocol::filters::Filters::tremolo(),
         "vibrato" => crate::protocol::filters::Filters::vibrato(),
         _ => {
-            let error = ErrorResponse::new(
-                400,
-                "Invalid preset".to_string(),
-                Some(format!("Unknown preset: {preset_name}")),
-                format!(
-                    "/v4/sessions/{session_id}/players/{guild_id}/filters/preset/{preset_name}"
-                ),
-            );
-            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+            return ApiError::bad_request(format!("Unknown preset: {preset_name}")).into_response();
         }
     };
 
@@ -2118,17 +2355,10 @@ pub async fn apply_filter_preset_handler(
 
             // Check if player belongs to this session
             if player_guard.session_id != session_id {
-                let error = ErrorResponse::new(
-                    404,
-                    "Player not found".to_string(),
-                    Some(format!(
-                        "Player for guild {guild_id} not found in session {session_id}"
-                    )),
-                    format!(
-                        "/v4/sessions/{session_id}/players/{guild_id}/filters/preset/{preset_name}"
-                    ),
-                );
-                return (StatusCode::NOT_FOUND, Json(error)).into_response();
+                return ApiError::not_found(format!(
+                    "Player for guild {guild_id} not found in session {session_id}"
+                ))
+                .into_response();
             }
 
             // Apply preset filters
@@ -2143,28 +2373,10 @@ pub async fn apply_filter_preset_handler(
                 }
                 Err(e) => {
                     error!("Failed to apply preset filters: {}", e);
-                    let error = ErrorResponse::new(
-                        400,
-                        "Preset application failed".to_string(),
-                        Some(format!("Failed to apply preset: {e}")),
-                        format!(
-                            "/v4/sessions/{session_id}/players/{guild_id}/filters/preset/{preset_name}"
-                        ),
-                    );
-                    (StatusCode::BAD_REQUEST, Json(error)).into_response()
+                    ApiError::bad_request(format!("Failed to apply preset: {e}")).into_response()
                 }
             }
         }
-        None => {
-            let error = ErrorResponse::new(
-                404,
-                "Player not found".to_string(),
-                Some(format!("Player for guild {guild_id} not found")),
-                format!(
-                    "/v4/sessions/{session_id}/players/{guild_id}/filters/preset/{preset_name}"
-                ),
-            );
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
-        }
+        None => ApiError::player_not_found().into_response(),
     }
 }
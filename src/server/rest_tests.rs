@@ -274,6 +274,46 @@ mod rest_api_feature_tests {
         response.assert_status(axum::http::StatusCode::FORBIDDEN);
     }
 
+    #[tokio::test]
+    async fn test_batch_player_update() {
+        let server = create_test_server().await;
+
+        server
+            .patch("/v4/sessions/batch-session")
+            .add_header(auth_header().0, auth_header().1)
+            .json(&serde_json::json!({}))
+            .await
+            .assert_status_ok();
+
+        let updates = serde_json::json!({
+            "111": { "volume": 50, "paused": true },
+            "222": { "voice": { "token": "", "endpoint": "", "sessionId": "" } }
+        });
+        let response = server
+            .patch("/v4/sessions/batch-session/players")
+            .add_header(auth_header().0, auth_header().1)
+            .json(&updates)
+            .await;
+
+        response.assert_status_ok();
+        let json: Value = response.json();
+        assert_eq!(json["111"]["player"]["guildId"], "111");
+        assert_eq!(json["111"]["player"]["volume"], 50);
+        assert_eq!(json["111"]["player"]["paused"], true);
+        assert_eq!(json["222"]["error"]["status"], 400);
+        assert_eq!(
+            json["222"]["error"]["path"],
+            "/v4/sessions/batch-session/players/222"
+        );
+
+        let response = server
+            .patch("/v4/sessions/missing-session/players")
+            .add_header(auth_header().0, auth_header().1)
+            .json(&updates)
+            .await;
+        response.assert_status(axum::http::StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_plugin_config_update_success() {
         let server = create_test_server().await;
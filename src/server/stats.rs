@@ -1,8 +1,10 @@
+use std::sync::Arc;
 #[cfg(feature = "system-stats")]
 use std::sync::Mutex;
 use std::time::SystemTime;
 use tokio::time::Duration;
 
+use super::limits::Rejections;
 use crate::player::PlayerManager;
 use crate::protocol::{Cpu, FrameStats, Memory, Stats};
 
@@ -16,6 +18,8 @@ pub struct StatsCollector {
     system: Mutex<System>,
     #[cfg(feature = "system-stats")]
     last_cpu_refresh: Mutex<SystemTime>,
+    /// Counters of the client limits, when they are configured
+    rejections: Option<Arc<Rejections>>,
 }
 
 impl StatsCollector {
@@ -31,16 +35,24 @@ impl StatsCollector {
                 start_time: SystemTime::now(),
                 system: Mutex::new(system),
                 last_cpu_refresh: Mutex::new(SystemTime::now()),
+                rejections: None,
             }
         }
         #[cfg(not(feature = "system-stats"))]
         {
             Self {
                 start_time: SystemTime::now(),
+                rejections: None,
             }
         }
     }
 
+    /// Report the requests refused by the client limits
+    pub fn with_rejections(mut self, rejections: Arc<Rejections>) -> Self {
+        self.rejections = Some(rejections);
+        self
+    }
+
     /// Get current server statistics
     #[allow(dead_code)] // Kept for backward compatibility and potential future use
     pub async fn get_stats(&self) -> Stats {
@@ -57,6 +69,10 @@ impl StatsCollector {
             memory: self.get_memory_stats(),
             cpu: self.get_cpu_stats(),
             frame_stats: Some(self.get_frame_stats()),
+            rejections: self
+                .rejections
+                .as_ref()
+                .map(|rejections| rejections.snapshot()),
         }
     }
 
@@ -77,6 +93,10 @@ impl StatsCollector {
             memory: self.get_memory_stats(),
             cpu: self.get_cpu_stats(),
             frame_stats: Some(self.get_frame_stats()),
+            rejections: self
+                .rejections
+                .as_ref()
+                .map(|rejections| rejections.snapshot()),
         }
     }
 
@@ -262,7 +262,7 @@ mod rest_api_tests {
         // First create a session
         let session_data = serde_json::json!({
             "resuming": false,
-            "timeout": 60000
+            "timeout": 60
         });
 
         server
@@ -291,7 +291,7 @@ mod rest_api_tests {
         // First create a session
         let session_data = serde_json::json!({
             "resuming": false,
-            "timeout": 60000
+            "timeout": 60
         });
 
         server
@@ -337,7 +337,7 @@ mod rest_api_tests {
         // First create a session
         let session_data = serde_json::json!({
             "resuming": false,
-            "timeout": 60000
+            "timeout": 60
         });
 
         server
@@ -438,4 +438,344 @@ mod rest_api_tests {
             "path"
         );
     }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn test_session_event_stream_replays_after_last_event_id() {
+        use axum::extract::{Path, State};
+        use futures_util::StreamExt;
+
+        let config = create_test_config();
+        let server = LavalinkServer::new(config).await.unwrap();
+        let state = server.app_state.clone();
+
+        // Streams only attach to sessions that exist
+        let unknown = rest::session_events_handler(
+            Path("sse-session".to_string()),
+            State(state.clone()),
+            axum::http::HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+        assert!(!state.sessions.contains_key("sse-session"));
+
+        state.sessions.insert(
+            "sse-session".to_string(),
+            crate::server::WebSocketSession::new("sse-session".to_string()),
+        );
+        let first = rest::session_events_handler(
+            Path("sse-session".to_string()),
+            State(state.clone()),
+            axum::http::HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(
+            first.headers()["content-type"],
+            HeaderValue::from_static("text/event-stream")
+        );
+
+        let session = state.sessions.get("sse-session").unwrap().clone();
+        for guild_id in ["1", "2", "3"] {
+            let message = crate::protocol::Message::event(crate::protocol::Event::track_start(
+                guild_id.to_string(),
+                create_mock_track(),
+            ));
+            session.send_message(message).await.unwrap();
+        }
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("Last-Event-ID", HeaderValue::from_static("2"));
+        let resumed = rest::session_events_handler(
+            Path("sse-session".to_string()),
+            State(state.clone()),
+            headers,
+        )
+        .await;
+
+        let mut body = resumed.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("id: 3") {
+            let chunk = body.next().await.unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(text.contains(r#""op":"ready","resumed":true"#));
+        assert!(!text.contains(r#""guildId":"2""#));
+        assert!(text.contains(r#""guildId":"3""#));
+
+        // Both streams stay attached, so the session outlives a WebSocket
+        assert!(session.events.lock().unwrap().has_listeners());
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("Last-Event-ID", HeaderValue::from_static("latest"));
+        let invalid = rest::session_events_handler(
+            Path("sse-session".to_string()),
+            State(state.clone()),
+            headers,
+        )
+        .await;
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        // Without resuming the session is gone once its last stream closes
+        drop(first);
+        assert!(state.sessions.contains_key("sse-session"));
+        drop(body);
+        assert!(!state.sessions.contains_key("sse-session"));
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn test_slow_event_streams_are_closed() {
+        use crate::server::{SessionEvents, EVENT_STREAM_BUFFER};
+
+        let mut events = SessionEvents::default();
+        let listener = events.listen(None);
+        let message = crate::protocol::Message::ready(false, "session".to_string());
+        for _ in 0..EVENT_STREAM_BUFFER {
+            events.publish(&message, false);
+        }
+        assert!(events.has_listeners());
+
+        // The stream is dropped instead of buffering without bound
+        let last_id = events.publish(&message, false);
+        assert!(!events.has_listeners());
+        drop(listener);
+
+        // Its client catches up from the replay buffer when it reconnects
+        let resumed = events.listen(Some(EVENT_STREAM_BUFFER as u64));
+        assert!(resumed.resumed);
+        assert_eq!(resumed.replay.len(), 1);
+        assert_eq!(resumed.replay[0].0, last_id);
+    }
+
+    /// Error responses have the same shape as the Java server's
+    #[tokio::test]
+    async fn test_error_responses_match_lavalink_fixtures() {
+        let server = create_test_server().await;
+        let path = "/v4/sessions/xtaug914v9k5032f/players/817327181659111454";
+
+        for (fixture, query) in [
+            (
+                include_str!("../../tests/fixtures/errors/session_not_found.json"),
+                "",
+            ),
+            (
+                include_str!("../../tests/fixtures/errors/session_not_found_trace.json"),
+                "?trace=true",
+            ),
+        ] {
+            let expected: Value = serde_json::from_str(fixture).unwrap();
+            let response = server
+                .patch(&format!("{path}{query}"))
+                .add_header(auth_header().0, auth_header().1)
+                .json(&serde_json::json!({}))
+                .await;
+
+            response.assert_status(StatusCode::NOT_FOUND);
+            let actual: Value = response.json();
+
+            let mut expected_keys: Vec<_> = expected.as_object().unwrap().keys().collect();
+            let mut actual_keys: Vec<_> = actual.as_object().unwrap().keys().collect();
+            expected_keys.sort();
+            actual_keys.sort();
+            assert_eq!(actual_keys, expected_keys);
+
+            for field in ["status", "error", "message", "path"] {
+                assert_eq!(actual[field], expected[field], "field {field}");
+            }
+            assert!(actual["timestamp"].as_u64().unwrap() > 1_667_857_581_613);
+            if expected.get("trace").is_some() {
+                assert!(actual["trace"]
+                    .as_str()
+                    .unwrap()
+                    .contains("Session not found"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_key_scopes_are_enforced() {
+        let mut config = create_test_config();
+        config.lavalink.server.api_keys = Some(vec![crate::config::ApiKeyConfig {
+            name: "music-bot".to_string(),
+            key: "bot-key".to_string(),
+            scopes: Some(vec![crate::config::ApiScope::Load]),
+        }]);
+        let server = LavalinkServer::new(config).await.unwrap();
+        let server = TestServer::new(server.build_router()).unwrap();
+        let bot_key = HeaderValue::from_static("bot-key");
+
+        let response = server
+            .post("/v4/decodetracks")
+            .add_header(auth_header().0, bot_key.clone())
+            .json(&serde_json::json!({ "tracks": [] }))
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        let response = server
+            .get("/v4/openapi.json")
+            .add_header(auth_header().0, bot_key.clone())
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        let response = server
+            .post("/v4/plugins/example/reload")
+            .add_header(auth_header().0, bot_key)
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+        let error: Value = response.json();
+        assert_eq!(
+            error["message"],
+            "API key 'music-bot' lacks the 'admin:plugins' scope"
+        );
+
+        // The password keeps access to everything
+        let response = server
+            .get("/v4/plugins")
+            .add_header(auth_header().0, auth_header().1)
+            .await;
+        response.assert_status(StatusCode::OK);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn test_sessions_belong_to_their_api_key() {
+        let mut config = create_test_config();
+        config.lavalink.server.api_keys = Some(vec![crate::config::ApiKeyConfig {
+            name: "music-bot".to_string(),
+            key: "bot-key".to_string(),
+            scopes: None,
+        }]);
+        let server = LavalinkServer::new(config).await.unwrap();
+        let server = TestServer::new(server.build_router()).unwrap();
+        let bot_key = HeaderValue::from_static("bot-key");
+
+        let response = server
+            .patch("/v4/sessions/bot-session")
+            .add_header(auth_header().0, bot_key.clone())
+            .json(&serde_json::json!({ "resuming": true }))
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        // Another key can neither use nor list the session
+        for path in [
+            "/v4/sessions/bot-session",
+            "/v4/sessions/bot-session/players",
+            "/v4/sessions/bot-session/events",
+        ] {
+            let response = server
+                .get(path)
+                .add_header(auth_header().0, auth_header().1)
+                .await;
+            response.assert_status(StatusCode::FORBIDDEN);
+        }
+        let response = server
+            .get("/v4/sessions")
+            .add_header(auth_header().0, auth_header().1)
+            .await;
+        assert_eq!(response.json::<Value>(), serde_json::json!([]));
+
+        let response = server
+            .get("/v4/sessions/bot-session")
+            .add_header(auth_header().0, bot_key.clone())
+            .await;
+        response.assert_status(StatusCode::OK);
+        let response = server
+            .get("/v4/sessions")
+            .add_header(auth_header().0, bot_key)
+            .await;
+        assert_eq!(response.json::<Value>().as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_requests_get_retry_after() {
+        let mut config = create_test_config();
+        config.lavalink.server.client_limits = Some(crate::config::ClientLimitsConfig {
+            player_updates: Some(crate::config::TokenBucketConfig {
+                capacity: 1,
+                refill_per_second: 0.1,
+            }),
+            ..Default::default()
+        });
+        let server = LavalinkServer::new(config).await.unwrap();
+        let server = TestServer::new(server.build_router()).unwrap();
+        let path = "/v4/sessions/xtaug914v9k5032f/players/817327181659111454";
+
+        let response = server
+            .patch(path)
+            .add_header(auth_header().0, auth_header().1)
+            .json(&serde_json::json!({}))
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+
+        let response = server
+            .patch(path)
+            .add_header(auth_header().0, auth_header().1)
+            .json(&serde_json::json!({}))
+            .await;
+        response.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .header("retry-after")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=10).contains(&retry_after));
+        let error: Value = response.json();
+        assert_eq!(error["path"], path);
+
+        // Reading players is not limited
+        let response = server
+            .get(path)
+            .add_header(auth_header().0, auth_header().1)
+            .await;
+        assert_ne!(response.status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+        let stats: Value = server
+            .get("/v4/stats")
+            .add_header(auth_header().0, auth_header().1)
+            .await
+            .json();
+        assert_eq!(stats["rejections"]["playerUpdates"], 1);
+        assert_eq!(stats["rejections"]["loadTracks"], 0);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn test_batch_player_updates_take_a_token_per_guild() {
+        let mut config = create_test_config();
+        config.lavalink.server.client_limits = Some(crate::config::ClientLimitsConfig {
+            player_updates: Some(crate::config::TokenBucketConfig {
+                capacity: 2,
+                refill_per_second: 0.1,
+            }),
+            ..Default::default()
+        });
+        let server = LavalinkServer::new(config).await.unwrap();
+        let server = TestServer::new(server.build_router()).unwrap();
+        server
+            .patch("/v4/sessions/batch-session")
+            .add_header(auth_header().0, auth_header().1)
+            .json(&serde_json::json!({}))
+            .await
+            .assert_status(StatusCode::OK);
+        let batch = |guilds: &[&str]| {
+            let body: serde_json::Map<_, _> = guilds
+                .iter()
+                .map(|guild| (guild.to_string(), serde_json::json!({})))
+                .collect();
+            server
+                .patch("/v4/sessions/batch-session/players")
+                .add_header(auth_header().0, auth_header().1)
+                .json(&body)
+        };
+
+        // More guilds than the bucket ever holds can't be waited for
+        batch(&["1", "2", "3"])
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        batch(&["1", "2"]).await.assert_status(StatusCode::OK);
+        batch(&["1"])
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
 }
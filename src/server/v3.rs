@@ -0,0 +1,423 @@
+//! Lavalink v3 WebSocket compatibility
+//!
+//! Lavalink v4 only talks to clients over REST, but older clients still send
+//! their player commands as WebSocket ops. Sessions opened with the
+//! `Lavalink-Api-Version: 3` header, or on `/` when `v3Compatibility` is
+//! enabled, have their ops mapped onto the same player operations the REST
+//! handlers use and receive events in the v3 shape.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::{
+    limits::{ClientId, LimitKind},
+    AppState,
+};
+use crate::player::LavalinkPlayer;
+use crate::protocol::{
+    self,
+    messages::{Event, VoiceState},
+    Filters, Track,
+};
+
+/// Header a client sends to select the v3 protocol
+pub const API_VERSION_HEADER: &str = "Lavalink-Api-Version";
+
+/// Header a v3 client sends to resume a session configured with `configureResuming`
+pub const RESUME_KEY_HEADER: &str = "Resume-Key";
+
+/// An op sent by a v3 client
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum V3Op {
+    #[serde(rename_all = "camelCase")]
+    VoiceUpdate {
+        guild_id: String,
+        session_id: String,
+        event: VoiceServerEvent,
+    },
+    #[serde(rename_all = "camelCase")]
+    Play {
+        guild_id: String,
+        track: String,
+        #[serde(default)]
+        start_time: Option<u64>,
+        #[serde(default)]
+        end_time: Option<u64>,
+        #[serde(default)]
+        volume: Option<u16>,
+        #[serde(default)]
+        no_replace: bool,
+        #[serde(default)]
+        pause: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Stop { guild_id: String },
+    #[serde(rename_all = "camelCase")]
+    Pause { guild_id: String, pause: bool },
+    #[serde(rename_all = "camelCase")]
+    Seek { guild_id: String, position: u64 },
+    #[serde(rename_all = "camelCase")]
+    Volume { guild_id: String, volume: u16 },
+    #[serde(rename_all = "camelCase")]
+    Filters {
+        guild_id: String,
+        #[serde(flatten)]
+        filters: Box<Filters>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Destroy { guild_id: String },
+    #[serde(rename_all = "camelCase")]
+    ConfigureResuming {
+        #[serde(default)]
+        key: Option<String>,
+        /// Seconds the session is kept after a disconnect
+        #[serde(default)]
+        timeout: Option<u64>,
+    },
+}
+
+/// The Discord VOICE_SERVER_UPDATE payload forwarded by `voiceUpdate`
+#[derive(Debug, Clone, Deserialize)]
+pub struct VoiceServerEvent {
+    pub token: String,
+    pub endpoint: Option<String>,
+}
+
+/// Parse and apply an op received from a v3 session
+pub async fn handle_op(state: &Arc<AppState>, session_id: &str, client: &ClientId, text: &str) {
+    let op = match serde_json::from_str::<V3Op>(text) {
+        Ok(op) => op,
+        Err(e) => {
+            warn!("Ignoring invalid v3 op from session {}: {}", session_id, e);
+            return;
+        }
+    };
+    // v3 has no op to report errors, so ops over the limit are dropped
+    if let Err(e) = state.limits.check_rate(LimitKind::PlayerUpdates, client) {
+        warn!("Dropping v3 op from session {}: {}", session_id, e);
+        return;
+    }
+    debug!("Handling v3 op from session {}: {:?}", session_id, op);
+    if let Err(e) = apply_op(state, session_id, op).await {
+        warn!("Failed to apply v3 op from session {}: {}", session_id, e);
+    }
+}
+
+async fn apply_op(state: &Arc<AppState>, session_id: &str, op: V3Op) -> Result<(), String> {
+    match op {
+        V3Op::VoiceUpdate {
+            guild_id,
+            session_id: voice_session_id,
+            event,
+        } => {
+            let player = session_player(state, session_id, &guild_id, true).await?;
+            let mut player = player.write().await;
+            let voice = VoiceState {
+                token: event.token,
+                endpoint: event.endpoint.unwrap_or_default(),
+                session_id: voice_session_id,
+            };
+            player
+                .update_voice_state(voice)
+                .await
+                .map_err(|e| format!("Failed to connect to voice server: {e}"))?;
+            // Players restored from a snapshot continue once voice is back
+            player
+                .resume_restored()
+                .await
+                .map_err(|e| format!("Failed to resume restored player: {e}"))?;
+        }
+        V3Op::Play {
+            guild_id,
+            track,
+            start_time,
+            end_time,
+            volume,
+            no_replace,
+            pause,
+        } => {
+            let track = Track::decode(&track).map_err(|e| format!("Invalid track: {e}"))?;
+            let player = session_player(state, session_id, &guild_id, true).await?;
+            let mut player = player.write().await;
+            if no_replace && player.current_track.is_some() {
+                debug!("Not replacing the playing track in guild {}", guild_id);
+                return Ok(());
+            }
+            if let Some(volume) = volume {
+                player.volume = clamp_volume(volume);
+            }
+            player
+                .play_track(track, start_time, end_time)
+                .await
+                .map_err(|e| e.to_string())?;
+            if pause {
+                player.set_paused(true).await?;
+            }
+        }
+        V3Op::Stop { guild_id } => {
+            let player = session_player(state, session_id, &guild_id, false).await?;
+            let mut player = player.write().await;
+            if let Some(engine) = &player.audio_engine {
+                engine.stop().await.map_err(|e| e.to_string())?;
+            }
+            player.replace_current_track(None);
+        }
+        V3Op::Pause { guild_id, pause } => {
+            let player = session_player(state, session_id, &guild_id, false).await?;
+            player.write().await.set_paused(pause).await?;
+        }
+        V3Op::Seek { guild_id, position } => {
+            let player = session_player(state, session_id, &guild_id, false).await?;
+            player.write().await.seek(position).await?;
+        }
+        V3Op::Volume { guild_id, volume } => {
+            let player = session_player(state, session_id, &guild_id, true).await?;
+            let mut player = player.write().await;
+            player.volume = clamp_volume(volume);
+            player.mark_changed();
+        }
+        V3Op::Filters { guild_id, filters } => {
+            let player = session_player(state, session_id, &guild_id, true).await?;
+            player
+                .write()
+                .await
+                .apply_filters(*filters)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        V3Op::Destroy { guild_id } => {
+            // Destroying an unknown player is not an error in v3
+            if session_player(state, session_id, &guild_id, false)
+                .await
+                .is_ok()
+            {
+                state.player_manager.remove_player(&guild_id).await;
+                info!("Player {} destroyed by v3 session {}", guild_id, session_id);
+            }
+        }
+        V3Op::ConfigureResuming { key, timeout } => {
+            state.resume_keys.retain(|_, id| id != session_id);
+            if let Some(mut session) = state.sessions.get_mut(session_id) {
+                session.resuming = key.is_some();
+                if let Some(timeout) = timeout {
+                    session.timeout = timeout;
+                }
+            }
+            if let Some(key) = key {
+                state.resume_keys.insert(key, session_id.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The player of a guild if it belongs to the session, created on demand
+async fn session_player(
+    state: &AppState,
+    session_id: &str,
+    guild_id: &str,
+    create: bool,
+) -> Result<Arc<RwLock<LavalinkPlayer>>, String> {
+    match state.player_manager.get_player(guild_id).await {
+        Some(player) if player.read().await.session_id == session_id => Ok(player),
+        Some(_) => Err(format!(
+            "Player for guild {guild_id} belongs to another session"
+        )),
+        None if create => {
+            let mut players = state
+                .player_manager
+                .get_or_create_players(vec![guild_id.to_string()], session_id, |owned, added| {
+                    state.limits.check_player_count(owned, added)
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(players.remove(0))
+        }
+        None => Err(format!("Player for guild {guild_id} not found")),
+    }
+}
+
+/// v3 volumes range up to 1000 like the players' own
+fn clamp_volume(volume: u16) -> u16 {
+    volume.min(crate::player::MAX_VOLUME)
+}
+
+/// Convert an outgoing message to its v3 shape
+///
+/// v3 has no `ready` op and only knows the five original events, so those
+/// messages are not sent at all. Tracks in events are sent as their encoded
+/// string and end reasons in upper case.
+pub fn to_v3(message: &protocol::Message) -> Option<Value> {
+    match message {
+        protocol::Message::Ready { .. } => None,
+        protocol::Message::Stats(_) | protocol::Message::PlayerUpdate { .. } => {
+            serde_json::to_value(message).ok()
+        }
+        protocol::Message::Event(event) => v3_event(event),
+    }
+}
+
+fn v3_event(event: &Event) -> Option<Value> {
+    let value = match event {
+        Event::TrackStart { guild_id, track } => json!({
+            "type": "TrackStartEvent",
+            "guildId": guild_id,
+            "track": track.encoded,
+        }),
+        Event::TrackEnd {
+            guild_id,
+            track,
+            reason,
+        } => json!({
+            "type": "TrackEndEvent",
+            "guildId": guild_id,
+            "track": track.encoded,
+            "reason": upper_case(serde_json::to_value(reason).ok()?),
+        }),
+        Event::TrackException {
+            guild_id,
+            track,
+            exception,
+        } => json!({
+            "type": "TrackExceptionEvent",
+            "guildId": guild_id,
+            "track": track.encoded,
+            "error": exception.message,
+            "exception": {
+                "message": exception.message,
+                "severity": upper_case(serde_json::to_value(&exception.severity).ok()?),
+                "cause": exception.cause,
+            },
+        }),
+        Event::TrackStuck {
+            guild_id,
+            track,
+            threshold_ms,
+        } => json!({
+            "type": "TrackStuckEvent",
+            "guildId": guild_id,
+            "track": track.encoded,
+            "thresholdMs": threshold_ms,
+        }),
+        Event::WebSocketClosed {
+            guild_id,
+            code,
+            reason,
+            by_remote,
+        } => json!({
+            "type": "WebSocketClosedEvent",
+            "guildId": guild_id,
+            "code": code,
+            "reason": reason,
+            "byRemote": by_remote,
+        }),
+        _ => return None,
+    };
+    let mut value = value;
+    value["op"] = json!("event");
+    Some(value)
+}
+
+fn upper_case(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.to_uppercase()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::TrackEndReason;
+    use crate::protocol::TrackInfo;
+    use std::collections::HashMap;
+
+    fn track() -> Track {
+        Track {
+            encoded: "QAAAjQIAJVJpY2sgQXN0bGV5".to_string(),
+            info: TrackInfo {
+                identifier: "dQw4w9WgXcQ".to_string(),
+                is_seekable: true,
+                author: "Rick Astley".to_string(),
+                length: 212_000,
+                is_stream: false,
+                position: 0,
+                title: "Never Gonna Give You Up".to_string(),
+                uri: None,
+                source_name: "youtube".to_string(),
+                artwork_url: None,
+                isrc: None,
+            },
+            plugin_info: HashMap::new(),
+            user_data: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_v3_ops() {
+        let op: V3Op = serde_json::from_str(
+            r#"{"op":"play","guildId":"1","track":"abc","startTime":5000,"noReplace":true}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            op,
+            V3Op::Play { ref guild_id, start_time: Some(5000), end_time: None, no_replace: true, pause: false, .. }
+                if guild_id == "1"
+        ));
+
+        let op: V3Op = serde_json::from_str(
+            r#"{"op":"voiceUpdate","guildId":"1","sessionId":"s","event":{"token":"t","guild_id":"1","endpoint":"e"}}"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(op, V3Op::VoiceUpdate { ref event, .. } if event.endpoint.as_deref() == Some("e"))
+        );
+
+        let op: V3Op = serde_json::from_str(
+            r#"{"op":"filters","guildId":"1","volume":0.5,"timescale":{"speed":1.2}}"#,
+        )
+        .unwrap();
+        assert!(matches!(op, V3Op::Filters { ref filters, .. } if filters.volume.is_present()));
+
+        let op: V3Op =
+            serde_json::from_str(r#"{"op":"configureResuming","key":"k","timeout":60}"#).unwrap();
+        assert!(matches!(
+            op,
+            V3Op::ConfigureResuming {
+                key: Some(_),
+                timeout: Some(60)
+            }
+        ));
+
+        assert!(serde_json::from_str::<V3Op>(r#"{"op":"unknown","guildId":"1"}"#).is_err());
+        assert_eq!(clamp_volume(500), 500);
+        assert_eq!(clamp_volume(4000), 1000);
+    }
+
+    #[test]
+    fn test_v3_event_shape() {
+        let end = protocol::Message::event(Event::TrackEnd {
+            guild_id: "1".to_string(),
+            track: track(),
+            reason: TrackEndReason::Finished,
+        });
+        let value = to_v3(&end).unwrap();
+        assert_eq!(value["op"], "event");
+        assert_eq!(value["type"], "TrackEndEvent");
+        assert_eq!(value["track"], track().encoded);
+        assert_eq!(value["reason"], "FINISHED");
+
+        assert!(to_v3(&protocol::Message::ready(false, "s".to_string())).is_none());
+        let title = protocol::Message::event(Event::StreamTitle {
+            guild_id: "1".to_string(),
+            track: track(),
+            title: "Live".to_string(),
+            url: None,
+        });
+        assert!(to_v3(&title).is_none());
+    }
+}
@@ -1,12 +1,18 @@
 use axum::extract::ws::{Message, WebSocket};
+use dashmap::mapref::entry::Entry;
 use futures_util::{SinkExt, StreamExt};
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use super::AppState;
-use crate::protocol;
+use super::{limits::ClientId, v3, AppState};
+use crate::protocol::{self, messages::EventSubscription};
 
 /// WebSocket session information
 #[derive(Debug, Clone)]
@@ -14,8 +20,154 @@ pub struct WebSocketSession {
     pub session_id: String,
 
     pub resuming: bool,
+    /// Seconds a resuming session is kept after its connection closes
     pub timeout: u64,
     pub message_sender: Option<mpsc::UnboundedSender<protocol::Message>>,
+    /// Messages this session receives
+    pub subscription: EventSubscription,
+    /// Numbered history of sent messages and the event streams attached to the session
+    pub events: Arc<Mutex<SessionEvents>>,
+    /// When the session last lost its connection, if it has not come back since
+    pub detached: Option<Instant>,
+    /// Name of the API key that opened the session, the only key that may use it
+    pub owner: Option<String>,
+}
+
+/// Seconds sessions are kept for resuming unless configured otherwise
+pub const DEFAULT_SESSION_TIMEOUT: u64 = 60;
+
+/// Number of messages kept per session for `Last-Event-ID` resumes
+pub const REPLAY_BUFFER_SIZE: usize = 512;
+
+/// Messages an event stream may fall behind before it is closed
+///
+/// Clients of closed streams catch up from the replay buffer when they
+/// reconnect with `Last-Event-ID`.
+pub const EVENT_STREAM_BUFFER: usize = 256;
+
+/// Messages sent to a session, numbered so event streams can resume
+#[derive(Debug, Default)]
+pub struct SessionEvents {
+    last_id: u64,
+    replay: VecDeque<(u64, protocol::Message)>,
+    listeners: Vec<mpsc::Sender<(u64, protocol::Message)>>,
+    /// Whether an event stream was ever attached, so its client may resume
+    streamed: bool,
+}
+
+/// An event stream attached to a session
+#[derive(Debug)]
+pub struct EventListener {
+    /// Whether every message after the requested id was still buffered
+    pub resumed: bool,
+    /// Buffered messages after the requested id
+    pub replay: Vec<(u64, protocol::Message)>,
+    pub receiver: mpsc::Receiver<(u64, protocol::Message)>,
+}
+
+impl SessionEvents {
+    /// Number a message and pass it to the event streams
+    ///
+    /// The message is kept for replays if the session is `resuming` or has
+    /// ever had an event stream; other sessions have no client to replay to.
+    /// Streams that fell [`EVENT_STREAM_BUFFER`] messages behind are closed.
+    pub fn publish(&mut self, message: &protocol::Message, resuming: bool) -> u64 {
+        self.last_id += 1;
+        let id = self.last_id;
+        if resuming || self.streamed {
+            if self.replay.len() == REPLAY_BUFFER_SIZE {
+                self.replay.pop_front();
+            }
+            self.replay.push_back((id, message.clone()));
+        }
+        self.listeners
+            .retain(|listener| listener.try_send((id, message.clone())).is_ok());
+        id
+    }
+
+    /// Attach an event stream, replaying the messages sent after `last_event_id`
+    pub fn listen(&mut self, last_event_id: Option<u64>) -> EventListener {
+        let (tx, receiver) = mpsc::channel(EVENT_STREAM_BUFFER);
+        self.listeners.push(tx);
+        self.streamed = true;
+
+        let Some(last_event_id) = last_event_id else {
+            return EventListener {
+                resumed: false,
+                replay: Vec::new(),
+                receiver,
+            };
+        };
+        let oldest = self.replay.front().map_or(self.last_id + 1, |(id, _)| *id);
+        EventListener {
+            resumed: last_event_id <= self.last_id && last_event_id + 1 >= oldest,
+            replay: self
+                .replay
+                .iter()
+                .filter(|(id, _)| *id > last_event_id)
+                .cloned()
+                .collect(),
+            receiver,
+        }
+    }
+
+    /// Whether an event stream is still attached
+    pub fn has_listeners(&mut self) -> bool {
+        self.listeners.retain(|listener| !listener.is_closed());
+        !self.listeners.is_empty()
+    }
+}
+
+/// Options a client picked when opening a WebSocket connection
+#[derive(Debug, Clone, Default)]
+pub struct SessionOptions {
+    pub client_name: Option<String>,
+    /// Name of the API key the session authenticated with
+    pub api_key: String,
+    /// Speak the Lavalink v3 protocol
+    pub v3_compat: bool,
+    /// Subscription from the handshake, if it set one
+    pub subscription: Option<EventSubscription>,
+}
+
+/// Read a subscription from the handshake query
+///
+/// `events` and `guilds` are comma separated lists, `playerUpdateInterval`
+/// is in milliseconds. Without any of them there is no subscription.
+pub fn parse_subscription(
+    params: &HashMap<String, String>,
+) -> Result<Option<EventSubscription>, String> {
+    if ["events", "guilds", "playerUpdateInterval"]
+        .iter()
+        .all(|name| !params.contains_key(*name))
+    {
+        return Ok(None);
+    }
+    let list = |name: &str| {
+        params.get(name).map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+    };
+    let player_update_interval = params
+        .get("playerUpdateInterval")
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid playerUpdateInterval: {value}"))
+        })
+        .transpose()?;
+    let subscription = EventSubscription {
+        events: list("events"),
+        guilds: list("guilds"),
+        player_update_interval,
+    };
+    subscription.validate()?;
+    Ok(Some(subscription))
 }
 
 /// Handle a WebSocket connection
@@ -25,38 +177,67 @@ pub async fn handle_websocket(
     addr: SocketAddr,
     user_id: String,
     session_id: Option<String>,
-    _client_name: Option<String>,
+    options: SessionOptions,
 ) {
+    let v3_compat = options.v3_compat;
+    let resuming = session_id.is_some();
     let session_id = session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
 
     info!(
-        "WebSocket session {} established for user {} from {}",
-        session_id, user_id, addr
+        "WebSocket session {} established for user {} from {} ({})",
+        session_id,
+        user_id,
+        addr,
+        options.client_name.as_deref().unwrap_or("unknown client")
     );
 
     // Create channels for communication
     let (tx, mut rx) = mpsc::unbounded_channel::<protocol::Message>();
 
-    // Create session
-    let session = WebSocketSession {
-        session_id: session_id.clone(),
-        resuming: false,
-        timeout: 60000, // 60 seconds default
-        message_sender: Some(tx),
-    };
-
-    // Store session
-    state.sessions.insert(session_id.clone(), session.clone());
+    // Create session, keeping the settings, history and event streams of one that
+    // already exists; a subscription from the handshake replaces the old one
+    match state.sessions.entry(session_id.clone()) {
+        Entry::Occupied(mut entry) => {
+            let session = entry.get_mut();
+            // Checked before the upgrade too, this catches a session opened meanwhile
+            if !session.is_usable_by(&options.api_key) {
+                warn!(
+                    "API key {} cannot resume session {} of another key",
+                    options.api_key, session_id
+                );
+                return;
+            }
+            session.message_sender = Some(tx.clone());
+            session.detached = None;
+            if let Some(subscription) = options.subscription {
+                session.subscription = subscription;
+            }
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(WebSocketSession {
+                message_sender: Some(tx.clone()),
+                subscription: options.subscription.unwrap_or_default(),
+                owner: Some(options.api_key.clone()),
+                ..WebSocketSession::new(session_id.clone())
+            });
+        }
+    }
 
     // Split the socket
     let (mut sender, mut receiver) = socket.split();
 
-    // Send ready message
-    let ready_message = protocol::Message::ready(false, session_id.clone());
-    if let Ok(json) = serde_json::to_string(&ready_message) {
-        if let Err(e) = sender.send(Message::Text(json)).await {
-            error!("Failed to send ready message: {}", e);
-            return;
+    // A resumed session picks up its players, including ones restored from a snapshot
+    let resumed = resuming && state.player_manager.resume_session(&session_id).await;
+
+    // Send ready message; v3 has no ready op
+    if !v3_compat {
+        let ready_message = protocol::Message::ready(resumed, session_id.clone());
+        if let Ok(json) = serde_json::to_string(&ready_message) {
+            if let Err(e) = sender.send(Message::Text(json)).await {
+                error!("Failed to send ready message: {}", e);
+                detach_websocket(&state, &session_id, &tx);
+                return;
+            }
         }
     }
 
@@ -64,7 +245,15 @@ pub async fn handle_websocket(
     let session_id_clone = session_id.clone();
     let outgoing_task = tokio::spawn(async move {
         while let Some(message) = rx.recv().await {
-            match serde_json::to_string(&message) {
+            let json = if v3_compat {
+                match v3::to_v3(&message) {
+                    Some(value) => serde_json::to_string(&value),
+                    None => continue,
+                }
+            } else {
+                serde_json::to_string(&message)
+            };
+            match json {
                 Ok(json) => {
                     debug!("Sending message to session {}: {}", session_id_clone, json);
                     if let Err(e) = sender.send(Message::Text(json)).await {
@@ -88,6 +277,11 @@ pub async fn handle_websocket(
     // Handle incoming messages
     let session_id_clone = session_id.clone();
     let state_clone = state.clone();
+    let sender_clone = tx.clone();
+    let client = ClientId {
+        api_key: options.api_key,
+        ip: Some(addr.ip()),
+    };
     let incoming_task = tokio::spawn(async move {
         while let Some(msg) = receiver.next().await {
             match msg {
@@ -96,11 +290,15 @@ pub async fn handle_websocket(
                         "Received message from session {}: {}",
                         session_id_clone, text
                     );
-                    // In Lavalink v4, WebSocket messages are not supported
-                    // All communication is done via REST API
-                    warn!(
-                        "Lavalink v4 does not support websocket messages. Please use the REST API."
-                    );
+                    if v3_compat {
+                        v3::handle_op(&state_clone, &session_id_clone, &client, &text).await;
+                    } else {
+                        // In Lavalink v4, WebSocket messages are not supported
+                        // All communication is done via REST API
+                        warn!(
+                            "Lavalink v4 does not support websocket messages. Please use the REST API."
+                        );
+                    }
                 }
                 Ok(Message::Close(_)) => {
                     info!(
@@ -120,7 +318,7 @@ pub async fn handle_websocket(
         }
 
         // Clean up session
-        state_clone.sessions.remove(&session_id_clone);
+        detach_websocket(&state_clone, &session_id_clone, &sender_clone);
         info!("Session {} cleaned up", session_id_clone);
     });
 
@@ -135,16 +333,153 @@ pub async fn handle_websocket(
     }
 
     // Clean up
-    state.sessions.remove(&session_id);
+    detach_websocket(&state, &session_id, &tx);
     info!("WebSocket session {} terminated", session_id);
 }
 
+/// Drop a closed WebSocket from its session
+///
+/// The session stays around while event streams are still attached to it,
+/// and resuming sessions for their timeout after that. Nothing happens if
+/// the session has since been picked up by another connection.
+fn detach_websocket(
+    state: &Arc<AppState>,
+    session_id: &str,
+    sender: &mpsc::UnboundedSender<protocol::Message>,
+) {
+    let Some(mut session) = state.sessions.get_mut(session_id) else {
+        return;
+    };
+    if !session
+        .message_sender
+        .as_ref()
+        .is_some_and(|current| current.same_channel(sender))
+    {
+        return;
+    }
+    session.message_sender = None;
+    drop(session);
+    release_session(state, session_id);
+}
+
+/// Let go of a session once neither a WebSocket nor an event stream uses it
+///
+/// Sessions without resuming are removed right away, resuming ones after
+/// their timeout unless a connection picks them up again.
+fn release_session(state: &Arc<AppState>, session_id: &str) {
+    let Some(mut session) = state.sessions.get_mut(session_id) else {
+        return;
+    };
+    let in_use = session.message_sender.is_some()
+        || session
+            .events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .has_listeners();
+    if in_use {
+        return;
+    }
+    if !session.resuming {
+        drop(session);
+        remove_session(state, session_id);
+        return;
+    }
+
+    let detached = Instant::now();
+    session.detached = Some(detached);
+    let timeout = Duration::from_secs(session.timeout);
+    drop(session);
+    debug!(
+        "Keeping session {} for {}s to be resumed",
+        session_id,
+        timeout.as_secs()
+    );
+
+    let state = state.clone();
+    let session_id = session_id.to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        let expired = state.sessions.get(&session_id).is_some_and(|session| {
+            session.detached == Some(detached)
+                && session.message_sender.is_none()
+                && !session
+                    .events
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .has_listeners()
+        });
+        if expired {
+            remove_session(&state, &session_id);
+            info!("Session {} was not resumed in time", session_id);
+        }
+    });
+}
+
+/// Live messages of an event stream, releasing the session once dropped
+pub struct LiveEvents {
+    receiver: mpsc::Receiver<(u64, protocol::Message)>,
+    state: Arc<AppState>,
+    session_id: String,
+}
+
+impl LiveEvents {
+    pub fn new(listener: EventListener, state: Arc<AppState>, session_id: String) -> Self {
+        Self {
+            receiver: listener.receiver,
+            state,
+            session_id,
+        }
+    }
+
+    /// The next message sent to the session
+    pub async fn recv(&mut self) -> Option<(u64, protocol::Message)> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for LiveEvents {
+    fn drop(&mut self) {
+        // Closing first lets the session see that this stream is gone
+        self.receiver.close();
+        release_session(&self.state, &self.session_id);
+    }
+}
+
+/// Forget a session along with the resume keys pointing at it
+fn remove_session(state: &AppState, session_id: &str) {
+    state.sessions.remove(session_id);
+    state.resume_keys.retain(|_, id| id != session_id);
+}
+
 impl WebSocketSession {
+    /// A session without a connection, using the default settings
+    pub fn new(session_id: String) -> Self {
+        Self {
+            session_id,
+            resuming: false,
+            timeout: DEFAULT_SESSION_TIMEOUT,
+            message_sender: None,
+            subscription: EventSubscription::default(),
+            events: Arc::default(),
+            detached: None,
+            owner: None,
+        }
+    }
+
+    /// Whether the API key named `key` may use this session
+    pub fn is_usable_by(&self, key: &str) -> bool {
+        self.owner.as_deref().is_none_or(|owner| owner == key)
+    }
+
     /// Send a message to this session
     pub async fn send_message(
         &self,
         message: protocol::Message,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .publish(&message, self.resuming);
         if let Some(ref sender) = self.message_sender {
             sender
                 .send(message)
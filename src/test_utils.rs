@@ -26,6 +26,11 @@ pub fn create_test_config() -> LavalinkConfig {
                     nico: Some(true),
                     http: Some(true),
                     local: Some(false),
+                    podcast: Some(true),
+                    synth: Some(true),
+                    mirroring: None,
+                    local_library: None,
+                    podcast_feeds: None,
                 },
                 filters: FiltersConfig {
                     volume: Some(true),
@@ -55,6 +60,11 @@ pub fn create_test_config() -> LavalinkConfig {
                 http_config: None,
                 timeouts: None,
                 discord_bot_token: None,
+                artwork: None,
+                persistence: None,
+                v3_compatibility: None,
+                api_keys: None,
+                client_limits: None,
             },
             plugins: Some(PluginsConfig::default()),
         },
@@ -223,6 +223,11 @@ fn create_discord_test_config(bot_token: String) -> LavalinkConfig {
                     vimeo: Some(true),
                     http: Some(true),
                     local: Some(false),
+                    podcast: Some(true),
+                    synth: Some(true),
+                    mirroring: None,
+                    local_library: None,
+                    podcast_feeds: None,
                     nico: Some(false),
                 },
                 filters: FiltersConfig {
@@ -253,6 +258,11 @@ fn create_discord_test_config(bot_token: String) -> LavalinkConfig {
                 http_config: None,
                 timeouts: None,
                 discord_bot_token: Some(bot_token), // Set the Discord bot token
+                artwork: None,
+                persistence: None,
+                v3_compatibility: None,
+                api_keys: None,
+                client_limits: None,
             },
             plugins: Some(lavalink_rust::config::PluginsConfig::default()),
         },
@@ -0,0 +1,162 @@
+// Synthetic source tests
+// These tests play generated audio through the player, filter and voice
+// pipelines, so they need neither network access nor audio fixtures
+
+#![cfg(feature = "audio-sources")]
+
+use lavalink_rust::audio::sources::{SynthSpec, SynthStream};
+use lavalink_rust::audio::AudioSourceManager;
+use lavalink_rust::player::AudioPlayerEngine;
+use lavalink_rust::protocol::{Filters, LoadResultData, LoadType, Omissible, Track};
+use tokio::sync::mpsc;
+
+async fn load_synth(identifier: &str) -> Track {
+    let result = AudioSourceManager::new()
+        .load_item(identifier)
+        .await
+        .expect("Failed to load synth track");
+    assert!(matches!(result.load_type, LoadType::Track));
+    match result.data {
+        Some(LoadResultData::Track(track)) => *track,
+        other => panic!("Expected a track, got {other:?}"),
+    }
+}
+
+fn render(identifier: &str) -> Vec<f32> {
+    let mut stream = SynthStream::new(SynthSpec::parse(identifier).unwrap());
+    let mut samples = Vec::new();
+    let mut block = [0.0f32; 960];
+    loop {
+        let written = stream.read_samples(&mut block);
+        if written == 0 {
+            break;
+        }
+        samples.extend_from_slice(&block[..written]);
+    }
+    samples
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+}
+
+/// Test that synth identifiers load as tracks with the expected metadata
+#[tokio::test]
+async fn test_load_synth_tracks() {
+    let track = load_synth("synth:sine?freq=440&duration=5000").await;
+    assert_eq!(track.info.source_name, "synth");
+    assert_eq!(track.info.length, 5000);
+    assert!(!track.info.is_stream);
+    assert_eq!(track.info.title, "Sine 440 Hz");
+
+    for identifier in ["synth:pink", "synth:silence", "synth:sweep"] {
+        let track = load_synth(identifier).await;
+        assert_eq!(track.info.uri.as_deref(), Some(identifier));
+    }
+
+    let result = AudioSourceManager::new()
+        .load_item("synth:sine?freq=-1")
+        .await
+        .unwrap();
+    assert!(matches!(result.load_type, LoadType::Error));
+}
+
+/// Test that rendered audio is deterministic and matches the requested signal
+#[test]
+fn test_rendered_audio() {
+    let sine = render("synth:sine?freq=1000&duration=100&amplitude=0.8");
+    assert_eq!(sine.len(), 4_800 * 2);
+    assert!((peak(&sine) - 0.8).abs() < 0.01);
+    // Both channels carry the same signal
+    assert!(sine.chunks_exact(2).all(|frame| frame[0] == frame[1]));
+    assert_eq!(
+        sine,
+        render("synth:sine?freq=1000&duration=100&amplitude=0.8")
+    );
+
+    assert_eq!(peak(&render("synth:silence?duration=100")), 0.0);
+
+    let pink = render("synth:pink?duration=100&seed=1");
+    assert!(peak(&pink) > 0.0);
+    assert_eq!(pink, render("synth:pink?duration=100&seed=1"));
+    assert_ne!(pink, render("synth:pink?duration=100&seed=2"));
+}
+
+/// Test that player filters are applied to synthetic audio by the engine
+#[tokio::test]
+async fn test_filters_process_synth_audio() {
+    let (sender, _receiver) = mpsc::unbounded_channel();
+    let engine = AudioPlayerEngine::new("123456789".to_string(), sender);
+    engine
+        .apply_filters(Filters {
+            volume: Omissible::Present(0.5),
+            ..Filters::new()
+        })
+        .await
+        .unwrap();
+
+    let original = render("synth:sine?freq=440&duration=100&amplitude=0.8");
+    let mut filtered = original.clone();
+    engine.process_audio_filters(&mut filtered).await.unwrap();
+
+    assert!((peak(&filtered) - peak(&original) * 0.5).abs() < 0.01);
+}
+
+/// Test that a short synthetic track plays to the end through the player
+#[cfg(not(feature = "discord"))]
+#[tokio::test]
+async fn test_synth_track_plays_to_end() {
+    use lavalink_rust::player::{PlayerEvent, PlayerManager, TrackEndReason};
+    use std::time::Duration;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let player_manager = PlayerManager::with_event_sender(sender);
+    let player = player_manager
+        .get_or_create_player("123456789".to_string(), "session123".to_string())
+        .await;
+
+    let track = load_synth("synth:sine?duration=300").await;
+    player
+        .write()
+        .await
+        .play_track(track.clone(), None, None)
+        .await
+        .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(PlayerEvent::TrackEnd { track, reason, .. }) = receiver.recv().await {
+                return (track, reason);
+            }
+        }
+    })
+    .await
+    .expect("Track did not end");
+    assert_eq!(event.0.info.identifier, track.info.identifier);
+    assert_eq!(event.1, TrackEndReason::Finished);
+}
+
+/// Test that songbird decodes synthetic tracks through its raw codec
+#[cfg(feature = "discord")]
+#[tokio::test]
+async fn test_synth_voice_input_decodes() {
+    use songbird::input::codecs::{get_codec_registry, get_probe};
+    use songbird::input::{Input, LiveInput};
+
+    let spec = SynthSpec::parse("synth:sine?freq=440&duration=100").unwrap();
+    let input = lavalink_rust::audio::sources::synth::songbird_input(spec)
+        .make_playable_async(get_codec_registry(), get_probe())
+        .await
+        .expect("Songbird could not parse synth input");
+    let Input::Live(LiveInput::Parsed(mut parsed), _) = input else {
+        panic!("Synth input was not parsed");
+    };
+
+    let mut frames = 0;
+    while let Ok(packet) = parsed.format.next_packet() {
+        frames += parsed.decoder.decode(&packet).unwrap().frames();
+    }
+    assert_eq!(frames, 4_800);
+}